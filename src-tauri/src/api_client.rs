@@ -1,3 +1,4 @@
+use crate::currency;
 use crate::utils::{parse_first_number, to_f64};
 use anyhow::{Context, Result};
 use chrono::{Datelike, Local, NaiveDate};
@@ -10,6 +11,8 @@ const QUOTA_UNIT_PER_DOLLAR: f64 = 500000.0;
 pub struct ApiBalanceResult {
     pub success: bool,
     pub balance: Option<f64>,
+    /// 余额货币代码，按 currency.providers 中该站点的配置
+    pub currency: String,
     pub source: String,
     pub message: String,
 }
@@ -19,6 +22,7 @@ impl ApiBalanceResult {
         Self {
             success: true,
             balance: Some(balance),
+            currency: String::new(),
             source: source.to_string(),
             message: message.to_string(),
         }
@@ -28,6 +32,7 @@ impl ApiBalanceResult {
         Self {
             success: false,
             balance: None,
+            currency: String::new(),
             source: String::new(),
             message: message.into(),
         }
@@ -37,6 +42,8 @@ impl ApiBalanceResult {
 #[derive(Clone)]
 pub struct ApiBalanceClient {
     base_url: String,
+    /// base_url 的主机名，用于查找站点货币
+    provider: String,
     client: reqwest::Client,
 }
 
@@ -46,13 +53,26 @@ impl ApiBalanceClient {
             .timeout(std::time::Duration::from_secs(timeout_seconds.max(1)))
             .build()
             .with_context(|| "创建HTTP客户端失败")?;
+        let provider = reqwest::Url::parse(base_url.trim())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
         Ok(Self {
             base_url: base_url.trim_end_matches('/').to_string(),
+            provider,
             client,
         })
     }
 
     pub async fn query_balance(&self, api_key: &str) -> ApiBalanceResult {
+        let mut result = self.fetch_balance(api_key).await;
+        if result.success {
+            result.currency = currency::provider_currency(&self.provider);
+        }
+        result
+    }
+
+    async fn fetch_balance(&self, api_key: &str) -> ApiBalanceResult {
         let key = api_key.trim();
         if key.is_empty() {
             return ApiBalanceResult::fail("缺少 API Key");
@@ -112,7 +132,7 @@ impl ApiBalanceClient {
 
                     if let Some(header_value) = extract_balance_from_headers(response.headers()) {
                         return ApiBalanceResult::ok(
-                            header_value.max(0.0),
+                            header_value,
                             &format!("header:{}", path),
                             "通过响应头获取余额",
                         );
@@ -129,7 +149,7 @@ impl ApiBalanceClient {
 
                     if let Some(body_value) = extract_balance_from_body(&body_text) {
                         return ApiBalanceResult::ok(
-                            body_value.max(0.0),
+                            body_value,
                             &format!("body:{}", path),
                             "通过响应体获取余额",
                         );
@@ -189,7 +209,8 @@ impl ApiBalanceClient {
         } else {
             total_usage
         };
-        let remain = (hard_limit - usage_usd).max(0.0);
+        // 用量超过额度时余额为负(透支)，保留负值由上层标记
+        let remain = hard_limit - usage_usd;
 
         tracing::debug!(
             "账单路由余额计算: hard_limit_usd={:.4}, total_usage_raw={:.4}, usage_usd={:.4}, remain={:.4}",
@@ -227,7 +248,7 @@ fn extract_balance_from_headers(headers: &HeaderMap) -> Option<f64> {
     for key in usd_keys {
         if let Some(raw) = get_header_text(headers, key) {
             if let Some(value) = parse_first_number(&raw) {
-                return Some(value.max(0.0));
+                return Some(value);
             }
         }
     }
@@ -235,7 +256,7 @@ fn extract_balance_from_headers(headers: &HeaderMap) -> Option<f64> {
     for key in quota_keys {
         if let Some(raw) = get_header_text(headers, key) {
             if let Some(value) = parse_first_number(&raw) {
-                return Some((value / QUOTA_UNIT_PER_DOLLAR).max(0.0));
+                return Some(value / QUOTA_UNIT_PER_DOLLAR);
             }
         }
     }
@@ -256,14 +277,14 @@ fn extract_balance_from_body(text: &str) -> Option<f64> {
     let data: Value = serde_json::from_str(text).ok()?;
 
     if let Some(value) = to_f64(data.get("total_available")) {
-        return Some(value.max(0.0));
+        return Some(value);
     }
 
     if let Some(value) = to_f64(data.get("balance")) {
-        return Some(normalize_balance_value(value, "balance").max(0.0));
+        return Some(normalize_balance_value(value, "balance"));
     }
 
-    scan_balance_value(&data, 0).map(|item| item.max(0.0))
+    scan_balance_value(&data, 0)
 }
 
 fn scan_balance_value(obj: &Value, depth: usize) -> Option<f64> {
@@ -338,7 +359,150 @@ fn normalize_balance_value(value: f64, key_hint: &str) -> f64 {
     value
 }
 
-
 fn first_day_of_month(today: NaiveDate) -> Result<NaiveDate> {
     NaiveDate::from_ymd_opt(today.year(), today.month(), 1).with_context(|| "计算月初日期失败")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Rng;
+    use reqwest::header::HeaderName;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    const CASES: usize = 500;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    fn fixtures_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/balance")
+    }
+
+    /// 与查询流程相同: 先看响应头，再看响应体
+    fn extract_fixture(fixture: &Value) -> Option<f64> {
+        let mut headers = HeaderMap::new();
+        if let Some(map) = fixture.get("headers").and_then(Value::as_object) {
+            for (name, value) in map {
+                headers.insert(
+                    HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                    HeaderValue::from_str(value.as_str().unwrap_or_default()).unwrap(),
+                );
+            }
+        }
+        if let Some(value) = extract_balance_from_headers(&headers) {
+            return Some(value);
+        }
+        let body = match fixture.get("body") {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => return None,
+        };
+        extract_balance_from_body(&body)
+    }
+
+    #[test]
+    fn fixtures_match_expected_balance() {
+        let mut checked = 0;
+        for entry in std::fs::read_dir(fixtures_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let raw = std::fs::read_to_string(&path).unwrap();
+            let fixture: Value = serde_json::from_str(&raw)
+                .unwrap_or_else(|e| panic!("{} 不是合法的 JSON: {e}", path.display()));
+            let expected = fixture.get("expected").and_then(Value::as_f64);
+            let actual = extract_fixture(&fixture);
+            let matched = match (expected, actual) {
+                (Some(expected), Some(actual)) => approx_eq(expected, actual),
+                (None, None) => true,
+                _ => false,
+            };
+            assert!(
+                matched,
+                "{}: 期望 {expected:?}，实际 {actual:?}",
+                path.display()
+            );
+            checked += 1;
+        }
+        assert!(checked > 0, "未找到余额解析样例");
+    }
+
+    #[test]
+    fn quota_keys_always_convert_units() {
+        let mut rng = Rng::new(0x5eed_0001);
+        for _ in 0..CASES {
+            let value = rng.amount(10_000_000);
+            for key in ["quota", "remain_quota", "left_quota"] {
+                assert!(approx_eq(
+                    normalize_balance_value(value, key),
+                    value / QUOTA_UNIT_PER_DOLLAR
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn usd_keys_convert_only_large_values() {
+        let mut rng = Rng::new(0x5eed_0002);
+        for _ in 0..CASES {
+            let value = rng.amount(10_000_000);
+            let normalized = normalize_balance_value(value, "balance");
+            if value.abs() > 100000.0 {
+                assert!(approx_eq(normalized, value / QUOTA_UNIT_PER_DOLLAR));
+            } else {
+                assert_eq!(normalized, value);
+            }
+            assert_eq!(normalized.is_sign_negative(), value.is_sign_negative());
+        }
+    }
+
+    #[test]
+    fn number_and_string_balances_agree() {
+        let mut rng = Rng::new(0x5eed_0003);
+        for _ in 0..CASES {
+            let value = rng.amount(50_000);
+            let as_number = extract_balance_from_body(&json!({ "balance": value }).to_string());
+            let as_string =
+                extract_balance_from_body(&json!({ "balance": format!("{value:.2}") }).to_string());
+            assert_eq!(as_number, Some(value));
+            assert!(approx_eq(as_string.unwrap(), value));
+        }
+    }
+
+    #[test]
+    fn scan_stops_below_max_depth() {
+        let mut rng = Rng::new(0x5eed_0004);
+        for depth in 0..=8 {
+            let value = rng.amount(1000);
+            let mut body = json!({ "available_balance": value });
+            for level in 0..depth {
+                body = if level % 2 == 0 {
+                    json!({ "data": body })
+                } else {
+                    json!([body])
+                };
+            }
+            let found = scan_balance_value(&body, 0);
+            if depth <= 5 {
+                assert_eq!(found, Some(value), "depth {depth}");
+            } else {
+                assert_eq!(found, None, "depth {depth}");
+            }
+        }
+    }
+
+    #[test]
+    fn usd_fields_take_precedence_over_quota() {
+        let mut rng = Rng::new(0x5eed_0005);
+        for _ in 0..CASES {
+            let usd = rng.amount(1000);
+            let quota = rng.amount(10_000_000);
+            let body = json!({ "data": { "remain_quota": quota, "credit_balance": usd } });
+            assert_eq!(scan_balance_value(&body, 0), Some(usd));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::models::AppLockConfig;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// PIN 哈希的迭代次数，增加离线暴力破解的成本
+const HASH_ROUNDS: u32 = 100_000;
+/// 连续输错该次数后暂停尝试
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct LockState {
+    /// 最近一次解锁或受保护操作的时间，None 表示已锁定
+    last_activity: Option<Instant>,
+    failed_attempts: u32,
+    retry_after: Option<Instant>,
+}
+
+/// 应用锁的运行时状态；PIN 哈希与自动锁定时间来自配置
+#[derive(Debug, Default)]
+pub struct AppLock {
+    inner: Mutex<LockState>,
+}
+
+impl AppLock {
+    /// 是否处于解锁状态；超过空闲时间时顺带重新锁定
+    pub fn is_unlocked(&self, config: &AppLockConfig) -> bool {
+        if !config.active() {
+            return true;
+        }
+        let Ok(mut guard) = self.inner.lock() else {
+            return false;
+        };
+        let Some(last) = guard.last_activity else {
+            return false;
+        };
+        let idle = Duration::from_secs(config.idle_lock_minutes * 60);
+        if config.idle_lock_minutes > 0 && last.elapsed() >= idle {
+            guard.last_activity = None;
+            tracing::info!("空闲超过 {} 分钟，应用已自动锁定", config.idle_lock_minutes);
+            return false;
+        }
+        true
+    }
+
+    /// 受保护操作放行时调用，重新计算空闲时间
+    pub fn touch(&self) {
+        if let Ok(mut guard) = self.inner.lock()
+            && guard.last_activity.is_some()
+        {
+            guard.last_activity = Some(Instant::now());
+        }
+    }
+
+    /// 连续输错后的暂停期内拒绝再次尝试
+    pub fn check_retry(&self) -> Result<(), String> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|_| "应用锁状态不可用".to_string())?;
+        if let Some(until) = guard.retry_after
+            && until > Instant::now()
+        {
+            let secs = until.saturating_duration_since(Instant::now()).as_secs() + 1;
+            return Err(format!("PIN 错误次数过多，请 {secs} 秒后再试"));
+        }
+        Ok(())
+    }
+
+    /// 记录一次 PIN 校验结果，正确时解锁
+    pub fn finish_unlock(&self, verified: bool) -> Result<(), String> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|_| "应用锁状态不可用".to_string())?;
+        if !verified {
+            guard.failed_attempts += 1;
+            if guard.failed_attempts >= MAX_FAILED_ATTEMPTS {
+                guard.failed_attempts = 0;
+                guard.retry_after = Some(Instant::now() + LOCKOUT);
+                tracing::warn!("应用锁 PIN 连续输错 {} 次", MAX_FAILED_ATTEMPTS);
+            }
+            return Err("PIN 不正确".to_string());
+        }
+        guard.failed_attempts = 0;
+        guard.retry_after = None;
+        guard.last_activity = Some(Instant::now());
+        Ok(())
+    }
+
+    pub fn lock(&self) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.last_activity = None;
+        }
+    }
+}
+
+/// 生成 "salt$hash" 形式的 PIN 哈希，写入 app_lock.pin_hash
+pub fn hash_pin(pin: &str) -> String {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|item| item.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    let salt = hex::encode(&hasher.finalize()[..16]);
+    format!("{salt}${}", stretch(&salt, pin))
+}
+
+/// 按 hash_pin 的格式校验 PIN；迭代哈希较慢，应在阻塞线程调用
+pub fn verify_pin(pin: &str, stored: &str) -> bool {
+    let Some((salt, expected)) = stored.trim().split_once('$') else {
+        return false;
+    };
+    let actual = stretch(salt, pin);
+    // 逐字节比较全部内容，耗时不随首个不同字节的位置变化
+    actual.len() == expected.len()
+        && actual
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0_u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn stretch(salt: &str, pin: &str) -> String {
+    let mut digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(pin.as_bytes())
+        .finalize();
+    for _ in 1..HASH_ROUNDS {
+        digest = Sha256::new()
+            .chain_update(digest)
+            .chain_update(salt.as_bytes())
+            .finalize();
+    }
+    hex::encode(digest)
+}
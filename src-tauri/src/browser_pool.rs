@@ -1,10 +1,12 @@
-use crate::driver_manager::get_chromedriver_path;
-use crate::models::WebCheckConfig;
+use crate::driver_manager::{get_chromedriver_path, resolve_browser_binary};
+use crate::models::{InfrastructureErrorEvent, WebCheckConfig};
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File, OpenOptions};
 use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread::sleep;
 use std::time::{Duration, Instant, SystemTime};
@@ -30,6 +32,7 @@ impl DriverProcess {
     }
 
     fn kill(&mut self) {
+        untrack_driver_pid(self.child.id());
         let _ = self.child.kill();
         let _ = self.child.wait();
     }
@@ -48,14 +51,22 @@ pub struct BrowserPool {
     processes: Vec<DriverProcess>,
     stats: HashMap<String, f64>,
     chromedriver_path: PathBuf,
+    driver_log: bool,
+    driver_log_max_bytes: u64,
+    driver_log_keep: usize,
+    driver_log_level: String,
+    driver_verbose: bool,
+    driver_append_log: bool,
 }
 
 impl BrowserPool {
-    pub fn new(config: &WebCheckConfig) -> Result<Self> {
+    /// binary_path 为浏览器可执行文件路径，自动选择驱动时以它的版本为准
+    pub fn new(config: &WebCheckConfig, binary_path: &str) -> Result<Self> {
         let path = if !config.chromedriver_path.trim().is_empty() {
             PathBuf::from(config.chromedriver_path.trim())
         } else {
-            get_chromedriver_path()?
+            let binary = resolve_browser_binary(binary_path).unwrap_or_default();
+            get_chromedriver_path(config.driver_version.trim(), &binary)?
         };
 
         let mut pool = Self {
@@ -66,8 +77,18 @@ impl BrowserPool {
                 ("total_created".to_string(), 0.0),
                 ("total_reused".to_string(), 0.0),
                 ("total_requests".to_string(), 0.0),
+                ("creation_failures".to_string(), 0.0),
+                ("evictions".to_string(), 0.0),
+                ("wait_count".to_string(), 0.0),
+                ("total_wait_secs".to_string(), 0.0),
             ]),
             chromedriver_path: path,
+            driver_log: config.driver_log,
+            driver_log_max_bytes: config.driver_log_max_kb.max(1) * 1024,
+            driver_log_keep: config.driver_log_keep,
+            driver_log_level: config.driver_log_level.trim().to_uppercase(),
+            driver_verbose: config.driver_verbose,
+            driver_append_log: config.driver_append_log,
         };
         pool.pool_size = pool.pool_size.min(pool.max_pool_size);
         pool.init_pool()?;
@@ -88,25 +109,59 @@ impl BrowserPool {
     }
 
     fn create_process(&mut self, id: &str) -> Result<DriverProcess> {
+        let created = self.spawn_process(id);
+        if created.is_err() {
+            *self
+                .stats
+                .entry("creation_failures".to_string())
+                .or_default() += 1.0;
+        }
+        created
+    }
+
+    fn spawn_process(&mut self, id: &str) -> Result<DriverProcess> {
         let port = find_free_port()?;
         let mut cmd = Command::new(&self.chromedriver_path);
-        cmd.arg(format!("--port={port}"))
-            .stdout(Stdio::null())
-            .stderr(Stdio::null());
+        cmd.arg(format!("--port={port}"));
+        if self.driver_verbose || DRIVER_VERBOSE_OVERRIDE.load(Ordering::Relaxed) {
+            cmd.arg("--verbose");
+        } else if !self.driver_log_level.is_empty() {
+            cmd.arg(format!("--log-level={}", self.driver_log_level));
+        }
+        if self.driver_append_log {
+            cmd.arg("--append-log");
+        }
+        match self.open_driver_log(id) {
+            Some(file) => {
+                let stderr = file
+                    .try_clone()
+                    .map(Stdio::from)
+                    .unwrap_or_else(|_| Stdio::null());
+                cmd.stdout(Stdio::from(file)).stderr(stderr);
+            }
+            None => {
+                cmd.stdout(Stdio::null()).stderr(Stdio::null());
+            }
+        }
         #[cfg(windows)]
         {
             use std::os::windows::process::CommandExt;
             cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
         }
-        let child = cmd.spawn().with_context(|| {
+        let mut child = cmd.spawn().with_context(|| {
             format!(
                 "启动 chromedriver 失败: path={}",
                 self.chromedriver_path.display()
             )
         })?;
+        track_driver_pid(child.id());
 
-        wait_port_ready(port, Duration::from_secs(8))
-            .with_context(|| format!("等待 chromedriver 端口就绪失败: {port}"))?;
+        if let Err(err) = wait_port_ready(port, Duration::from_secs(8)) {
+            untrack_driver_pid(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err).with_context(|| format!("等待 chromedriver 端口就绪失败: {port}"));
+        }
 
         let now = SystemTime::now();
         let process = DriverProcess {
@@ -123,6 +178,41 @@ impl BrowserPool {
         Ok(process)
     }
 
+    /// 开启 driver_log(或运行时打开详细日志)时打开 logs/chromedriver_<id>.log，超限先轮转
+    fn open_driver_log(&self, id: &str) -> Option<File> {
+        if !self.driver_log && !DRIVER_VERBOSE_OVERRIDE.load(Ordering::Relaxed) {
+            return None;
+        }
+        let dir = DRIVER_LOG_DIR.get()?;
+        if let Err(err) = fs::create_dir_all(dir) {
+            tracing::warn!(
+                "创建 chromedriver 日志目录失败: {}, err={}",
+                dir.display(),
+                err
+            );
+            return None;
+        }
+        let path = dir.join(format!("chromedriver_{id}.log"));
+        rotate_log_file(&path, self.driver_log_max_bytes, self.driver_log_keep);
+        let mut options = OpenOptions::new();
+        if self.driver_append_log {
+            options.create(true).append(true);
+        } else {
+            options.create(true).write(true).truncate(true);
+        }
+        match options.open(&path) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                tracing::warn!(
+                    "打开 chromedriver 日志失败: {}, err={}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
     /// 尝试获取一个可用的浏览器实例（非阻塞）。
     /// 返回 Ok(Some(ticket)) 表示成功获取，Ok(None) 表示当前无可用实例。
     /// 调用方应在获取失败时释放锁后等待重试，避免持锁睡眠导致死锁。
@@ -168,6 +258,45 @@ impl BrowserPool {
         Ok(None)
     }
 
+    /// 补齐到 pool_size 个存活实例，返回新创建的数量
+    pub fn fill_to_min_size(&mut self) -> usize {
+        self.remove_dead_processes();
+        let mut created = 0;
+        while self.processes.len() < self.pool_size {
+            let id = format!("browser_{}", self.processes.len());
+            match self.create_process(&id) {
+                Ok(process) => {
+                    self.processes.push(process);
+                    created += 1;
+                }
+                Err(err) => {
+                    tracing::warn!("浏览器池补齐失败: id={}, err={}", id, err);
+                    break;
+                }
+            }
+        }
+        created
+    }
+
+    /// 记录一次获取实例的等待耗时
+    pub fn record_wait(&mut self, wait_secs: f64) {
+        *self.stats.entry("wait_count".to_string()).or_default() += 1.0;
+        *self.stats.entry("total_wait_secs".to_string()).or_default() += wait_secs;
+    }
+
+    /// 结束无响应的实例进程，下次获取时会被清理并按需重建
+    pub fn discard(&mut self, ticket: PoolTicket) {
+        if let Some(item) = self
+            .processes
+            .iter_mut()
+            .find(|item| item.url == ticket.url)
+        {
+            tracing::warn!("强制结束无响应的浏览器进程: id={}", item.id);
+            item.kill();
+            item.is_busy = false;
+        }
+    }
+
     pub fn release(&mut self, ticket: PoolTicket) {
         if let Some(item) = self.processes.get_mut(ticket.index) {
             item.is_busy = false;
@@ -198,9 +327,35 @@ impl BrowserPool {
                 0.0
             },
         );
+        let wait_count = *data.get("wait_count").unwrap_or(&0.0);
+        let total_wait_secs = *data.get("total_wait_secs").unwrap_or(&0.0);
+        data.insert(
+            "avg_wait_secs".to_string(),
+            if wait_count > 0.0 {
+                total_wait_secs / wait_count
+            } else {
+                0.0
+            },
+        );
         data
     }
 
+    /// 结束所有空闲实例，之后按需以当前参数重建
+    pub fn recycle_idle(&mut self) -> usize {
+        let mut kept = Vec::new();
+        let mut recycled = 0;
+        for mut item in self.processes.drain(..) {
+            if item.is_busy {
+                kept.push(item);
+            } else {
+                item.kill();
+                recycled += 1;
+            }
+        }
+        self.processes = kept;
+        recycled
+    }
+
     pub fn shutdown(&mut self) {
         for item in &mut self.processes {
             item.kill();
@@ -220,6 +375,7 @@ impl BrowserPool {
             } else {
                 tracing::warn!("移除失效浏览器进程: id={}", item.id);
                 item.kill();
+                *self.stats.entry("evictions".to_string()).or_default() += 1.0;
             }
         }
         self.processes = kept;
@@ -232,15 +388,56 @@ impl Drop for BrowserPool {
     }
 }
 
-pub fn get_global_pool(config: &WebCheckConfig) -> Result<Arc<Mutex<BrowserPool>>> {
+pub fn get_global_pool(
+    config: &WebCheckConfig,
+    binary_path: &str,
+) -> Result<Arc<Mutex<BrowserPool>>> {
     if let Some(pool) = GLOBAL_POOL.get() {
         return Ok(pool.clone());
     }
-    let created = Arc::new(Mutex::new(BrowserPool::new(config)?));
+    let created = Arc::new(Mutex::new(BrowserPool::new(config, binary_path)?));
     let _ = GLOBAL_POOL.set(created.clone());
     Ok(created)
 }
 
+/// 预热全局浏览器池: 未创建时创建，已创建时补齐失效实例
+pub fn prewarm_global_pool(config: &WebCheckConfig, binary_path: &str) -> Result<usize> {
+    let existed = GLOBAL_POOL.get().is_some();
+    let pool = get_global_pool(config, binary_path)?;
+    let mut guard = pool
+        .lock()
+        .map_err(|_| anyhow::anyhow!("浏览器池锁获取失败"))?;
+    let created = if existed {
+        guard.fill_to_min_size()
+    } else {
+        guard.processes.len()
+    };
+    Ok(created)
+}
+
+/// 全局浏览器池已创建时返回其统计数据
+pub fn global_pool_stats() -> Option<HashMap<String, f64>> {
+    let pool = GLOBAL_POOL.get()?;
+    let guard = pool.lock().ok()?;
+    Some(guard.get_stats())
+}
+
+/// 运行时切换 chromedriver 详细日志，空闲实例立即回收以便新参数生效，返回回收数量
+pub fn set_driver_verbose(enabled: bool) -> usize {
+    DRIVER_VERBOSE_OVERRIDE.store(enabled, Ordering::Relaxed);
+    let Some(pool) = GLOBAL_POOL.get() else {
+        return 0;
+    };
+    match pool.lock() {
+        Ok(mut guard) => guard.recycle_idle(),
+        Err(_) => 0,
+    }
+}
+
+pub fn driver_verbose_enabled() -> bool {
+    DRIVER_VERBOSE_OVERRIDE.load(Ordering::Relaxed)
+}
+
 pub fn shutdown_global_pool() {
     if let Some(pool) = GLOBAL_POOL.get() {
         if let Ok(mut guard) = pool.lock() {
@@ -270,3 +467,126 @@ fn wait_port_ready(port: u16, timeout: Duration) -> Result<()> {
     anyhow::bail!("端口未就绪: {port}")
 }
 static GLOBAL_POOL: OnceLock<Arc<Mutex<BrowserPool>>> = OnceLock::new();
+
+/// 运行时打开的 chromedriver 详细日志，无需重启应用
+static DRIVER_VERBOSE_OVERRIDE: AtomicBool = AtomicBool::new(false);
+
+/// chromedriver 日志目录，启动时设置一次
+static DRIVER_LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_driver_log_dir(dir: PathBuf) {
+    let _ = DRIVER_LOG_DIR.set(dir);
+}
+
+/// 文件超过 max_bytes 时轮转: name.log -> name.log.1 -> ... -> name.log.<keep>
+fn rotate_log_file(path: &Path, max_bytes: u64, keep: usize) {
+    let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    if size < max_bytes {
+        return;
+    }
+    let rotated = |index: usize| PathBuf::from(format!("{}.{index}", path.display()));
+    if keep == 0 {
+        let _ = fs::remove_file(path);
+        return;
+    }
+    let _ = fs::remove_file(rotated(keep));
+    for index in (1..keep).rev() {
+        let from = rotated(index);
+        if from.exists() {
+            let _ = fs::rename(&from, rotated(index + 1));
+        }
+    }
+    let _ = fs::rename(path, rotated(1));
+}
+
+/// 浏览器池启动的 chromedriver 进程 PID，独立于池锁，供资源采样读取
+static DRIVER_PIDS: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+fn track_driver_pid(pid: u32) {
+    if let Ok(mut guard) = DRIVER_PIDS.lock() {
+        guard.insert(pid);
+    }
+}
+
+fn untrack_driver_pid(pid: u32) {
+    if let Ok(mut guard) = DRIVER_PIDS.lock() {
+        guard.remove(&pid);
+    }
+}
+
+pub fn tracked_driver_pids() -> Vec<u32> {
+    DRIVER_PIDS
+        .lock()
+        .map(|guard| guard.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+type InfrastructureListener = Box<dyn Fn(&InfrastructureErrorEvent) + Send + Sync>;
+
+/// 浏览器池与驱动故障的事件回调，启动时注册一次
+static INFRA_LISTENER: OnceLock<InfrastructureListener> = OnceLock::new();
+
+/// 各类故障最近一次发送事件的时间，并发账号同时失败时只提示一次
+static INFRA_LAST_SENT: Mutex<BTreeMap<&'static str, Instant>> = Mutex::new(BTreeMap::new());
+
+const INFRA_EVENT_COOLDOWN: Duration = Duration::from_secs(30);
+
+pub fn set_infrastructure_listener(
+    listener: impl Fn(&InfrastructureErrorEvent) + Send + Sync + 'static,
+) {
+    let _ = INFRA_LISTENER.set(Box::new(listener));
+}
+
+/// 获取浏览器实例失败时按错误类型发送故障事件，同类故障冷却期内不重复发送
+pub fn report_infrastructure_error(kind: &'static str, err: &anyhow::Error) {
+    let Some(listener) = INFRA_LISTENER.get() else {
+        return;
+    };
+    if let Ok(mut guard) = INFRA_LAST_SENT.lock() {
+        if guard
+            .get(kind)
+            .is_some_and(|at| at.elapsed() < INFRA_EVENT_COOLDOWN)
+        {
+            return;
+        }
+        guard.insert(kind, Instant::now());
+    }
+    let event = InfrastructureErrorEvent {
+        kind: kind.to_string(),
+        message: format!("{err:#}"),
+        hints: infrastructure_hints(kind)
+            .iter()
+            .map(|item| item.to_string())
+            .collect(),
+    };
+    tracing::warn!("浏览器基础设施故障[{}]: {}", kind, event.message);
+    listener(&event);
+}
+
+/// 浏览器池初始化或创建实例失败的类型: 驱动进程没能启动，或根本找不到可用驱动
+pub fn classify_driver_error(err: &anyhow::Error) -> &'static str {
+    let text = format!("{err:#}");
+    if text.contains("启动 chromedriver 失败") || text.contains("chromedriver 端口就绪") {
+        "driver_spawn_failed"
+    } else {
+        "driver_unavailable"
+    }
+}
+
+fn infrastructure_hints(kind: &str) -> &'static [&'static str] {
+    match kind {
+        "pool_exhausted" => &[
+            "调大 web_check.max_pool_size，或降低同时查询的账号数",
+            "检查是否有卡住的浏览器进程，可在工具菜单查看浏览器池报告",
+        ],
+        "driver_spawn_failed" => &[
+            "检查杀毒软件或系统策略是否拦截了 chromedriver",
+            "删除驱动缓存目录后重新下载驱动",
+            "确认驱动版本与浏览器主版本一致",
+        ],
+        _ => &[
+            "重新下载驱动，或在 web_check.chromedriver_path 指定已安装的驱动",
+            "确认 browser.binary_path 指向可用的浏览器",
+        ],
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// 命令错误的分类，前端据此决定提示方式(如忙碌时稍后重试、锁定时弹出解锁框)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 参数不合法或当前状态不允许该操作
+    Validation,
+    /// 账号、记录或文件不存在
+    NotFound,
+    /// 查询进行中，需等待本轮结束
+    Busy,
+    /// 应用锁未解锁
+    Locked,
+    /// 读写本地文件失败
+    Io,
+    /// 其他失败，如站点请求或浏览器出错
+    Internal,
+}
+
+/// 命令失败时返回给前端的错误: code 用于分支处理与本地化，message 为可直接显示的中文说明
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn busy(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Busy, message)
+    }
+
+    pub fn locked(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Locked, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Io, message)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// 未分类的错误文本视为 internal，便于沿用返回 String 的辅助函数
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
@@ -0,0 +1,874 @@
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::{ensure_credentials_writable, ensure_unlocked, metered_command};
+use crate::config::{load_accounts, save_accounts};
+use crate::models::{Account, AccountImport};
+use crate::{AppState, QUERY_BUSY_ERROR, config, cookies, models, privacy, web_native};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionResponse {
+    success: bool,
+    #[serde(serialize_with = "privacy::serialize_text")]
+    message: String,
+    #[serde(serialize_with = "privacy::serialize_accounts")]
+    accounts: Vec<Account>,
+}
+
+/// 界面与账号文件都修改过的账号
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountConflict {
+    #[serde(serialize_with = "privacy::serialize_username")]
+    username: String,
+    /// 界面侧的修改: 新增 / 已删除 / 修改了密码、Key
+    local: String,
+    /// 账号文件侧的修改
+    disk: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadResponse {
+    success: bool,
+    message: String,
+    #[serde(serialize_with = "privacy::serialize_accounts")]
+    accounts: Vec<Account>,
+    /// 未指定 resolution 时返回的冲突，此时账号列表保持不变
+    conflicts: Vec<AccountConflict>,
+}
+
+metered_command! {
+/// 重新读取账号文件并与界面中尚未写入的修改合并: 只有一侧修改的账号直接采用该侧，
+/// 两侧都修改的账号作为冲突返回。resolution 为 disk(以文件为准) 或 memory(保留界面修改) 时按其解决冲突
+#[tauri::command]
+pub async fn reload_accounts_command(
+    state: State<'_, AppState>,
+    resolution: Option<String>,
+) -> CommandResult<ReloadResponse> {
+    ensure_unlocked(&state).await?;
+    let resolution = resolution
+        .map(|item| item.trim().to_ascii_lowercase())
+        .filter(|item| !item.is_empty());
+    if let Some(value) = resolution.as_deref()
+        && value != "disk"
+        && value != "memory"
+    {
+        return Err(CommandError::validation(format!(
+            "未知的冲突处理方式: {value}"
+        )));
+    }
+
+    let mut guard = state.accounts.write().await;
+    let mut disk = load_accounts(&state.files.credentials_file)
+        .map_err(|e| CommandError::io(format!("读取账号文件失败: {e}")))?;
+    sort_accounts(&mut disk);
+    let base = state.accounts_base.read().await.clone();
+    let (mut merged, conflicts) = merge_accounts(&base, &guard, &disk, resolution.as_deref());
+    if !conflicts.is_empty() && resolution.is_none() {
+        return Ok(ReloadResponse {
+            success: false,
+            message: format!(
+                "{} 个账号在界面与账号文件中都有修改，请选择以哪一侧为准",
+                conflicts.len()
+            ),
+            accounts: guard.clone(),
+            conflicts,
+        });
+    }
+    sort_accounts(&mut merged);
+    let kept_local = merged != disk;
+    privacy::set_usernames(&merged);
+    *guard = merged.clone();
+    *state.accounts_base.write().await = disk;
+    // 合并结果与文件不同(保留了界面修改)时写回文件，否则清除待写标记
+    state.credentials_dirty.store(kept_local, Ordering::Release);
+    drop(guard);
+    state.invalidate_snapshot("accounts");
+    state.state_store.write().await.touch_all();
+    Ok(ReloadResponse {
+        success: true,
+        message: if kept_local {
+            format!(
+                "已重新加载 {} 个账号，保留了界面中尚未写入的修改",
+                merged.len()
+            )
+        } else {
+            format!("已重新加载 {} 个账号", merged.len())
+        },
+        accounts: merged,
+        conflicts,
+    })
+}
+}
+
+/// 三方合并账号列表: base 为上次与文件同步时的内容，memory 为界面侧，disk 为文件侧
+fn merge_accounts(
+    base: &[Account],
+    memory: &[Account],
+    disk: &[Account],
+    resolution: Option<&str>,
+) -> (Vec<Account>, Vec<AccountConflict>) {
+    let find = |list: &[Account], username: &str| {
+        list.iter().find(|item| item.username == username).cloned()
+    };
+    let usernames: BTreeSet<&str> = base
+        .iter()
+        .chain(memory)
+        .chain(disk)
+        .map(|item| item.username.as_str())
+        .collect();
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for username in usernames {
+        let base_item = find(base, username);
+        let memory_item = find(memory, username);
+        let disk_item = find(disk, username);
+        let chosen = if memory_item == disk_item || memory_item == base_item {
+            disk_item
+        } else if disk_item == base_item {
+            memory_item
+        } else {
+            conflicts.push(AccountConflict {
+                username: username.to_string(),
+                local: describe_account_change(base_item.as_ref(), memory_item.as_ref()),
+                disk: describe_account_change(base_item.as_ref(), disk_item.as_ref()),
+            });
+            if resolution == Some("memory") {
+                memory_item
+            } else {
+                disk_item
+            }
+        };
+        merged.extend(chosen);
+    }
+    (merged, conflicts)
+}
+
+fn describe_account_change(base: Option<&Account>, current: Option<&Account>) -> String {
+    match (base, current) {
+        (None, Some(_)) => "新增".to_string(),
+        (Some(_), None) => "已删除".to_string(),
+        (Some(base), Some(current)) => {
+            let mut fields = Vec::new();
+            if base.password != current.password {
+                fields.push("密码");
+            }
+            if base.api_key != current.api_key || base.keys != current.keys {
+                fields.push("Key");
+            }
+            if base.alias != current.alias {
+                fields.push("别名");
+            }
+            format!("修改了{}", fields.join("、"))
+        }
+        (None, None) => "无".to_string(),
+    }
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn upsert_account_command(
+    state: State<'_, AppState>,
+    username: String,
+    password: String,
+    api_key: Option<String>,
+    alias: Option<String>,
+) -> CommandResult<ActionResponse> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let password = password.trim().to_string();
+    let alias = normalize_alias(&alias.unwrap_or_default());
+    let keys = config::parse_account_keys(&api_key.unwrap_or_default());
+    let api_key = keys
+        .first()
+        .map(|item| item.key.clone())
+        .unwrap_or_default();
+    if username.is_empty() || password.is_empty() {
+        return Err(CommandError::validation("用户名和密码不能为空"));
+    }
+    ensure_credentials_writable(&state).await?;
+
+    let mut guard = state.accounts.write().await;
+    let mut accounts = guard.clone();
+    let mut replaced = false;
+    for item in &mut accounts {
+        if item.username == username {
+            item.password = password.clone();
+            item.api_key = api_key.clone();
+            item.keys = keys.clone();
+            item.alias = alias.clone();
+            replaced = true;
+            break;
+        }
+    }
+    if !replaced {
+        accounts.push(Account {
+            username: username.clone(),
+            password,
+            api_key,
+            keys,
+            alias,
+        });
+    }
+    sort_accounts(&mut accounts);
+
+    *guard = accounts.clone();
+    state.mark_credentials_dirty();
+    state.state_store.write().await.touch_account(&username);
+
+    Ok(ActionResponse {
+        success: true,
+        message: if replaced {
+            format!("已更新账号: {username}")
+        } else {
+            format!("已新增账号: {username}")
+        },
+        accounts,
+    })
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn remove_account_command(
+    state: State<'_, AppState>,
+    username: String,
+) -> CommandResult<ActionResponse> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    if username.is_empty() {
+        return Err(CommandError::validation("账号名不能为空"));
+    }
+    ensure_credentials_writable(&state).await?;
+    state.ensure_not_querying(&username)?;
+    let mut guard = state.accounts.write().await;
+    let before_len = guard.len();
+    let mut accounts = guard.clone();
+    accounts.retain(|item| item.username != username);
+    if accounts.len() == before_len {
+        return Ok(ActionResponse {
+            success: false,
+            message: format!("未找到账号: {username}"),
+            accounts: guard.clone(),
+        });
+    }
+    if let Some(removed) = guard.iter().find(|item| item.username == username).cloned() {
+        state
+            .state_store
+            .write()
+            .await
+            .record_removed_account(removed);
+    }
+
+    *guard = accounts.clone();
+    state.mark_credentials_dirty();
+    Ok(ActionResponse {
+        success: true,
+        message: format!("已删除账号: {username}"),
+        accounts,
+    })
+}
+}
+
+/// 已删除账号的摘要，不含密码与 Key
+#[derive(Debug, Clone, Serialize)]
+pub struct RemovedAccount {
+    #[serde(serialize_with = "privacy::serialize_username")]
+    username: String,
+    key_count: usize,
+    removed_at: String,
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn list_removed_accounts_command(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<RemovedAccount>> {
+    Ok(state
+        .state_store
+        .read()
+        .await
+        .removed_accounts()
+        .into_iter()
+        .map(|item| RemovedAccount {
+            key_count: item
+                .account
+                .keys
+                .len()
+                .max(usize::from(!item.account.api_key.trim().is_empty())),
+            username: item.account.username,
+            removed_at: item.removed_at,
+        })
+        .collect())
+}
+}
+
+metered_command! {
+/// 撤销删除: 按最近一次删除时的密码与 Key 恢复账号；已有同名账号时拒绝
+#[tauri::command]
+pub async fn restore_account_command(
+    state: State<'_, AppState>,
+    username: String,
+) -> CommandResult<ActionResponse> {
+    ensure_unlocked(&state).await?;
+    let removed = state.state_store.read().await.removed_accounts();
+    let username = privacy::resolve_among(
+        &username,
+        removed.iter().map(|item| item.account.username.as_str()),
+    );
+    ensure_credentials_writable(&state).await?;
+    let mut guard = state.accounts.write().await;
+    if guard.iter().any(|item| item.username == username) {
+        return Err(CommandError::validation(format!(
+            "账号 {username} 已存在，无需恢复"
+        )));
+    }
+    let Some(account) = state
+        .state_store
+        .write()
+        .await
+        .take_removed_account(&username)
+    else {
+        return Err(CommandError::not_found(format!(
+            "没有 {username} 的删除记录"
+        )));
+    };
+    let mut accounts = guard.clone();
+    accounts.push(account);
+    sort_accounts(&mut accounts);
+    *guard = accounts.clone();
+    state.mark_credentials_dirty();
+    state.state_store.write().await.touch_account(&username);
+    tracing::info!("已恢复删除的账号: {}", username);
+    Ok(ActionResponse {
+        success: true,
+        message: format!("已恢复账号: {username}"),
+        accounts,
+    })
+}
+}
+
+metered_command! {
+/// 通过网页修改账号密码，新密码重新登录验证后写回账号文件并更新已保存的 Cookie
+#[tauri::command]
+pub async fn change_password_command(
+    state: State<'_, AppState>,
+    username: String,
+    new_password: String,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let new_password = new_password.trim().to_string();
+    if new_password.is_empty() || new_password.contains(',') {
+        return Err(CommandError::validation("新密码不能为空，且不能包含逗号"));
+    }
+    ensure_credentials_writable(&state).await?;
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    if account.password == new_password {
+        return Err(CommandError::validation("新密码与当前密码相同"));
+    }
+    let Ok(_query_guard) = state.query_lock.try_lock() else {
+        return Err(CommandError::busy(format!(
+            "{QUERY_BUSY_ERROR}，请稍后再试"
+        )));
+    };
+    let config = state.config.read().await.clone();
+    let outcome = web_native::run_password_change(
+        &account,
+        &new_password,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await
+    .map_err(|e| format!("修改密码失败: {e}"))?;
+
+    // 站点已接受修改，之后无论验证结果如何都以新密码为准，避免丢失
+    let mut guard = state.accounts.write().await;
+    let mut accounts = guard.clone();
+    if let Some(item) = accounts.iter_mut().find(|item| item.username == username) {
+        item.password = new_password;
+    }
+    save_accounts(&state.files.credentials_file, &accounts).map_err(|e| {
+        CommandError::io(format!("密码已修改但写入账号文件失败，请手动更新: {e}"))
+    })?;
+    *guard = accounts;
+    drop(guard);
+    state.invalidate_snapshot("accounts");
+    state.state_store.write().await.touch_account(&username);
+
+    if !outcome.cookies.is_empty()
+        && let Err(e) = cookies::save_account_cookies(&username, &outcome.cookies)
+    {
+        tracing::warn!("账号 {} 保存新 Cookie 失败: {}", username, e);
+    }
+    tracing::info!("账号 {} {}", username, outcome.message);
+    if outcome.verified {
+        Ok(outcome.message)
+    } else {
+        Err(outcome.message.into())
+    }
+}
+}
+
+metered_command! {
+/// 登录账号并保存当前站点 Cookie；指定 path 时另存一份到该文件
+#[tauri::command]
+pub async fn export_account_cookies_command(
+    state: State<'_, AppState>,
+    username: String,
+    path: Option<String>,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    let config = state.config.read().await.clone();
+    let items = web_native::fetch_account_cookies(
+        &account,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await
+    .map_err(|e| format!("读取 Cookie 失败: {e}"))?;
+    let saved = cookies::save_account_cookies(&username, &items)
+        .map_err(|e| CommandError::io(format!("保存 Cookie 失败: {e}")))?;
+    let target = path.map(|item| item.trim().to_string()).unwrap_or_default();
+    if target.is_empty() {
+        return Ok(format!(
+            "已保存 {} 条 Cookie: {}",
+            items.len(),
+            saved.display()
+        ));
+    }
+    cookies::write_cookie_file(Path::new(&target), &items)
+        .map_err(|e| CommandError::io(format!("导出 Cookie 失败: {e}")))?;
+    Ok(format!("已导出 {} 条 Cookie: {}", items.len(), target))
+}
+}
+
+metered_command! {
+/// 从浏览器导出的 Cookie 文件导入，之后网页检查优先使用这些 Cookie 登录
+#[tauri::command]
+pub async fn import_account_cookies_command(
+    state: State<'_, AppState>,
+    username: String,
+    path: String,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    if !state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .any(|item| item.username == username)
+    {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    }
+    let content = std::fs::read_to_string(path.trim())
+        .map_err(|e| CommandError::io(format!("读取 Cookie 文件失败: {e}")))?;
+    let items =
+        cookies::parse_cookie_text(&content).map_err(|e| format!("解析 Cookie 失败: {e}"))?;
+    let saved = cookies::save_account_cookies(&username, &items)
+        .map_err(|e| CommandError::io(format!("保存 Cookie 失败: {e}")))?;
+    tracing::info!("账号 {} 导入 {} 条 Cookie", username, items.len());
+    Ok(format!(
+        "已导入 {} 条 Cookie: {}",
+        items.len(),
+        saved.display()
+    ))
+}
+}
+
+metered_command! {
+/// 手动在浏览器完成签到后，将账号标记为当前周期已完成网页登录
+#[tauri::command]
+pub async fn mark_web_done_command(
+    state: State<'_, AppState>,
+    username: String,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    if !state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .any(|item| item.username == username)
+    {
+        return Err(CommandError::not_found(format!("账号不存在: {username}")));
+    }
+    let mut store = state.state_store.write().await;
+    store
+        .mark_web_query_success(&username)
+        .map_err(|e| CommandError::io(format!("更新每日网页状态失败: {e}")))?;
+    Ok(format!(
+        "已标记 {} 在周期日 {} 完成网页登录",
+        username,
+        store.current_cycle_day()
+    ))
+}
+}
+
+metered_command! {
+/// 清除每日网页登录状态，下次查询重新强制网页登录；username 为空或 all 时清除全部
+#[tauri::command]
+pub async fn reset_web_state_command(
+    state: State<'_, AppState>,
+    username: Option<String>,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let target = username
+        .map(|item| privacy::resolve_username(&item))
+        .filter(|item| !item.is_empty() && !item.eq_ignore_ascii_case("all"));
+    let removed = state
+        .state_store
+        .write()
+        .await
+        .reset_web_state(target.as_deref());
+    Ok(match target {
+        Some(name) if removed == 0 => format!("{name} 没有每日网页登录记录"),
+        Some(name) => format!("已重置 {name} 的每日网页登录状态"),
+        None => format!("已重置全部 {removed} 个账号的每日网页登录状态"),
+    })
+}
+}
+
+metered_command! {
+/// 登录管理员账号，列出站点上的子账号与管理员自己的令牌，并标记本地已有的条目。
+/// admin_username 为空时使用 web_check.discovery_admin
+#[tauri::command]
+pub async fn discover_accounts_command(
+    state: State<'_, AppState>,
+    admin_username: Option<String>,
+) -> CommandResult<web_native::AccountDiscovery> {
+    ensure_unlocked(&state).await?;
+    let config = state.config.read().await.clone();
+    let admin = admin_username
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .unwrap_or_else(|| config.web_check.discovery_admin.trim().to_string());
+    if admin.is_empty() {
+        return Err(CommandError::validation(
+            "未指定管理员账号(web_check.discovery_admin)",
+        ));
+    }
+    let accounts = state.accounts.read().await.clone();
+    let Some(account) = accounts.iter().find(|item| item.username == admin).cloned() else {
+        return Err(CommandError::not_found(format!(
+            "未找到管理员账号: {admin}"
+        )));
+    };
+    let mut discovery = web_native::run_account_discovery(
+        &account,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await
+    .map_err(|e| format!("发现账号失败: {e}"))?;
+
+    for user in &mut discovery.users {
+        user.exists = accounts.iter().any(|item| item.username == user.username);
+    }
+    for token in &mut discovery.tokens {
+        token.exists = account.has_key(&token.key);
+    }
+    tracing::info!(
+        "管理员账号 {} 发现 {} 个子账号、{} 个令牌",
+        admin,
+        discovery.users.len(),
+        discovery.tokens.len()
+    );
+    Ok(discovery)
+}
+}
+
+metered_command! {
+/// 把发现的账号写入账号文件: 新账号需要密码，已有账号只追加尚未记录的 Key
+#[tauri::command]
+pub async fn import_discovered_accounts_command(
+    state: State<'_, AppState>,
+    imports: Vec<AccountImport>,
+) -> CommandResult<ActionResponse> {
+    ensure_unlocked(&state).await?;
+    apply_account_imports(&state, imports).await
+}
+}
+
+metered_command! {
+/// 导出去掉密码的账号列表供队友导入；sanitized 为 true 时连 Key 也不导出。
+/// path 为空时写到配置目录下的 shared-accounts.json
+#[tauri::command]
+pub async fn export_shared_config_command(
+    state: State<'_, AppState>,
+    path: Option<String>,
+    sanitized: Option<bool>,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let sanitized = sanitized.unwrap_or(false);
+    let target = path
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| state.files.config_dir.join("shared-accounts.json"));
+    let accounts = state.accounts.read().await.clone();
+    let count = config::save_shared_config(&target, &accounts, sanitized)
+        .map_err(|e| CommandError::io(format!("导出共享账号列表失败: {e:#}")))?;
+    tracing::info!(
+        "已导出共享账号列表: {} ({} 个账号)",
+        target.display(),
+        count
+    );
+    Ok(format!(
+        "已导出 {} 个账号{}: {}",
+        count,
+        if sanitized {
+            "(仅用户名)"
+        } else {
+            "(不含密码)"
+        },
+        target.display()
+    ))
+}
+}
+
+metered_command! {
+/// 读取共享账号列表，供界面在导入前确认并为新账号补充密码
+#[tauri::command]
+pub fn read_shared_config_command(path: String) -> CommandResult<models::SharedConfigFile> {
+    config::load_shared_config(Path::new(path.trim()))
+        .map_err(|e| CommandError::io(format!("{e:#}")))
+}
+}
+
+metered_command! {
+/// 合并共享账号列表: 已有账号只追加尚未记录的 Key，不改动本地密码与已有 Key；
+/// 新账号需要在 passwords 中提供密码，否则跳过
+#[tauri::command]
+pub async fn import_shared_config_command(
+    state: State<'_, AppState>,
+    path: String,
+    passwords: Option<BTreeMap<String, String>>,
+) -> CommandResult<ActionResponse> {
+    ensure_unlocked(&state).await?;
+    let file =
+        config::load_shared_config(Path::new(path.trim())).map_err(|e| format!("{e:#}"))?;
+    let passwords = passwords.unwrap_or_default();
+    let imports = file
+        .accounts
+        .into_iter()
+        .map(|item| AccountImport {
+            password: passwords.get(&item.username).cloned().unwrap_or_default(),
+            username: item.username,
+            keys: item.keys,
+            alias: item.alias,
+        })
+        .collect();
+    apply_account_imports(&state, imports).await
+}
+}
+
+/// 把导入条目合并进账号文件: 新账号需要密码，已有账号只追加尚未记录的 Key
+async fn apply_account_imports(
+    state: &State<'_, AppState>,
+    imports: Vec<AccountImport>,
+) -> CommandResult<ActionResponse> {
+    ensure_credentials_writable(state).await?;
+    let mut guard = state.accounts.write().await;
+    let mut accounts = guard.clone();
+    let mut created = Vec::new();
+    let mut appended_keys = 0_usize;
+    let mut skipped = Vec::new();
+    for item in imports {
+        let username = item.username.trim().to_string();
+        let keys: Vec<models::NamedKey> = item
+            .keys
+            .into_iter()
+            .filter(|key| {
+                let value = key.key.trim();
+                !value.is_empty() && !value.contains('*') && !value.contains([';', ','])
+            })
+            .map(|key| models::NamedKey {
+                name: key.name.trim().replace(['=', ';', ','], "-"),
+                key: key.key.trim().to_string(),
+            })
+            .collect();
+        if username.is_empty() || username.contains(',') {
+            skipped.push(format!("{username}(用户名无效)"));
+            continue;
+        }
+        if let Some(existing) = accounts.iter_mut().find(|acc| acc.username == username) {
+            for key in keys {
+                if existing.has_key(&key.key) {
+                    continue;
+                }
+                if existing.keys.is_empty() && !existing.api_key.trim().is_empty() {
+                    existing.keys.push(models::NamedKey {
+                        name: "default".to_string(),
+                        key: existing.api_key.clone(),
+                    });
+                }
+                if existing.api_key.trim().is_empty() {
+                    existing.api_key = key.key.clone();
+                }
+                existing.keys.push(key);
+                appended_keys += 1;
+            }
+            continue;
+        }
+        let password = item.password.trim().to_string();
+        if password.is_empty() || password.contains(',') {
+            skipped.push(format!("{username}(缺少密码)"));
+            continue;
+        }
+        accounts.push(Account {
+            username: username.clone(),
+            password,
+            api_key: keys.first().map(|key| key.key.clone()).unwrap_or_default(),
+            keys,
+            alias: normalize_alias(&item.alias),
+        });
+        created.push(username);
+    }
+    if created.is_empty() && appended_keys == 0 {
+        return Ok(ActionResponse {
+            success: false,
+            message: if skipped.is_empty() {
+                "没有需要导入的账号或 Key".to_string()
+            } else {
+                format!("没有导入任何账号，跳过: {}", skipped.join(", "))
+            },
+            accounts,
+        });
+    }
+    sort_accounts(&mut accounts);
+    *guard = accounts.clone();
+    state.mark_credentials_dirty();
+    {
+        let mut store = state.state_store.write().await;
+        for username in &created {
+            store.touch_account(username);
+        }
+    }
+
+    let mut message = format!(
+        "已导入 {} 个新账号，追加 {} 个 Key",
+        created.len(),
+        appended_keys
+    );
+    if !skipped.is_empty() {
+        message.push_str(&format!("，跳过: {}", skipped.join(", ")));
+    }
+    tracing::info!("{}", message);
+    Ok(ActionResponse {
+        success: true,
+        message,
+        accounts,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountOrderResponse {
+    #[serde(serialize_with = "privacy::serialize_usernames")]
+    order: Vec<String>,
+    #[serde(serialize_with = "privacy::serialize_usernames")]
+    pinned: Vec<String>,
+}
+
+metered_command! {
+/// 保存账号的手动顺序与置顶: order 为期望的显示顺序，未列出的账号按用户名排在其后；
+/// pinned 省略时保留当前置顶。结果列表与快照按此顺序返回
+#[tauri::command]
+pub async fn reorder_accounts_command(
+    state: State<'_, AppState>,
+    order: Vec<String>,
+    pinned: Option<Vec<String>>,
+) -> CommandResult<AccountOrderResponse> {
+    ensure_unlocked(&state).await?;
+    let known: BTreeSet<String> = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .map(|item| item.username.clone())
+        .collect();
+    let resolve = |names: Vec<String>| -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        names
+            .iter()
+            .map(|name| privacy::resolve_username(name))
+            .filter(|name| known.contains(name) && seen.insert(name.clone()))
+            .collect()
+    };
+    let order = resolve(order);
+    let mut store = state.state_store.write().await;
+    let pinned: BTreeSet<String> = match pinned {
+        Some(names) => resolve(names).into_iter().collect(),
+        None => store
+            .pinned_accounts()
+            .into_iter()
+            .filter(|name| known.contains(name))
+            .collect(),
+    };
+    store.set_account_order(order, pinned);
+    let mut effective: Vec<String> = known.into_iter().collect();
+    store.sort_by_account_order(&mut effective, String::as_str);
+    let pinned = store.pinned_accounts();
+    drop(store);
+    state.invalidate_snapshot("accounts");
+    tracing::info!("账号顺序已更新，置顶 {} 个", pinned.len());
+    Ok(AccountOrderResponse {
+        order: effective,
+        pinned,
+    })
+}
+}
+
+/// 以账号文件为准替换内存中的账号，丢弃尚未写入的修改
+pub async fn reload_accounts_from_disk(state: &State<'_, AppState>) -> CommandResult<Vec<Account>> {
+    let mut guard = state.accounts.write().await;
+    let mut accounts = load_accounts(&state.files.credentials_file)
+        .map_err(|e| CommandError::io(format!("读取账号文件失败: {e}")))?;
+    sort_accounts(&mut accounts);
+    state.credentials_dirty.store(false, Ordering::Release);
+    *state.accounts_base.write().await = accounts.clone();
+    privacy::set_usernames(&accounts);
+    *guard = accounts.clone();
+    state.invalidate_snapshot("accounts");
+    state.state_store.write().await.touch_all();
+    Ok(accounts)
+}
+
+pub fn sort_accounts(accounts: &mut [Account]) {
+    accounts.sort_by(|a, b| a.username.cmp(&b.username));
+}
+
+/// 别名写在账号文件最后一列，逗号会破坏列格式，替换为空格
+fn normalize_alias(alias: &str) -> String {
+    alias.replace(',', " ").trim().to_string()
+}
@@ -0,0 +1,79 @@
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::metered_command;
+use crate::{AppState, app_lock, privacy};
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppLockStatus {
+    enabled: bool,
+    locked: bool,
+    idle_lock_minutes: u64,
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn get_lock_status_command(state: State<'_, AppState>) -> CommandResult<AppLockStatus> {
+    let lock_config = state.config.read().await.app_lock.clone();
+    Ok(AppLockStatus {
+        enabled: lock_config.active(),
+        locked: !state.app_lock.is_unlocked(&lock_config),
+        idle_lock_minutes: lock_config.idle_lock_minutes,
+    })
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn unlock_command(state: State<'_, AppState>, pin: String) -> CommandResult<String> {
+    let lock_config = state.config.read().await.app_lock.clone();
+    if !lock_config.active() {
+        return Ok("未启用应用锁".to_string());
+    }
+    state.app_lock.check_retry().map_err(CommandError::locked)?;
+    let pin = pin.trim().to_string();
+    let verified =
+        tokio::task::spawn_blocking(move || app_lock::verify_pin(&pin, &lock_config.pin_hash))
+            .await
+            .map_err(|e| format!("校验 PIN 失败: {e}"))?;
+    state
+        .app_lock
+        .finish_unlock(verified)
+        .map_err(CommandError::validation)?;
+    tracing::info!("应用已解锁");
+    Ok("已解锁".to_string())
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub fn lock_command(state: State<'_, AppState>) -> String {
+    state.app_lock.lock();
+    tracing::info!("应用已锁定");
+    "已锁定".to_string()
+}
+}
+
+metered_command! {
+/// 切换隐私模式，返回切换后的状态；privacy.enabled 只决定启动时的初始状态
+#[tauri::command]
+pub fn set_privacy_mode_command(enabled: bool) -> bool {
+    privacy::set_enabled(enabled);
+    tracing::info!("隐私模式已{}", if enabled { "开启" } else { "关闭" });
+    enabled
+}
+}
+
+metered_command! {
+/// 生成写入 app_lock.pin_hash 的 PIN 哈希
+#[tauri::command]
+pub async fn hash_pin_command(pin: String) -> CommandResult<String> {
+    let pin = pin.trim().to_string();
+    if pin.len() < 4 {
+        return Err(CommandError::validation("PIN 至少 4 位"));
+    }
+    tokio::task::spawn_blocking(move || app_lock::hash_pin(&pin))
+        .await
+        .map_err(|e| CommandError::from(format!("生成 PIN 哈希失败: {e}")))
+}
+}
@@ -0,0 +1,189 @@
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::query::{build_schedule, emit_alerts_changed};
+use crate::commands::{ensure_unlocked, metered_command};
+use crate::models::{AlertRecord, HookInfo, MaintenanceReport, StateRepairReport};
+use crate::{AppState, hooks, privacy};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertListResponse {
+    #[serde(serialize_with = "privacy::serialize_alerts")]
+    alerts: Vec<AlertRecord>,
+    active_count: usize,
+}
+
+metered_command! {
+/// 导出本地状态快照: 余额缓存、每日网页状态与连续失败次数
+#[tauri::command]
+pub async fn get_state_dump_command(state: State<'_, AppState>) -> CommandResult<Value> {
+    Ok(privacy::mask_json(&state.state_store.read().await.dump())?)
+}
+}
+
+metered_command! {
+/// 按当前账号列表清理遗留条目并修正格式不规范的日期
+#[tauri::command]
+pub async fn repair_state_command(state: State<'_, AppState>) -> CommandResult<StateRepairReport> {
+    ensure_unlocked(&state).await?;
+    let usernames: BTreeSet<String> = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .map(|item| item.username.clone())
+        .collect();
+    let report = state.state_store.write().await.repair(&usernames);
+    tracing::info!("{}", report.message);
+    Ok(report)
+}
+}
+
+metered_command! {
+/// 立即按保留策略清理本地状态
+#[tauri::command]
+pub async fn run_maintenance_command(
+    state: State<'_, AppState>,
+) -> CommandResult<MaintenanceReport> {
+    let report = state.state_store.write().await.run_maintenance();
+    tracing::info!("{}", report.message);
+    Ok(report)
+}
+}
+
+metered_command! {
+/// 当前周期各账号每日网页登录的完成情况
+#[tauri::command]
+pub async fn get_daily_status_command(state: State<'_, AppState>) -> CommandResult<Value> {
+    let usernames: Vec<String> = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .map(|item| item.username.clone())
+        .collect();
+    Ok(privacy::mask_json(
+        &state.state_store.read().await.daily_status(&usernames),
+    )?)
+}
+}
+
+metered_command! {
+/// 账号检查历史的统计: 成功率、平均耗时、网页与 API 检查的比例及最近 recent 条结果(默认 10)
+#[tauri::command]
+pub async fn get_account_stats_command(
+    state: State<'_, AppState>,
+    username: String,
+    recent: Option<usize>,
+) -> CommandResult<Value> {
+    let username = privacy::resolve_username(&username);
+    if !state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .any(|item| item.username == username)
+    {
+        return Err(CommandError::not_found(format!(
+            "账号不存在: {}",
+            privacy::mask_username(&username)
+        )));
+    }
+    let stats = state
+        .state_store
+        .read()
+        .await
+        .account_stats(&username, recent.unwrap_or(10));
+    Ok(privacy::mask_json(&stats)?)
+}
+}
+
+metered_command! {
+/// 各账号网页提取余额命中的策略汇总，只剩兜底策略命中时说明站点可能已改版
+#[tauri::command]
+pub async fn get_selector_health_command(state: State<'_, AppState>) -> CommandResult<Value> {
+    let usernames: Vec<String> = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .map(|item| item.username.clone())
+        .collect();
+    Ok(privacy::mask_json(
+        &state.state_store.read().await.selector_health(&usernames),
+    )?)
+}
+}
+
+metered_command! {
+/// 按 performance.account_intervals / interval_groups 计算各账号下次自动查询的时间
+#[tauri::command]
+pub async fn get_schedule_command(
+    state: State<'_, AppState>,
+    default_interval: Option<u64>,
+) -> CommandResult<Value> {
+    let config = state.config.read().await.clone();
+    let accounts = state.accounts.read().await.clone();
+    let default_interval = default_interval.unwrap_or(config.performance.query_interval);
+    Ok(privacy::mask_json(
+        &build_schedule(&state, &config, &accounts, default_interval).await,
+    )?)
+}
+}
+
+metered_command! {
+/// 列出告警，默认只返回未确认的
+#[tauri::command]
+pub async fn list_alerts_command(
+    state: State<'_, AppState>,
+    include_acknowledged: Option<bool>,
+) -> CommandResult<AlertListResponse> {
+    let store = state.state_store.read().await;
+    Ok(AlertListResponse {
+        alerts: store.list_alerts(include_acknowledged.unwrap_or(false)),
+        active_count: store.active_alert_count(),
+    })
+}
+}
+
+metered_command! {
+/// 确认告警，id 为空时确认全部未处理告警
+#[tauri::command]
+pub async fn ack_alert_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: Option<String>,
+) -> CommandResult<AlertListResponse> {
+    let id = id
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty());
+    let mut store = state.state_store.write().await;
+    let count = store.acknowledge_alerts(id.as_deref());
+    if id.is_some() && count == 0 {
+        return Err(CommandError::not_found("告警不存在或已确认"));
+    }
+    let active_count = store.active_alert_count();
+    emit_alerts_changed(&app, active_count);
+    Ok(AlertListResponse {
+        alerts: store.list_alerts(false),
+        active_count,
+    })
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn list_hooks_command(state: State<'_, AppState>) -> CommandResult<Vec<HookInfo>> {
+    let usernames: Vec<String> = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .map(|item| item.username.clone())
+        .collect();
+    let config = state.config.read().await.clone();
+    Ok(hooks::list_hooks(&config.web_check, &usernames))
+}
+}
@@ -0,0 +1,101 @@
+pub mod accounts;
+pub mod lock;
+pub mod maintenance;
+pub mod query;
+pub mod snapshot;
+pub mod system;
+pub mod tokens;
+
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::query::QueryStatus;
+use crate::config::RuntimeFiles;
+use crate::models::StartupIssue;
+use crate::{APP_LOCKED_ERROR, AppState, performance_monitor};
+use std::time::Instant;
+use tauri::State;
+
+/// 命令返回值的成败，用于调用统计
+pub trait CommandOutcome {
+    fn failure(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<T> CommandOutcome for CommandResult<T> {
+    fn failure(&self) -> Option<&str> {
+        self.as_ref().err().map(|e| e.message.as_str())
+    }
+}
+
+impl CommandOutcome for String {}
+
+impl CommandOutcome for bool {}
+
+impl CommandOutcome for QueryStatus {}
+
+fn record_command<R: CommandOutcome>(name: &str, started: Instant, outcome: &R) {
+    if let Ok(mut guard) = performance_monitor::get_performance_monitor().lock() {
+        guard.record_command(name, started.elapsed().as_secs_f64(), outcome.failure());
+    }
+}
+
+/// 执行命令并记录调用次数、耗时与失败率，界面频繁调用或经常失败的命令可在性能报告中查看
+pub async fn metered<R: CommandOutcome>(name: &str, task: impl Future<Output = R>) -> R {
+    let started = Instant::now();
+    let outcome = task.await;
+    record_command(name, started, &outcome);
+    outcome
+}
+
+pub fn metered_sync<R: CommandOutcome>(name: &str, task: impl FnOnce() -> R) -> R {
+    let started = Instant::now();
+    let outcome = task();
+    record_command(name, started, &outcome);
+    outcome
+}
+
+/// 包裹命令定义，以函数名为命令名调用 metered/metered_sync，命令体本身不需要改动
+macro_rules! metered_command {
+    ($(#[$meta:meta])* $vis:vis async fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty $body:block) => {
+        $(#[$meta])*
+        $vis async fn $name($($arg: $ty),*) -> $ret {
+            $crate::commands::metered(stringify!($name), async move $body).await
+        }
+    };
+    ($(#[$meta:meta])* $vis:vis fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty $body:block) => {
+        $(#[$meta])*
+        $vis fn $name($($arg: $ty),*) -> $ret {
+            $crate::commands::metered_sync(stringify!($name), || $body)
+        }
+    };
+}
+pub(crate) use metered_command;
+
+/// 查看密钥或修改账号的命令先检查应用锁，放行时刷新空闲计时
+pub async fn ensure_unlocked(state: &State<'_, AppState>) -> CommandResult<()> {
+    let lock_config = state.config.read().await.app_lock.clone();
+    if !state.app_lock.is_unlocked(&lock_config) {
+        return Err(CommandError::locked(APP_LOCKED_ERROR));
+    }
+    state.app_lock.touch();
+    Ok(())
+}
+
+/// 账号文件解析失败且未处理时拒绝写入，避免用空列表覆盖原文件
+pub async fn ensure_credentials_writable(state: &State<'_, AppState>) -> CommandResult<()> {
+    check_credentials_writable(&state.files, &state.startup_issues.lock().await)
+}
+
+/// 同上，供已持有启动问题列表的调用方使用
+pub fn check_credentials_writable(
+    files: &RuntimeFiles,
+    issues: &[StartupIssue],
+) -> CommandResult<()> {
+    let file = files.credentials_file.display().to_string();
+    if issues.iter().any(|issue| issue.file == file) {
+        return Err(CommandError::validation(
+            "账号文件启动时解析失败，请先在启动问题中修复或重置",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,522 @@
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::metered_command;
+use crate::commands::snapshot::build_cached_results;
+use crate::models::{
+    Account, AlertsChangedEvent, AppConfig, BatchSummary, CheckResult, ScheduleReport,
+};
+use crate::state::SharedStateStore;
+use crate::{
+    AppState, QUERY_BUSY_ERROR, currency, models, monitor, mqtt, notifier, privacy, utils,
+    web_native, webhook,
+};
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+use tauri::{Emitter, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResponse {
+    #[serde(serialize_with = "privacy::serialize_results")]
+    results: Vec<CheckResult>,
+    elapsed_secs: f64,
+    finished_at: String,
+    success_count: usize,
+    fail_count: usize,
+    total_balance: f64,
+    total_balance_count: usize,
+    /// 总余额的货币代码
+    total_currency: String,
+    /// 各货币折合统一货币的汇率，前端重新汇总时使用
+    currency_rates: BTreeMap<String, f64>,
+    deferred: Vec<String>,
+    /// 本轮检查按来源的拆分统计
+    summary: BatchSummary,
+}
+
+/// 查询占用状态，界面据此禁用冲突的操作
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct QueryStatus {
+    running: bool,
+    /// 批量查询的类型；其他占用查询锁的操作(如交互登录、冒烟测试)为空
+    kind: String,
+    started_at: String,
+    elapsed_secs: u64,
+    #[serde(serialize_with = "privacy::serialize_usernames")]
+    accounts: Vec<String>,
+}
+
+metered_command! {
+#[tauri::command]
+pub fn is_query_running_command(state: State<'_, AppState>) -> QueryStatus {
+    if state.query_lock.try_lock().is_ok() {
+        return QueryStatus::default();
+    }
+    let run = state.query_run.lock().ok().and_then(|guard| guard.clone());
+    match run {
+        Some(run) => QueryStatus {
+            running: true,
+            kind: run.kind.to_string(),
+            started_at: run.started_at,
+            elapsed_secs: run.started.elapsed().as_secs(),
+            accounts: run.usernames.into_iter().collect(),
+        },
+        None => QueryStatus {
+            running: true,
+            ..Default::default()
+        },
+    }
+}
+}
+
+metered_command! {
+/// due_interval 为前端当前的默认间隔时只查询按各自间隔已到期的账号(自动轮询)
+#[tauri::command]
+pub async fn query_balances_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    target_username: Option<String>,
+    due_interval: Option<u64>,
+) -> CommandResult<QueryResponse> {
+    let _query_guard = state.query_lock.lock().await;
+
+    let mut accounts = state.accounts.read().await.clone();
+    let config = state.config.read().await.clone();
+    let target_username = target_username.map(|item| privacy::resolve_username(&item));
+    if let Some(default_interval) = due_interval
+        && target_username.is_none()
+    {
+        let schedule = build_schedule(&state, &config, &accounts, default_interval).await;
+        let due: BTreeSet<String> = schedule
+            .accounts
+            .into_iter()
+            .filter(|item| item.due_in_secs == 0)
+            .map(|item| item.username)
+            .collect();
+        accounts.retain(|item| due.contains(&item.username));
+        if accounts.is_empty() {
+            return Ok(QueryResponse {
+                results: Vec::new(),
+                elapsed_secs: 0.0,
+                finished_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                success_count: 0,
+                fail_count: 0,
+                total_balance: 0.0,
+                total_balance_count: 0,
+                total_currency: currency::canonical(),
+                currency_rates: currency::rates(),
+                deferred: Vec::new(),
+                summary: BatchSummary::default(),
+            });
+        }
+    }
+    let _query_run = state.begin_query_run("query", &accounts, target_username.as_deref());
+    let notify_config = config.notify.clone();
+    let before = snapshot_cached_balances(&state.state_store, &accounts).await;
+    let started = Instant::now();
+    let results = monitor::check_accounts(
+        accounts,
+        config,
+        state.state_store.clone(),
+        target_username,
+        app.clone(),
+    )
+    .await;
+    notify_round(&app, &state.state_store, &notify_config, &results, &before).await;
+
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    let success_count = results.iter().filter(|item| item.success).count();
+    let fail_count = results.len().saturating_sub(success_count);
+    let (total_balance, total_balance_count) = calculate_total_balance(&results);
+    let deferred = results
+        .iter()
+        .filter(|item| item.deferred)
+        .map(|item| item.username.clone())
+        .collect();
+    let summary = monitor::summarize_batch(&results, elapsed_secs);
+    Ok(QueryResponse {
+        results,
+        elapsed_secs,
+        finished_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        success_count,
+        fail_count,
+        total_balance,
+        total_balance_count,
+        total_currency: currency::canonical(),
+        currency_rates: currency::rates(),
+        deferred,
+        summary,
+    })
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn web_login_only_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    target_username: Option<String>,
+) -> CommandResult<QueryResponse> {
+    let _query_guard = state.query_lock.lock().await;
+
+    let accounts = state.accounts.read().await.clone();
+    let config = state.config.read().await.clone();
+    let target_username = target_username.map(|item| privacy::resolve_username(&item));
+    let _query_run = state.begin_query_run("web_login", &accounts, target_username.as_deref());
+    let notify_config = config.notify.clone();
+    let before = snapshot_cached_balances(&state.state_store, &accounts).await;
+    let started = Instant::now();
+    let results = monitor::check_accounts_web_only(
+        accounts,
+        config,
+        state.state_store.clone(),
+        target_username,
+        app.clone(),
+    )
+    .await;
+    notify_round(&app, &state.state_store, &notify_config, &results, &before).await;
+
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    let success_count = results.iter().filter(|item| item.success).count();
+    let fail_count = results.len().saturating_sub(success_count);
+    let (total_balance, total_balance_count) = calculate_total_balance(&results);
+    let deferred = results
+        .iter()
+        .filter(|item| item.deferred)
+        .map(|item| item.username.clone())
+        .collect();
+    let summary = monitor::summarize_batch(&results, elapsed_secs);
+    Ok(QueryResponse {
+        results,
+        elapsed_secs,
+        finished_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        success_count,
+        fail_count,
+        total_balance,
+        total_balance_count,
+        total_currency: currency::canonical(),
+        currency_rates: currency::rates(),
+        deferred,
+        summary,
+    })
+}
+}
+
+metered_command! {
+/// 只重新检查最近一轮失败的账号，结果合并进上一轮结果集返回
+#[tauri::command]
+pub async fn retry_failed_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> CommandResult<QueryResponse> {
+    let _query_guard = state.query_lock.lock().await;
+
+    let all_accounts = state.accounts.read().await.clone();
+    let failed = state.state_store.read().await.last_round_failures();
+    let accounts: Vec<Account> = all_accounts
+        .iter()
+        .filter(|item| failed.contains(&item.username))
+        .cloned()
+        .collect();
+    if accounts.is_empty() {
+        return Err(CommandError::not_found("最近一轮没有失败的账号"));
+    }
+    tracing::info!("重试上一轮失败的 {} 个账号", accounts.len());
+    let _query_run = state.begin_query_run("retry_failed", &accounts, None);
+
+    let config = state.config.read().await.clone();
+    let notify_config = config.notify.clone();
+    let before = snapshot_cached_balances(&state.state_store, &accounts).await;
+    let started = Instant::now();
+    let retried = monitor::check_accounts(
+        accounts,
+        config,
+        state.state_store.clone(),
+        None,
+        app.clone(),
+    )
+    .await;
+    notify_round(&app, &state.state_store, &notify_config, &retried, &before).await;
+
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    let success_count = retried.iter().filter(|item| item.success).count();
+    let fail_count = retried.len().saturating_sub(success_count);
+    let deferred = retried
+        .iter()
+        .filter(|item| item.deferred)
+        .map(|item| item.username.clone())
+        .collect();
+    let results = merge_round_results(&all_accounts, state.state_store.clone()).await;
+    let (total_balance, total_balance_count) = calculate_total_balance(&results);
+    let summary = monitor::summarize_batch(&retried, elapsed_secs);
+    Ok(QueryResponse {
+        results,
+        elapsed_secs,
+        finished_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        success_count,
+        fail_count,
+        total_balance,
+        total_balance_count,
+        total_currency: currency::canonical(),
+        currency_rates: currency::rates(),
+        deferred,
+        summary,
+    })
+}
+}
+
+metered_command! {
+/// 打开可见浏览器由用户手动登录，完成后自动提取余额与同步额度
+#[tauri::command]
+pub async fn interactive_login_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    username: String,
+    wait_secs: Option<u64>,
+) -> CommandResult<CheckResult> {
+    let username = privacy::resolve_username(&username);
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    let Ok(_query_guard) = state.query_lock.try_lock() else {
+        return Err(CommandError::busy(format!(
+            "{QUERY_BUSY_ERROR}，请稍后再试"
+        )));
+    };
+    let config = state.config.read().await.clone();
+    let result = monitor::check_account_interactive(
+        account,
+        config,
+        state.state_store.clone(),
+        wait_secs.unwrap_or(300).max(30),
+        &app,
+    )
+    .await;
+    Ok(privacy::mask_result(&result))
+}
+}
+
+metered_command! {
+/// 重新同步 API Key 额度；未指定账号时处理所有上次同步失败的账号。
+/// key 指定要同步的 Key 名称，覆盖 web_check.quota_sync_key
+#[tauri::command]
+pub async fn resync_quota_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    username: Option<String>,
+    key: Option<String>,
+) -> CommandResult<Vec<CheckResult>> {
+    let target = username
+        .map(|item| privacy::resolve_username(&item))
+        .filter(|item| !item.is_empty());
+    let all_accounts = state.accounts.read().await.clone();
+    let accounts: Vec<Account> = match &target {
+        Some(name) => all_accounts
+            .into_iter()
+            .filter(|item| &item.username == name)
+            .collect(),
+        None => {
+            let store = state.state_store.read().await;
+            all_accounts
+                .into_iter()
+                .filter(|item| {
+                    store
+                        .get_cached_balance_record(&item.username)
+                        .is_some_and(|record| record.apikey_sync_success == Some(false))
+                })
+                .collect()
+        }
+    };
+    if accounts.is_empty() {
+        return Err(CommandError::not_found(match target {
+            Some(name) => format!("未找到账号: {name}"),
+            None => "没有额度同步失败的账号".to_string(),
+        }));
+    }
+    let Ok(_query_guard) = state.query_lock.try_lock() else {
+        return Err(CommandError::busy(format!(
+            "{QUERY_BUSY_ERROR}，请稍后再试"
+        )));
+    };
+    let mut config = state.config.read().await.clone();
+    if let Some(key) = key.map(|item| item.trim().to_string())
+        && !key.is_empty()
+    {
+        config.web_check.quota_sync_key = key;
+    }
+    let mut results = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        let result = monitor::resync_account_quota(
+            account,
+            config.clone(),
+            state.state_store.clone(),
+            &app,
+        )
+        .await;
+        results.push(result);
+    }
+    Ok(privacy::mask_results(&results))
+}
+}
+
+metered_command! {
+/// 额度同步预演: 登录并计算将写入的额度与目标令牌，不提交修改，用于核对比例识别是否正确
+#[tauri::command]
+pub async fn preview_quota_sync_command(
+    state: State<'_, AppState>,
+    username: String,
+    key: Option<String>,
+) -> CommandResult<web_native::QuotaSyncPreview> {
+    let username = privacy::resolve_username(&username);
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    let Ok(_query_guard) = state.query_lock.try_lock() else {
+        return Err(CommandError::busy(format!(
+            "{QUERY_BUSY_ERROR}，请稍后再试"
+        )));
+    };
+    let mut config = state.config.read().await.clone();
+    if let Some(key) = key.map(|item| item.trim().to_string())
+        && !key.is_empty()
+    {
+        config.web_check.quota_sync_key = key;
+    }
+    web_native::run_quota_preview(
+        &account,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await
+    .map_err(|e| CommandError::from(format!("额度同步预演失败: {e}")))
+}
+}
+
+pub async fn build_schedule(
+    state: &AppState,
+    config: &AppConfig,
+    accounts: &[Account],
+    default_interval: u64,
+) -> ScheduleReport {
+    let default_interval = default_interval.max(1);
+    let intervals: Vec<(String, u64)> = accounts
+        .iter()
+        .map(|item| {
+            let interval = config
+                .performance
+                .account_interval(&item.username, default_interval);
+            (item.username.clone(), interval)
+        })
+        .collect();
+    state
+        .state_store
+        .read()
+        .await
+        .schedule(&intervals, default_interval)
+}
+
+/// 每个账号取最近一次检查结果，本次会话未检查过的账号回退到缓存结果
+pub async fn merge_round_results(
+    accounts: &[Account],
+    state_store: SharedStateStore,
+) -> Vec<CheckResult> {
+    let cached = build_cached_results(accounts, state_store.clone()).await;
+    let guard = state_store.read().await;
+    // 别名以当前账号列表为准，修改后无需等下一轮查询
+    cached
+        .into_iter()
+        .map(|item| match guard.last_round_result(&item.username) {
+            Some(row) => CheckResult {
+                alias: item.alias,
+                ..row
+            },
+            None => item,
+        })
+        .collect()
+}
+
+/// 记录本轮开始前各账号的缓存余额，供通知判断余额是否下降
+pub async fn snapshot_cached_balances(
+    state_store: &SharedStateStore,
+    accounts: &[Account],
+) -> BTreeMap<String, String> {
+    let store = state_store.read().await;
+    accounts
+        .iter()
+        .filter_map(|account| {
+            store
+                .get_cached_balance_text(&account.username)
+                .map(|text| (account.username.clone(), text))
+        })
+        .collect()
+}
+
+/// 记录本轮触发的告警并按通知策略推送
+pub async fn notify_round(
+    app: &tauri::AppHandle,
+    state_store: &SharedStateStore,
+    config: &models::NotifyConfig,
+    results: &[CheckResult],
+    before: &BTreeMap<String, String>,
+) {
+    let alerts = notifier::collect_alerts(results, before, config);
+    if !alerts.is_empty() {
+        let mut store = state_store.write().await;
+        store.record_alerts(alerts.clone());
+        emit_alerts_changed(app, store.active_alert_count());
+    }
+    if let Some(event) = notifier::evaluate_round(results, &alerts, config) {
+        notifier::deliver(app, &event, config);
+    }
+    webhook::spawn_result_webhooks(&config.webhook, results, &alerts);
+    mqtt::publish_round(results);
+}
+
+pub fn emit_alerts_changed(app: &tauri::AppHandle, active_count: usize) {
+    if let Err(e) = app.emit("alerts-changed", &AlertsChangedEvent { active_count }) {
+        tracing::warn!("发送告警变化事件失败: {}", e);
+    }
+}
+
+/// 按 currency.rates 把各账号余额换算为统一货币后汇总，没有汇率的货币不计入
+fn calculate_total_balance(results: &[CheckResult]) -> (f64, usize) {
+    let mut total = 0.0_f64;
+    let mut count = 0_usize;
+    for row in results {
+        if !row.success {
+            continue;
+        }
+        let Some(value) = utils::parse_first_number(&row.balance_text) else {
+            continue;
+        };
+        let code = currency::result_currency(row);
+        match currency::to_canonical(value, &code) {
+            Some(value) => {
+                total += value;
+                count += 1;
+            }
+            None => tracing::warn!(
+                "账号 {} 余额货币 {} 未配置汇率(currency.rates)，未计入总余额",
+                row.username,
+                code
+            ),
+        }
+    }
+    (total, count)
+}
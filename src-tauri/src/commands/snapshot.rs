@@ -0,0 +1,480 @@
+use crate::command_error::CommandResult;
+use crate::commands::metered_command;
+use crate::commands::tokens::key_hint;
+use crate::config::RuntimeFiles;
+use crate::models::{Account, AppConfig, CheckResult, ResultQuery};
+use crate::state::SharedStateStore;
+use crate::{AppState, privacy, utils};
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSnapshot {
+    /// 快照版本，账号、配置或状态变化后递增
+    version: u64,
+    config_dir: String,
+    profile: String,
+    query_interval: u64,
+    /// 是否为部分账号单独设置了查询间隔，此时自动轮询只查询到期的账号
+    scheduled_intervals: bool,
+    daily_rollover_hour: u32,
+    fallback_to_web: bool,
+    prewarm_before_round_secs: u64,
+    #[serde(serialize_with = "privacy::serialize_accounts")]
+    accounts: Vec<Account>,
+    #[serde(serialize_with = "privacy::serialize_results")]
+    cached_results: Vec<CheckResult>,
+    /// 按查询条件筛选后的结果总数，分页时用于计算页数
+    result_total: usize,
+    active_alerts: usize,
+    /// 应用锁处于锁定状态，此时 accounts 中的密码与 Key 已打码
+    locked: bool,
+    /// 隐私模式已开启，此时用户名已打码
+    privacy: bool,
+    /// 置顶的账号
+    #[serde(serialize_with = "privacy::serialize_usernames")]
+    pinned: Vec<String>,
+}
+
+/// 自 since_cursor 之后有变化的缓存结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangesResponse {
+    /// 下次请求时传回的游标
+    cursor: u64,
+    /// 游标失效(如应用重启)时为 true，results 为全部账号
+    full: bool,
+    /// 当前账号列表，调用方据此移除已删除的账号
+    #[serde(serialize_with = "privacy::serialize_usernames")]
+    usernames: Vec<String>,
+    #[serde(serialize_with = "privacy::serialize_results")]
+    results: Vec<CheckResult>,
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn get_snapshot_command(
+    state: State<'_, AppState>,
+    query: Option<ResultQuery>,
+) -> CommandResult<AppSnapshot> {
+    let version = state.snapshot_version().await;
+    let cached = state
+        .snapshot_cache
+        .lock()
+        .await
+        .as_ref()
+        .filter(|item| item.version == version)
+        .cloned();
+    if let Some(snapshot) = cached {
+        return Ok(apply_snapshot_query(
+            redact_snapshot(&state, snapshot).await,
+            query.as_ref(),
+        ));
+    }
+
+    let config = state.config.read().await.clone();
+    let accounts = state.accounts.read().await.clone();
+    let snapshot = assemble_snapshot(
+        version,
+        &state.files,
+        &state.profile,
+        &config,
+        accounts,
+        state.state_store.clone(),
+    )
+    .await;
+    *state.snapshot_cache.lock().await = Some(snapshot.clone());
+    Ok(apply_snapshot_query(
+        redact_snapshot(&state, snapshot).await,
+        query.as_ref(),
+    ))
+}
+}
+
+/// 由配置、账号与本地状态组装未筛选、未打码的快照，账号与结果均按置顶与手动顺序排列
+async fn assemble_snapshot(
+    version: u64,
+    files: &RuntimeFiles,
+    profile: &str,
+    config: &AppConfig,
+    mut accounts: Vec<Account>,
+    state_store: SharedStateStore,
+) -> AppSnapshot {
+    let cached_results = build_cached_results(&accounts, state_store.clone()).await;
+    let (active_alerts, pinned) = {
+        let store = state_store.read().await;
+        store.sort_by_account_order(&mut accounts, |item| item.username.as_str());
+        (store.active_alert_count(), store.pinned_accounts())
+    };
+    AppSnapshot {
+        version,
+        config_dir: files.config_dir.to_string_lossy().to_string(),
+        profile: profile.to_string(),
+        query_interval: config.performance.query_interval.max(1),
+        scheduled_intervals: config.performance.has_account_intervals(),
+        daily_rollover_hour: config.performance.daily_rollover_hour,
+        fallback_to_web: config.api.fallback_to_web,
+        prewarm_before_round_secs: config.web_check.prewarm_before_round_secs,
+        accounts,
+        result_total: cached_results.len(),
+        cached_results,
+        active_alerts,
+        locked: false,
+        privacy: false,
+        pinned,
+    }
+}
+
+/// 应用锁锁定时快照中不返回密码与完整 Key；隐私模式的用户名打码在序列化时进行
+async fn redact_snapshot(state: &State<'_, AppState>, mut snapshot: AppSnapshot) -> AppSnapshot {
+    snapshot.privacy = privacy::enabled();
+    let lock_config = state.config.read().await.app_lock.clone();
+    if !state.app_lock.is_unlocked(&lock_config) {
+        redact_credentials(&mut snapshot);
+    }
+    snapshot
+}
+
+fn redact_credentials(snapshot: &mut AppSnapshot) {
+    snapshot.locked = true;
+    for account in &mut snapshot.accounts {
+        account.password.clear();
+        account.api_key = key_hint(&account.api_key);
+        for item in &mut account.keys {
+            item.key = key_hint(&item.key);
+        }
+    }
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn get_cached_results_command(
+    state: State<'_, AppState>,
+    query: Option<ResultQuery>,
+) -> CommandResult<Vec<CheckResult>> {
+    let accounts = state.accounts.read().await.clone();
+    let results = build_cached_results(&accounts, state.state_store.clone()).await;
+    let results = match query {
+        Some(query) => query_results(results, &query).1,
+        None => results,
+    };
+    Ok(privacy::mask_results(&results))
+}
+}
+
+metered_command! {
+/// 只返回游标之后有变化的账号结果，未传游标时返回全部
+#[tauri::command]
+pub async fn get_changes_command(
+    state: State<'_, AppState>,
+    since_cursor: Option<u64>,
+) -> CommandResult<ChangesResponse> {
+    let accounts = state.accounts.read().await.clone();
+    let (cursor, changed) = {
+        let store = state.state_store.read().await;
+        (
+            store.change_cursor(),
+            since_cursor.and_then(|since| store.changed_since(since)),
+        )
+    };
+    let full = changed.is_none();
+    let selected: Vec<Account> = match &changed {
+        Some(names) => accounts
+            .iter()
+            .filter(|item| names.contains(&item.username))
+            .cloned()
+            .collect(),
+        None => accounts.clone(),
+    };
+    let results = build_cached_results(&selected, state.state_store.clone()).await;
+    Ok(ChangesResponse {
+        cursor,
+        full,
+        usernames: accounts.into_iter().map(|item| item.username).collect(),
+        results,
+    })
+}
+}
+
+fn apply_snapshot_query(mut snapshot: AppSnapshot, query: Option<&ResultQuery>) -> AppSnapshot {
+    if let Some(query) = query {
+        let (total, results) = query_results(std::mem::take(&mut snapshot.cached_results), query);
+        snapshot.result_total = total;
+        snapshot.cached_results = results;
+    }
+    snapshot
+}
+
+/// 与前端视图相同的状态分类: banned / cache / idle / ok / fail
+fn result_status_key(item: &CheckResult) -> &'static str {
+    if item.banned || item.health == "banned" {
+        return "banned";
+    }
+    match item.source.as_str() {
+        "cache" => "cache",
+        "-" => "idle",
+        _ if item.success => "ok",
+        _ => "fail",
+    }
+}
+
+/// 按搜索词、状态筛选并排序，返回(筛选后总数, 当前页)
+fn query_results(results: Vec<CheckResult>, query: &ResultQuery) -> (usize, Vec<CheckResult>) {
+    let search = query.search.trim().to_lowercase();
+    let status = query.status.trim();
+    let mut rows: Vec<CheckResult> = results
+        .into_iter()
+        .filter(|item| {
+            search.is_empty()
+                || [
+                    &item.username,
+                    &item.alias,
+                    &item.message,
+                    &item.source,
+                    &item.balance_text,
+                ]
+                .iter()
+                .any(|field| field.to_lowercase().contains(&search))
+        })
+        .filter(|item| status.is_empty() || status == "all" || result_status_key(item) == status)
+        .collect();
+
+    match query.sort.trim() {
+        sort @ ("balance_desc" | "balance_asc") => {
+            let descending = sort == "balance_desc";
+            // 无法解析余额的行始终排在最后
+            let key = |item: &CheckResult| {
+                item.success
+                    .then(|| utils::parse_first_number(&item.balance_text))
+                    .flatten()
+            };
+            rows.sort_by(|a, b| {
+                let ordering = match (key(a), key(b)) {
+                    (Some(x), Some(y)) if descending => y.total_cmp(&x),
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                };
+                ordering.then_with(|| a.username.cmp(&b.username))
+            });
+        }
+        "username_asc" => rows.sort_by(|a, b| a.username.cmp(&b.username)),
+        _ => {}
+    }
+
+    let total = rows.len();
+    let limit = match query.limit {
+        0 => usize::MAX,
+        limit => limit,
+    };
+    let page = rows.into_iter().skip(query.offset).take(limit).collect();
+    (total, page)
+}
+
+pub async fn build_cached_results(
+    accounts: &[Account],
+    state_store: SharedStateStore,
+) -> Vec<CheckResult> {
+    let guard = state_store.read().await;
+    let mut results = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        if let Some(record) = guard.get_cached_balance_record(&account.username) {
+            results.push(CheckResult {
+                username: account.username.clone(),
+                alias: account.alias.trim().to_string(),
+                success: true,
+                balance_text: record.balance.clone(),
+                source: "cache".to_string(),
+                message: if record.updated_at.trim().is_empty() {
+                    "缓存余额".to_string()
+                } else {
+                    format!("缓存更新时间: {}", record.updated_at)
+                },
+                ..Default::default()
+            });
+        } else {
+            results.push(CheckResult {
+                username: account.username.clone(),
+                alias: account.alias.trim().to_string(),
+                success: false,
+                balance_text: "等待".to_string(),
+                source: "-".to_string(),
+                message: "待机".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+    for item in &mut results {
+        guard.apply_health(item);
+    }
+    guard.sort_by_account_order(&mut results, |item| item.username.as_str());
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NamedKey, RetentionConfig};
+    use crate::state::StateStore;
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("art_rs_snapshot_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn account(username: &str, key: &str) -> Account {
+        Account {
+            username: username.to_string(),
+            password: format!("{username}-pass"),
+            api_key: key.to_string(),
+            keys: vec![NamedKey {
+                name: "default".to_string(),
+                key: key.to_string(),
+            }],
+            alias: String::new(),
+        }
+    }
+
+    fn result(username: &str, success: bool, balance: &str, source: &str) -> CheckResult {
+        CheckResult {
+            username: username.to_string(),
+            success,
+            balance_text: balance.to_string(),
+            source: source.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn usernames<T>(items: &[T], name: impl Fn(&T) -> &str) -> Vec<&str> {
+        items.iter().map(name).collect()
+    }
+
+    /// 三个账号: alice 有缓存余额，bob 置顶，carol 尚未查询
+    async fn sample_snapshot(name: &str) -> (PathBuf, AppSnapshot) {
+        let dir = scratch_dir(name);
+        let files = RuntimeFiles::new(dir.clone());
+        let mut store = StateStore::load(&files, 8, "", RetentionConfig::default()).unwrap();
+        store
+            .update_balance_cache("alice", "$12.50", None, None)
+            .unwrap();
+        store.set_account_order(Vec::new(), BTreeSet::from(["bob".to_string()]));
+        let mut config = AppConfig::default();
+        config.performance.query_interval = 0;
+        config.api.fallback_to_web = true;
+        let accounts = vec![
+            account("alice", "sk-alice-0123456789"),
+            account("bob", "sk-bob-0123456789"),
+            account("carol", "sk-carol-0123456789"),
+        ];
+        let snapshot = assemble_snapshot(
+            7,
+            &files,
+            "work",
+            &config,
+            accounts,
+            Arc::new(RwLock::new(store)),
+        )
+        .await;
+        (dir, snapshot)
+    }
+
+    #[tokio::test]
+    async fn assemble_orders_accounts_and_results() {
+        let (dir, snapshot) = sample_snapshot("assemble").await;
+        assert_eq!(snapshot.version, 7);
+        assert_eq!(snapshot.profile, "work");
+        assert_eq!(snapshot.config_dir, dir.to_string_lossy());
+        assert_eq!(snapshot.query_interval, 1);
+        assert!(snapshot.fallback_to_web);
+        assert_eq!(snapshot.pinned, vec!["bob".to_string()]);
+        assert_eq!(
+            usernames(&snapshot.accounts, |item| &item.username),
+            ["bob", "alice", "carol"]
+        );
+        assert_eq!(
+            usernames(&snapshot.cached_results, |item| &item.username),
+            ["bob", "alice", "carol"]
+        );
+        assert_eq!(snapshot.result_total, 3);
+
+        let alice = &snapshot.cached_results[1];
+        assert!(alice.success);
+        assert_eq!(alice.source, "cache");
+        assert_eq!(alice.balance_text, "$12.50");
+        let carol = &snapshot.cached_results[2];
+        assert!(!carol.success);
+        assert_eq!(result_status_key(carol), "idle");
+        assert!(!snapshot.locked);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn snapshot_query_filters_and_pages() {
+        let (dir, snapshot) = sample_snapshot("query").await;
+        let query = ResultQuery {
+            status: "idle".to_string(),
+            limit: 1,
+            ..Default::default()
+        };
+        let filtered = apply_snapshot_query(snapshot.clone(), Some(&query));
+        assert_eq!(filtered.result_total, 2);
+        assert_eq!(
+            usernames(&filtered.cached_results, |item| &item.username),
+            ["bob"]
+        );
+
+        let unchanged = apply_snapshot_query(snapshot, None);
+        assert_eq!(unchanged.result_total, 3);
+        assert_eq!(unchanged.cached_results.len(), 3);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn locked_snapshot_hides_credentials() {
+        let (dir, mut snapshot) = sample_snapshot("redact").await;
+        redact_credentials(&mut snapshot);
+        assert!(snapshot.locked);
+        for account in &snapshot.accounts {
+            assert!(account.password.is_empty());
+            assert!(account.api_key.contains("..."));
+            assert!(account.keys.iter().all(|item| item.key.contains("...")));
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn query_results_sorts_by_balance_with_unknown_last() {
+        let rows = vec![
+            result("a", true, "$5", "api"),
+            result("b", false, "登录失败", "web"),
+            result("c", true, "$20", "api"),
+            result("d", true, "余额未知", "web"),
+        ];
+        let query = ResultQuery {
+            sort: "balance_desc".to_string(),
+            ..Default::default()
+        };
+        let (total, page) = query_results(rows.clone(), &query);
+        assert_eq!(total, 4);
+        assert_eq!(
+            usernames(&page, |item| &item.username),
+            ["c", "a", "b", "d"]
+        );
+
+        let query = ResultQuery {
+            search: "API".to_string(),
+            sort: "balance_asc".to_string(),
+            ..Default::default()
+        };
+        let (total, page) = query_results(rows, &query);
+        assert_eq!(total, 2);
+        assert_eq!(usernames(&page, |item| &item.username), ["a", "c"]);
+    }
+}
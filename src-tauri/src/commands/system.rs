@@ -0,0 +1,583 @@
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::accounts::reload_accounts_from_disk;
+use crate::commands::{check_credentials_writable, ensure_unlocked, metered_command};
+use crate::config::{RuntimeFiles, load_app_config, save_accounts};
+use crate::models::{PreflightCheck, PreflightReport, StartupIssue};
+use crate::{
+    AppState, QUERY_BUSY_ERROR, browser_pool, config, currency, driver_manager,
+    performance_monitor, privacy, web_native,
+};
+use chrono::Local;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tauri::State;
+
+metered_command! {
+#[tauri::command]
+pub fn performance_report_command() -> String {
+    let monitor = performance_monitor::get_performance_monitor();
+    match monitor.lock() {
+        Ok(guard) => privacy::mask_text(&guard.generate_report()),
+        Err(_) => "性能监控状态不可用".to_string(),
+    }
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub fn browser_pool_report_command() -> String {
+    let current = browser_pool::global_pool_stats();
+    let monitor = performance_monitor::get_performance_monitor();
+    match monitor.lock() {
+        Ok(guard) => guard.generate_pool_report(current.as_ref()),
+        Err(_) => "性能监控状态不可用".to_string(),
+    }
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub fn set_driver_verbose_command(enabled: bool) -> String {
+    let recycled = browser_pool::set_driver_verbose(enabled);
+    tracing::info!(
+        "chromedriver 详细日志: {}, 回收空闲实例={}",
+        if enabled { "开启" } else { "关闭" },
+        recycled
+    );
+    if browser_pool::driver_verbose_enabled() {
+        format!("已开启 chromedriver 详细日志，回收空闲实例 {recycled} 个，输出写入 logs 目录")
+    } else {
+        format!("已关闭 chromedriver 详细日志，回收空闲实例 {recycled} 个")
+    }
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub fn get_system_metrics_command(
+    limit: Option<usize>,
+) -> CommandResult<performance_monitor::SystemMetricsReport> {
+    performance_monitor::system_metrics_report(limit.unwrap_or(120))
+        .ok_or_else(|| CommandError::not_found("系统资源采样尚未启动"))
+}
+}
+
+metered_command! {
+/// 取消正在进行的驱动下载；正在等待该驱动的查询会以下载已取消失败
+#[tauri::command]
+pub async fn cancel_driver_download_command() -> CommandResult<String> {
+    if driver_manager::cancel_download() {
+        tracing::info!("已请求取消驱动下载");
+        Ok("已请求取消驱动下载".to_string())
+    } else {
+        Ok("当前没有正在下载的驱动".to_string())
+    }
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn prewarm_browser_pool_command(state: State<'_, AppState>) -> CommandResult<String> {
+    if state.query_lock.try_lock().is_err() {
+        return Ok("查询进行中，跳过浏览器池预热".to_string());
+    }
+    let config = state.config.read().await.clone();
+    web_native::prewarm_browser_pool(
+        &config.web_check,
+        &config.browser,
+        config.web_check.prewarm_navigate,
+    )
+    .await
+    .map_err(|e| CommandError::from(format!("浏览器池预热失败: {e}")))
+}
+}
+
+metered_command! {
+/// 安装自检: 启动驱动与浏览器、打开登录页、用内置页面验证余额提取脚本，返回各阶段结果
+#[tauri::command]
+pub async fn smoke_test_command(
+    state: State<'_, AppState>,
+) -> CommandResult<web_native::SmokeTestReport> {
+    let Ok(_query_guard) = state.query_lock.try_lock() else {
+        return Err(CommandError::busy(format!(
+            "{QUERY_BUSY_ERROR}，请稍后再试"
+        )));
+    };
+    let config = state.config.read().await.clone();
+    let report = web_native::run_smoke_test(&config.web_check, &config.browser).await;
+    tracing::info!(
+        "冒烟测试{}: {}",
+        if report.success {
+            "通过"
+        } else {
+            "未通过"
+        },
+        report
+            .stages
+            .iter()
+            .map(|item| format!("{}={}", item.name, item.status))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(report)
+}
+}
+
+fn preflight_check(
+    id: &str,
+    label: &str,
+    status: &str,
+    message: String,
+    fix_action: &str,
+    fix_target: &str,
+) -> PreflightCheck {
+    PreflightCheck {
+        id: id.to_string(),
+        label: label.to_string(),
+        status: status.to_string(),
+        message,
+        fix_action: fix_action.to_string(),
+        fix_target: fix_target.to_string(),
+    }
+}
+
+metered_command! {
+/// 启动检查: 账号、配置、浏览器、驱动、网络与最近一次查询，每项附界面可执行的修复操作。
+/// 只检查本地已有的驱动，不会触发下载
+#[tauri::command]
+pub async fn preflight_command(state: State<'_, AppState>) -> CommandResult<PreflightReport> {
+    let config = state.config.read().await.clone();
+    let config_file = state.files.config_file.display().to_string();
+    let credentials_file = state.files.credentials_file.display().to_string();
+    let issues = state.startup_issues.lock().await.clone();
+    let mut checks = Vec::new();
+
+    let account_count = state.accounts.read().await.len();
+    checks.push(
+        match issues.iter().find(|issue| issue.kind == "credentials") {
+            Some(issue) => preflight_check(
+                "accounts",
+                "账号",
+                "fail",
+                format!("账号文件解析失败: {}", issue.error),
+                "open_file",
+                &credentials_file,
+            ),
+            None if account_count == 0 => preflight_check(
+                "accounts",
+                "账号",
+                "fail",
+                "尚未添加账号".to_string(),
+                "open_file",
+                &credentials_file,
+            ),
+            None => preflight_check(
+                "accounts",
+                "账号",
+                "ok",
+                format!("{account_count} 个账号"),
+                "",
+                "",
+            ),
+        },
+    );
+
+    checks.push(match issues.iter().find(|issue| issue.kind == "config") {
+        Some(issue) => preflight_check(
+            "config",
+            "配置",
+            "fail",
+            format!("配置文件解析失败，当前使用默认配置: {}", issue.error),
+            "open_file",
+            &config_file,
+        ),
+        None => preflight_check("config", "配置", "ok", "配置有效".to_string(), "", ""),
+    });
+
+    let binary_path = config.browser.binary_path.trim().to_string();
+    let driver_version = config.web_check.driver_version.trim().to_string();
+    let (chrome, driver) = tokio::task::spawn_blocking(move || {
+        let binary = driver_manager::resolve_browser_binary(&binary_path).unwrap_or_default();
+        (
+            driver_manager::detect_chrome_version(&binary),
+            driver_manager::find_local_chromedriver(&driver_version, &binary),
+        )
+    })
+    .await
+    .map_err(|e| format!("检查浏览器与驱动失败: {e}"))?;
+
+    let configured_binary = config.browser.binary_path.trim();
+    checks.push(match chrome {
+        _ if !configured_binary.is_empty() && !Path::new(configured_binary).exists() => {
+            preflight_check(
+                "chrome",
+                "浏览器",
+                "fail",
+                format!("browser.binary_path 指向的文件不存在: {configured_binary}"),
+                "open_file",
+                &config_file,
+            )
+        }
+        Ok(Some(version)) => preflight_check(
+            "chrome",
+            "浏览器",
+            "ok",
+            format!("Chrome {version}"),
+            "",
+            "",
+        ),
+        Ok(None) => preflight_check(
+            "chrome",
+            "浏览器",
+            "fail",
+            "未检测到 Chrome/Chromium，请安装或在 browser.binary_path 中指定".to_string(),
+            "open_file",
+            &config_file,
+        ),
+        Err(e) => preflight_check(
+            "chrome",
+            "浏览器",
+            "fail",
+            format!("检测浏览器版本失败: {e:#}"),
+            "run_doctor",
+            "",
+        ),
+    });
+
+    let configured_driver = config.web_check.chromedriver_path.trim();
+    checks.push(match driver {
+        _ if !configured_driver.is_empty() => {
+            if Path::new(configured_driver).exists() {
+                preflight_check(
+                    "driver",
+                    "驱动",
+                    "ok",
+                    configured_driver.to_string(),
+                    "",
+                    "",
+                )
+            } else {
+                preflight_check(
+                    "driver",
+                    "驱动",
+                    "fail",
+                    format!(
+                        "web_check.chromedriver_path 指向的文件不存在: {configured_driver}"
+                    ),
+                    "open_file",
+                    &config_file,
+                )
+            }
+        }
+        Ok(Some(path)) => {
+            preflight_check("driver", "驱动", "ok", path.display().to_string(), "", "")
+        }
+        Ok(None) => preflight_check(
+            "driver",
+            "驱动",
+            "warn",
+            "本地没有可用的驱动，首次网页查询时会自动下载".to_string(),
+            "download_driver",
+            "",
+        ),
+        Err(e) => preflight_check(
+            "driver",
+            "驱动",
+            "fail",
+            format!("检查驱动缓存失败: {e:#}"),
+            "download_driver",
+            "",
+        ),
+    });
+
+    let base_url = config.api.base_url.trim().to_string();
+    let reachable = reqwest::Client::builder()
+        .timeout(Duration::from_secs(8))
+        .build()
+        .map_err(|e| e.to_string())
+        .map(|client| client.get(&base_url).send());
+    checks.push(match reachable {
+        Ok(request) => match request.await {
+            Ok(response) => preflight_check(
+                "network",
+                "网络",
+                "ok",
+                format!("{base_url} 可访问 (HTTP {})", response.status().as_u16()),
+                "",
+                "",
+            ),
+            Err(e) => preflight_check(
+                "network",
+                "网络",
+                "fail",
+                format!("无法访问 {base_url}: {e}"),
+                "run_doctor",
+                "",
+            ),
+        },
+        Err(e) => preflight_check(
+            "network",
+            "网络",
+            "fail",
+            format!("创建 HTTP 客户端失败: {e}"),
+            "",
+            "",
+        ),
+    });
+
+    let latest = state.state_store.read().await.latest_check_at();
+    checks.push(match latest {
+        None => preflight_check(
+            "last_round",
+            "最近查询",
+            "warn",
+            "尚未查询过余额".to_string(),
+            "run_query",
+            "",
+        ),
+        Some(at) => {
+            let minutes = (Local::now() - at).num_minutes().max(0);
+            if minutes >= 24 * 60 {
+                preflight_check(
+                    "last_round",
+                    "最近查询",
+                    "warn",
+                    format!("距上次查询已 {} 小时", minutes / 60),
+                    "run_query",
+                    "",
+                )
+            } else {
+                preflight_check(
+                    "last_round",
+                    "最近查询",
+                    "ok",
+                    format!("{minutes} 分钟前"),
+                    "",
+                    "",
+                )
+            }
+        }
+    });
+
+    let failed: Vec<&str> = checks
+        .iter()
+        .filter(|item| item.status == "fail")
+        .map(|item| item.label.as_str())
+        .collect();
+    if failed.is_empty() {
+        tracing::info!("启动检查通过");
+    } else {
+        tracing::warn!("启动检查未通过: {}", failed.join(", "));
+    }
+    Ok(PreflightReport {
+        ok: failed.is_empty(),
+        checks,
+    })
+}
+}
+
+metered_command! {
+/// 用系统默认程序打开当前配置集中的运行时文件或目录，文件不存在时打开所在目录
+#[tauri::command]
+pub async fn open_path_command(state: State<'_, AppState>, path: String) -> CommandResult<()> {
+    let files = &state.files;
+    let target = PathBuf::from(path.trim());
+    let allowed = [
+        &files.config_dir,
+        &files.config_file,
+        &files.credentials_file,
+        &files.logs_dir,
+        &files.log_file,
+        &files.hooks_dir,
+        &files.driver_cache_dir,
+    ];
+    if !allowed.iter().any(|item| **item == target) {
+        return Err(CommandError::validation(format!(
+            "不允许打开的路径: {}",
+            target.display()
+        )));
+    }
+    let target = if target.exists() {
+        target
+    } else {
+        files.config_dir.clone()
+    };
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program)
+        .arg(&target)
+        .spawn()
+        .map_err(|e| format!("打开 {} 失败: {e}", target.display()))?;
+    Ok(())
+}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    active: String,
+    pinned: bool,
+    profiles: Vec<String>,
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn list_profiles_command(state: State<'_, AppState>) -> CommandResult<ProfileList> {
+    Ok(ProfileList {
+        active: state.profile.clone(),
+        pinned: state.profile_pinned,
+        profiles: config::list_profiles(&state.base_dir),
+    })
+}
+}
+
+metered_command! {
+/// 切换到另一配置集并重启应用；create 为 true 时配置集不存在则新建空目录。
+/// 各配置集的 config.json、credentials.txt 与本地状态互相独立
+#[tauri::command]
+pub async fn switch_profile_command(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    create: Option<bool>,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let name = name.trim().to_string();
+    config::validate_profile_name(&name).map_err(|e| e.to_string())?;
+    if name == state.profile {
+        return Ok(format!("当前已是配置集 {name}"));
+    }
+    if state.profile_pinned {
+        return Err(CommandError::validation(
+            "配置集已由 --profile 或 ART_RS_PROFILE 指定，请修改启动参数切换",
+        ));
+    }
+    let dir = config::profile_dir(&state.base_dir, &name);
+    if !dir.exists() {
+        if !create.unwrap_or(false) {
+            return Err(CommandError::not_found(format!("配置集不存在: {name}")));
+        }
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| CommandError::io(format!("创建配置集目录失败: {e}")))?;
+    }
+    let Ok(_guard) = state.query_lock.try_lock() else {
+        return Err(CommandError::busy(format!(
+            "{QUERY_BUSY_ERROR}，请结束后再切换配置集"
+        )));
+    };
+    config::save_active_profile(&state.base_dir, &name).map_err(|e| format!("{e:#}"))?;
+    if let Err(e) = state.state_store.write().await.flush() {
+        tracing::warn!("切换配置集前保存本地状态失败: {}", e);
+    }
+    if let Err(e) = state.flush_credentials().await {
+        tracing::warn!("切换配置集前写入账号文件失败: {:#}", e);
+    }
+    tracing::info!("切换配置集: {} -> {}，正在重启", state.profile, name);
+    browser_pool::shutdown_global_pool();
+    app.restart()
+}
+}
+
+metered_command! {
+/// 当前生效的配置目录与各运行时文件位置
+#[tauri::command]
+pub async fn get_paths_command(state: State<'_, AppState>) -> CommandResult<RuntimeFiles> {
+    Ok(state.files.clone())
+}
+}
+
+metered_command! {
+/// 启动时解析失败、尚未处理的文件
+#[tauri::command]
+pub async fn get_startup_issues_command(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<StartupIssue>> {
+    Ok(state.startup_issues.lock().await.clone())
+}
+}
+
+metered_command! {
+/// 处理启动问题: action 为 reload 时重新读取(用户已手动修复)，
+/// 为 reset 时把原文件备份为 .broken-<时间> 后改用默认内容。返回剩余问题
+#[tauri::command]
+pub async fn resolve_startup_issue_command(
+    state: State<'_, AppState>,
+    file: String,
+    action: String,
+) -> CommandResult<Vec<StartupIssue>> {
+    ensure_unlocked(&state).await?;
+    let reset = match action.trim() {
+        "reload" => false,
+        "reset" => true,
+        other => return Err(CommandError::validation(format!("不支持的操作: {other}"))),
+    };
+    let mut issues = state.startup_issues.lock().await;
+    let Some(issue) = issues.iter().find(|issue| issue.file == file).cloned() else {
+        return Err(CommandError::not_found(format!(
+            "没有待处理的启动问题: {file}"
+        )));
+    };
+    let path = PathBuf::from(&issue.file);
+    let backup = match issue.kind.as_str() {
+        "config" => {
+            let backup = if reset {
+                Some(config::backup_broken_file(&path).map_err(|e| format!("{e:#}"))?)
+            } else {
+                None
+            };
+            let config = load_app_config(&path).map_err(|e| format!("{e:#}"))?;
+            currency::configure(&config.currency);
+            *state.config.write().await = config;
+            backup
+        }
+        "credentials" => {
+            if reset {
+                // 本条问题即将处理，只检查其余问题是否仍阻止写入账号文件
+                let others: Vec<StartupIssue> = issues
+                    .iter()
+                    .filter(|item| item.file != issue.file)
+                    .cloned()
+                    .collect();
+                check_credentials_writable(&state.files, &others)?;
+                let backup = config::backup_broken_file(&path).map_err(|e| format!("{e:#}"))?;
+                save_accounts(&path, &state.accounts.read().await)
+                    .map_err(|e| CommandError::io(format!("写入账号文件失败: {e}")))?;
+                Some(backup)
+            } else {
+                reload_accounts_from_disk(&state).await?;
+                None
+            }
+        }
+        _ => state
+            .state_store
+            .write()
+            .await
+            .resolve_load_failure(&path, reset)
+            .map_err(|e| format!("{e:#}"))?,
+    };
+    issues.retain(|item| item.file != issue.file);
+    state.invalidate_snapshot(if issue.kind == "config" {
+        "config"
+    } else {
+        "startup"
+    });
+    match backup {
+        Some(backup) => tracing::info!(
+            "启动问题已重置: {} (原文件备份为 {})",
+            issue.file,
+            backup.display()
+        ),
+        None => tracing::info!("启动问题已修复: {}", issue.file),
+    }
+    if issue.kind == "config" {
+        tracing::info!("配置已重新加载，部分设置需重启后生效");
+    }
+    Ok(issues.clone())
+}
+}
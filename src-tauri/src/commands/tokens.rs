@@ -0,0 +1,621 @@
+use crate::command_error::{CommandError, CommandResult};
+use crate::commands::query::merge_round_results;
+use crate::commands::{ensure_unlocked, metered_command};
+use crate::models::{
+    AutoSwitchResult, DeploymentCell, DeploymentPlan, DeploymentRecord, DeploymentReport,
+    TokenLabel,
+};
+use crate::paths::{claude_settings_path, codex_auth_path, tool_config_backup_path};
+use crate::token_deploy::{
+    cached_wsl_distros, deploy_key, read_current_claude_token, save_claude_token, save_openai_key,
+    select_wsl_distros,
+};
+use crate::{AppState, privacy, utils, web_native};
+use chrono::Local;
+use serde::Serialize;
+use tauri::State;
+
+metered_command! {
+#[tauri::command]
+pub async fn save_claude_token_command(
+    state: State<'_, AppState>,
+    username: String,
+    key: Option<String>,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let key = find_account_api_key(&state, &username, key.as_deref()).await?;
+    let path = save_claude_token(&key)
+        .map_err(|e| CommandError::io(format!("写入 Claude Token 失败: {e}")))?;
+    state
+        .state_store
+        .write()
+        .await
+        .mark_claude_deployed(username.trim());
+    record_deployments(&state, username.trim(), &["claude".to_string()]).await;
+    Ok(format!("已写入 Claude Token: {}", path.display()))
+}
+}
+
+metered_command! {
+/// 为 Claude 挑选另一个账号并写入其 Key。strategy: highest_balance(默认，余额最高) /
+/// least_recent(最久未写入 Claude 的账号，同等时取余额高者)。
+/// 指定 threshold 时只在当前账号余额低于阈值才切换，且候选账号余额须不低于阈值
+#[tauri::command]
+pub async fn auto_switch_claude_command(
+    state: State<'_, AppState>,
+    strategy: Option<String>,
+    threshold: Option<f64>,
+) -> CommandResult<AutoSwitchResult> {
+    ensure_unlocked(&state).await?;
+    let strategy = strategy.unwrap_or_default().trim().to_ascii_lowercase();
+    let least_recent = match strategy.as_str() {
+        "" | "highest_balance" => false,
+        "least_recent" => true,
+        other => {
+            return Err(CommandError::validation(format!(
+                "不支持的切换策略: {other}"
+            )));
+        }
+    };
+    let threshold = threshold.filter(|value| value.is_finite() && *value >= 0.0);
+    let accounts = state.accounts.read().await.clone();
+    let current_token = read_current_claude_token().unwrap_or_default();
+    let current = accounts
+        .iter()
+        .find(|item| item.has_key(&current_token))
+        .map(|item| item.username.clone())
+        .unwrap_or_default();
+
+    let rows = merge_round_results(&accounts, state.state_store.clone()).await;
+    let balance_of = |username: &str| {
+        rows.iter()
+            .find(|row| row.username == username && row.success && !row.banned)
+            .and_then(|row| utils::parse_first_number(&row.balance_text))
+    };
+    let current_balance = balance_of(&current);
+    // 按阈值自动切换时，当前 Token 不属于任何账号或余额未知则不动，避免覆盖用户自己的配置
+    if threshold.is_some() && current_balance.is_none() {
+        return Ok(AutoSwitchResult {
+            from: current.clone(),
+            to: current,
+            message: "当前 Claude 账号未识别或余额未知，跳过自动切换".to_string(),
+            ..Default::default()
+        });
+    }
+    if let (Some(limit), Some(balance)) = (threshold, current_balance)
+        && balance >= limit
+    {
+        return Ok(AutoSwitchResult {
+            from: current.clone(),
+            to: current,
+            balance: Some(balance),
+            message: format!("当前账号余额 ${balance:.2} 不低于阈值 ${limit:.2}，无需切换"),
+            ..Default::default()
+        });
+    }
+
+    let store = state.state_store.read().await;
+    let mut candidates: Vec<(String, f64, String)> = accounts
+        .iter()
+        .filter(|item| !item.api_key.trim().is_empty() && item.username != current)
+        .filter_map(|item| {
+            let balance = balance_of(&item.username)?;
+            let deployed_at = store
+                .get_cached_balance_record(&item.username)
+                .map(|record| record.claude_deployed_at)
+                .unwrap_or_default();
+            Some((item.username.clone(), balance, deployed_at))
+        })
+        .filter(|(_, balance, _)| {
+            *balance > 0.0 && threshold.is_none_or(|limit| *balance >= limit)
+        })
+        .collect();
+    drop(store);
+    // 从未写入过的账号 deployed_at 为空，按字符串比较排在最前
+    candidates.sort_by(|a, b| {
+        if least_recent {
+            a.2.cmp(&b.2).then(b.1.total_cmp(&a.1))
+        } else {
+            b.1.total_cmp(&a.1)
+        }
+    });
+    let Some((target, balance, _)) = candidates.into_iter().next() else {
+        return Ok(AutoSwitchResult {
+            from: current.clone(),
+            to: current,
+            balance: current_balance,
+            message: "没有可切换的账号(需要配置 API Key 且余额已知)".to_string(),
+            ..Default::default()
+        });
+    };
+    if let Some(current_balance) = current_balance
+        && !least_recent
+        && current_balance >= balance
+    {
+        return Ok(AutoSwitchResult {
+            from: current.clone(),
+            to: current,
+            balance: Some(current_balance),
+            message: "当前账号余额已是最高，无需切换".to_string(),
+            ..Default::default()
+        });
+    }
+
+    let key = find_account_api_key(&state, &target, None).await?;
+    save_claude_token(&key)
+        .map_err(|e| CommandError::io(format!("写入 Claude Token 失败: {e}")))?;
+    state
+        .state_store
+        .write()
+        .await
+        .mark_claude_deployed(&target);
+    record_deployments(&state, &target, &["claude".to_string()]).await;
+    let from_text = if current.is_empty() {
+        "(未识别)".to_string()
+    } else {
+        match current_balance {
+            Some(value) => format!("{current}(${value:.2})"),
+            None => current.clone(),
+        }
+    };
+    let message = format!("已切换 Claude Token: {from_text} -> {target}(${balance:.2})");
+    tracing::info!("{}", message);
+    Ok(AutoSwitchResult {
+        switched: true,
+        from: current,
+        to: target,
+        balance: Some(balance),
+        message,
+    })
+}
+}
+
+metered_command! {
+/// 写入本机 Codex 配置并同步 WSL。distros 指定本次写入的发行版，skip_wsl 为 true 时
+/// 只写本机；两者都未指定时按 config.wsl 选择
+#[tauri::command]
+pub async fn save_openai_key_command(
+    state: State<'_, AppState>,
+    username: String,
+    distros: Option<Vec<String>>,
+    skip_wsl: Option<bool>,
+    key: Option<String>,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let key = find_account_api_key(&state, &username, key.as_deref()).await?;
+    let wsl_config = state.config.read().await.wsl.clone();
+    let distros =
+        tokio::task::spawn_blocking(move || select_wsl_distros(&wsl_config, distros, skip_wsl))
+            .await
+            .map_err(|e| format!("获取 WSL 发行版失败: {e}"))?;
+    let (summary, targets) =
+        tokio::task::spawn_blocking(move || save_openai_key(&key, &distros))
+            .await
+            .map_err(|e| CommandError::io(format!("写入 OpenAI Key 失败: {e}")))?
+            .map_err(|e| CommandError::io(format!("写入 OpenAI Key 失败: {e}")))?;
+    record_deployments(&state, username.trim(), &targets).await;
+    Ok(summary)
+}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvExport {
+    shell: String,
+    content: String,
+    /// 写入的文件，未指定 output_path 时为空
+    path: String,
+}
+
+metered_command! {
+/// 生成设置账号 Key 与站点地址的环境变量脚本，供读取环境变量而非配置文件的工具使用。
+/// shell: bash / zsh / powershell；指定 output_path 时同时写入该文件
+#[tauri::command]
+pub async fn export_env_command(
+    state: State<'_, AppState>,
+    username: String,
+    shell: String,
+    output_path: Option<String>,
+    key: Option<String>,
+) -> CommandResult<EnvExport> {
+    ensure_unlocked(&state).await?;
+    let shell = shell.trim().to_ascii_lowercase();
+    let username = privacy::resolve_username(&username);
+    let key = find_account_api_key(&state, &username, key.as_deref()).await?;
+    let base_url = state
+        .config
+        .read()
+        .await
+        .api
+        .base_url
+        .trim()
+        .trim_end_matches('/')
+        .to_string();
+    let vars = [
+        ("ANTHROPIC_AUTH_TOKEN", key.clone()),
+        ("ANTHROPIC_BASE_URL", base_url.clone()),
+        ("OPENAI_API_KEY", key),
+        ("OPENAI_BASE_URL", format!("{base_url}/v1")),
+    ];
+    let content = render_env_snippet(&shell, &vars)?;
+
+    let path = match output_path.map(|item| item.trim().to_string()) {
+        Some(path) if !path.is_empty() => {
+            std::fs::write(&path, &content)
+                .map_err(|e| CommandError::io(format!("写入文件失败 {path}: {e}")))?;
+            tracing::info!("已导出 {} 的环境变量脚本: {}", username.trim(), path);
+            path
+        }
+        _ => String::new(),
+    };
+    Ok(EnvExport {
+        shell,
+        content,
+        path,
+    })
+}
+}
+
+fn render_env_snippet(shell: &str, vars: &[(&str, String)]) -> CommandResult<String> {
+    let lines: Vec<String> = match shell {
+        "bash" | "zsh" | "sh" => vars
+            .iter()
+            .map(|(name, value)| format!("export {name}='{}'", value.replace('\'', "'\\''")))
+            .collect(),
+        "powershell" | "pwsh" => vars
+            .iter()
+            .map(|(name, value)| format!("$env:{name} = '{}'", value.replace('\'', "''")))
+            .collect(),
+        other => return Err(CommandError::validation(format!("不支持的 shell: {other}"))),
+    };
+    let newline = if shell.starts_with('p') { "\r\n" } else { "\n" };
+    Ok(lines.join(newline) + newline)
+}
+
+/// 把写入成功的目标记入部署登记，由后台 flush 落盘
+async fn record_deployments(state: &State<'_, AppState>, username: &str, targets: &[String]) {
+    let mut store = state.state_store.write().await;
+    for target in targets {
+        store.record_deployment(target, username);
+    }
+}
+
+metered_command! {
+/// 把 Claude(~/.claude/settings.json) 或 Codex(~/.codex/auth.json) 配置回滚到最近一次修改前。
+/// target: claude / codex
+#[tauri::command]
+pub async fn restore_tool_config_command(
+    state: State<'_, AppState>,
+    target: String,
+) -> CommandResult<String> {
+    ensure_unlocked(&state).await?;
+    let path = match target.trim() {
+        "claude" => claude_settings_path(),
+        "codex" => codex_auth_path(),
+        other => {
+            return Err(CommandError::validation(format!(
+                "不支持的恢复目标: {other}"
+            )));
+        }
+    }
+    .map_err(|e| format!("{e:#}"))?;
+    let backup = tool_config_backup_path(&path);
+    if !backup.exists() {
+        return Err(CommandError::not_found(format!(
+            "没有可恢复的备份: {}",
+            backup.display()
+        )));
+    }
+    std::fs::rename(&backup, &path)
+        .map_err(|e| CommandError::io(format!("恢复配置失败 {}: {e}", path.display())))?;
+    tracing::info!("已从备份恢复配置: {}", path.display());
+    Ok(format!("已恢复 {}", path.display()))
+}
+}
+
+metered_command! {
+/// 列出 WSL 发行版；refresh 为 true 时重新调用 wsl.exe 获取，否则使用缓存
+#[tauri::command]
+pub async fn list_wsl_distros_command(refresh: Option<bool>) -> CommandResult<Vec<String>> {
+    tokio::task::spawn_blocking(move || cached_wsl_distros(refresh.unwrap_or(false)))
+        .await
+        .map_err(|e| CommandError::from(format!("获取 WSL 发行版失败: {e}")))
+}
+}
+
+metered_command! {
+/// 各部署目标最近一次写入的账号
+#[tauri::command]
+pub async fn list_deployments_command(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<DeploymentRecord>> {
+    Ok(state.state_store.read().await.list_deployments())
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn list_deploy_plans_command(
+    state: State<'_, AppState>,
+) -> CommandResult<Vec<DeploymentPlan>> {
+    Ok(state.config.read().await.deploy_plans.clone())
+}
+}
+
+metered_command! {
+/// 按 config.deploy_plans 中的计划把各账号的 Key 写入各自目标，逐个单元报告结果。
+/// 某个单元失败不影响其余单元
+#[tauri::command]
+pub async fn deploy_plan_command(
+    state: State<'_, AppState>,
+    plan: String,
+) -> CommandResult<DeploymentReport> {
+    ensure_unlocked(&state).await?;
+    let name = plan.trim().to_string();
+    let plan = state
+        .config
+        .read()
+        .await
+        .deploy_plans
+        .iter()
+        .find(|item| item.name == name)
+        .cloned()
+        .ok_or_else(|| format!("未找到部署计划: {name}"))?;
+    if plan.entries.is_empty() {
+        return Err(CommandError::validation(format!(
+            "部署计划 {name} 没有任何条目"
+        )));
+    }
+    let mut cells = Vec::with_capacity(plan.entries.len());
+    for entry in &plan.entries {
+        let username = entry.username.trim();
+        let target = entry.target.trim();
+        let result = match find_account_api_key(&state, username, Some(&entry.key)).await {
+            Ok(key) => deploy_key(target, &key).map_err(|e| format!("{e:#}")),
+            Err(e) => Err(e.message),
+        };
+        let (success, message) = match result {
+            Ok(message) => {
+                record_deployments(&state, username, &[target.to_string()]).await;
+                (true, message)
+            }
+            Err(message) => (false, message),
+        };
+        cells.push(DeploymentCell {
+            username: username.to_string(),
+            target: target.to_string(),
+            success,
+            message,
+        });
+    }
+    let success_count = cells.iter().filter(|item| item.success).count();
+    let fail_count = cells.len() - success_count;
+    tracing::info!(
+        "部署计划 {} 执行完成: 成功 {}，失败 {}",
+        plan.name,
+        success_count,
+        fail_count
+    );
+    Ok(DeploymentReport {
+        plan: plan.name,
+        cells,
+        success_count,
+        fail_count,
+    })
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn get_current_claude_account_command(
+    state: State<'_, AppState>,
+) -> CommandResult<String> {
+    let token = read_current_claude_token().unwrap_or_default();
+    if token.is_empty() {
+        return Ok(String::new());
+    }
+    let accounts = state.accounts.read().await;
+    // 优先采用部署登记；Token 已被外部改动时再按 Key 反查
+    let registered = state.state_store.read().await.deployed_username("claude");
+    if let Some(username) = registered
+        && accounts
+            .iter()
+            .any(|item| item.username == username && item.has_key(&token))
+    {
+        return Ok(privacy::mask_username(&username));
+    }
+    for account in accounts.iter() {
+        if account.has_key(&token) {
+            return Ok(privacy::mask_username(&account.username));
+        }
+    }
+    Ok(String::new())
+}
+}
+
+metered_command! {
+#[tauri::command]
+pub async fn list_account_tokens_command(
+    state: State<'_, AppState>,
+    username: String,
+) -> CommandResult<Vec<web_native::WebToken>> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    let config = state.config.read().await.clone();
+    web_native::list_account_tokens(
+        &account,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await
+    .map_err(|e| CommandError::from(format!("获取令牌列表失败: {e}")))
+}
+}
+
+/// create_account_token_command 的结果；key 仅在站点返回明文时提供
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedTokenResponse {
+    label: TokenLabel,
+    key: String,
+}
+
+metered_command! {
+/// 在站点上为账号新建令牌，名称按 web_check.token_name_template 生成，并在本地记录令牌归属。
+/// tool 说明令牌的用途(如 claude、codex)，用于模板中的 {tool}
+#[tauri::command]
+pub async fn create_account_token_command(
+    state: State<'_, AppState>,
+    username: String,
+    tool: Option<String>,
+) -> CommandResult<CreatedTokenResponse> {
+    ensure_unlocked(&state).await?;
+    let username = privacy::resolve_username(&username);
+    let tool = tool.unwrap_or_default().trim().to_string();
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    let config = state.config.read().await.clone();
+    let hostname = utils::hostname();
+    let name = render_token_name(
+        &config.web_check.token_name_template,
+        &hostname,
+        &username,
+        &tool,
+    );
+    let created = web_native::create_account_token(
+        &account,
+        &name,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await
+    .map_err(|e| format!("新建令牌失败: {e}"))?;
+
+    let label = TokenLabel {
+        username: username.clone(),
+        name: created.name,
+        hostname,
+        tool,
+        key_hint: key_hint(&created.key),
+        created_at: Local::now().to_rfc3339(),
+    };
+    state
+        .state_store
+        .write()
+        .await
+        .record_token_label(label.clone());
+    tracing::info!("账号 {} 已新建令牌 {}", username, label.name);
+    Ok(CreatedTokenResponse {
+        label,
+        key: created.key,
+    })
+}
+}
+
+metered_command! {
+/// 本程序新建的令牌及其归属；未指定账号时返回全部
+#[tauri::command]
+pub async fn list_token_labels_command(
+    state: State<'_, AppState>,
+    username: Option<String>,
+) -> CommandResult<Vec<TokenLabel>> {
+    let username = username
+        .map(|item| privacy::resolve_username(&item))
+        .filter(|item| !item.is_empty());
+    Ok(state
+        .state_store
+        .read()
+        .await
+        .list_token_labels(username.as_deref()))
+}
+}
+
+/// 替换命名模板中的占位符；站点令牌名称只保留字母、数字、- 与 _
+fn render_token_name(template: &str, hostname: &str, username: &str, tool: &str) -> String {
+    let now = Local::now();
+    let template = if template.trim().is_empty() {
+        "art-rs-{hostname}-{date}"
+    } else {
+        template.trim()
+    };
+    let user = username.split('@').next().unwrap_or(username);
+    let rendered = template
+        .replace("{hostname}", hostname)
+        .replace("{date}", &now.format("%Y%m%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+        .replace("{username}", user)
+        .replace("{tool}", if tool.is_empty() { "default" } else { tool });
+    rendered
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+pub fn key_hint(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 10 {
+        return String::new();
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{head}...{tail}")
+}
+
+/// 查找账号的 Key；selector 为 Key 名称，留空使用默认 Key
+async fn find_account_api_key(
+    state: &State<'_, AppState>,
+    username: &str,
+    selector: Option<&str>,
+) -> CommandResult<String> {
+    if username.trim().is_empty() {
+        return Err(CommandError::validation("账号名不能为空"));
+    }
+    let account = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .find(|item| item.username == username)
+        .cloned();
+    let Some(account) = account else {
+        return Err(CommandError::not_found(format!("未找到账号: {username}")));
+    };
+    let selector = selector.unwrap_or_default().trim();
+    match account.key(selector) {
+        Some(key) => Ok(key.to_string()),
+        None if selector.is_empty() => Err(CommandError::not_found(format!(
+            "账号 {username} 未配置 API Key"
+        ))),
+        None => Err(CommandError::not_found(format!(
+            "账号 {username} 没有名为 {selector} 的 Key"
+        ))),
+    }
+}
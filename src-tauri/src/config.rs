@@ -1,15 +1,27 @@
-use crate::models::{Account, AppConfig};
+use crate::models::{Account, AppConfig, NamedKey, SharedAccount, SharedConfigFile};
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RuntimeFiles {
     pub config_dir: PathBuf,
     pub config_file: PathBuf,
     pub credentials_file: PathBuf,
     pub balance_cache_file: PathBuf,
     pub daily_web_state_file: PathBuf,
+    pub account_history_file: PathBuf,
+    pub alerts_file: PathBuf,
+    pub deployments_file: PathBuf,
+    /// 隐私模式打码哈希所用的本机密钥
+    pub privacy_secret_file: PathBuf,
+    pub logs_dir: PathBuf,
+    pub cookies_dir: PathBuf,
+    pub hooks_dir: PathBuf,
+    pub driver_cache_dir: PathBuf,
+    pub log_file: PathBuf,
 }
 
 impl RuntimeFiles {
@@ -19,9 +31,115 @@ impl RuntimeFiles {
             credentials_file: config_dir.join("credentials.txt"),
             balance_cache_file: config_dir.join("balance_cache.json"),
             daily_web_state_file: config_dir.join("daily_web_login_state.json"),
+            account_history_file: config_dir.join("account_history.json"),
+            alerts_file: config_dir.join("alerts.json"),
+            deployments_file: config_dir.join("deployments.json"),
+            privacy_secret_file: config_dir.join("privacy_secret"),
+            logs_dir: config_dir.join("logs"),
+            cookies_dir: config_dir.join("cookies"),
+            hooks_dir: config_dir.join("hooks"),
+            driver_cache_dir: config_dir.join("drivers"),
+            log_file: config_dir.join("anyrouter_monitor.log"),
             config_dir,
         }
     }
+
+    /// 按 config.paths 与 logging.file 覆盖默认位置，相对路径基于配置目录
+    pub fn with_overrides(mut self, config: &AppConfig) -> Self {
+        let paths = &config.paths;
+        if let Some(dir) = self.resolve(&paths.state_dir) {
+            self.balance_cache_file = dir.join("balance_cache.json");
+            self.daily_web_state_file = dir.join("daily_web_login_state.json");
+            self.account_history_file = dir.join("account_history.json");
+            self.alerts_file = dir.join("alerts.json");
+            self.deployments_file = dir.join("deployments.json");
+            self.privacy_secret_file = dir.join("privacy_secret");
+        }
+        if let Some(dir) = self.resolve(&paths.logs_dir) {
+            self.logs_dir = dir;
+        }
+        if let Some(dir) = self.resolve(&paths.cookies_dir) {
+            self.cookies_dir = dir;
+        }
+        if let Some(dir) = self.resolve(&paths.hooks_dir) {
+            self.hooks_dir = dir;
+        }
+        if let Some(dir) = self.resolve(&paths.driver_cache_dir) {
+            self.driver_cache_dir = dir;
+        }
+        if let Some(file) = self.resolve(&config.logging.file) {
+            self.log_file = file;
+        }
+        self
+    }
+
+    fn resolve(&self, value: &str) -> Option<PathBuf> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        let path = PathBuf::from(value);
+        Some(if path.is_absolute() {
+            path
+        } else {
+            self.config_dir.join(path)
+        })
+    }
+}
+
+/// 不带 --profile 时使用的配置集，即配置目录本身
+pub const DEFAULT_PROFILE: &str = "default";
+/// 记录上次选择的配置集，位于基础配置目录
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+/// 配置集名只允许字母、数字、- 和 _，避免越出 profiles/ 目录
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        anyhow::bail!("配置集名称只能包含字母、数字、- 和 _: {name}");
+    }
+    Ok(())
+}
+
+/// 配置集目录: default 为基础配置目录，其他为 profiles/<名称>/
+pub fn profile_dir(base_dir: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        base_dir.to_path_buf()
+    } else {
+        base_dir.join("profiles").join(name)
+    }
+}
+
+/// 全部配置集名称，default 在最前
+pub fn list_profiles(base_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(base_dir.join("profiles"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| validate_profile_name(name).is_ok() && name != DEFAULT_PROFILE)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+pub fn load_active_profile(base_dir: &Path) -> Option<String> {
+    let raw = fs::read_to_string(base_dir.join(ACTIVE_PROFILE_FILE)).ok()?;
+    let name = raw.trim().to_string();
+    validate_profile_name(&name).ok()?;
+    Some(name)
+}
+
+pub fn save_active_profile(base_dir: &Path, name: &str) -> Result<()> {
+    let path = base_dir.join(ACTIVE_PROFILE_FILE);
+    fs::write(&path, name).with_context(|| format!("写入当前配置集失败: {}", path.display()))
 }
 
 pub fn load_app_config(config_file: &Path) -> Result<AppConfig> {
@@ -34,6 +152,22 @@ pub fn load_app_config(config_file: &Path) -> Result<AppConfig> {
     Ok(config)
 }
 
+/// 把无法解析的文件改名为 <文件名>.broken-<时间> 备份，返回备份路径
+pub fn backup_broken_file(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup = path.with_file_name(format!(
+        "{file_name}.broken-{}",
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    ));
+    if path.exists() {
+        fs::rename(path, &backup).with_context(|| format!("备份文件失败: {}", path.display()))?;
+    }
+    Ok(backup)
+}
+
 pub fn load_accounts(credentials_file: &Path) -> Result<Vec<Account>> {
     if !credentials_file.exists() {
         return Ok(Vec::new());
@@ -47,10 +181,11 @@ pub fn load_accounts(credentials_file: &Path) -> Result<Vec<Account>> {
             continue;
         }
 
-        let mut parts = text.splitn(3, ',').map(|item| item.trim());
+        let mut parts = text.splitn(4, ',').map(|item| item.trim());
         let username = parts.next().unwrap_or_default();
         let password = parts.next().unwrap_or_default();
-        let api_key = parts.next().unwrap_or_default();
+        let keys = parse_account_keys(parts.next().unwrap_or_default());
+        let alias = parts.next().unwrap_or_default();
 
         if username.is_empty() || password.is_empty() {
             tracing::warn!("账号文件第{}行格式无效，已跳过", idx + 1);
@@ -60,7 +195,12 @@ pub fn load_accounts(credentials_file: &Path) -> Result<Vec<Account>> {
         accounts.push(Account {
             username: username.to_string(),
             password: password.to_string(),
-            api_key: api_key.to_string(),
+            api_key: keys
+                .first()
+                .map(|item| item.key.clone())
+                .unwrap_or_default(),
+            keys,
+            alias: alias.to_string(),
         });
     }
     Ok(accounts)
@@ -69,21 +209,147 @@ pub fn load_accounts(credentials_file: &Path) -> Result<Vec<Account>> {
 pub fn save_accounts(credentials_file: &Path, accounts: &[Account]) -> Result<()> {
     let mut lines = Vec::new();
     lines.push("# AnyRouter账号配置文件".to_string());
-    lines.push("# 格式: 用户名,密码,API_KEY(可选)".to_string());
+    lines.push(
+        "# 格式: 用户名,密码,API_KEY(可选，多个 Key 写作 名称=Key;名称=Key),别名(可选)".to_string(),
+    );
     for account in accounts {
         let mut line = format!("{},{}", account.username, account.password);
-        if !account.api_key.trim().is_empty() {
+        let keys = if account.keys.is_empty() {
+            account.api_key.trim().to_string()
+        } else {
+            format_account_keys(&account.keys)
+        };
+        let alias = account.alias.trim();
+        if !keys.is_empty() || !alias.is_empty() {
+            line.push(',');
+            line.push_str(&keys);
+        }
+        if !alias.is_empty() {
             line.push(',');
-            line.push_str(account.api_key.trim());
+            line.push_str(alias);
         }
         lines.push(line);
     }
     let content = lines.join("\n") + "\n";
-    fs::write(credentials_file, content)
+    // 先写临时文件并落盘再替换，避免写入中断或断电时留下残缺的账号文件
+    let tmp = credentials_file.with_extension("tmp");
+    let mut file =
+        fs::File::create(&tmp).with_context(|| format!("创建临时文件失败: {}", tmp.display()))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("写入临时文件失败: {}", tmp.display()))?;
+    drop(file);
+    fs::rename(&tmp, credentials_file)
         .with_context(|| format!("写入账号文件失败: {}", credentials_file.display()))?;
     Ok(())
 }
 
+/// 解析账号文件第三列: 单个 Key，或以分号分隔的多个 `名称=Key`。
+/// 未命名的 Key 依次命名为 default、key2、key3...，第一个为默认 Key。
+/// 名称只含字母、数字、`_`、`-` 且 `=` 后仍有内容时才按命名解析，
+/// 否则整段视为 Key，避免 `abc==` 这类带填充的旧 Key 被拆开
+pub fn parse_account_keys(field: &str) -> Vec<NamedKey> {
+    field
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .enumerate()
+        .map(|(idx, item)| {
+            let (name, key) = match item.split_once('=') {
+                Some((name, key)) if is_key_name(name.trim()) && is_named_value(key.trim()) => {
+                    (name.trim(), key.trim())
+                }
+                _ => ("", item),
+            };
+            let name = match (name, idx) {
+                ("", 0) => "default".to_string(),
+                ("", _) => format!("key{}", idx + 1),
+                (name, _) => name.to_string(),
+            };
+            NamedKey {
+                name,
+                key: key.to_string(),
+            }
+        })
+        .filter(|item| !item.key.is_empty())
+        .collect()
+}
+
+fn is_key_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+/// `=` 后为空或仍以 `=` 开头(如 `abc==` 的填充)时不是命名 Key
+fn is_named_value(key: &str) -> bool {
+    !key.is_empty() && !key.starts_with('=')
+}
+
+/// parse_account_keys 的逆操作；只有一个名为 default 的 Key 时写成单个 Key
+pub fn format_account_keys(keys: &[NamedKey]) -> String {
+    if let [only] = keys
+        && only.name == "default"
+    {
+        return only.key.trim().to_string();
+    }
+    keys.iter()
+        .filter(|item| !item.key.trim().is_empty())
+        .map(|item| format!("{}={}", item.name.trim(), item.key.trim()))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// 共享账号列表的格式版本
+const SHARED_CONFIG_VERSION: u32 = 1;
+
+/// 导出去掉密码的账号列表；sanitized 为 true 时只保留用户名
+pub fn save_shared_config(path: &Path, accounts: &[Account], sanitized: bool) -> Result<usize> {
+    let file = SharedConfigFile {
+        version: SHARED_CONFIG_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        sanitized,
+        accounts: accounts
+            .iter()
+            .map(|account| SharedAccount {
+                username: account.username.clone(),
+                keys: if sanitized {
+                    Vec::new()
+                } else if account.keys.is_empty() {
+                    parse_account_keys(&account.api_key)
+                } else {
+                    account.keys.clone()
+                },
+                alias: if sanitized {
+                    String::new()
+                } else {
+                    account.alias.clone()
+                },
+            })
+            .collect(),
+    };
+    let content = serde_json::to_string_pretty(&file).context("序列化共享账号列表失败")?;
+    fs::write(path, content)
+        .with_context(|| format!("写入共享账号列表失败: {}", path.display()))?;
+    Ok(file.accounts.len())
+}
+
+pub fn load_shared_config(path: &Path) -> Result<SharedConfigFile> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("读取共享账号列表失败: {}", path.display()))?;
+    let file: SharedConfigFile = serde_json::from_str(&raw)
+        .with_context(|| format!("解析共享账号列表失败: {}", path.display()))?;
+    if file.version > SHARED_CONFIG_VERSION {
+        anyhow::bail!(
+            "共享账号列表版本 {} 高于当前支持的 {}",
+            file.version,
+            SHARED_CONFIG_VERSION
+        );
+    }
+    Ok(file)
+}
+
 pub fn remove_account(credentials_file: &Path, username: &str) -> Result<bool> {
     let mut accounts = load_accounts(credentials_file)?;
     let before = accounts.len();
@@ -94,3 +360,127 @@ pub fn remove_account(credentials_file: &Path, username: &str) -> Result<bool> {
     save_accounts(credentials_file, &accounts)?;
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("art_rs_config_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn key(name: &str, key: &str) -> NamedKey {
+        NamedKey {
+            name: name.to_string(),
+            key: key.to_string(),
+        }
+    }
+
+    fn account(username: &str, keys: Vec<NamedKey>, alias: &str) -> Account {
+        Account {
+            username: username.to_string(),
+            password: format!("{username}-pass"),
+            api_key: keys
+                .first()
+                .map(|item| item.key.clone())
+                .unwrap_or_default(),
+            keys,
+            alias: alias.to_string(),
+        }
+    }
+
+    #[test]
+    fn credentials_round_trip() {
+        let dir = scratch_dir("round_trip");
+        let path = dir.join("credentials.txt");
+        let accounts = vec![
+            account("alice", Vec::new(), ""),
+            account("bob", vec![key("default", "sk-bob")], ""),
+            account(
+                "carol",
+                vec![key("claude", "sk-c1"), key("codex", "sk-c2")],
+                "备用账号",
+            ),
+            account("dave", Vec::new(), "只有别名"),
+        ];
+        save_accounts(&path, &accounts).unwrap();
+        assert_eq!(load_accounts(&path).unwrap(), accounts);
+        assert!(!path.with_extension("tmp").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn load_skips_comments_and_invalid_lines() {
+        let dir = scratch_dir("invalid_lines");
+        let path = dir.join("credentials.txt");
+        fs::write(
+            &path,
+            "# 注释\n\nalice,alice-pass\nbob\n,pw-only\n  carol , carol-pass , sk-1;work=sk-2 , 别名 \n",
+        )
+        .unwrap();
+        let expected = vec![
+            account("alice", Vec::new(), ""),
+            account(
+                "carol",
+                vec![key("default", "sk-1"), key("work", "sk-2")],
+                "别名",
+            ),
+        ];
+        assert_eq!(load_accounts(&path).unwrap(), expected);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn bare_keys_with_padding_stay_whole() {
+        assert_eq!(
+            parse_account_keys("c2stYWJjZA=="),
+            vec![key("default", "c2stYWJjZA==")]
+        );
+        assert_eq!(
+            parse_account_keys("sk-1;work=c2stYWJjZA==;c2stZWY="),
+            vec![
+                key("default", "sk-1"),
+                key("work", "c2stYWJjZA=="),
+                key("key3", "c2stZWY="),
+            ]
+        );
+        assert_eq!(
+            parse_account_keys("my key=sk-1"),
+            vec![key("default", "my key=sk-1")]
+        );
+        let keys = vec![key("default", "c2stYWJjZA==")];
+        assert_eq!(parse_account_keys(&format_account_keys(&keys)), keys);
+    }
+
+    #[test]
+    fn load_missing_file_is_empty() {
+        let dir = scratch_dir("missing");
+        assert!(
+            load_accounts(&dir.join("credentials.txt"))
+                .unwrap()
+                .is_empty()
+        );
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn account_keys_round_trip() {
+        assert_eq!(
+            format_account_keys(&parse_account_keys("sk-only")),
+            "sk-only"
+        );
+        let keys = parse_account_keys("sk-1; work = sk-2 ;;sk-3");
+        assert_eq!(
+            keys,
+            vec![
+                key("default", "sk-1"),
+                key("work", "sk-2"),
+                key("key3", "sk-3")
+            ]
+        );
+        assert_eq!(parse_account_keys(&format_account_keys(&keys)), keys);
+    }
+}
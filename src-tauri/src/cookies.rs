@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 站点 Cookie，与浏览器导出格式互转
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub domain: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    /// 过期时间(Unix 秒)，会话 Cookie 为空
+    #[serde(default)]
+    pub expiry: Option<i64>,
+}
+
+/// 账号 Cookie 存放目录，启动时设置一次
+static COOKIES_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_cookies_dir(dir: PathBuf) {
+    let _ = COOKIES_DIR.set(dir);
+}
+
+fn cookie_file(username: &str) -> Option<PathBuf> {
+    let dir = COOKIES_DIR.get()?;
+    let safe_name: String = username
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' || ch == '_' || ch == '.' || ch == '@' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(dir.join(format!("{safe_name}.json")))
+}
+
+/// 读取账号已保存的 Cookie，跳过已过期的条目
+pub fn load_account_cookies(username: &str) -> Vec<StoredCookie> {
+    let Some(path) = cookie_file(username) else {
+        return Vec::new();
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    let cookies: Vec<StoredCookie> = match serde_json::from_str(&content) {
+        Ok(items) => items,
+        Err(err) => {
+            tracing::warn!("解析账号 Cookie 失败: {}, err={}", path.display(), err);
+            return Vec::new();
+        }
+    };
+    let now = chrono::Local::now().timestamp();
+    cookies
+        .into_iter()
+        .filter(|item| item.expiry.is_none_or(|expiry| expiry > now))
+        .collect()
+}
+
+pub fn save_account_cookies(username: &str, cookies: &[StoredCookie]) -> Result<PathBuf> {
+    let path = cookie_file(username).with_context(|| "Cookie 目录未初始化")?;
+    write_cookie_file(&path, cookies)?;
+    Ok(path)
+}
+
+pub fn write_cookie_file(path: &Path, cookies: &[StoredCookie]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("创建 Cookie 目录失败: {}", parent.display()))?;
+    }
+    let content = serde_json::to_string_pretty(cookies).with_context(|| "序列化 Cookie 失败")?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content).with_context(|| format!("写入临时文件失败: {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("原子替换失败: {}", path.display()))?;
+    Ok(())
+}
+
+/// 解析浏览器导出的 Cookie: JSON 数组(WebDriver 或浏览器扩展格式)、
+/// 带 cookies 字段的 JSON 对象，或 Netscape cookies.txt
+pub fn parse_cookie_text(text: &str) -> Result<Vec<StoredCookie>> {
+    let trimmed = text.trim();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        let value: Value = serde_json::from_str(trimmed).with_context(|| "Cookie JSON 格式错误")?;
+        let items = match &value {
+            Value::Array(items) => items.clone(),
+            Value::Object(obj) => obj
+                .get("cookies")
+                .and_then(Value::as_array)
+                .cloned()
+                .with_context(|| "Cookie JSON 缺少 cookies 数组")?,
+            _ => Vec::new(),
+        };
+        return Ok(items.iter().filter_map(cookie_from_json).collect());
+    }
+
+    let cookies: Vec<StoredCookie> = trimmed
+        .lines()
+        .filter_map(|line| {
+            // #HttpOnly_ 前缀表示 HttpOnly Cookie，其余 # 开头为注释
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(rest) => (rest, true),
+                None if line.starts_with('#') => return None,
+                None => (line, false),
+            };
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+            let expiry = fields[4]
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|item| *item > 0);
+            Some(StoredCookie {
+                name: fields[5].to_string(),
+                value: fields[6].trim_end().to_string(),
+                domain: fields[0].to_string(),
+                path: fields[2].to_string(),
+                secure: fields[3].eq_ignore_ascii_case("TRUE"),
+                http_only,
+                expiry,
+            })
+        })
+        .collect();
+    if cookies.is_empty() {
+        anyhow::bail!("未识别到有效的 Cookie");
+    }
+    Ok(cookies)
+}
+
+fn cookie_from_json(item: &Value) -> Option<StoredCookie> {
+    let text = |key: &str| {
+        item.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    let name = text("name");
+    if name.is_empty() {
+        return None;
+    }
+    let flag = |keys: &[&str]| {
+        keys.iter()
+            .any(|key| item.get(*key).and_then(Value::as_bool).unwrap_or(false))
+    };
+    let expiry = ["expiry", "expirationDate", "expires"]
+        .iter()
+        .find_map(|key| item.get(*key).and_then(Value::as_f64))
+        .filter(|item| *item > 0.0)
+        .map(|item| item as i64);
+    Some(StoredCookie {
+        name,
+        value: text("value"),
+        domain: text("domain"),
+        path: text("path"),
+        secure: flag(&["secure"]),
+        http_only: flag(&["httpOnly", "http_only"]),
+        expiry,
+    })
+}
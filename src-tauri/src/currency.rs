@@ -0,0 +1,166 @@
+use crate::models::{CheckResult, CurrencyConfig};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+/// 货币设置，启动时与重新载入配置时更新
+static CONFIG: OnceLock<RwLock<CurrencyConfig>> = OnceLock::new();
+
+/// 货币符号与代码；按长度从长到短匹配，避免 US$ 被识别为 $
+const SYMBOLS: &[(&str, &str)] = &[
+    ("US$", "USD"),
+    ("USD", "USD"),
+    ("CNY", "CNY"),
+    ("RMB", "CNY"),
+    ("EUR", "EUR"),
+    ("GBP", "GBP"),
+    ("$", "USD"),
+    ("¥", "CNY"),
+    ("￥", "CNY"),
+    ("元", "CNY"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+];
+
+pub fn configure(config: &CurrencyConfig) {
+    let lock = CONFIG.get_or_init(|| RwLock::new(CurrencyConfig::default()));
+    if let Ok(mut guard) = lock.write() {
+        *guard = config.clone();
+    }
+}
+
+fn with_config<T>(read: impl FnOnce(&CurrencyConfig) -> T) -> T {
+    match CONFIG.get().and_then(|lock| lock.read().ok()) {
+        Some(guard) => read(&guard),
+        None => read(&CurrencyConfig::default()),
+    }
+}
+
+/// 结果与汇总统一使用的货币代码
+pub fn canonical() -> String {
+    with_config(|config| normalize_code(&config.canonical)).unwrap_or_else(|| "USD".to_string())
+}
+
+/// 站点余额的默认货币: currency.providers 中的配置，未配置时为统一货币。
+/// 页面或接口未带货币符号时按此解释
+pub fn provider_currency(provider: &str) -> String {
+    with_config(|config| {
+        config
+            .providers
+            .get(provider)
+            .and_then(|code| normalize_code(code))
+    })
+    .unwrap_or_else(canonical)
+}
+
+fn normalize_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    if code.is_empty() {
+        return None;
+    }
+    let upper = code.to_ascii_uppercase();
+    Some(
+        SYMBOLS
+            .iter()
+            .find(|(symbol, _)| *symbol == code || *symbol == upper)
+            .map(|(_, code)| code.to_string())
+            .unwrap_or(upper),
+    )
+}
+
+/// 文本中出现的货币符号或代码对应的货币
+pub fn detect_currency(text: &str) -> Option<&'static str> {
+    let upper = text.to_uppercase();
+    SYMBOLS
+        .iter()
+        .find(|(symbol, _)| upper.contains(symbol))
+        .map(|(_, code)| *code)
+}
+
+fn amount_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\d[\d.,]*").unwrap())
+}
+
+/// 数字前紧邻负号(允许隔着货币符号与空白，如 -$12、$ -12、−€5)时为负数
+fn is_negative(prefix: &str) -> bool {
+    let trimmed = prefix.trim_end_matches(|ch: char| {
+        ch.is_whitespace() || SYMBOLS.iter().any(|(symbol, _)| symbol.contains(ch))
+    });
+    trimmed.ends_with(['-', '−'])
+}
+
+/// 解析带格式的金额: 同时出现逗号与句点时以靠后的为小数点；
+/// 只有逗号且其后不是三位数字(如 12,50)时按小数点处理。保留负号(透支)
+pub fn parse_amount(text: &str) -> Option<f64> {
+    let found = amount_regex().find(text)?;
+    let matched = found.as_str();
+    let mut raw: String = matched
+        .chars()
+        .filter(|ch| ch.is_ascii_digit() || matches!(ch, '.' | ','))
+        .collect();
+    if is_negative(&text[..found.start()]) {
+        raw.insert(0, '-');
+    }
+    let raw = raw.trim_end_matches(['.', ',']);
+    let last_comma = raw.rfind(',');
+    let last_dot = raw.rfind('.');
+    let normalized = match (last_comma, last_dot) {
+        (Some(comma), Some(dot)) if comma > dot => raw.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => raw.replace(',', ""),
+        (Some(comma), None) if raw.matches(',').count() == 1 && raw.len() - comma - 1 != 3 => {
+            raw.replace(',', ".")
+        }
+        (Some(_), None) => raw.replace(',', ""),
+        (None, Some(_)) if raw.matches('.').count() > 1 => raw.replace('.', ""),
+        _ => raw.to_string(),
+    };
+    normalized.parse::<f64>().ok()
+}
+
+/// 解析余额文本，返回金额与文本中识别到的货币(未带符号时为空)
+pub fn parse_money(text: &str) -> Option<(f64, Option<&'static str>)> {
+    let amount = parse_amount(text)?;
+    Some((amount, detect_currency(text)))
+}
+
+/// 按货币输出余额文本，常见货币用符号前缀，其余在数值后附代码
+pub fn format_money(value: f64, code: &str) -> String {
+    match code {
+        "" | "USD" => format!("${value:.1}"),
+        "CNY" => format!("¥{value:.1}"),
+        "EUR" => format!("€{value:.1}"),
+        "GBP" => format!("£{value:.1}"),
+        other => format!("{value:.1} {other}"),
+    }
+}
+
+/// 结果行的余额货币: 结果中的代码，缺失时(如缓存结果)从余额文本识别，仍无法识别时视为统一货币
+pub fn result_currency(row: &CheckResult) -> String {
+    if !row.currency.is_empty() {
+        return row.currency.clone();
+    }
+    detect_currency(&row.balance_text)
+        .map(str::to_string)
+        .unwrap_or_else(canonical)
+}
+
+/// 换算到统一货币的汇率表，统一货币自身为 1
+pub fn rates() -> BTreeMap<String, f64> {
+    let canonical = canonical();
+    let mut rates: BTreeMap<String, f64> = with_config(|config| {
+        config
+            .rates
+            .iter()
+            .filter(|(_, rate)| rate.is_finite() && **rate > 0.0)
+            .filter_map(|(code, rate)| Some((normalize_code(code)?, *rate)))
+            .collect()
+    });
+    rates.insert(canonical, 1.0);
+    rates
+}
+
+/// 把金额折算为统一货币，currency.rates 中没有该货币的汇率时无法折算
+pub fn to_canonical(amount: f64, code: &str) -> Option<f64> {
+    rates().get(code).map(|rate| amount * rate)
+}
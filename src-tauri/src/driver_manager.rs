@@ -1,39 +1,152 @@
+use crate::models::{DriverDownloadProgress, DriverMirrorConfig};
 use anyhow::{Context, Result};
 use once_cell::sync::OnceCell;
 use regex::Regex;
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs::{self, File};
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use zip::ZipArchive;
 
 const VERSION_API: &str = "https://googlechromelabs.github.io/chrome-for-testing/last-known-good-versions-with-downloads.json";
+/// Chrome for Testing 版本 JSON 与压缩包的地址前缀，配置镜像时替换为镜像地址
+const CFT_API_BASE: &str = "https://googlechromelabs.github.io/chrome-for-testing/";
+const CFT_STORAGE_BASE: &str = "https://storage.googleapis.com/chrome-for-testing-public/";
+const SNAP_CHROMIUM: &str = "/snap/bin/chromium";
+const SNAP_CHROMEDRIVER: &str = "/snap/bin/chromium.chromedriver";
+const FLATPAK_CHROMIUM_ID: &str = "org.chromium.Chromium";
+/// Chrome for Testing 提供 ChromeDriver 的平台
+const CFT_PLATFORMS: [&str; 5] = ["linux64", "mac-arm64", "mac-x64", "win32", "win64"];
+/// 发行版 chromedriver 常见的安装位置(PATH 之外)
+const SYSTEM_DRIVER_PATHS: [&str; 3] = [
+    "/usr/lib/chromium/chromedriver",
+    "/usr/lib/chromium-browser/chromedriver",
+    "/usr/lib64/chromium-browser/chromedriver",
+];
+/// 驱动压缩包的下载超时，覆盖客户端默认的 20 秒
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+/// 两次下载进度事件的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// 驱动缓存清单文件名，位于缓存目录下
+const MANIFEST_FILE: &str = "manifest.json";
 const PATCH_API: &str = "https://googlechromelabs.github.io/chrome-for-testing/latest-patch-versions-per-build-with-downloads.json";
 
+/// 驱动缓存清单: 各缓存目录下载时压缩包与可执行文件的 SHA-256，键为目录名
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DriverManifest {
+    #[serde(default)]
+    drivers: BTreeMap<String, DriverManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DriverManifestEntry {
+    /// 补录的旧缓存没有压缩包哈希
+    #[serde(default)]
+    zip_sha256: String,
+    #[serde(default)]
+    binary_sha256: String,
+    #[serde(default)]
+    recorded_at: String,
+}
+
 #[derive(Debug)]
 pub struct ChromeDriverManager {
     cache_dir: PathBuf,
     client: Client,
+    mirror: DriverMirrorConfig,
 }
 
 impl ChromeDriverManager {
-    pub fn new(cache_dir: Option<PathBuf>) -> Result<Self> {
+    pub fn new(cache_dir: Option<PathBuf>, mirror: DriverMirrorConfig) -> Result<Self> {
         let dir = cache_dir.unwrap_or_else(default_cache_dir);
         fs::create_dir_all(&dir).with_context(|| format!("创建缓存目录失败: {}", dir.display()))?;
         let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(20))
+            .timeout(Duration::from_secs(20))
             .build()
             .with_context(|| "创建 HTTP 客户端失败")?;
+        if mirror.active() {
+            tracing::info!("使用内部驱动镜像下载ChromeDriver");
+        }
         Ok(Self {
             cache_dir: dir,
             client,
+            mirror,
         })
     }
 
-    pub fn get_chrome_version(&self) -> Option<String> {
+    /// 配置了镜像 base_url 时把 Chrome for Testing 的地址换成镜像中的同一路径
+    fn mirror_url(&self, url: &str) -> String {
+        let base = self.mirror.base_url.trim().trim_end_matches('/');
+        if base.is_empty() {
+            return url.to_string();
+        }
+        match url
+            .strip_prefix(CFT_STORAGE_BASE)
+            .or_else(|| url.strip_prefix(CFT_API_BASE))
+        {
+            Some(rest) => format!("{base}/{rest}"),
+            None => url.to_string(),
+        }
+    }
+
+    fn with_mirror_headers(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if self.mirror.active() {
+            for (name, value) in &self.mirror.headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+        }
+        request
+    }
+
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.with_mirror_headers(self.client.get(self.mirror_url(url)))
+    }
+
+    fn head(&self, url: &str) -> RequestBuilder {
+        self.with_mirror_headers(self.client.head(self.mirror_url(url)))
+    }
+
+    /// 只配置了 versions 映射: 映射之外的版本不再访问任何外部地址
+    fn mirror_map_only(&self) -> bool {
+        self.mirror.base_url.trim().is_empty() && !self.mirror.versions.is_empty()
+    }
+
+    /// 在 versions 映射中查找: 完整版本号优先，其次主版本号，再次同主版本中最新的条目
+    fn mirror_version_url(&self, chrome_version: &str) -> Option<(String, String)> {
+        let versions = &self.mirror.versions;
+        if let Some(url) = versions.get(chrome_version) {
+            return Some((chrome_version.to_string(), url.clone()));
+        }
+        let major = Self::get_major_version(chrome_version);
+        if let Some(url) = versions.get(&major.to_string()) {
+            return Some((chrome_version.to_string(), url.clone()));
+        }
+        versions
+            .iter()
+            .filter(|(version, _)| Self::get_major_version(version) == major)
+            .max_by_key(|(version, _)| version_parts(version))
+            .map(|(version, url)| (version.clone(), url.clone()))
+    }
+
+    /// binary_path 非空时只检测该浏览器的版本
+    pub fn get_chrome_version(&self, binary_path: &str) -> Option<String> {
+        if !binary_path.is_empty() {
+            let version = query_binary_version(binary_path);
+            match &version {
+                Some(item) => tracing::info!("检测到浏览器版本: {} ({})", item, binary_path),
+                None => tracing::warn!("未能检测到浏览器版本: {}", binary_path),
+            }
+            return version;
+        }
+
         #[cfg(windows)]
         {
             if let Some(version) = self.get_chrome_version_from_windows_registry() {
@@ -87,7 +200,7 @@ impl ChromeDriverManager {
             .cache_dir
             .join(format!("chromedriver_{version}"))
             .join(driver_name);
-        if exact.exists() {
+        if exact.exists() && self.verify_cached_driver(&exact) {
             tracing::info!("使用缓存的ChromeDriver: {}", exact.display());
             return Some(exact);
         }
@@ -101,7 +214,8 @@ impl ChromeDriverManager {
             if !file_name.starts_with(&prefix) {
                 continue;
             }
-            let candidate = find_file_recursively(&path, driver_name);
+            let candidate = find_file_recursively(&path, driver_name)
+                .filter(|item| self.verify_cached_driver(item));
             if let Some(item) = candidate {
                 tracing::info!("使用主版本缓存的ChromeDriver: {}", item.display());
                 return Some(item);
@@ -110,7 +224,11 @@ impl ChromeDriverManager {
         None
     }
 
-    pub fn get_or_download_driver(&self, chrome_version: Option<&str>) -> Result<PathBuf> {
+    pub fn get_or_download_driver(
+        &self,
+        chrome_version: Option<&str>,
+        binary_path: &str,
+    ) -> Result<PathBuf> {
         if let Some(item) = chrome_version {
             let version = item.trim();
             if !version.is_empty() {
@@ -118,7 +236,7 @@ impl ChromeDriverManager {
             }
         }
 
-        if let Some(version) = self.get_chrome_version() {
+        if let Some(version) = self.get_chrome_version(binary_path) {
             return self.get_or_download_driver_by_version(&version);
         }
 
@@ -142,7 +260,35 @@ impl ChromeDriverManager {
         self.download_latest_stable_driver()
     }
 
-    pub fn get_driver_path(&self) -> Result<PathBuf> {
+    /// 使用固定版本的驱动: 只接受完全一致的缓存，否则按该版本精确下载
+    pub fn get_pinned_driver(&self, version: &str) -> Result<PathBuf> {
+        ensure_cft_platform()?;
+        let exact_dir = self.cache_dir.join(format!("chromedriver_{version}"));
+        if let Some(found) = find_file_recursively(&exact_dir, driver_binary_name())
+            .filter(|item| self.verify_cached_driver(item))
+        {
+            tracing::info!("使用固定版本的ChromeDriver: {}", found.display());
+            return Ok(found);
+        }
+
+        let guard = download_lock()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("驱动下载锁获取失败"))?;
+        let _guard = guard;
+        if let Some(found) = find_file_recursively(&exact_dir, driver_binary_name())
+            .filter(|item| self.verify_cached_driver(item))
+        {
+            return Ok(found);
+        }
+        let platform = platform_name();
+        let url = self.get_direct_url(version, &platform).with_context(|| {
+            format!("固定版本的ChromeDriver不存在: version={version}, platform={platform}")
+        })?;
+        tracing::info!("下载固定版本的ChromeDriver: {}", version);
+        self.download_driver_from_url(version, &url)
+    }
+
+    pub fn get_driver_path(&self, pinned_version: &str, binary_path: &str) -> Result<PathBuf> {
         if let Ok(path) = std::env::var("CHROMEDRIVER_PATH") {
             let item = PathBuf::from(path.trim());
             if item.exists() {
@@ -150,12 +296,73 @@ impl ChromeDriverManager {
             }
         }
 
+        if !pinned_version.is_empty() {
+            return self.get_pinned_driver(pinned_version);
+        }
+
+        // Snap 版 Chromium 自带同版本的驱动，直接使用
+        if binary_path.starts_with("/snap/") && Path::new(SNAP_CHROMEDRIVER).exists() {
+            tracing::info!("使用 Snap Chromium 自带的驱动: {}", SNAP_CHROMEDRIVER);
+            return Ok(PathBuf::from(SNAP_CHROMEDRIVER));
+        }
+
         let local = PathBuf::from(driver_binary_name());
         if local.exists() {
             return Ok(local);
         }
 
-        self.get_or_download_driver(None)
+        // Chrome for Testing 没有对应构建的平台只能使用系统安装的驱动
+        if let Err(err) = ensure_cft_platform() {
+            if let Some(found) = find_system_driver() {
+                tracing::info!("使用系统安装的ChromeDriver: {}", found.display());
+                return Ok(found);
+            }
+            return Err(err);
+        }
+
+        match self.get_or_download_driver(None, binary_path) {
+            Ok(path) => Ok(path),
+            Err(err) => match find_system_driver() {
+                Some(found) => {
+                    tracing::warn!(
+                        "获取ChromeDriver失败，回退到系统安装的驱动: {}, err={}",
+                        found.display(),
+                        err
+                    );
+                    Ok(found)
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    /// 不下载时即可使用的驱动: 与 get_driver_path 的查找顺序一致，但跳过所有下载
+    pub fn find_local_driver(&self, pinned_version: &str, binary_path: &str) -> Option<PathBuf> {
+        if let Ok(path) = std::env::var("CHROMEDRIVER_PATH") {
+            let item = PathBuf::from(path.trim());
+            if item.exists() {
+                return Some(item);
+            }
+        }
+        if !pinned_version.is_empty() {
+            let exact_dir = self
+                .cache_dir
+                .join(format!("chromedriver_{pinned_version}"));
+            return find_file_recursively(&exact_dir, driver_binary_name())
+                .filter(|item| self.verify_cached_driver(item));
+        }
+        if binary_path.starts_with("/snap/") && Path::new(SNAP_CHROMEDRIVER).exists() {
+            return Some(PathBuf::from(SNAP_CHROMEDRIVER));
+        }
+        let local = PathBuf::from(driver_binary_name());
+        if local.exists() {
+            return Some(local);
+        }
+        let cached = match self.get_chrome_version(binary_path) {
+            Some(version) => self.find_cached_driver(&version),
+            None => self.find_any_cached_driver(),
+        };
+        cached.or_else(find_system_driver)
     }
 
     fn download_driver(&self, chrome_version: &str) -> Result<PathBuf> {
@@ -177,12 +384,16 @@ impl ChromeDriverManager {
     }
 
     fn get_direct_url(&self, chrome_version: &str, platform: &str) -> Option<String> {
-        let url = format!(
-            "https://storage.googleapis.com/chrome-for-testing-public/{}/{}/chromedriver-{}.zip",
-            chrome_version, platform, platform
-        );
+        if let Some(url) = self.mirror.versions.get(chrome_version) {
+            return Some(url.clone());
+        }
+        if self.mirror_map_only() {
+            return None;
+        }
+        let url = self.mirror_url(&format!(
+            "{CFT_STORAGE_BASE}{chrome_version}/{platform}/chromedriver-{platform}.zip"
+        ));
         let ok = self
-            .client
             .head(&url)
             .send()
             .map(|item| item.status().is_success())
@@ -191,12 +402,15 @@ impl ChromeDriverManager {
     }
 
     fn get_patch_url(&self, chrome_version: &str, platform: &str) -> Option<String> {
+        if self.mirror_map_only() {
+            return None;
+        }
         let build_prefix = chrome_version
             .split('.')
             .take(3)
             .collect::<Vec<_>>()
             .join(".");
-        let payload: Value = self.client.get(PATCH_API).send().ok()?.json().ok()?;
+        let payload: Value = self.get(PATCH_API).send().ok()?.json().ok()?;
         let build = payload.get("builds")?.get(&build_prefix)?;
         let items = build.get("downloads")?.get("chromedriver")?.as_array()?;
         for item in items {
@@ -205,16 +419,22 @@ impl ChromeDriverManager {
                 .and_then(Value::as_str)
                 .unwrap_or_default();
             if p == platform {
-                let url = item.get("url").and_then(Value::as_str)?.to_string();
-                return Some(url);
+                let url = item.get("url").and_then(Value::as_str)?;
+                return Some(self.mirror_url(url));
             }
         }
         None
     }
 
     fn get_last_known_url(&self, platform: &str, chrome_version: &str) -> Option<String> {
+        if let Some((_, url)) = self.mirror_version_url(chrome_version) {
+            return Some(url);
+        }
+        if self.mirror_map_only() {
+            return None;
+        }
         let major = Self::get_major_version(chrome_version);
-        let payload: Value = self.client.get(VERSION_API).send().ok()?.json().ok()?;
+        let payload: Value = self.get(VERSION_API).send().ok()?.json().ok()?;
         let channels = payload.get("channels")?.as_object()?;
         for (_name, channel) in channels {
             let version = channel
@@ -234,7 +454,7 @@ impl ChromeDriverManager {
                     return item
                         .get("url")
                         .and_then(Value::as_str)
-                        .map(|s| s.to_string());
+                        .map(|s| self.mirror_url(s));
                 }
             }
         }
@@ -271,7 +491,9 @@ impl ChromeDriverManager {
             if !name.starts_with("chromedriver_") {
                 continue;
             }
-            if let Some(found) = find_file_recursively(&path, driver_name) {
+            if let Some(found) = find_file_recursively(&path, driver_name)
+                .filter(|item| self.verify_cached_driver(item))
+            {
                 return Some(found);
             }
         }
@@ -279,9 +501,18 @@ impl ChromeDriverManager {
     }
 
     fn download_latest_stable_driver(&self) -> Result<PathBuf> {
+        if self.mirror_map_only() {
+            let (version, url) = self
+                .mirror
+                .versions
+                .iter()
+                .max_by_key(|(version, _)| version_parts(version))
+                .with_context(|| "驱动镜像 versions 为空")?;
+            tracing::info!("使用驱动镜像中最新的版本: {}", version);
+            return self.download_driver_from_url(version, url);
+        }
         let platform = platform_name();
         let payload: Value = self
-            .client
             .get(VERSION_API)
             .send()
             .and_then(|item| item.error_for_status())
@@ -324,7 +555,7 @@ impl ChromeDriverManager {
                     version,
                     platform
                 );
-                return self.download_driver_from_url(version, url);
+                return self.download_driver_from_url(version, &self.mirror_url(url));
             }
         }
 
@@ -334,32 +565,54 @@ impl ChromeDriverManager {
     fn download_driver_from_url(&self, version_tag: &str, url: &str) -> Result<PathBuf> {
         tracing::info!("下载ChromeDriver: {}", url);
         let response = self
-            .client
             .get(url)
+            .timeout(DOWNLOAD_TIMEOUT)
             .send()
             .and_then(|item| item.error_for_status())
             .with_context(|| "下载ChromeDriver失败")?;
-        let data = response.bytes().with_context(|| "读取驱动压缩包失败")?;
 
         let temp_zip = self.cache_dir.join(format!(
             "chromedriver_{}.zip.tmp",
             chrono::Local::now().timestamp_millis()
         ));
-        {
-            let mut file = File::create(&temp_zip)
-                .with_context(|| format!("创建临时文件失败: {}", temp_zip.display()))?;
-            file.write_all(&data)
-                .with_context(|| format!("写入临时压缩包失败: {}", temp_zip.display()))?;
+        let streamed = stream_to_file(response, &temp_zip, version_tag);
+        if streamed.is_err() {
+            let _ = fs::remove_file(&temp_zip);
         }
+        streamed?;
 
-        let target_dir = self.cache_dir.join(format!("chromedriver_{version_tag}"));
-        fs::create_dir_all(&target_dir)
-            .with_context(|| format!("创建目标目录失败: {}", target_dir.display()))?;
-        unzip_file(&temp_zip, &target_dir)?;
+        let zip_sha256 = sha256_file(&temp_zip);
+        // 先解压到临时目录再整体改名，其他线程不会看到解压了一半的缓存
+        let dir_name = format!("chromedriver_{version_tag}");
+        let staging_dir = self.cache_dir.join(format!(
+            ".extract_{}",
+            chrono::Local::now().timestamp_millis()
+        ));
+        let extracted = unzip_file(&temp_zip, &staging_dir);
         let _ = fs::remove_file(&temp_zip);
+        if let Err(err) = extracted {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(err);
+        }
+        let target_dir = self.cache_dir.join(&dir_name);
+        if target_dir.exists() {
+            let _ = fs::remove_dir_all(&target_dir);
+        }
+        if let Err(err) = fs::rename(&staging_dir, &target_dir) {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(err).with_context(|| format!("移动驱动目录失败: {}", target_dir.display()));
+        }
 
         let driver = find_file_recursively(&target_dir, driver_binary_name())
             .with_context(|| "压缩包中未找到 chromedriver 可执行文件")?;
+        match (zip_sha256, sha256_file(&driver)) {
+            (Ok(zip_sha256), Ok(binary_sha256)) => {
+                self.record_driver(&dir_name, zip_sha256, binary_sha256)
+            }
+            (Err(err), _) | (_, Err(err)) => {
+                tracing::warn!("计算驱动哈希失败，未写入缓存清单: {:#}", err)
+            }
+        }
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
@@ -374,6 +627,114 @@ impl ChromeDriverManager {
         Ok(driver)
     }
 
+    fn load_manifest(&self) -> DriverManifest {
+        let path = self.cache_dir.join(MANIFEST_FILE);
+        let Ok(text) = fs::read_to_string(&path) else {
+            return DriverManifest::default();
+        };
+        serde_json::from_str(&text).unwrap_or_else(|err| {
+            tracing::warn!(
+                "驱动缓存清单格式错误，将重新记录: {}, err={}",
+                path.display(),
+                err
+            );
+            DriverManifest::default()
+        })
+    }
+
+    fn save_manifest(&self, manifest: &DriverManifest) {
+        let path = self.cache_dir.join(MANIFEST_FILE);
+        let written = serde_json::to_string_pretty(manifest)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| fs::write(&path, text).map_err(anyhow::Error::from));
+        if let Err(err) = written {
+            tracing::warn!("写入驱动缓存清单失败: {}, err={}", path.display(), err);
+        }
+    }
+
+    fn record_driver(&self, dir_name: &str, zip_sha256: String, binary_sha256: String) {
+        let Ok(_guard) = manifest_lock().lock() else {
+            return;
+        };
+        let mut manifest = self.load_manifest();
+        manifest.drivers.insert(
+            dir_name.to_string(),
+            DriverManifestEntry {
+                zip_sha256,
+                binary_sha256,
+                recorded_at: chrono::Local::now().to_rfc3339(),
+            },
+        );
+        self.save_manifest(&manifest);
+    }
+
+    /// 使用缓存驱动前按清单校验可执行文件的 SHA-256，不一致时删除整个缓存目录以便重新下载。
+    /// 清单中没有记录的旧缓存能正常输出版本号时补录哈希，否则同样删除
+    fn verify_cached_driver(&self, driver: &Path) -> bool {
+        let Some(dir_name) = driver
+            .strip_prefix(&self.cache_dir)
+            .ok()
+            .and_then(|item| item.components().next())
+            .and_then(|item| item.as_os_str().to_str())
+            .map(str::to_string)
+        else {
+            return true;
+        };
+        let actual = match sha256_file(driver) {
+            Ok(item) => item,
+            Err(err) => {
+                tracing::warn!("读取缓存驱动失败: {:#}", err);
+                self.discard_cached_driver(&dir_name);
+                return false;
+            }
+        };
+        let expected = {
+            let Ok(_guard) = manifest_lock().lock() else {
+                return true;
+            };
+            self.load_manifest().drivers.get(&dir_name).cloned()
+        };
+        match expected {
+            Some(entry) if entry.binary_sha256 == actual => true,
+            Some(entry) => {
+                tracing::warn!(
+                    "缓存驱动哈希与清单不一致，重新下载: {}, 期望={}, 实际={}",
+                    driver.display(),
+                    entry.binary_sha256,
+                    actual
+                );
+                self.discard_cached_driver(&dir_name);
+                false
+            }
+            None if query_binary_version(&driver.to_string_lossy()).is_some() => {
+                tracing::info!("补录旧缓存驱动的哈希: {}", driver.display());
+                self.record_driver(&dir_name, String::new(), actual);
+                true
+            }
+            None => {
+                tracing::warn!(
+                    "缓存驱动无法运行且没有清单记录，重新下载: {}",
+                    driver.display()
+                );
+                self.discard_cached_driver(&dir_name);
+                false
+            }
+        }
+    }
+
+    fn discard_cached_driver(&self, dir_name: &str) {
+        let dir = self.cache_dir.join(dir_name);
+        if let Err(err) = fs::remove_dir_all(&dir) {
+            tracing::warn!("删除缓存驱动目录失败: {}, err={}", dir.display(), err);
+        }
+        if let Ok(_guard) = manifest_lock().lock() {
+            let mut manifest = self.load_manifest();
+            if manifest.drivers.remove(dir_name).is_some() {
+                self.save_manifest(&manifest);
+            }
+        }
+    }
+
     #[cfg(windows)]
     fn get_chrome_version_from_windows_registry(&self) -> Option<String> {
         let queries = [
@@ -416,10 +777,105 @@ impl ChromeDriverManager {
     }
 }
 
-pub fn get_chromedriver_path() -> Result<PathBuf> {
+/// 驱动缓存目录，启动时按配置目录设置一次；未设置时使用 ~/.cache/chromedriver
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_cache_dir(dir: PathBuf) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+/// 内部驱动镜像，启动时按配置设置一次
+static MIRROR: OnceLock<DriverMirrorConfig> = OnceLock::new();
+
+pub fn set_mirror(config: &DriverMirrorConfig) {
+    let _ = MIRROR.set(config.clone());
+}
+
+fn global_manager() -> Result<&'static ChromeDriverManager> {
     static INSTANCE: OnceCell<ChromeDriverManager> = OnceCell::new();
-    let manager = INSTANCE.get_or_try_init(|| ChromeDriverManager::new(None))?;
-    manager.get_driver_path()
+    INSTANCE.get_or_try_init(|| {
+        ChromeDriverManager::new(
+            CACHE_DIR.get().cloned(),
+            MIRROR.get().cloned().unwrap_or_default(),
+        )
+    })
+}
+
+/// pinned_version 非空时使用该固定版本，忽略检测到的 Chrome 版本；
+/// binary_path 非空时按该浏览器的版本选择驱动
+pub fn get_chromedriver_path(pinned_version: &str, binary_path: &str) -> Result<PathBuf> {
+    global_manager()?.get_driver_path(pinned_version, binary_path)
+}
+
+/// 本地已有的驱动，不触发下载；用于启动检查
+pub fn find_local_chromedriver(pinned_version: &str, binary_path: &str) -> Result<Option<PathBuf>> {
+    Ok(global_manager()?.find_local_driver(pinned_version, binary_path))
+}
+
+/// 检测浏览器版本，binary_path 为空时按常见安装位置查找
+pub fn detect_chrome_version(binary_path: &str) -> Result<Option<String>> {
+    Ok(global_manager()?.get_chrome_version(binary_path))
+}
+
+/// 确定实际启动的浏览器: 已配置路径优先；Linux 上未安装原生 Chrome/Chromium 时
+/// 回退到 Snap/Flatpak 版 Chromium
+pub fn resolve_browser_binary(configured: &str) -> Option<String> {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        return Some(configured.to_string());
+    }
+    static DETECTED: OnceLock<Option<String>> = OnceLock::new();
+    DETECTED.get_or_init(detect_sandboxed_chromium).clone()
+}
+
+fn detect_sandboxed_chromium() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let native = [
+        "/usr/bin/google-chrome",
+        "/usr/bin/google-chrome-stable",
+        "/opt/google/chrome/chrome",
+        "/usr/bin/chromium",
+    ];
+    if native.iter().any(|item| Path::new(item).exists()) {
+        return None;
+    }
+    if Path::new(SNAP_CHROMIUM).exists() {
+        tracing::info!("未找到原生 Chrome，使用 Snap Chromium: {}", SNAP_CHROMIUM);
+        return Some(SNAP_CHROMIUM.to_string());
+    }
+    let mut exports = vec![PathBuf::from("/var/lib/flatpak/exports/bin").join(FLATPAK_CHROMIUM_ID)];
+    if let Ok(home) = std::env::var("HOME") {
+        exports.push(
+            PathBuf::from(home)
+                .join(".local/share/flatpak/exports/bin")
+                .join(FLATPAK_CHROMIUM_ID),
+        );
+    }
+    let found = exports.into_iter().find(|item| item.exists())?;
+    tracing::info!(
+        "未找到原生 Chrome，使用 Flatpak Chromium: {}",
+        found.display()
+    );
+    Some(found.to_string_lossy().to_string())
+}
+
+/// Snap/Flatpak 沙箱内的浏览器无法访问 chromedriver 在 /tmp 创建的临时目录，
+/// 返回沙箱可写区域下存放用户数据目录的位置
+pub fn sandbox_profile_root(binary: &str) -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var("HOME").ok()?);
+    if binary.starts_with("/snap/") {
+        return Some(home.join("snap/chromium/common/art-rs-profiles"));
+    }
+    if binary.contains("flatpak") && binary.ends_with(FLATPAK_CHROMIUM_ID) {
+        return Some(
+            home.join(".var/app")
+                .join(FLATPAK_CHROMIUM_ID)
+                .join("data/art-rs-profiles"),
+        );
+    }
+    None
 }
 
 fn default_cache_dir() -> PathBuf {
@@ -458,8 +914,49 @@ fn platform_name() -> String {
                 "mac-x64".to_string()
             }
         }
-        _ => "linux64".to_string(),
+        _ => {
+            if cfg!(target_env = "musl") {
+                "linux-musl".to_string()
+            } else if arch == "aarch64" {
+                "linux-arm64".to_string()
+            } else if arch == "arm" {
+                "linux-arm".to_string()
+            } else {
+                "linux64".to_string()
+            }
+        }
+    }
+}
+
+fn ensure_cft_platform() -> Result<()> {
+    let platform = platform_name();
+    if CFT_PLATFORMS.contains(&platform.as_str()) {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Chrome for Testing 未提供 {} 平台的 ChromeDriver，请用系统包管理器安装 chromedriver(如 apt install chromium-driver)并确保其在 PATH 中，或通过 web_check.chromedriver_path 指定驱动路径",
+        platform
+    )
+}
+
+/// 查找发行版安装的 chromedriver: 先搜索 PATH，再检查常见安装位置
+fn find_system_driver() -> Option<PathBuf> {
+    let driver_name = driver_binary_name();
+    if let Some(paths) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&paths) {
+            let candidate = dir.join(driver_name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
     }
+    if cfg!(windows) {
+        return None;
+    }
+    SYSTEM_DRIVER_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|item| item.is_file())
 }
 
 fn chrome_version_commands() -> Vec<(String, Vec<String>)> {
@@ -495,10 +992,32 @@ fn chrome_version_commands() -> Vec<(String, Vec<String>)> {
                 vec!["--version".to_string()],
             ),
             ("chromium".to_string(), vec!["--version".to_string()]),
+            (SNAP_CHROMIUM.to_string(), vec!["--version".to_string()]),
+            (
+                "flatpak".to_string(),
+                vec![
+                    "run".to_string(),
+                    FLATPAK_CHROMIUM_ID.to_string(),
+                    "--version".to_string(),
+                ],
+            ),
         ]
     }
 }
 
+fn query_binary_version(binary_path: &str) -> Option<String> {
+    let mut c = Command::new(binary_path);
+    c.arg("--version");
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        c.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = c.output().ok()?;
+    parse_chrome_version(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| parse_chrome_version(&String::from_utf8_lossy(&output.stderr)))
+}
+
 fn parse_chrome_version(text: &str) -> Option<String> {
     let re = Regex::new(r"(\d+\.\d+\.\d+\.\d+)").ok()?;
     re.captures(text)
@@ -570,6 +1089,122 @@ fn unzip_file(zip_path: &Path, target_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// 版本号按数字逐段比较，非数字段视为 0
+fn version_parts(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|item| item.trim().parse::<u32>().unwrap_or(0))
+        .collect()
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0_u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("读取文件失败: {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 驱动缓存清单的读写锁，校验与下载可能在不同线程同时进行
+fn manifest_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+type DownloadListener = Box<dyn Fn(&DriverDownloadProgress) + Send + Sync>;
+
+/// 驱动下载进度回调，启动时注册一次
+static DOWNLOAD_LISTENER: OnceLock<DownloadListener> = OnceLock::new();
+static DOWNLOAD_ACTIVE: AtomicBool = AtomicBool::new(false);
+static DOWNLOAD_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_download_listener(listener: impl Fn(&DriverDownloadProgress) + Send + Sync + 'static) {
+    let _ = DOWNLOAD_LISTENER.set(Box::new(listener));
+}
+
+/// 请求取消正在进行的驱动下载，返回当时是否有下载在进行
+pub fn cancel_download() -> bool {
+    let active = DOWNLOAD_ACTIVE.load(Ordering::SeqCst);
+    if active {
+        DOWNLOAD_CANCELLED.store(true, Ordering::SeqCst);
+    }
+    active
+}
+
+fn emit_download_progress(progress: &DriverDownloadProgress) {
+    if let Some(listener) = DOWNLOAD_LISTENER.get() {
+        listener(progress);
+    }
+}
+
+/// 分块写入驱动压缩包并按间隔发送进度，每块之间检查取消请求
+fn stream_to_file(
+    mut response: reqwest::blocking::Response,
+    path: &Path,
+    version: &str,
+) -> Result<()> {
+    DOWNLOAD_CANCELLED.store(false, Ordering::SeqCst);
+    DOWNLOAD_ACTIVE.store(true, Ordering::SeqCst);
+    let started = Instant::now();
+    let mut progress = DriverDownloadProgress {
+        version: version.to_string(),
+        downloaded_bytes: 0,
+        total_bytes: response.content_length().unwrap_or(0),
+        bytes_per_sec: 0.0,
+        finished: false,
+    };
+    let result = (|| -> Result<()> {
+        let mut file =
+            File::create(path).with_context(|| format!("创建临时文件失败: {}", path.display()))?;
+        let mut buffer = vec![0_u8; 64 * 1024];
+        let mut last_emit = Instant::now();
+        emit_download_progress(&progress);
+        loop {
+            if DOWNLOAD_CANCELLED.load(Ordering::SeqCst) {
+                anyhow::bail!("驱动下载已取消");
+            }
+            let read = response
+                .read(&mut buffer)
+                .with_context(|| "读取驱动压缩包失败")?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&buffer[..read])
+                .with_context(|| format!("写入临时压缩包失败: {}", path.display()))?;
+            progress.downloaded_bytes += read as u64;
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                progress.bytes_per_sec =
+                    progress.downloaded_bytes as f64 / started.elapsed().as_secs_f64().max(0.001);
+                emit_download_progress(&progress);
+            }
+        }
+        Ok(())
+    })();
+    DOWNLOAD_ACTIVE.store(false, Ordering::SeqCst);
+    progress.bytes_per_sec =
+        progress.downloaded_bytes as f64 / started.elapsed().as_secs_f64().max(0.001);
+    progress.finished = true;
+    emit_download_progress(&progress);
+    match &result {
+        Ok(()) => tracing::info!(
+            "驱动压缩包下载完成: {} 字节, 耗时 {:.1}s",
+            progress.downloaded_bytes,
+            started.elapsed().as_secs_f64()
+        ),
+        Err(err) => tracing::warn!("驱动压缩包下载中断: {:#}", err),
+    }
+    result
+}
+
 fn download_lock() -> &'static Mutex<()> {
     static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
     LOCK.get_or_init(|| Mutex::new(()))
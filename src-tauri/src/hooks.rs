@@ -0,0 +1,223 @@
+use crate::models::{HookDefinition, HookInfo, WebCheckConfig};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 钩子目录，启动时设置一次
+static HOOKS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 表示使用原生网页登录流程的钩子名
+pub const NATIVE_HOOK: &str = "native";
+
+pub fn set_hooks_dir(dir: PathBuf) {
+    let _ = HOOKS_DIR.set(dir);
+}
+
+/// 扫描 hooks/ 目录: <name>.json 为钩子清单，其余按扩展名识别为脚本或可执行文件。
+/// 同名时清单优先，目录不存在时返回空列表
+pub fn discover_hooks() -> Vec<HookDefinition> {
+    let Some(dir) = HOOKS_DIR.get() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|item| item.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut hooks: Vec<HookDefinition> = Vec::new();
+    for path in &paths {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        match load_manifest(path, dir) {
+            Ok(hook) => hooks.push(hook),
+            Err(e) => tracing::warn!("跳过钩子清单 {}: {:#}", path.display(), e),
+        }
+    }
+    for path in &paths {
+        let Some(hook) = script_hook(path, dir) else {
+            continue;
+        };
+        if hooks.iter().any(|item| item.name == hook.name) {
+            continue;
+        }
+        hooks.push(hook);
+    }
+    hooks
+}
+
+fn load_manifest(path: &Path, dir: &Path) -> Result<HookDefinition> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("读取钩子清单失败: {}", path.display()))?;
+    let mut hook: HookDefinition =
+        serde_json::from_str(&raw).with_context(|| "解析钩子清单失败")?;
+    if hook.name.trim().is_empty() {
+        hook.name = file_stem(path);
+    }
+    if hook.command.trim().is_empty() && hook.url.trim().is_empty() {
+        anyhow::bail!("钩子清单缺少 command 或 url");
+    }
+    if hook.working_dir.trim().is_empty() {
+        hook.working_dir = dir.to_string_lossy().to_string();
+    }
+    Ok(hook)
+}
+
+/// 按扩展名为脚本选择解释器；发现的脚本默认使用 v2 协议，不在命令行传递凭据
+fn script_hook(path: &Path, dir: &Path) -> Option<HookDefinition> {
+    let script = path.to_string_lossy().to_string();
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let (command, args) = match ext.as_str() {
+        "py" => ("python".to_string(), vec![script]),
+        "js" | "mjs" => ("node".to_string(), vec![script]),
+        "ps1" => (
+            "powershell".to_string(),
+            vec![
+                "-NoProfile".to_string(),
+                "-ExecutionPolicy".to_string(),
+                "Bypass".to_string(),
+                "-File".to_string(),
+                script,
+            ],
+        ),
+        "sh" => ("sh".to_string(), vec![script]),
+        "exe" | "bat" | "cmd" => (script, Vec::new()),
+        "" if is_executable(path) => (script, Vec::new()),
+        _ => return None,
+    };
+    Some(HookDefinition {
+        name: file_stem(path),
+        description: String::new(),
+        command,
+        args,
+        timeout_seconds: 0,
+        hook_protocol: 2,
+        working_dir: dir.to_string_lossy().to_string(),
+        ..Default::default()
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|meta| meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// web_check.command 对应的兼容钩子
+fn legacy_hook(config: &WebCheckConfig) -> Option<HookDefinition> {
+    if config.command.trim().is_empty() && config.hook_url.trim().is_empty() {
+        return None;
+    }
+    let description = if config.command.trim().is_empty() {
+        "web_check.hook_url"
+    } else {
+        "web_check.command"
+    };
+    Some(HookDefinition {
+        name: "default".to_string(),
+        description: description.to_string(),
+        command: config.command.clone(),
+        args: config.args.clone(),
+        timeout_seconds: 0,
+        hook_protocol: config.hook_protocol,
+        working_dir: config.hook_working_dir.clone(),
+        url: config.hook_url.clone(),
+        headers: config.hook_headers.clone(),
+    })
+}
+
+fn find_hook(name: &str, config: &WebCheckConfig) -> Option<HookDefinition> {
+    config
+        .hooks
+        .iter()
+        .find(|hook| hook.name == name)
+        .cloned()
+        .or_else(|| discover_hooks().into_iter().find(|hook| hook.name == name))
+        .or_else(|| legacy_hook(config).filter(|hook| hook.name == name))
+}
+
+/// 为账号选择钩子: account_hooks > default_hook > web_check.command。
+/// 返回 None 表示走原生网页登录；指定的钩子不存在时报错，避免静默换成其他实现
+pub fn resolve_hook(username: &str, config: &WebCheckConfig) -> Result<Option<HookDefinition>> {
+    let selected = config
+        .account_hooks
+        .get(username)
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .or_else(|| Some(config.default_hook.trim()).filter(|name| !name.is_empty()));
+    let Some(name) = selected else {
+        return Ok(legacy_hook(config));
+    };
+    if name.eq_ignore_ascii_case(NATIVE_HOOK) {
+        return Ok(None);
+    }
+    find_hook(name, config)
+        .map(Some)
+        .with_context(|| format!("未找到网页签到钩子: {name}"))
+}
+
+/// 列出全部可用钩子及各自被哪些账号使用
+pub fn list_hooks(config: &WebCheckConfig, usernames: &[String]) -> Vec<HookInfo> {
+    let mut hooks: Vec<(HookDefinition, &str)> = Vec::new();
+    for hook in &config.hooks {
+        hooks.push((hook.clone(), "config"));
+    }
+    for hook in discover_hooks() {
+        if !hooks.iter().any(|(item, _)| item.name == hook.name) {
+            hooks.push((hook, "hooks_dir"));
+        }
+    }
+    if let Some(hook) = legacy_hook(config)
+        && !hooks.iter().any(|(item, _)| item.name == hook.name)
+    {
+        hooks.push((hook, "legacy"));
+    }
+
+    hooks
+        .into_iter()
+        .map(|(hook, source)| {
+            let accounts = usernames
+                .iter()
+                .filter(|username| {
+                    matches!(
+                        resolve_hook(username, config),
+                        Ok(Some(ref selected)) if selected.name == hook.name
+                    )
+                })
+                .cloned()
+                .collect();
+            let is_http = !hook.url.trim().is_empty();
+            HookInfo {
+                name: hook.name,
+                description: hook.description,
+                source: source.to_string(),
+                kind: if is_http { "http" } else { "command" }.to_string(),
+                command: if is_http { hook.url } else { hook.command },
+                hook_protocol: hook.hook_protocol.max(1),
+                accounts,
+            }
+        })
+        .collect()
+}
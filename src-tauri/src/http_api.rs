@@ -0,0 +1,426 @@
+use crate::command_error::{CommandError, ErrorCode};
+use crate::commands::maintenance::list_alerts_command;
+use crate::commands::query::query_balances_command;
+use crate::commands::snapshot::{get_cached_results_command, get_changes_command};
+use crate::models::{ApiTokenConfig, HttpApiConfig, NamedKey, ResultQuery};
+use crate::{APP_LOCKED_ERROR, AppState};
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, Request, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::broadcast;
+
+/// 接口权限: read 只能读取结果与告警；control 还可触发查询、读取 API Key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Scope {
+    Read,
+    Control,
+}
+
+impl Scope {
+    fn parse(value: &str) -> Scope {
+        if value.trim().eq_ignore_ascii_case("control") {
+            Scope::Control
+        } else {
+            Scope::Read
+        }
+    }
+}
+
+/// 通过 WebSocket 转发给外部订阅者的内部事件
+const BRIDGED_EVENTS: &[&str] = &[
+    "progress-log",
+    "balance-changed",
+    "notification",
+    "alerts-changed",
+    "rollover-prerun",
+    "slow-operation",
+    "infrastructure-error",
+    "batch-summary",
+    "state-changed",
+];
+
+/// 订阅者来不及读取时最多缓存的事件数，超出后丢弃最旧的
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone)]
+struct BridgedEvent {
+    name: &'static str,
+    /// {"event": 事件名, "payload": 事件载荷}
+    text: String,
+}
+
+struct ApiState {
+    app: AppHandle,
+    config: HttpApiConfig,
+    events: broadcast::Sender<BridgedEvent>,
+}
+
+type SharedApiState = Arc<ApiState>;
+
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl From<CommandError> for ApiError {
+    fn from(error: CommandError) -> Self {
+        let status = match error.code {
+            ErrorCode::Validation => StatusCode::BAD_REQUEST,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Busy => StatusCode::CONFLICT,
+            ErrorCode::Locked => StatusCode::LOCKED,
+            ErrorCode::Io | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, error.message)
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct QueryRequest {
+    #[serde(default)]
+    username: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ChangesQuery {
+    #[serde(default)]
+    since: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct EventFilter {
+    /// 逗号分隔的事件名，留空订阅全部
+    #[serde(default)]
+    events: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct TokenQuery {
+    #[serde(default)]
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AccountKey {
+    username: String,
+    api_key: String,
+    keys: Vec<NamedKey>,
+}
+
+/// 按配置在后台启动内嵌 HTTP 接口，启动失败只记录日志
+pub fn start(app: AppHandle, config: &HttpApiConfig) {
+    if !config.enabled {
+        return;
+    }
+    let config = config.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(app, config).await {
+            tracing::warn!("HTTP 接口启动失败: {:#}", e);
+        }
+    });
+}
+
+async fn serve(app: AppHandle, config: HttpApiConfig) -> Result<()> {
+    let addr: SocketAddr = config
+        .bind
+        .trim()
+        .parse()
+        .with_context(|| format!("http_api.bind 格式错误: {}", config.bind))?;
+    if config.tokens.is_empty() && !addr.ip().is_loopback() {
+        anyhow::bail!("未配置 http_api.tokens 时只允许监听本机地址，当前为 {addr}");
+    }
+
+    let events = bridge_events(&app);
+    let shared = Arc::new(ApiState {
+        app,
+        config,
+        events,
+    });
+    let router = Router::new()
+        .route("/api/health", get(health))
+        .route("/api/events", get(events_socket))
+        .route("/api/results", get(results))
+        .route("/api/changes", get(changes))
+        .route("/api/alerts", get(alerts))
+        .route("/api/keys", get(keys))
+        .route("/api/query", post(query))
+        .layer(middleware::from_fn_with_state(shared.clone(), guard))
+        .with_state(shared);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("监听 {addr} 失败"))?;
+    tracing::info!("HTTP 接口已启动: http://{}", addr);
+    axum::serve(listener, router)
+        .await
+        .with_context(|| "HTTP 接口异常退出")
+}
+
+/// 监听内部事件并转入广播通道；没有订阅者时直接丢弃
+fn bridge_events(app: &AppHandle) -> broadcast::Sender<BridgedEvent> {
+    let (sender, _) = broadcast::channel(EVENT_BUFFER);
+    for &name in BRIDGED_EVENTS {
+        let sender = sender.clone();
+        app.listen_any(name, move |event| {
+            if sender.receiver_count() == 0 {
+                return;
+            }
+            let payload = if event.payload().is_empty() {
+                "null"
+            } else {
+                event.payload()
+            };
+            let text = format!("{{\"event\":\"{name}\",\"payload\":{payload}}}");
+            let _ = sender.send(BridgedEvent { name, text });
+        });
+    }
+    sender
+}
+
+/// 各路径所需权限，None 表示无需鉴权
+fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    match path {
+        "/api/health" => None,
+        "/api/keys" => Some(Scope::Control),
+        _ if method == Method::POST => Some(Scope::Control),
+        _ => Some(Scope::Read),
+    }
+}
+
+/// 统一处理跨域与鉴权: 非白名单 Origin 一律拒绝，令牌取自 Authorization: Bearer 或 X-API-Token
+async fn guard(State(api): State<SharedApiState>, request: Request, next: Next) -> Response {
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    if let Some(origin) = origin.as_deref()
+        && !origin_allowed(&api.config.allowed_origins, origin)
+    {
+        return ApiError(StatusCode::FORBIDDEN, format!("不允许的来源: {origin}")).into_response();
+    }
+
+    let mut response = if request.method() == Method::OPTIONS {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        match required_scope(request.method(), request.uri().path()) {
+            Some(required) => {
+                let query_token = query_token(&request);
+                match granted_scope(&api.config, request.headers(), query_token.as_deref()) {
+                    Some(granted) if granted >= required => next.run(request).await,
+                    Some(_) => {
+                        ApiError(StatusCode::FORBIDDEN, "令牌权限不足".to_string()).into_response()
+                    }
+                    None => ApiError(StatusCode::UNAUTHORIZED, "缺少或无效的令牌".to_string())
+                        .into_response(),
+                }
+            }
+            None => next.run(request).await,
+        }
+    };
+
+    if let Some(origin) = origin
+        && let Ok(value) = HeaderValue::from_str(&origin)
+    {
+        let headers = response.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, value);
+        headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST, OPTIONS"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static("Authorization, Content-Type, X-API-Token"),
+        );
+    }
+    response
+}
+
+fn origin_allowed(allowed: &[String], origin: &str) -> bool {
+    allowed
+        .iter()
+        .map(|item| item.trim().trim_end_matches('/'))
+        .any(|item| item == "*" || item.eq_ignore_ascii_case(origin.trim_end_matches('/')))
+}
+
+/// 浏览器 WebSocket 无法设置请求头，只有事件订阅路径接受查询参数 token(按 URL 编码解析)；
+/// 其他路径只认请求头，避免令牌出现在访问日志与浏览记录中
+fn query_token(request: &Request) -> Option<String> {
+    if request.uri().path() != "/api/events" {
+        return None;
+    }
+    Query::<TokenQuery>::try_from_uri(request.uri())
+        .ok()
+        .map(|Query(item)| item.token)
+        .filter(|item| !item.trim().is_empty())
+}
+
+/// 未配置令牌时(仅限本机监听)匿名请求只有 read 权限
+fn granted_scope(
+    config: &HttpApiConfig,
+    headers: &HeaderMap,
+    query_token: Option<&str>,
+) -> Option<Scope> {
+    if config.tokens.is_empty() {
+        return Some(Scope::Read);
+    }
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            headers
+                .get("x-api-token")
+                .and_then(|value| value.to_str().ok())
+        })
+        .or(query_token)?
+        .trim();
+    config
+        .tokens
+        .iter()
+        .find(|item| !item.token.is_empty() && token_matches(item, provided))
+        .map(|item| Scope::parse(&item.scope))
+}
+
+/// 逐字节比较全部内容，耗时不随首个不同字符的位置变化
+fn token_matches(item: &ApiTokenConfig, provided: &str) -> bool {
+    let expected = item.token.as_bytes();
+    let provided = provided.as_bytes();
+    if expected.len() != provided.len() {
+        return false;
+    }
+    expected
+        .iter()
+        .zip(provided)
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({ "ok": true, "version": env!("CARGO_PKG_VERSION") }))
+}
+
+/// WebSocket 推送实时事件，可用 ?events=progress-log,balance-changed 过滤
+async fn events_socket(
+    State(api): State<SharedApiState>,
+    Query(filter): Query<EventFilter>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let wanted: Vec<String> = filter
+        .events
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect();
+    let receiver = api.events.subscribe();
+    ws.on_upgrade(move |socket| forward_events(socket, receiver, wanted))
+}
+
+async fn forward_events(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<BridgedEvent>,
+    wanted: Vec<String>,
+) {
+    tracing::debug!("WebSocket 订阅者已连接");
+    loop {
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(event) => {
+                    if !wanted.is_empty() && !wanted.iter().any(|item| item == event.name) {
+                        continue;
+                    }
+                    if socket.send(Message::Text(event.text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!("WebSocket 订阅者读取过慢，丢弃 {} 条事件", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    tracing::debug!("WebSocket 订阅者已断开");
+}
+
+/// 支持 ?search=&status=&sort=&offset=&limit= 筛选分页
+async fn results(
+    State(api): State<SharedApiState>,
+    Query(query): Query<ResultQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let results = get_cached_results_command(api.app.state::<AppState>(), Some(query)).await?;
+    Ok(Json(results))
+}
+
+async fn changes(
+    State(api): State<SharedApiState>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let changes = get_changes_command(api.app.state::<AppState>(), query.since).await?;
+    Ok(Json(changes))
+}
+
+async fn alerts(State(api): State<SharedApiState>) -> Result<impl IntoResponse, ApiError> {
+    let alerts = list_alerts_command(api.app.state::<AppState>(), Some(false)).await?;
+    Ok(Json(alerts))
+}
+
+/// 应用锁定期间与界面一样不返回 Key；只检查不刷新空闲计时，避免外部轮询让应用一直保持解锁
+async fn keys(State(api): State<SharedApiState>) -> Result<Json<Vec<AccountKey>>, ApiError> {
+    let state = api.app.state::<AppState>();
+    let lock_config = state.config.read().await.app_lock.clone();
+    if !state.app_lock.is_unlocked(&lock_config) {
+        return Err(CommandError::locked(APP_LOCKED_ERROR).into());
+    }
+    let keys = state
+        .accounts
+        .read()
+        .await
+        .iter()
+        .map(|item| AccountKey {
+            username: item.username.clone(),
+            api_key: item.api_key.clone(),
+            keys: item.keys.clone(),
+        })
+        .collect();
+    Ok(Json(keys))
+}
+
+async fn query(
+    State(api): State<SharedApiState>,
+    body: Option<Json<QueryRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let request = body.map(|Json(item)| item).unwrap_or_default();
+    let username = request
+        .username
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty());
+    let response =
+        query_balances_command(api.app.clone(), api.app.state::<AppState>(), username, None)
+            .await?;
+    Ok(Json(response))
+}
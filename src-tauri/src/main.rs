@@ -1,68 +1,214 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod api_client;
+mod app_lock;
 mod browser_pool;
+mod command_error;
+mod commands;
 mod config;
+mod cookies;
+mod currency;
 mod driver_manager;
+mod hooks;
+mod http_api;
 mod models;
 mod monitor;
+mod mqtt;
+mod notifier;
+mod paths;
 mod performance_monitor;
+mod privacy;
+mod simulation;
 mod state;
+#[cfg(test)]
+mod test_support;
+mod token_deploy;
 mod utils;
 mod web_check;
 mod web_native;
+mod webhook;
 
 use anyhow::{Context, Result};
 use chrono::Local;
+use command_error::{CommandError, CommandResult};
+use commands::accounts::sort_accounts;
+use commands::query::{merge_round_results, notify_round, snapshot_cached_balances};
+use commands::snapshot::AppSnapshot;
 use config::{RuntimeFiles, load_accounts, load_app_config, save_accounts};
-use models::{Account, AppConfig, CheckResult};
-use serde::Serialize;
-use serde_json::{Map, Value};
-use state::StateStore;
+use models::{Account, AppConfig, StartupIssue, StateChangedEvent};
+use paths::{resolve_config_dir, resolve_profile};
+use state::{SharedStateStore, StateStore};
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::sync::Arc;
-use std::time::Instant;
-use tauri::State;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
 use tokio::sync::{Mutex, RwLock};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::prelude::*;
 
+const POOL_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+const PRERUN_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// 快速刷新关闭时重新读取配置的间隔
+const FAST_REFRESH_IDLE_INTERVAL: Duration = Duration::from_secs(30);
+/// 应用锁拒绝命令时返回的错误，前端据此弹出解锁输入框
+const APP_LOCKED_ERROR: &str = "应用已锁定，请先输入 PIN 解锁";
+/// 查询进行中拒绝冲突操作时返回的错误前缀，前端据此提示等待本轮结束
+const QUERY_BUSY_ERROR: &str = "查询进行中";
+const STATE_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+/// 账号修改后合并写入账号文件的间隔，连续编辑只写一次
+const CREDENTIALS_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const MAINTENANCE_STARTUP_DELAY: Duration = Duration::from_secs(300);
+
 #[derive(Debug)]
 struct AppState {
+    /// 基础配置目录，各配置集位于其 profiles/ 下
+    base_dir: PathBuf,
+    profile: String,
+    /// 由 --profile 或 ART_RS_PROFILE 指定时为 true，此时不能在界面切换
+    profile_pinned: bool,
     files: RuntimeFiles,
     config: Arc<RwLock<AppConfig>>,
     accounts: Arc<RwLock<Vec<Account>>>,
-    state_store: Arc<Mutex<StateStore>>,
+    state_store: SharedStateStore,
     query_lock: Mutex<()>,
+    /// 正在执行的批量查询，界面据此显示忙碌状态，冲突的账号修改据此拒绝
+    query_run: std::sync::Mutex<Option<QueryRun>>,
+    /// 账号或配置每次修改时递增，与状态存储的游标共同组成快照版本
+    data_version: AtomicU64,
+    /// 状态存储变更游标的副本，由变更回调同步，发送状态事件时无需获取状态存储
+    store_cursor: AtomicU64,
+    /// 发送 state-changed 事件用，应用构建完成后设置
+    events: std::sync::OnceLock<tauri::AppHandle>,
+    /// 最近一次组装的快照，版本未变时直接复用
+    snapshot_cache: Mutex<Option<AppSnapshot>>,
+    /// 启动时解析失败的文件，处理前账号文件不接受修改
+    startup_issues: Mutex<Vec<StartupIssue>>,
+    app_lock: app_lock::AppLock,
+    /// 内存中的账号列表有尚未写入账号文件的修改
+    credentials_dirty: AtomicBool,
+    /// 最近一次从账号文件读取或写入账号文件的内容，重新加载时据此判断哪一侧做了修改
+    accounts_base: RwLock<Vec<Account>>,
+}
+
+/// 正在执行的批量查询
+#[derive(Debug, Clone)]
+struct QueryRun {
+    /// query / web_login / retry_failed / rollover_prerun
+    kind: &'static str,
+    usernames: BTreeSet<String>,
+    started: Instant,
+    started_at: String,
+}
+
+/// 批量查询的登记，释放时清除
+struct QueryRunMarker<'a>(&'a std::sync::Mutex<Option<QueryRun>>);
+
+impl Drop for QueryRunMarker<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut guard) = self.0.lock() {
+            *guard = None;
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct AppSnapshot {
-    config_dir: String,
-    query_interval: u64,
-    daily_rollover_hour: u32,
-    fallback_to_web: bool,
-    accounts: Vec<Account>,
-    cached_results: Vec<CheckResult>,
-}
+impl AppState {
+    /// 持有查询锁后登记本轮查询的账号，target 为单账号查询的目标
+    fn begin_query_run(
+        &self,
+        kind: &'static str,
+        accounts: &[Account],
+        target: Option<&str>,
+    ) -> QueryRunMarker<'_> {
+        let usernames = accounts
+            .iter()
+            .filter(|item| target.is_none_or(|target| target == item.username))
+            .map(|item| item.username.clone())
+            .collect();
+        if let Ok(mut guard) = self.query_run.lock() {
+            *guard = Some(QueryRun {
+                kind,
+                usernames,
+                started: Instant::now(),
+                started_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            });
+        }
+        QueryRunMarker(&self.query_run)
+    }
+
+    /// 账号正在本轮查询中时拒绝删除等会与查询结果写入冲突的修改
+    fn ensure_not_querying(&self, username: &str) -> CommandResult<()> {
+        let Ok(guard) = self.query_run.lock() else {
+            return Ok(());
+        };
+        match guard.as_ref() {
+            Some(run) if run.usernames.contains(username) => Err(CommandError::busy(format!(
+                "{QUERY_BUSY_ERROR}，账号 {} 正在本轮检查中，请结束后再试",
+                privacy::mask_username(username)
+            ))
+            .with_details(serde_json::json!({
+                "kind": run.kind,
+                "elapsed_secs": run.started.elapsed().as_secs(),
+            }))),
+            _ => Ok(()),
+        }
+    }
 
-#[derive(Debug, Clone, Serialize)]
-struct ActionResponse {
-    success: bool,
-    message: String,
-    accounts: Vec<Account>,
-}
+    /// 账号或配置修改后调用，使缓存的快照失效并通知界面，kind 为变化类别
+    fn invalidate_snapshot(&self, kind: &'static str) {
+        self.data_version.fetch_add(1, Ordering::AcqRel);
+        self.emit_state_changed(kind);
+    }
+
+    /// 状态存储的变更回调: 缓存结果、告警等变化同样推进版本
+    fn on_store_changed(&self, cursor: u64, kind: &'static str) {
+        self.store_cursor.store(cursor, Ordering::Release);
+        self.emit_state_changed(kind);
+    }
 
-#[derive(Debug, Clone, Serialize)]
-struct QueryResponse {
-    results: Vec<CheckResult>,
-    elapsed_secs: f64,
-    finished_at: String,
-    success_count: usize,
-    fail_count: usize,
-    total_balance: f64,
-    total_balance_count: usize,
+    fn emit_state_changed(&self, kind: &'static str) {
+        let Some(app) = self.events.get() else {
+            return;
+        };
+        let version =
+            self.data_version.load(Ordering::Acquire) + self.store_cursor.load(Ordering::Acquire);
+        if let Err(err) = app.emit("state-changed", StateChangedEvent { version, kind }) {
+            tracing::warn!("发送状态变化事件失败: {}", err);
+        }
+    }
+
+    /// 账号列表修改后调用: 快照立即失效，账号文件由后台合并写入
+    fn mark_credentials_dirty(&self) {
+        self.credentials_dirty.store(true, Ordering::Release);
+        self.invalidate_snapshot("accounts");
+    }
+
+    /// 把尚未落盘的账号修改写入账号文件，写入失败时保留待写标记以便重试
+    async fn flush_credentials(&self) -> Result<()> {
+        if !self.credentials_dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let accounts = self.accounts.read().await.clone();
+        let path = self.files.credentials_file.clone();
+        let written = accounts.clone();
+        let result = tokio::task::spawn_blocking(move || save_accounts(&path, &written))
+            .await
+            .context("写入账号文件任务失败")
+            .and_then(|item| item);
+        match result {
+            Ok(()) => {
+                privacy::set_usernames(&accounts);
+                *self.accounts_base.write().await = accounts;
+            }
+            Err(_) => self.credentials_dirty.store(true, Ordering::Release),
+        }
+        result
+    }
+
+    async fn snapshot_version(&self) -> u64 {
+        self.data_version.load(Ordering::Acquire) + self.state_store.read().await.change_cursor()
+    }
 }
 
 fn main() {
@@ -72,21 +218,48 @@ fn main() {
 }
 
 fn run_app() -> Result<()> {
-    let config_dir = resolve_config_dir();
+    let base_dir = resolve_config_dir();
+    let (profile, profile_pinned) = resolve_profile(&base_dir);
+    let config_dir = config::profile_dir(&base_dir, &profile);
+    if let Err(e) = std::fs::create_dir_all(&config_dir) {
+        eprintln!("创建配置集目录失败: {} {}", config_dir.display(), e);
+    }
     let files = RuntimeFiles::new(config_dir);
-    let config = load_app_config(&files.config_file)?;
-    let log_path = resolve_log_path(&files, &config);
-    init_logger(&config.logging.level, &log_path)?;
+    let mut startup_issues = Vec::new();
+    let config = load_app_config(&files.config_file).unwrap_or_else(|e| {
+        startup_issues.push(startup_issue("config", &files.config_file, &e));
+        AppConfig::default()
+    });
+    let files = files.with_overrides(&config);
+    init_logger(&config.logging.level, &files.log_file)?;
+    currency::configure(&config.currency);
+    privacy::configure(&config.privacy);
+    if let Err(e) = privacy::load_secret(&files.privacy_secret_file) {
+        tracing::warn!("加载隐私模式打码密钥失败，本次使用临时密钥: {:#}", e);
+    }
 
     tracing::info!("ART-rs Tauri 启动");
-    tracing::info!("配置目录: {}", files.config_dir.display());
     tracing::info!(
-        "每日网页登录切日时间: {:02}:00",
-        config.performance.daily_rollover_hour
+        "配置目录: {} (配置集 {})",
+        files.config_dir.display(),
+        profile
+    );
+    tracing::info!(
+        "每日网页登录切日时间: {:02}:00 ({})",
+        config.performance.daily_rollover_hour,
+        if config.performance.daily_rollover_timezone.trim().is_empty() {
+            "本机时区"
+        } else {
+            config.performance.daily_rollover_timezone.trim()
+        }
     );
 
-    let mut accounts = load_accounts(&files.credentials_file)?;
+    let mut accounts = load_accounts(&files.credentials_file).unwrap_or_else(|e| {
+        startup_issues.push(startup_issue("credentials", &files.credentials_file, &e));
+        Vec::new()
+    });
     sort_accounts(&mut accounts);
+    privacy::set_usernames(&accounts);
     tracing::info!("成功加载 {} 个账号", accounts.len());
     if accounts.is_empty() {
         tracing::warn!(
@@ -97,425 +270,399 @@ fn run_app() -> Result<()> {
     }
 
     let state_store = StateStore::load(
-        files.balance_cache_file.clone(),
-        files.daily_web_state_file.clone(),
+        &files,
         config.performance.daily_rollover_hour,
+        &config.performance.daily_rollover_timezone,
+        config.retention.clone(),
     )
     .with_context(|| "初始化状态存储失败")?;
+    for (path, error) in state_store.load_failures() {
+        startup_issues.push(StartupIssue {
+            kind: "state".to_string(),
+            file: path.display().to_string(),
+            error,
+        });
+    }
+    for (provider, rate) in state_store.unit_rates() {
+        web_native::set_known_unit_rate(&provider, rate);
+    }
+    for issue in &startup_issues {
+        tracing::error!(
+            "启动检查: {} 解析失败，已进入安全模式: {}",
+            issue.file,
+            issue.error
+        );
+    }
+
+    if let Ok(mut guard) = performance_monitor::get_performance_monitor().lock() {
+        guard.set_slow_thresholds(
+            config.performance.slow_threshold_secs,
+            &config.performance.slow_thresholds,
+        );
+    }
+
+    browser_pool::set_driver_log_dir(files.logs_dir.clone());
+    cookies::set_cookies_dir(files.cookies_dir.clone());
+    hooks::set_hooks_dir(files.hooks_dir.clone());
+    driver_manager::set_cache_dir(files.driver_cache_dir.clone());
+    driver_manager::set_mirror(&config.web_check.driver_mirror);
+    performance_monitor::start_system_sampler(config.performance.system_sample_interval_secs);
+
+    let prewarm_on_startup = config
+        .web_check
+        .prewarm_on_startup
+        .then(|| (config.web_check.clone(), config.browser.clone()));
 
+    let mqtt_config = config.mqtt.clone();
+    let maintenance_interval_hours = config.retention.maintenance_interval_hours;
+    let http_api_config = config.http_api.clone();
+    let store_cursor = state_store.change_cursor();
+    let state_store = Arc::new(RwLock::new(state_store));
     let app_state = AppState {
+        base_dir,
+        profile,
+        profile_pinned,
         files,
         config: Arc::new(RwLock::new(config)),
-        accounts: Arc::new(RwLock::new(accounts)),
-        state_store: Arc::new(Mutex::new(state_store)),
+        accounts: Arc::new(RwLock::new(accounts.clone())),
+        state_store: state_store.clone(),
         query_lock: Mutex::new(()),
+        query_run: std::sync::Mutex::new(None),
+        data_version: AtomicU64::new(0),
+        store_cursor: AtomicU64::new(store_cursor),
+        events: std::sync::OnceLock::new(),
+        snapshot_cache: Mutex::new(None),
+        startup_issues: Mutex::new(startup_issues.clone()),
+        app_lock: app_lock::AppLock::default(),
+        credentials_dirty: AtomicBool::new(false),
+        accounts_base: RwLock::new(accounts),
     };
 
     let app = tauri::Builder::default()
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
-            get_snapshot_command,
-            reload_accounts_command,
-            upsert_account_command,
-            remove_account_command,
-            query_balances_command,
-            web_login_only_command,
-            get_cached_results_command,
-            save_claude_token_command,
-            save_openai_key_command,
-            performance_report_command,
-            get_current_claude_account_command
+            commands::snapshot::get_snapshot_command,
+            commands::accounts::reload_accounts_command,
+            commands::accounts::upsert_account_command,
+            commands::accounts::remove_account_command,
+            commands::query::is_query_running_command,
+            commands::query::query_balances_command,
+            commands::query::web_login_only_command,
+            commands::snapshot::get_cached_results_command,
+            commands::snapshot::get_changes_command,
+            commands::tokens::save_claude_token_command,
+            commands::tokens::save_openai_key_command,
+            commands::tokens::auto_switch_claude_command,
+            commands::tokens::list_deploy_plans_command,
+            commands::tokens::deploy_plan_command,
+            commands::tokens::list_deployments_command,
+            commands::tokens::list_wsl_distros_command,
+            commands::tokens::restore_tool_config_command,
+            commands::tokens::create_account_token_command,
+            commands::tokens::list_token_labels_command,
+            commands::accounts::discover_accounts_command,
+            commands::accounts::import_discovered_accounts_command,
+            commands::accounts::export_shared_config_command,
+            commands::lock::get_lock_status_command,
+            commands::accounts::list_removed_accounts_command,
+            commands::accounts::restore_account_command,
+            commands::lock::unlock_command,
+            commands::lock::lock_command,
+            commands::lock::hash_pin_command,
+            commands::lock::set_privacy_mode_command,
+            commands::accounts::read_shared_config_command,
+            commands::accounts::import_shared_config_command,
+            commands::tokens::export_env_command,
+            commands::system::performance_report_command,
+            commands::tokens::get_current_claude_account_command,
+            commands::tokens::list_account_tokens_command,
+            commands::system::prewarm_browser_pool_command,
+            commands::system::smoke_test_command,
+            commands::system::preflight_command,
+            commands::accounts::reorder_accounts_command,
+            commands::system::open_path_command,
+            commands::system::browser_pool_report_command,
+            commands::system::set_driver_verbose_command,
+            commands::query::retry_failed_command,
+            commands::query::interactive_login_command,
+            commands::query::resync_quota_command,
+            commands::query::preview_quota_sync_command,
+            commands::accounts::change_password_command,
+            commands::accounts::export_account_cookies_command,
+            commands::accounts::import_account_cookies_command,
+            commands::accounts::mark_web_done_command,
+            commands::accounts::reset_web_state_command,
+            commands::maintenance::get_daily_status_command,
+            commands::maintenance::get_selector_health_command,
+            commands::maintenance::get_account_stats_command,
+            commands::system::cancel_driver_download_command,
+            commands::maintenance::get_schedule_command,
+            commands::maintenance::get_state_dump_command,
+            commands::maintenance::repair_state_command,
+            commands::maintenance::run_maintenance_command,
+            commands::maintenance::list_alerts_command,
+            commands::maintenance::ack_alert_command,
+            commands::maintenance::list_hooks_command,
+            commands::system::get_system_metrics_command,
+            commands::system::get_paths_command,
+            commands::system::list_profiles_command,
+            commands::system::switch_profile_command,
+            commands::system::get_startup_issues_command,
+            commands::system::resolve_startup_issue_command
         ])
         .build(tauri::generate_context!())
         .with_context(|| "构建 Tauri 应用失败")?;
 
-    app.run(|_app, _event| {});
-    browser_pool::shutdown_global_pool();
-    Ok(())
-}
-
-#[tauri::command]
-async fn get_snapshot_command(state: State<'_, AppState>) -> Result<AppSnapshot, String> {
-    let config = state.config.read().await.clone();
-    let accounts = state.accounts.read().await.clone();
-    let cached_results = build_cached_results(&accounts, state.state_store.clone()).await;
-    Ok(AppSnapshot {
-        config_dir: state.files.config_dir.to_string_lossy().to_string(),
-        query_interval: config.performance.query_interval.max(1),
-        daily_rollover_hour: config.performance.daily_rollover_hour,
-        fallback_to_web: config.api.fallback_to_web,
-        accounts,
-        cached_results,
-    })
-}
-
-#[tauri::command]
-async fn reload_accounts_command(state: State<'_, AppState>) -> Result<ActionResponse, String> {
-    let accounts = reload_accounts_from_disk(&state).await?;
-    Ok(ActionResponse {
-        success: true,
-        message: format!("已重新加载 {} 个账号", accounts.len()),
-        accounts,
-    })
-}
-
-#[tauri::command]
-async fn upsert_account_command(
-    state: State<'_, AppState>,
-    username: String,
-    password: String,
-    api_key: Option<String>,
-) -> Result<ActionResponse, String> {
-    let username = username.trim().to_string();
-    let password = password.trim().to_string();
-    let api_key = api_key.unwrap_or_default().trim().to_string();
-    if username.is_empty() || password.is_empty() {
-        return Err("用户名和密码不能为空".to_string());
-    }
-
-    let mut guard = state.accounts.write().await;
-    let mut accounts = guard.clone();
-    let mut replaced = false;
-    for item in &mut accounts {
-        if item.username == username {
-            item.password = password.clone();
-            item.api_key = api_key.clone();
-            replaced = true;
-            break;
-        }
-    }
-    if !replaced {
-        accounts.push(Account {
-            username: username.clone(),
-            password,
-            api_key,
+    let slow_handle = app.handle().clone();
+    if let Ok(mut guard) = performance_monitor::get_performance_monitor().lock() {
+        guard.set_slow_listener(move |event| {
+            let payload = privacy::mask_json(event).unwrap_or_default();
+            if let Err(err) = slow_handle.emit("slow-operation", payload) {
+                tracing::warn!("发送慢操作事件失败: {}", err);
+            }
         });
     }
-    sort_accounts(&mut accounts);
 
-    save_accounts(&state.files.credentials_file, &accounts)
-        .map_err(|e| format!("写入账号文件失败: {e}"))?;
-    *guard = accounts.clone();
-
-    Ok(ActionResponse {
-        success: true,
-        message: if replaced {
-            format!("已更新账号: {username}")
-        } else {
-            format!("已新增账号: {username}")
-        },
-        accounts,
-    })
-}
+    let download_handle = app.handle().clone();
+    driver_manager::set_download_listener(move |progress| {
+        if let Err(err) = download_handle.emit("driver-download-progress", progress) {
+            tracing::warn!("发送驱动下载进度事件失败: {}", err);
+        }
+    });
 
-#[tauri::command]
-async fn remove_account_command(
-    state: State<'_, AppState>,
-    username: String,
-) -> Result<ActionResponse, String> {
-    let username = username.trim().to_string();
-    if username.is_empty() {
-        return Err("账号名不能为空".to_string());
-    }
-    let mut guard = state.accounts.write().await;
-    let before_len = guard.len();
-    let mut accounts = guard.clone();
-    accounts.retain(|item| item.username != username);
-    if accounts.len() == before_len {
-        return Ok(ActionResponse {
-            success: false,
-            message: format!("未找到账号: {username}"),
-            accounts: guard.clone(),
+    let infra_handle = app.handle().clone();
+    browser_pool::set_infrastructure_listener(move |event| {
+        let payload = privacy::mask_json(event).unwrap_or_default();
+        if let Err(err) = infra_handle.emit("infrastructure-error", payload) {
+            tracing::warn!("发送浏览器故障事件失败: {}", err);
+        }
+    });
+
+    let _ = app.state::<AppState>().events.set(app.handle().clone());
+    let change_handle = app.handle().clone();
+    state::set_change_listener(move |cursor, kind| {
+        change_handle
+            .state::<AppState>()
+            .on_store_changed(cursor, kind);
+    });
+
+    tauri::async_runtime::spawn(async {
+        loop {
+            tokio::time::sleep(POOL_SNAPSHOT_INTERVAL).await;
+            let Some(stats) = browser_pool::global_pool_stats() else {
+                continue;
+            };
+            if let Ok(mut guard) = performance_monitor::get_performance_monitor().lock() {
+                guard.record_pool_snapshot(stats);
+            }
+        }
+    });
+
+    if !startup_issues.is_empty()
+        && let Err(e) = app.emit("startup-issues", &startup_issues)
+    {
+        tracing::warn!("发送启动问题事件失败: {}", e);
+    }
+
+    spawn_rollover_prerun(app.handle().clone());
+    spawn_fast_refresh(app.handle().clone());
+    spawn_state_flusher(state_store.clone());
+    spawn_credentials_flusher(app.handle().clone());
+    spawn_maintenance(state_store.clone(), maintenance_interval_hours);
+    mqtt::start(&mqtt_config);
+    http_api::start(app.handle().clone(), &http_api_config);
+
+    if let Some((web_config, browser_config)) = prewarm_on_startup {
+        tauri::async_runtime::spawn(async move {
+            match web_native::prewarm_browser_pool(
+                &web_config,
+                &browser_config,
+                web_config.prewarm_navigate,
+            )
+            .await
+            {
+                Ok(message) => tracing::info!("启动预热: {}", message),
+                Err(err) => tracing::warn!("启动预热浏览器池失败: {}", err),
+            }
         });
     }
 
-    save_accounts(&state.files.credentials_file, &accounts)
-        .map_err(|e| format!("删除账号失败: {e}"))?;
-    *guard = accounts.clone();
-    Ok(ActionResponse {
-        success: true,
-        message: format!("已删除账号: {username}"),
-        accounts,
-    })
-}
-
-#[tauri::command]
-async fn query_balances_command(
-    app: tauri::AppHandle,
-    state: State<'_, AppState>,
-    target_username: Option<String>,
-) -> Result<QueryResponse, String> {
-    let _query_guard = state.query_lock.lock().await;
-
-    let accounts = state.accounts.read().await.clone();
-    let config = state.config.read().await.clone();
-    let started = Instant::now();
-    let results = monitor::check_accounts(
-        accounts,
-        config,
-        state.state_store.clone(),
-        target_username.map(|item| item.trim().to_string()),
-        app,
-    )
-    .await;
-
-    let elapsed_secs = started.elapsed().as_secs_f64();
-    let success_count = results.iter().filter(|item| item.success).count();
-    let fail_count = results.len().saturating_sub(success_count);
-    let (total_balance, total_balance_count) = calculate_total_balance(&results);
-    Ok(QueryResponse {
-        results,
-        elapsed_secs,
-        finished_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-        success_count,
-        fail_count,
-        total_balance,
-        total_balance_count,
-    })
-}
-
-#[tauri::command]
-async fn web_login_only_command(
-    app: tauri::AppHandle,
-    state: State<'_, AppState>,
-    target_username: Option<String>,
-) -> Result<QueryResponse, String> {
-    let _query_guard = state.query_lock.lock().await;
-
-    let accounts = state.accounts.read().await.clone();
-    let config = state.config.read().await.clone();
-    let started = Instant::now();
-    let results = monitor::check_accounts_web_only(
-        accounts,
-        config,
-        state.state_store.clone(),
-        target_username.map(|item| item.trim().to_string()),
-        app,
-    )
-    .await;
-
-    let elapsed_secs = started.elapsed().as_secs_f64();
-    let success_count = results.iter().filter(|item| item.success).count();
-    let fail_count = results.len().saturating_sub(success_count);
-    let (total_balance, total_balance_count) = calculate_total_balance(&results);
-    Ok(QueryResponse {
-        results,
-        elapsed_secs,
-        finished_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-        success_count,
-        fail_count,
-        total_balance,
-        total_balance_count,
-    })
-}
-
-#[tauri::command]
-async fn get_cached_results_command(
-    state: State<'_, AppState>,
-) -> Result<Vec<CheckResult>, String> {
-    let accounts = state.accounts.read().await.clone();
-    Ok(build_cached_results(&accounts, state.state_store.clone()).await)
-}
-
-#[tauri::command]
-async fn save_claude_token_command(
-    state: State<'_, AppState>,
-    username: String,
-) -> Result<String, String> {
-    let key = find_account_api_key(&state, username.trim()).await?;
-    let path = save_claude_token(&key).map_err(|e| format!("写入 Claude Token 失败: {e}"))?;
-    Ok(format!("已写入 Claude Token: {}", path.display()))
-}
-
-#[tauri::command]
-async fn save_openai_key_command(
-    state: State<'_, AppState>,
-    username: String,
-) -> Result<String, String> {
-    let key = find_account_api_key(&state, username.trim()).await?;
-    save_openai_key(&key).map_err(|e| format!("写入 OpenAI Key 失败: {e}"))
-}
-
-#[tauri::command]
-fn performance_report_command() -> String {
-    let monitor = performance_monitor::get_performance_monitor();
-    match monitor.lock() {
-        Ok(guard) => guard.generate_report(),
-        Err(_) => "性能监控状态不可用".to_string(),
-    }
-}
-
-#[tauri::command]
-async fn get_current_claude_account_command(
-    state: State<'_, AppState>,
-) -> Result<String, String> {
-    let token = read_current_claude_token().unwrap_or_default();
-    if token.is_empty() {
-        return Ok(String::new());
-    }
-    let accounts = state.accounts.read().await;
-    for account in accounts.iter() {
-        if !account.api_key.is_empty() && account.api_key == token {
-            return Ok(account.username.clone());
+    app.run(move |app, event| {
+        if let tauri::RunEvent::Exit = event {
+            if let Err(e) =
+                tauri::async_runtime::block_on(app.state::<AppState>().flush_credentials())
+            {
+                tracing::warn!("退出时写入账号文件失败: {:#}", e);
+            }
+            let mut store = tauri::async_runtime::block_on(state_store.write());
+            if let Err(e) = store.flush() {
+                tracing::warn!("退出时保存本地状态失败: {}", e);
+            }
+            tauri::async_runtime::block_on(async {
+                let _ = tokio::time::timeout(Duration::from_secs(2), mqtt::shutdown()).await;
+            });
         }
-    }
-    Ok(String::new())
+    });
+    browser_pool::shutdown_global_pool();
+    Ok(())
 }
 
-async fn find_account_api_key(
-    state: &State<'_, AppState>,
-    username: &str,
-) -> Result<String, String> {
-    if username.trim().is_empty() {
-        return Err("账号名不能为空".to_string());
-    }
-    let account = state
-        .accounts
-        .read()
-        .await
-        .iter()
-        .find(|item| item.username == username)
-        .cloned();
-    let Some(account) = account else {
-        return Err(format!("未找到账号: {username}"));
-    };
-    if account.api_key.trim().is_empty() {
-        return Err(format!("账号 {username} 未配置 API Key"));
+fn startup_issue(kind: &str, path: &Path, error: &anyhow::Error) -> StartupIssue {
+    StartupIssue {
+        kind: kind.to_string(),
+        file: path.display().to_string(),
+        error: format!("{error:#}"),
     }
-    Ok(account.api_key)
 }
 
-async fn reload_accounts_from_disk(state: &State<'_, AppState>) -> Result<Vec<Account>, String> {
-    let mut guard = state.accounts.write().await;
-    let mut accounts = load_accounts(&state.files.credentials_file)
-        .map_err(|e| format!("读取账号文件失败: {e}"))?;
-    sort_accounts(&mut accounts);
-    *guard = accounts.clone();
-    Ok(accounts)
-}
-
-async fn build_cached_results(
-    accounts: &[Account],
-    state_store: Arc<Mutex<StateStore>>,
-) -> Vec<CheckResult> {
-    let guard = state_store.lock().await;
-    let mut results = Vec::with_capacity(accounts.len());
-    for account in accounts {
-        if let Some(record) = guard.get_cached_balance_record(&account.username) {
-            results.push(CheckResult {
-                username: account.username.clone(),
-                success: true,
-                balance_text: record.balance.clone(),
-                source: "cache".to_string(),
-                message: if record.updated_at.trim().is_empty() {
-                    "缓存余额".to_string()
-                } else {
-                    format!("缓存更新时间: {}", record.updated_at)
-                },
-            });
-        } else {
-            results.push(CheckResult {
-                username: account.username.clone(),
-                success: false,
-                balance_text: "等待".to_string(),
-                source: "-".to_string(),
-                message: "待机".to_string(),
-            });
+/// 定时把 StateStore 中延迟写入的修改落盘，同一时间窗口内的多次修改只写一次文件
+fn spawn_state_flusher(state_store: SharedStateStore) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(STATE_FLUSH_INTERVAL).await;
+            // 先用读锁检查，避免没有修改时也阻塞读取；写文件在释放锁之后进行
+            if !state_store.read().await.is_dirty() {
+                continue;
+            }
+            let pending = state_store.write().await.take_pending_writes();
+            if let Err(e) = pending.write() {
+                tracing::warn!("后台保存本地状态失败，稍后重试: {}", e);
+                state_store.write().await.mark_dirty();
+            }
         }
-    }
-    results
+    });
 }
 
-fn calculate_total_balance(results: &[CheckResult]) -> (f64, usize) {
-    let mut total = 0.0_f64;
-    let mut count = 0_usize;
-    for row in results {
-        if !row.success {
-            continue;
-        }
-        if let Some(value) = utils::parse_first_number(&row.balance_text) {
-            total += value;
-            count += 1;
+/// 合并写入账号文件: 账号修改只标记待写，由该任务按固定间隔落盘
+fn spawn_credentials_flusher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CREDENTIALS_FLUSH_INTERVAL).await;
+            if let Err(e) = app.state::<AppState>().flush_credentials().await {
+                tracing::warn!("后台写入账号文件失败，稍后重试: {:#}", e);
+            }
         }
-    }
-    (total, count)
+    });
 }
 
-
-fn sort_accounts(accounts: &mut [Account]) {
-    accounts.sort_by(|a, b| a.username.cmp(&b.username));
-}
-
-fn resolve_config_dir() -> PathBuf {
-    if let Some(arg_path) = parse_config_dir_from_args() {
-        return arg_path;
+/// 按 retention.maintenance_interval_hours 定时执行保留策略清理
+fn spawn_maintenance(state_store: SharedStateStore, interval_hours: u64) {
+    if interval_hours == 0 {
+        return;
     }
-    if let Ok(raw) = std::env::var("ART_RS_CONFIG_DIR") {
-        let text = raw.trim();
-        if !text.is_empty() {
-            return PathBuf::from(text);
-        }
-    }
-
-    let mut seeds = Vec::new();
-    if let Ok(cwd) = std::env::current_dir() {
-        seeds.push(cwd);
-    }
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(parent) = exe.parent() {
-            seeds.push(parent.to_path_buf());
-        }
-    }
-    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    seeds.push(manifest_dir.clone());
-    if let Some(parent) = manifest_dir.parent() {
-        seeds.push(parent.to_path_buf());
-    }
-
-    let mut visited = std::collections::HashSet::new();
-    for dir in seeds {
-        let normalized = dir.to_string_lossy().to_string();
-        if !visited.insert(normalized) {
-            continue;
+    tauri::async_runtime::spawn(async move {
+        // 启动稍后先执行一次，避免长期不重启时从不清理
+        tokio::time::sleep(MAINTENANCE_STARTUP_DELAY).await;
+        loop {
+            let report = state_store.write().await.run_maintenance();
+            tracing::info!("{}", report.message);
+            tokio::time::sleep(Duration::from_secs(interval_hours * 3600)).await;
         }
-        if has_runtime_files(&dir) {
-            tracing::info!("已自动定位配置目录: {}", dir.display());
-            return dir;
+    });
+}
+
+/// 周期结束前为尚未完成当日网页登录的账号补跑一次，避免漏签
+fn spawn_rollover_prerun(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_prerun_day = None;
+        loop {
+            tokio::time::sleep(PRERUN_CHECK_INTERVAL).await;
+            let state = app.state::<AppState>();
+            let lead_minutes = state
+                .config
+                .read()
+                .await
+                .performance
+                .rollover_prerun_minutes;
+            if lead_minutes == 0 {
+                continue;
+            }
+            let (cycle_day, remaining_secs) = {
+                let store = state.state_store.read().await;
+                (store.current_cycle_day(), store.seconds_until_rollover())
+            };
+            if last_prerun_day == Some(cycle_day) || remaining_secs > (lead_minutes * 60) as i64 {
+                continue;
+            }
+            // 正在查询时等下一次检查再补跑
+            let Ok(_query_guard) = state.query_lock.try_lock() else {
+                continue;
+            };
+            last_prerun_day = Some(cycle_day);
+
+            let all_accounts = state.accounts.read().await.clone();
+            let pending: Vec<Account> = {
+                let store = state.state_store.read().await;
+                all_accounts
+                    .iter()
+                    .filter(|item| store.should_force_web_query(&item.username))
+                    .cloned()
+                    .collect()
+            };
+            if pending.is_empty() {
+                tracing::info!("切日前检查: 当前周期所有账号均已完成网页登录");
+                continue;
+            }
+            let names: Vec<&str> = pending.iter().map(|item| item.username.as_str()).collect();
+            tracing::info!(
+                "距离切日还有 {} 分钟，为 {} 个未完成网页登录的账号补跑: {}",
+                remaining_secs / 60,
+                pending.len(),
+                names.join(", ")
+            );
+            let _query_run = state.begin_query_run("rollover_prerun", &pending, None);
+
+            let config = state.config.read().await.clone();
+            let notify_config = config.notify.clone();
+            let before = snapshot_cached_balances(&state.state_store, &pending).await;
+            let results = monitor::check_accounts_web_only(
+                pending,
+                config,
+                state.state_store.clone(),
+                None,
+                app.clone(),
+            )
+            .await;
+            notify_round(&app, &state.state_store, &notify_config, &results, &before).await;
+            let merged = merge_round_results(&all_accounts, state.state_store.clone()).await;
+            if let Err(e) = app.emit("rollover-prerun", privacy::mask_results(&merged)) {
+                tracing::warn!("发送切日前补跑事件失败: {}", e);
+            }
         }
-    }
-
-    let fallback = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-    tracing::warn!(
-        "未定位到配置目录（config.json/credentials.txt），回退当前目录: {}",
-        fallback.display()
-    );
-    fallback
-}
-
-fn has_runtime_files(dir: &Path) -> bool {
-    dir.join("credentials.txt").exists() || dir.join("config.json").exists()
-}
-
-fn parse_config_dir_from_args() -> Option<PathBuf> {
-    let mut args = std::env::args().skip(1);
-    while let Some(arg) = args.next() {
-        if arg == "--config-dir" {
-            if let Some(value) = args.next() {
-                return Some(PathBuf::from(value));
+    });
+}
+
+/// 按 performance.fast_refresh_secs 循环做仅 API 的快速刷新。
+/// 不占用查询锁，网页登录轮次进行中也照常刷新；有更新时推送合并后的结果
+fn spawn_fast_refresh(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = app.state::<AppState>();
+            let config = state.config.read().await.clone();
+            let interval_secs = config.performance.fast_refresh_secs;
+            if interval_secs == 0 {
+                tokio::time::sleep(FAST_REFRESH_IDLE_INTERVAL).await;
+                continue;
+            }
+            tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+
+            let accounts = state.accounts.read().await.clone();
+            let updated = monitor::refresh_balances_api_only(
+                accounts.clone(),
+                config,
+                state.state_store.clone(),
+                app.clone(),
+            )
+            .await;
+            if updated.is_empty() {
+                continue;
+            }
+            let merged = merge_round_results(&accounts, state.state_store.clone()).await;
+            if let Err(e) = app.emit("fast-refresh", privacy::mask_results(&merged)) {
+                tracing::warn!("发送快速刷新事件失败: {}", e);
             }
-            return None;
         }
-    }
-    None
-}
-
-fn resolve_log_path(files: &RuntimeFiles, config: &AppConfig) -> PathBuf {
-    let candidate = PathBuf::from(config.logging.file.trim());
-    if candidate.is_absolute() {
-        candidate
-    } else {
-        files.config_dir.join(candidate)
-    }
+    });
 }
 
 fn init_logger(level: &str, log_path: &Path) -> Result<()> {
@@ -549,12 +696,13 @@ fn init_logger(level: &str, log_path: &Path) -> Result<()> {
 
     let stdout_layer = tracing_subscriber::fmt::layer()
         .with_target(true)
-        .with_thread_names(true);
+        .with_thread_names(true)
+        .with_writer(privacy::MaskedMakeWriter(std::io::stdout));
     let file_layer = tracing_subscriber::fmt::layer()
         .with_ansi(false)
         .with_target(true)
         .with_thread_names(true)
-        .with_writer(file_writer);
+        .with_writer(privacy::MaskedMakeWriter(file_writer));
 
     tracing_subscriber::registry()
         .with(env_filter)
@@ -566,194 +714,3 @@ fn init_logger(level: &str, log_path: &Path) -> Result<()> {
     tracing::info!("日志文件: {}", log_path.display());
     Ok(())
 }
-
-fn home_dir() -> Result<PathBuf> {
-    std::env::var_os("HOME")
-        .or_else(|| std::env::var_os("USERPROFILE"))
-        .map(PathBuf::from)
-        .with_context(|| "未找到用户目录")
-}
-
-fn read_current_claude_token() -> Result<String> {
-    let home = home_dir()?;
-    let target = home.join(".claude").join("settings.json");
-    if !target.exists() {
-        return Ok(String::new());
-    }
-    let raw = std::fs::read_to_string(&target)
-        .with_context(|| format!("读取配置失败: {}", target.display()))?;
-    let root: Value = serde_json::from_str(&raw).unwrap_or(Value::Null);
-    let token = root
-        .get("env")
-        .and_then(|env| env.get("ANTHROPIC_AUTH_TOKEN"))
-        .and_then(Value::as_str)
-        .unwrap_or("")
-        .trim()
-        .to_string();
-    Ok(token)
-}
-
-fn save_claude_token(token: &str) -> Result<PathBuf> {
-    let home = home_dir()?;
-    let target = home.join(".claude").join("settings.json");
-    if let Some(parent) = target.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("创建目录失败: {}", parent.display()))?;
-    }
-
-    let mut root = if target.exists() {
-        let raw = std::fs::read_to_string(&target)
-            .with_context(|| format!("读取配置失败: {}", target.display()))?;
-        serde_json::from_str::<Value>(&raw).unwrap_or_else(|_| Value::Object(Map::new()))
-    } else {
-        Value::Object(Map::new())
-    };
-
-    if !root.is_object() {
-        root = Value::Object(Map::new());
-    }
-    let obj = root.as_object_mut().expect("root object");
-    let env_value = obj
-        .entry("env".to_string())
-        .or_insert_with(|| Value::Object(Map::new()));
-    if !env_value.is_object() {
-        *env_value = Value::Object(Map::new());
-    }
-    let env_obj = env_value.as_object_mut().expect("env object");
-    env_obj.insert(
-        "ANTHROPIC_AUTH_TOKEN".to_string(),
-        Value::String(token.to_string()),
-    );
-
-    let content = serde_json::to_string_pretty(&root).with_context(|| "序列化配置失败")?;
-    std::fs::write(&target, content)
-        .with_context(|| format!("写入配置失败: {}", target.display()))?;
-    Ok(target)
-}
-
-fn save_openai_key_local(token: &str) -> Result<PathBuf> {
-    let home = home_dir()?;
-    let target = home.join(".codex").join("auth.json");
-    if let Some(parent) = target.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("创建目录失败: {}", parent.display()))?;
-    }
-
-    let mut root = if target.exists() {
-        let raw = std::fs::read_to_string(&target)
-            .with_context(|| format!("读取配置失败: {}", target.display()))?;
-        serde_json::from_str::<Value>(&raw).unwrap_or_else(|_| Value::Object(Map::new()))
-    } else {
-        Value::Object(Map::new())
-    };
-
-    if !root.is_object() {
-        root = Value::Object(Map::new());
-    }
-    let obj = root.as_object_mut().expect("root object");
-    obj.insert(
-        "OPENAI_API_KEY".to_string(),
-        Value::String(token.to_string()),
-    );
-
-    let content = serde_json::to_string_pretty(&root).with_context(|| "序列化配置失败")?;
-    std::fs::write(&target, content)
-        .with_context(|| format!("写入配置失败: {}", target.display()))?;
-    Ok(target)
-}
-
-fn decode_wsl_output(bytes: &[u8]) -> String {
-    if bytes.len() >= 2 && bytes[1] == 0 {
-        let mut u16s = Vec::with_capacity(bytes.len() / 2);
-        for chunk in bytes.chunks_exact(2) {
-            u16s.push(u16::from_le_bytes([chunk[0], chunk[1]]));
-        }
-        if let Ok(text) = String::from_utf16(&u16s) {
-            return text;
-        }
-    }
-    String::from_utf8_lossy(bytes).to_string()
-}
-
-fn discover_wsl_distros() -> Vec<String> {
-    if !cfg!(windows) {
-        return Vec::new();
-    }
-    let output = match {
-        let mut c = Command::new("wsl.exe");
-        c.args(["-l", "-q"]);
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
-            c.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        }
-        c.output()
-    } {
-        Ok(item) => item,
-        Err(_) => return Vec::new(),
-    };
-    if !output.status.success() {
-        return Vec::new();
-    }
-    let decoded = decode_wsl_output(&output.stdout).replace('\0', "");
-    decoded
-        .lines()
-        .map(|item| item.trim().trim_start_matches('\u{feff}').to_string())
-        .filter(|item| !item.is_empty())
-        .collect()
-}
-
-fn save_openai_key_to_wsl(distro: &str, token: &str) -> Result<String> {
-    let json_text = serde_json::to_string_pretty(&serde_json::json!({
-        "OPENAI_API_KEY": token
-    }))
-    .with_context(|| "构建OpenAI配置内容失败")?;
-    let script = format!(
-        "mkdir -p \"$HOME/.codex\" && cat <<'EOF' > \"$HOME/.codex/auth.json\"\n{}\nEOF\nprintf %s \"$HOME/.codex/auth.json\"",
-        json_text
-    );
-    let output = {
-        let mut c = Command::new("wsl.exe");
-        c.args(["-d", distro, "-e", "sh", "-lc", &script]);
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
-            c.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        }
-        c.output()
-            .with_context(|| format!("执行 wsl.exe 失败: {}", distro))?
-    };
-    if !output.status.success() {
-        let stderr = decode_wsl_output(&output.stderr).replace('\0', "");
-        anyhow::bail!("WSL[{}] 写入失败: {}", distro, stderr.trim());
-    }
-    let stdout = decode_wsl_output(&output.stdout).replace('\0', "");
-    let path = stdout.trim();
-    if path.is_empty() {
-        Ok("$HOME/.codex/auth.json".to_string())
-    } else {
-        Ok(path.to_string())
-    }
-}
-
-fn save_openai_key(token: &str) -> Result<String> {
-    let local = save_openai_key_local(token)?;
-    let mut summary = vec![format!("Windows: {}", local.display())];
-    let distros = discover_wsl_distros();
-    let mut wsl_success = 0_usize;
-    let mut wsl_fail = 0_usize;
-    for distro in distros {
-        match save_openai_key_to_wsl(&distro, token) {
-            Ok(path) => {
-                wsl_success += 1;
-                summary.push(format!("WSL[{distro}]: {path}"));
-            }
-            Err(err) => {
-                wsl_fail += 1;
-                summary.push(format!("WSL[{distro}]失败: {err}"));
-            }
-        }
-    }
-    summary.push(format!("WSL成功 {wsl_success}，失败 {wsl_fail}"));
-    Ok(summary.join(" | "))
-}
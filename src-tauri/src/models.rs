@@ -21,6 +21,14 @@ fn default_daily_rollover_hour() -> u32 {
     8
 }
 
+fn default_slow_threshold_secs() -> f64 {
+    10.0
+}
+
+fn default_system_sample_interval_secs() -> u64 {
+    5
+}
+
 fn default_api_base_url() -> String {
     "https://anyrouter.top".to_string()
 }
@@ -33,6 +41,70 @@ fn default_api_fallback_to_web() -> bool {
     true
 }
 
+fn default_api_adaptive_sources() -> bool {
+    true
+}
+
+fn default_notify_policy() -> String {
+    "on_change".to_string()
+}
+
+fn default_notify_low_balance_threshold() -> f64 {
+    1.0
+}
+
+fn default_notify_system_toast() -> bool {
+    true
+}
+
+fn default_webhook_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_webhook_retry_times() -> u32 {
+    2
+}
+
+fn default_retention_history_per_account() -> usize {
+    20
+}
+
+fn default_retention_max_alerts() -> usize {
+    200
+}
+
+fn default_retention_acknowledged_alert_days() -> u64 {
+    30
+}
+
+fn default_retention_maintenance_interval_hours() -> u64 {
+    24
+}
+
+fn default_http_api_bind() -> String {
+    "127.0.0.1:8787".to_string()
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "art-rs".to_string()
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "art-rs".to_string()
+}
+
+fn default_mqtt_retain() -> bool {
+    true
+}
+
+fn default_mqtt_keep_alive_secs() -> u64 {
+    30
+}
+
 fn default_log_level() -> String {
     "INFO".to_string()
 }
@@ -45,6 +117,10 @@ fn default_web_timeout_seconds() -> u64 {
     90
 }
 
+fn default_web_hook_protocol() -> u32 {
+    1
+}
+
 fn default_web_pool_size() -> usize {
     4
 }
@@ -53,6 +129,72 @@ fn default_web_pool_max_size() -> usize {
     9
 }
 
+fn default_web_driver_log_max_kb() -> u64 {
+    2048
+}
+
+fn default_web_driver_log_keep() -> usize {
+    3
+}
+
+fn default_web_driver_append_log() -> bool {
+    true
+}
+
+fn default_checkin_already_texts() -> Vec<String> {
+    [
+        "今日已签到",
+        "已签到",
+        "Checked in today",
+        "Already checked in",
+    ]
+    .iter()
+    .map(|item| item.to_string())
+    .collect()
+}
+
+fn default_checkin_button_texts() -> Vec<String> {
+    ["签到", "每日签到", "立即签到", "Check in", "Check-in"]
+        .iter()
+        .map(|item| item.to_string())
+        .collect()
+}
+
+fn default_checkin_success_texts() -> Vec<String> {
+    ["签到成功", "Checked in successfully", "Check-in successful"]
+        .iter()
+        .map(|item| item.to_string())
+        .collect()
+}
+
+fn default_web_watchdog_stall_secs() -> u64 {
+    60
+}
+
+fn default_web_watchdog_quit_secs() -> u64 {
+    10
+}
+
+fn default_web_token_name_template() -> String {
+    "art-rs-{hostname}-{date}".to_string()
+}
+
+fn default_quota_guard_enabled() -> bool {
+    true
+}
+
+fn default_quota_guard_max_change_ratio() -> f64 {
+    0.9
+}
+
+fn default_quota_guard_protect_above() -> f64 {
+    10.0
+}
+
+fn default_web_prewarm_before_round_secs() -> u64 {
+    30
+}
+
 fn default_browser_headless() -> bool {
     true
 }
@@ -81,6 +223,30 @@ fn default_browser_disable_javascript() -> bool {
     false
 }
 
+fn default_browser_page_load_strategy() -> String {
+    "eager".to_string()
+}
+
+fn default_browser_lang() -> String {
+    "zh-CN".to_string()
+}
+
+fn default_browser_accept_language() -> String {
+    "zh-CN,zh;q=0.9,en;q=0.8".to_string()
+}
+
+fn default_browser_navigation_wait_ms() -> u64 {
+    10000
+}
+
+fn default_browser_element_wait_ms() -> u64 {
+    5000
+}
+
+fn default_browser_network_idle_wait_ms() -> u64 {
+    3000
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceConfig {
     #[serde(default = "default_max_workers")]
@@ -93,6 +259,68 @@ pub struct PerformanceConfig {
     pub retry_delay: u64,
     #[serde(default = "default_daily_rollover_hour")]
     pub daily_rollover_hour: u32,
+    /// 切日时间所在时区(IANA 名称，如 Asia/Shanghai)，为空时使用本机时区
+    #[serde(default)]
+    pub daily_rollover_timezone: String,
+    /// 慢操作默认阈值(秒)
+    #[serde(default = "default_slow_threshold_secs")]
+    pub slow_threshold_secs: f64,
+    /// 按操作名前缀覆盖慢操作阈值(秒)，匹配最长前缀
+    #[serde(default)]
+    pub slow_thresholds: BTreeMap<String, f64>,
+    /// 单轮批量检查的时间预算(秒)，超出后剩余账号跳过网页登录，0 表示不限制
+    #[serde(default)]
+    pub batch_time_budget_secs: u64,
+    /// 单轮批量检查的最长时间(秒)，超出后仍在排队的账号取消检查并标记为推迟，0 表示不限制
+    #[serde(default)]
+    pub max_batch_seconds: u64,
+    /// 后台系统资源采样间隔(秒)
+    #[serde(default = "default_system_sample_interval_secs")]
+    pub system_sample_interval_secs: u64,
+    /// 距离切日不足该分钟数时，为当前周期尚未完成网页登录的账号自动补跑一次，0 表示关闭
+    #[serde(default)]
+    pub rollover_prerun_minutes: u64,
+    /// 按账号覆盖自动查询间隔(秒)，优先于 interval_groups
+    #[serde(default)]
+    pub account_intervals: BTreeMap<String, u64>,
+    /// 按分组设置自动查询间隔，账号属于多个分组时取第一个
+    #[serde(default)]
+    pub interval_groups: Vec<IntervalGroup>,
+    /// 仅 API 快速刷新的间隔(秒)，与网页登录轮次互不阻塞，0 表示关闭
+    #[serde(default)]
+    pub fast_refresh_secs: u64,
+}
+
+impl PerformanceConfig {
+    /// 是否为部分账号单独设置了自动查询间隔
+    pub fn has_account_intervals(&self) -> bool {
+        !self.account_intervals.is_empty() || !self.interval_groups.is_empty()
+    }
+
+    /// 账号的自动查询间隔: account_intervals > interval_groups > default_interval
+    pub fn account_interval(&self, username: &str, default_interval: u64) -> u64 {
+        self.account_intervals
+            .get(username)
+            .copied()
+            .or_else(|| {
+                self.interval_groups
+                    .iter()
+                    .find(|group| group.accounts.iter().any(|item| item == username))
+                    .map(|group| group.interval_secs)
+            })
+            .unwrap_or(default_interval)
+            .max(1)
+    }
+}
+
+/// 一组共用自动查询间隔的账号
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct IntervalGroup {
+    #[serde(default)]
+    pub name: String,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub accounts: Vec<String>,
 }
 
 impl Default for PerformanceConfig {
@@ -103,6 +331,16 @@ impl Default for PerformanceConfig {
             retry_times: default_retry_times(),
             retry_delay: default_retry_delay(),
             daily_rollover_hour: default_daily_rollover_hour(),
+            daily_rollover_timezone: String::new(),
+            slow_threshold_secs: default_slow_threshold_secs(),
+            slow_thresholds: BTreeMap::new(),
+            batch_time_budget_secs: 0,
+            max_batch_seconds: 0,
+            system_sample_interval_secs: default_system_sample_interval_secs(),
+            rollover_prerun_minutes: 0,
+            account_intervals: BTreeMap::new(),
+            interval_groups: Vec::new(),
+            fast_refresh_secs: 0,
         }
     }
 }
@@ -115,6 +353,13 @@ pub struct ApiConfig {
     pub timeout: u64,
     #[serde(default = "default_api_fallback_to_web")]
     pub fallback_to_web: bool,
+    /// 按最近的检查历史调整查询来源: Key 持续鉴权失败时跳过 API，网页持续失败而 API 可用时
+    /// 不再回退网页。关闭后始终按默认顺序查询
+    #[serde(default = "default_api_adaptive_sources")]
+    pub adaptive_sources: bool,
+    /// 查询余额使用的 Key 名称，留空或账号没有该名称时使用默认 Key
+    #[serde(default)]
+    pub balance_key: String,
 }
 
 impl Default for ApiConfig {
@@ -123,6 +368,69 @@ impl Default for ApiConfig {
             base_url: default_api_base_url(),
             timeout: default_api_timeout(),
             fallback_to_web: default_api_fallback_to_web(),
+            adaptive_sources: default_api_adaptive_sources(),
+            balance_key: String::new(),
+        }
+    }
+}
+
+/// 每轮查询结束后的通知策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// always: 每轮都通知; on_change: 仅在失败、余额下降或余额偏低时通知;
+    /// failures_only: 仅在有失败时通知; off: 不通知
+    #[serde(default = "default_notify_policy")]
+    pub policy: String,
+    /// 余额低于该值视为偏低，0 表示不检查
+    #[serde(default = "default_notify_low_balance_threshold")]
+    pub low_balance_threshold: f64,
+    /// Windows 上额外弹出系统通知
+    #[serde(default = "default_notify_system_toast")]
+    pub system_toast: bool,
+    /// 每轮结束后把结果推送到外部 Webhook
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+}
+
+/// 结果 Webhook: 每轮结束后 POST 本轮结果与告警
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub urls: Vec<String>,
+    /// 签名密钥，非空时以 HMAC-SHA256 签名 "<timestamp>.<body>" 并写入 X-ART-Signature
+    #[serde(default)]
+    pub secret: String,
+    #[serde(default = "default_webhook_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// 网络错误或 5xx/429 时的重试次数，重试沿用同一个 Idempotency-Key
+    #[serde(default = "default_webhook_retry_times")]
+    pub retry_times: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            urls: Vec::new(),
+            secret: String::new(),
+            timeout_seconds: default_webhook_timeout_seconds(),
+            retry_times: default_webhook_retry_times(),
+        }
+    }
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            policy: default_notify_policy(),
+            low_balance_threshold: default_notify_low_balance_threshold(),
+            system_toast: default_notify_system_toast(),
+            webhook: WebhookConfig::default(),
         }
     }
 }
@@ -162,6 +470,36 @@ pub struct BrowserConfig {
     pub disable_images: bool,
     #[serde(default = "default_browser_disable_javascript")]
     pub disable_javascript: bool,
+    /// 页面加载策略: normal / eager / none
+    #[serde(default = "default_browser_page_load_strategy")]
+    pub page_load_strategy: String,
+    /// 等待页面跳转/路由稳定的上限(毫秒)
+    #[serde(default = "default_browser_navigation_wait_ms")]
+    pub navigation_wait_ms: u64,
+    /// 等待元素出现的上限(毫秒)
+    #[serde(default = "default_browser_element_wait_ms")]
+    pub element_wait_ms: u64,
+    /// 等待网络静默的上限(毫秒)，0 表示不等待
+    #[serde(default = "default_browser_network_idle_wait_ms")]
+    pub network_idle_wait_ms: u64,
+    /// 追加到 Chrome 启动参数的额外参数，如 --proxy-bypass-list=*
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// 追加到 Chrome prefs 的键值，与内置项合并，同名时以此处为准
+    #[serde(default)]
+    pub prefs: BTreeMap<String, serde_json::Value>,
+    /// 浏览器界面语言(--lang)，留空不设置
+    #[serde(default = "default_browser_lang")]
+    pub lang: String,
+    /// 请求头 Accept-Language，留空不设置
+    #[serde(default = "default_browser_accept_language")]
+    pub accept_language: String,
+    /// 模拟时区(IANA 名称，如 Asia/Shanghai)，留空使用系统时区
+    #[serde(default)]
+    pub timezone: String,
+    /// 浏览器可执行文件路径(Chromium/Brave 等)，用于启动与版本检测，留空自动查找 Chrome
+    #[serde(default)]
+    pub binary_path: String,
 }
 
 impl Default for BrowserConfig {
@@ -175,6 +513,16 @@ impl Default for BrowserConfig {
             user_agent: None,
             disable_images: default_browser_disable_images(),
             disable_javascript: default_browser_disable_javascript(),
+            page_load_strategy: default_browser_page_load_strategy(),
+            navigation_wait_ms: default_browser_navigation_wait_ms(),
+            element_wait_ms: default_browser_element_wait_ms(),
+            network_idle_wait_ms: default_browser_network_idle_wait_ms(),
+            extra_args: Vec::new(),
+            prefs: BTreeMap::new(),
+            lang: default_browser_lang(),
+            accept_language: default_browser_accept_language(),
+            timezone: String::new(),
+            binary_path: String::new(),
         }
     }
 }
@@ -189,12 +537,214 @@ pub struct WebCheckConfig {
     pub args: Vec<String>,
     #[serde(default = "default_web_timeout_seconds")]
     pub timeout_seconds: u64,
+    /// 外部命令协议版本: 1 在命令行参数中替换凭据；2 经 stdin JSON 与 ART_* 环境变量传递凭据
+    #[serde(default = "default_web_hook_protocol")]
+    pub hook_protocol: u32,
+    /// 外部命令的工作目录，留空沿用程序当前目录
+    #[serde(default)]
+    pub hook_working_dir: String,
+    /// HTTP 钩子地址，作为 command 的替代: POST 账号 JSON 载荷，按 JSON 响应解析结果
+    #[serde(default)]
+    pub hook_url: String,
+    #[serde(default)]
+    pub hook_headers: BTreeMap<String, String>,
+    /// 额外命名钩子，与 hooks/ 目录中发现的钩子合并，同名时以此处为准
+    #[serde(default)]
+    pub hooks: Vec<HookDefinition>,
+    /// 未单独指定钩子的账号使用的钩子名；留空时沿用 command，"native" 表示原生网页登录
+    #[serde(default)]
+    pub default_hook: String,
+    /// 按账号指定钩子: 用户名 -> 钩子名
+    #[serde(default)]
+    pub account_hooks: BTreeMap<String, String>,
     #[serde(default)]
     pub chromedriver_path: String,
+    /// 固定 ChromeDriver 版本(如 126.0.6478.126)，不再按检测到的 Chrome 版本选择
+    #[serde(default)]
+    pub driver_version: String,
+    /// 内部驱动镜像，配置后作为唯一的驱动下载来源
+    #[serde(default)]
+    pub driver_mirror: DriverMirrorConfig,
     #[serde(default = "default_web_pool_size")]
     pub pool_size: usize,
     #[serde(default = "default_web_pool_max_size")]
     pub max_pool_size: usize,
+    /// 将 chromedriver 输出写入 logs/chromedriver_<id>.log
+    #[serde(default)]
+    pub driver_log: bool,
+    /// 单个 chromedriver 日志文件上限(KB)，超出后轮转
+    #[serde(default = "default_web_driver_log_max_kb")]
+    pub driver_log_max_kb: u64,
+    /// 轮转保留的旧日志份数
+    #[serde(default = "default_web_driver_log_keep")]
+    pub driver_log_keep: usize,
+    /// chromedriver --log-level(ALL/DEBUG/INFO/WARNING/SEVERE/OFF)，为空时使用默认级别
+    #[serde(default)]
+    pub driver_log_level: String,
+    /// chromedriver --verbose，优先于 driver_log_level
+    #[serde(default)]
+    pub driver_verbose: bool,
+    /// chromedriver --append-log，关闭时每次启动覆盖旧日志
+    #[serde(default = "default_web_driver_append_log")]
+    pub driver_append_log: bool,
+    /// 看门狗: 网页会话连续多少秒没有阶段进展即判定为挂起；实际取值不低于单个阶段的等待上限之和
+    #[serde(default = "default_web_watchdog_stall_secs")]
+    pub watchdog_stall_secs: u64,
+    /// 看门狗: 关闭会话的等待上限(秒)，超时后直接结束 chromedriver 进程
+    #[serde(default = "default_web_watchdog_quit_secs")]
+    pub watchdog_quit_secs: u64,
+    /// 启动时在后台预热浏览器池
+    #[serde(default)]
+    pub prewarm_on_startup: bool,
+    /// 自动轮询开始前多少秒预热浏览器池，0 表示不预热
+    #[serde(default = "default_web_prewarm_before_round_secs")]
+    pub prewarm_before_round_secs: u64,
+    /// 预热时额外打开一次登录页，提前加载浏览器程序与解析域名
+    #[serde(default)]
+    pub prewarm_navigate: bool,
+    /// 站点签到相关的页面特征
+    #[serde(default)]
+    pub checkin: CheckinProfile,
+    /// 同步额度的 Key 名称；留空同步控制台中的首个令牌，指定时通过站点接口定位对应令牌
+    #[serde(default)]
+    pub quota_sync_key: String,
+    /// 新建令牌的命名模板，可用 {hostname} {date} {time} {username} {tool}
+    #[serde(default = "default_web_token_name_template")]
+    pub token_name_template: String,
+    /// 写入额度前的合理性检查
+    #[serde(default)]
+    pub quota_guard: QuotaGuardConfig,
+    /// 发现账号时登录的管理员账号(本地账号列表中的用户名)
+    #[serde(default)]
+    pub discovery_admin: String,
+}
+
+/// 企业内部的驱动镜像。base_url 按 Chrome for Testing 的布局提供版本 JSON 与
+/// <版本>/<平台>/chromedriver-<平台>.zip；versions 直接给出版本到压缩包地址的映射
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DriverMirrorConfig {
+    #[serde(default)]
+    pub base_url: String,
+    /// 键为完整版本号或主版本号，优先于 base_url
+    #[serde(default)]
+    pub versions: BTreeMap<String, String>,
+    /// 请求镜像时附加的请求头，如内部制品库的 Authorization
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl DriverMirrorConfig {
+    pub fn active(&self) -> bool {
+        !self.base_url.trim().is_empty() || !self.versions.is_empty()
+    }
+}
+
+/// 额度写入的合理性边界，金额与余额同单位(美元)，按识别到的比例与站点额度值换算。
+/// 超出边界时不写入并在同步结果中说明原因
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaGuardConfig {
+    #[serde(default = "default_quota_guard_enabled")]
+    pub enabled: bool,
+    /// 写入额度下限，0 表示不限制
+    #[serde(default)]
+    pub min_amount: f64,
+    /// 写入额度上限，0 表示不限制
+    #[serde(default)]
+    pub max_amount: f64,
+    /// 单次同步允许的最大降幅(0.9 表示最多降低 90%)，0 表示不限制；增加由 max_amount 约束
+    #[serde(default = "default_quota_guard_max_change_ratio")]
+    pub max_change_ratio: f64,
+    /// 令牌当前额度不低于该金额时才检查相对变化，避免小额令牌频繁被拦截
+    #[serde(default = "default_quota_guard_protect_above")]
+    pub protect_above: f64,
+}
+
+impl Default for QuotaGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_quota_guard_enabled(),
+            min_amount: 0.0,
+            max_amount: 0.0,
+            max_change_ratio: default_quota_guard_max_change_ratio(),
+            protect_above: default_quota_guard_protect_above(),
+        }
+    }
+}
+
+/// 命名的外部网页签到钩子(脚本、可执行文件或 HTTP 服务)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookDefinition {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 超时秒数，0 表示沿用 web_check.timeout_seconds
+    #[serde(default)]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_web_hook_protocol")]
+    pub hook_protocol: u32,
+    #[serde(default)]
+    pub working_dir: String,
+    /// HTTP 钩子地址，设置后改为 POST 账号载荷到该地址，忽略 command/args
+    #[serde(default)]
+    pub url: String,
+    /// HTTP 钩子附加请求头(如鉴权)
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// 钩子列表中的一项，附带来源与使用该钩子的账号
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct HookInfo {
+    pub name: String,
+    pub description: String,
+    /// config: config.json 中定义；hooks_dir: 从 hooks/ 目录发现；legacy: web_check.command
+    pub source: String,
+    /// command 或 http
+    pub kind: String,
+    /// 命令钩子为可执行文件，HTTP 钩子为请求地址
+    pub command: String,
+    pub hook_protocol: u32,
+    pub accounts: Vec<String>,
+}
+
+/// 站点签到相关的页面特征
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckinProfile {
+    /// 控制台出现任一文本即视为今日已签到
+    #[serde(default = "default_checkin_already_texts")]
+    pub already_texts: Vec<String>,
+    /// 登录后点击签到按钮(部分站点需要手动签到才会发放额度)
+    #[serde(default)]
+    pub click_button: bool,
+    /// 签到按钮所在页面，留空在控制台首页查找
+    #[serde(default)]
+    pub page_url: String,
+    /// 签到按钮 CSS 选择器，留空按 button_texts 匹配按钮文本
+    #[serde(default)]
+    pub button_selector: String,
+    #[serde(default = "default_checkin_button_texts")]
+    pub button_texts: Vec<String>,
+    /// 点击后出现任一文本(提示框或页面)即视为签到成功，提示中的首个数字记为到账额度
+    #[serde(default = "default_checkin_success_texts")]
+    pub success_texts: Vec<String>,
+}
+
+impl Default for CheckinProfile {
+    fn default() -> Self {
+        Self {
+            already_texts: default_checkin_already_texts(),
+            click_button: false,
+            page_url: String::new(),
+            button_selector: String::new(),
+            button_texts: default_checkin_button_texts(),
+            success_texts: default_checkin_success_texts(),
+        }
+    }
 }
 
 impl Default for WebCheckConfig {
@@ -204,9 +754,34 @@ impl Default for WebCheckConfig {
             command: String::new(),
             args: Vec::new(),
             timeout_seconds: default_web_timeout_seconds(),
+            hook_protocol: default_web_hook_protocol(),
+            hook_working_dir: String::new(),
+            hook_url: String::new(),
+            hook_headers: BTreeMap::new(),
+            hooks: Vec::new(),
+            default_hook: String::new(),
+            account_hooks: BTreeMap::new(),
             chromedriver_path: String::new(),
+            driver_version: String::new(),
+            driver_mirror: DriverMirrorConfig::default(),
             pool_size: default_web_pool_size(),
             max_pool_size: default_web_pool_max_size(),
+            driver_log: false,
+            driver_log_max_kb: default_web_driver_log_max_kb(),
+            driver_log_keep: default_web_driver_log_keep(),
+            driver_log_level: String::new(),
+            driver_verbose: false,
+            driver_append_log: default_web_driver_append_log(),
+            watchdog_stall_secs: default_web_watchdog_stall_secs(),
+            watchdog_quit_secs: default_web_watchdog_quit_secs(),
+            prewarm_on_startup: false,
+            prewarm_before_round_secs: default_web_prewarm_before_round_secs(),
+            prewarm_navigate: false,
+            checkin: CheckinProfile::default(),
+            quota_sync_key: String::new(),
+            token_name_template: default_web_token_name_template(),
+            quota_guard: QuotaGuardConfig::default(),
+            discovery_admin: String::new(),
         }
     }
 }
@@ -223,63 +798,962 @@ pub struct AppConfig {
     pub logging: LoggingConfig,
     #[serde(default)]
     pub web_check: WebCheckConfig,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Account {
-    pub username: String,
-    pub password: String,
-    pub api_key: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct BalanceCacheRecord {
     #[serde(default)]
-    pub balance: String,
+    pub notify: NotifyConfig,
     #[serde(default)]
-    pub updated_at: String,
+    pub mqtt: MqttConfig,
     #[serde(default)]
-    pub apikey_sync_success: Option<bool>,
+    pub http_api: HttpApiConfig,
     #[serde(default)]
-    pub apikey_sync_message: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct BalanceCacheFile {
+    pub retention: RetentionConfig,
     #[serde(default)]
-    pub version: u32,
+    pub paths: PathsConfig,
+    /// 保存的批量部署计划，由 deploy_plan_command 按名称执行
     #[serde(default)]
-    pub updated_at: String,
+    pub deploy_plans: Vec<DeploymentPlan>,
     #[serde(default)]
-    pub accounts: BTreeMap<String, BalanceCacheRecord>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct DailyWebStateFile {
+    pub wsl: WslConfig,
     #[serde(default)]
-    pub version: u32,
+    pub currency: CurrencyConfig,
     #[serde(default)]
-    pub updated_at: String,
+    pub app_lock: AppLockConfig,
     #[serde(default)]
-    pub accounts: BTreeMap<String, String>,
+    pub privacy: PrivacyConfig,
+    #[serde(default)]
+    pub simulation: SimulationConfig,
+}
+
+fn default_app_lock_idle_minutes() -> u64 {
+    10
 }
 
+/// 应用锁: 启用后查看密钥、修改账号等命令需先输入 PIN 解锁
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CheckResult {
-    pub username: String,
-    pub success: bool,
-    pub balance_text: String,
-    pub source: String,
-    pub message: String,
+pub struct AppLockConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PIN 的加盐哈希，由 hash_pin_command 生成；为空时应用锁不生效
+    #[serde(default)]
+    pub pin_hash: String,
+    /// 解锁后无受保护操作超过该分钟数自动重新锁定，0 表示不自动锁定
+    #[serde(default = "default_app_lock_idle_minutes")]
+    pub idle_lock_minutes: u64,
 }
 
-/// 实时进度日志事件载荷
-#[derive(Debug, Clone, Serialize)]
-pub struct ProgressEvent {
-    /// 日志级别: "info" / "warn" / "error" / "success"
-    pub level: String,
-    /// 关联的账号名，批次级日志为空字符串
+impl Default for AppLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pin_hash: String::new(),
+            idle_lock_minutes: default_app_lock_idle_minutes(),
+        }
+    }
+}
+
+impl AppLockConfig {
+    pub fn active(&self) -> bool {
+        self.enabled && !self.pin_hash.trim().is_empty()
+    }
+}
+
+/// 隐私模式: 界面结果、快照、日志与报告中的用户名打码为首字符加短哈希，便于公开截图或直播
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PrivacyConfig {
+    /// 启动时是否开启，运行中可在界面切换
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// 模拟账号: 不访问站点，按用户名生成确定的余额与耗时，用于测试界面与通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    /// 模拟的账号(用户名)，仍需在账号文件中存在
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// 其中始终返回查询失败的账号，用于测试失败告警
+    #[serde(default)]
+    pub failing: Vec<String>,
+    #[serde(default = "default_simulation_min_delay_ms")]
+    pub min_delay_ms: u64,
+    #[serde(default = "default_simulation_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            failing: Vec::new(),
+            min_delay_ms: default_simulation_min_delay_ms(),
+            max_delay_ms: default_simulation_max_delay_ms(),
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn is_simulated(&self, username: &str) -> bool {
+        self.accounts.iter().any(|item| item.trim() == username)
+    }
+
+    pub fn is_failing(&self, username: &str) -> bool {
+        self.failing.iter().any(|item| item.trim() == username)
+    }
+}
+
+fn default_simulation_min_delay_ms() -> u64 {
+    200
+}
+
+fn default_simulation_max_delay_ms() -> u64 {
+    1500
+}
+
+fn default_currency_canonical() -> String {
+    "USD".to_string()
+}
+
+/// 余额货币: 站点以何种货币显示余额，以及结果与汇总统一使用的货币
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrencyConfig {
+    /// 统一货币代码(USD/CNY/EUR 等)
+    #[serde(default = "default_currency_canonical")]
+    pub canonical: String,
+    /// 站点 -> 货币代码；页面或接口返回的余额不带货币符号时按此解释
+    #[serde(default)]
+    pub providers: BTreeMap<String, String>,
+    /// 货币代码 -> 1 单位折合多少统一货币，汇总时据此换算；未配置的货币不计入总余额
+    #[serde(default)]
+    pub rates: BTreeMap<String, f64>,
+}
+
+impl Default for CurrencyConfig {
+    fn default() -> Self {
+        Self {
+            canonical: default_currency_canonical(),
+            providers: BTreeMap::new(),
+            rates: BTreeMap::new(),
+        }
+    }
+}
+
+/// 设置 OpenAI Key 时同步 WSL 的范围
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WslConfig {
+    /// 不写入任何 WSL 发行版
+    #[serde(default)]
+    pub skip: bool,
+    /// 只写入这些发行版；留空时写入全部已发现的发行版
+    #[serde(default)]
+    pub distros: Vec<String>,
+    /// 不写入的发行版
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// 一次批量部署要写入的账号与目标
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DeploymentPlan {
+    pub name: String,
+    #[serde(default)]
+    pub entries: Vec<DeploymentEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DeploymentEntry {
+    pub username: String,
+    /// 写入的 Key 名称，留空使用默认 Key
+    #[serde(default)]
+    pub key: String,
+    /// claude: 本机 ~/.claude/settings.json; codex: 本机 ~/.codex/auth.json;
+    /// codex_wsl:<发行版>: 指定 WSL 发行版中的 ~/.codex/auth.json
+    pub target: String,
+}
+
+/// 批量部署中一个(账号, 目标)单元的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentCell {
+    pub username: String,
+    pub target: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentReport {
+    pub plan: String,
+    pub cells: Vec<DeploymentCell>,
+    pub success_count: usize,
+    pub fail_count: usize,
+}
+
+/// 运行时文件的位置。留空时放在配置目录下，相对路径也相对配置目录解析
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PathsConfig {
+    /// balance_cache.json、daily_web_login_state.json、account_history.json、alerts.json、
+    /// deployments.json 所在目录
+    #[serde(default)]
+    pub state_dir: String,
+    /// chromedriver 日志目录，默认 logs/
+    #[serde(default)]
+    pub logs_dir: String,
+    /// 账号 Cookie 目录，默认 cookies/
+    #[serde(default)]
+    pub cookies_dir: String,
+    /// 外部签到钩子目录，默认 hooks/
+    #[serde(default)]
+    pub hooks_dir: String,
+    /// 下载的 chromedriver 缓存目录，默认 drivers/
+    #[serde(default)]
+    pub driver_cache_dir: String,
+}
+
+/// 本地状态的保留策略，由定时维护任务或 run_maintenance_command 执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// 每个账号保留的最近检查记录条数
+    #[serde(default = "default_retention_history_per_account")]
+    pub history_per_account: usize,
+    /// 告警记录上限，超出时优先丢弃最早的已确认告警
+    #[serde(default = "default_retention_max_alerts")]
+    pub max_alerts: usize,
+    /// 已确认告警保留天数，0 表示不按时间清理
+    #[serde(default = "default_retention_acknowledged_alert_days")]
+    pub acknowledged_alert_days: u64,
+    /// 余额缓存超过该天数未更新即删除，0 表示永久保留
+    #[serde(default)]
+    pub cache_ttl_days: u64,
+    /// 定时维护间隔(小时)，0 表示只手动执行
+    #[serde(default = "default_retention_maintenance_interval_hours")]
+    pub maintenance_interval_hours: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            history_per_account: default_retention_history_per_account(),
+            max_alerts: default_retention_max_alerts(),
+            acknowledged_alert_days: default_retention_acknowledged_alert_days(),
+            cache_ttl_days: 0,
+            maintenance_interval_hours: default_retention_maintenance_interval_hours(),
+        }
+    }
+}
+
+/// 内嵌 HTTP 接口，供局域网内的脚本或面板拉取结果、触发查询
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 监听地址；未配置 tokens 时只允许本机地址
+    #[serde(default = "default_http_api_bind")]
+    pub bind: String,
+    /// 访问令牌，未配置时本机匿名请求只有只读权限
+    #[serde(default)]
+    pub tokens: Vec<ApiTokenConfig>,
+    /// 允许跨域访问的来源(如 http://192.168.1.10:8123)，"*" 表示全部；带 Origin 的请求不在列表中时拒绝
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for HttpApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_http_api_bind(),
+            tokens: Vec::new(),
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ApiTokenConfig {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub token: String,
+    /// read: 读取结果与告警；control: 另可触发查询、读取 API Key
+    #[serde(default)]
+    pub scope: String,
+}
+
+/// 每轮结束后把各账号余额发布到 MQTT，供 Home Assistant 等面板订阅。
+/// 主题: <topic_prefix>/accounts/<用户名>/state、<topic_prefix>/summary，
+/// 可用性主题 <topic_prefix>/status 为 online/offline(遗嘱消息)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub topic_prefix: String,
+    /// 0/1/2
+    #[serde(default)]
+    pub qos: u8,
+    /// 以保留消息发布，面板重启后可立即拿到最近一次余额
+    #[serde(default = "default_mqtt_retain")]
+    pub retain: bool,
+    #[serde(default = "default_mqtt_keep_alive_secs")]
+    pub keep_alive_secs: u64,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: default_mqtt_port(),
+            username: String::new(),
+            password: String::new(),
+            client_id: default_mqtt_client_id(),
+            topic_prefix: default_mqtt_topic_prefix(),
+            qos: 0,
+            retain: default_mqtt_retain(),
+            keep_alive_secs: default_mqtt_keep_alive_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub username: String,
+    pub password: String,
+    /// 默认 Key，即 keys 中的第一个
+    pub api_key: String,
+    /// 账号的全部命名 Key
+    #[serde(default)]
+    pub keys: Vec<NamedKey>,
+    /// 显示用的别名，为空时显示用户名；匹配、状态记录仍以用户名为准
+    #[serde(default)]
+    pub alias: String,
+}
+
+/// 账号下的一个 API Key，名称用于区分不同工具
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct NamedKey {
+    pub name: String,
+    pub key: String,
+}
+
+/// 导入发现的账号: 本地不存在时新建(需要密码)，已存在时只追加其中尚未记录的 Key
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct AccountImport {
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub keys: Vec<NamedKey>,
+    #[serde(default)]
+    pub alias: String,
+}
+
+/// 共享给队友的账号列表: 不含密码，sanitized 为 true 时也不含 Key
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SharedConfigFile {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: String,
+    #[serde(default)]
+    pub sanitized: bool,
+    #[serde(default)]
+    pub accounts: Vec<SharedAccount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SharedAccount {
+    pub username: String,
+    #[serde(default)]
+    pub keys: Vec<NamedKey>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub alias: String,
+}
+
+impl Account {
+    /// 按名称选择 Key(忽略大小写)；selector 为空时取默认 Key
+    pub fn key(&self, selector: &str) -> Option<&str> {
+        let selector = selector.trim();
+        if selector.is_empty() {
+            return Some(self.api_key.as_str()).filter(|key| !key.trim().is_empty());
+        }
+        self.keys
+            .iter()
+            .find(|item| item.name.eq_ignore_ascii_case(selector))
+            .map(|item| item.key.as_str())
+            .filter(|key| !key.trim().is_empty())
+    }
+
+    pub fn has_key(&self, token: &str) -> bool {
+        !token.is_empty()
+            && (self.api_key == token || self.keys.iter().any(|item| item.key == token))
+    }
+
+    /// 以选中的 Key 作为默认 Key 的副本；账号没有该名称时保持不变
+    pub fn with_active_key(&self, selector: &str) -> Account {
+        let mut account = self.clone();
+        if let Some(key) = self.key(selector) {
+            account.api_key = key.to_string();
+        }
+        account
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BalanceCacheRecord {
+    #[serde(default)]
+    pub balance: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub apikey_sync_success: Option<bool>,
+    #[serde(default)]
+    pub apikey_sync_message: String,
+    /// 最近一次把该账号 Key 写入 Claude 配置的时间，供“最久未用”切换策略使用
+    #[serde(default)]
+    pub claude_deployed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BalanceCacheFile {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, BalanceCacheRecord>,
+    /// 各站点识别到的额度换算比例(额度值/美元)
+    #[serde(default)]
+    pub unit_rates: BTreeMap<String, UnitRateRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UnitRateRecord {
+    #[serde(default)]
+    pub rate: f64,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DailyWebStateFile {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CheckResult {
+    pub username: String,
+    /// 账号别名，仅用于显示
+    #[serde(default)]
+    pub alias: String,
+    pub success: bool,
+    pub balance_text: String,
+    /// 余额的货币代码，为空时按统一货币处理
+    #[serde(default)]
+    pub currency: String,
+    pub source: String,
+    pub message: String,
+    /// 最近一次更新该行的刷新器: round(完整查询) / fast(仅 API 快速刷新)
+    #[serde(default)]
+    pub refresher: String,
+    /// 网页登录提取余额时命中的策略，API 结果为空
+    #[serde(default)]
+    pub extraction: String,
+    /// 账号健康状态: healthy / degraded / auth_failed / banned / quarantined / stale
+    #[serde(default)]
+    pub health: String,
+    /// 健康分(0-100)，按近期成功率计算
+    #[serde(default)]
+    pub health_score: u32,
+    /// 是否因超出本轮时间预算而推迟了网页登录
+    #[serde(default)]
+    pub deferred: bool,
+    /// 是否因超出本轮最长时间而取消了检查(同时标记为推迟)
+    #[serde(default)]
+    pub shed: bool,
+    /// 网页会话是否被看门狗判定为挂起
+    #[serde(default)]
+    pub hung: bool,
+    /// 站点提示账号已被封禁或停用；此后自动查询跳过该账号，单独查询恢复后解除
+    #[serde(default)]
+    pub banned: bool,
+    /// 余额为负(已透支)
+    #[serde(default)]
+    pub overdrawn: bool,
+    /// 本次点击签到到账的额度(签到提示中的数值)
+    #[serde(default)]
+    pub credited: Option<f64>,
+    /// 本次检查依次尝试过的路径(API → 网页 → API刷新 → 缓存)
+    #[serde(default)]
+    pub attempts: Vec<AttemptRecord>,
+    /// 最近一次 API Key 额度同步是否成功，未同步过为空
+    #[serde(default)]
+    pub apikey_sync_success: Option<bool>,
+    #[serde(default)]
+    pub apikey_sync_message: String,
+}
+
+/// 单次检查中的一步尝试
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AttemptRecord {
+    /// api / web_hook / web_only / already_checked_in / api_refresh / api_fallback / cache / deferred
+    #[serde(default)]
+    pub source: String,
+    /// success / failed / skipped
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// 单次检查结果的历史记录（用于健康度评估）
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AccountOutcome {
+    #[serde(default)]
+    pub at: String,
+    #[serde(default)]
+    pub success: bool,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub banned: bool,
+    /// 网页提取余额命中的策略，见 CheckResult.extraction
+    #[serde(default)]
+    pub extraction: String,
+    /// 本次检查各步尝试的总耗时，旧记录为 0
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// 本次检查中 API 查询的结果: success / failed / auth_failed，未查询为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_outcome: String,
+    /// 本次检查中网页登录的结果: success / failed，未登录为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub web_outcome: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AccountHistoryFile {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, Vec<AccountOutcome>>,
+    /// 最近删除的账号，供撤销删除
+    #[serde(default)]
+    pub removed: Vec<AccountTombstone>,
+    /// 手动排序的账号，未列出的账号按用户名排在其后
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// 置顶的账号
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+/// 被删除账号的完整记录(含密码与 Key)，恢复时原样写回账号文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountTombstone {
+    pub account: Account,
+    #[serde(default)]
+    pub removed_at: String,
+}
+
+/// 结果列表的筛选、排序与分页参数，取值与前端视图一致
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ResultQuery {
+    /// 匹配用户名、余额、来源与消息(不区分大小写)
+    #[serde(default)]
+    pub search: String,
+    /// all / ok / fail / banned / cache / idle
+    #[serde(default)]
+    pub status: String,
+    /// default / balance_desc / balance_asc / username_asc
+    #[serde(default)]
+    pub sort: String,
+    #[serde(default)]
+    pub offset: usize,
+    /// 0 表示不限制
+    #[serde(default)]
+    pub limit: usize,
+}
+
+/// 单个账号在当前周期的网页登录状态
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DailyStatusEntry {
+    pub username: String,
+    /// 当前周期是否已完成网页登录
+    pub completed: bool,
+    /// 最近一次完成网页登录的周期日
+    pub completed_day: String,
+    pub last_attempt_at: String,
+    /// 最近一次检查失败时的原因，成功时为空
+    pub last_error: String,
+}
+
+/// 账号检查历史的统计
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct AccountStats {
+    pub username: String,
+    /// 历史中的检查次数与成功次数
+    pub total: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    /// 有耗时记录的检查的平均耗时(毫秒)
+    pub avg_duration_ms: u64,
+    pub api_count: usize,
+    pub web_count: usize,
+    /// 网页检查占 API 与网页检查之和的比例(0-1)
+    pub web_ratio: f64,
+    pub first_at: String,
+    pub last_at: String,
+    /// 最近的检查结果，新的在前
+    pub recent: Vec<AccountOutcome>,
+}
+
+/// 当前周期每日网页登录完成情况
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DailyStatusReport {
+    pub cycle_day: String,
+    pub rollover_hour: u32,
+    pub seconds_until_rollover: i64,
+    pub completed_count: usize,
+    pub pending_count: usize,
+    pub accounts: Vec<DailyStatusEntry>,
+}
+
+/// 账号近期网页提取余额命中的策略
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct AccountSelectorHealth {
+    pub username: String,
+    /// 最近一次网页提取命中的策略与时间，从未网页提取过时为空
+    pub last_strategy: String,
+    pub last_at: String,
+    /// 历史记录中各策略的命中次数
+    pub counts: BTreeMap<String, u32>,
+    /// 近期的网页提取全部依赖启发式兜底策略
+    pub fragile_only: bool,
+}
+
+/// 余额提取策略的命中汇总，用于发现站点改版后只剩兜底策略可用
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct SelectorHealthReport {
+    /// 全部账号各策略的命中次数
+    pub totals: BTreeMap<String, u32>,
+    /// 兜底策略命中次数占全部网页提取的比例(0-1)
+    pub fragile_ratio: f64,
+    /// 有网页提取记录的账号均只剩兜底策略命中
+    pub degraded: bool,
+    pub accounts: Vec<AccountSelectorHealth>,
+}
+
+/// 账号的自动查询计划
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct AccountSchedule {
+    pub username: String,
+    pub interval_secs: u64,
+    /// 最近一次检查时间，从未检查过时为空
+    pub last_checked_at: String,
+    /// 距离下次到期的秒数，0 表示已到期
+    pub due_in_secs: u64,
+}
+
+/// 按账号间隔计算的自动查询计划；已封禁的账号不参与自动查询，不列出
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ScheduleReport {
+    pub accounts: Vec<AccountSchedule>,
+    /// 最早到期的账号距今的秒数，至少为 1，没有账号时为默认间隔
+    pub next_due_in_secs: u64,
+}
+
+/// 本地状态快照，供排查问题时查看
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct StateDump {
+    pub cycle_day: String,
+    pub balance_cache: BTreeMap<String, BalanceCacheRecord>,
+    pub daily_web_state: BTreeMap<String, String>,
+    /// 各账号最近连续失败次数
+    pub failure_counters: BTreeMap<String, usize>,
+    pub active_alerts: usize,
+    pub deployments: BTreeMap<String, DeploymentRecord>,
+}
+
+/// 状态修复结果
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct StateRepairReport {
+    /// 已删除账号遗留的条目数
+    pub removed_orphans: usize,
+    /// 修正为标准格式的日期数
+    pub fixed_dates: usize,
+    /// 日期无法识别而丢弃的条目数
+    pub dropped_invalid: usize,
+    pub message: String,
+}
+
+/// auto_switch_claude_command 的结果
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct AutoSwitchResult {
+    pub switched: bool,
+    /// 切换前 Claude 使用的账号，无法识别时为空
+    #[serde(serialize_with = "crate::privacy::serialize_username")]
+    pub from: String,
+    #[serde(serialize_with = "crate::privacy::serialize_username")]
+    pub to: String,
+    pub balance: Option<f64>,
+    #[serde(serialize_with = "crate::privacy::serialize_text")]
+    pub message: String,
+}
+
+/// 启动时无法解析、已用默认值代替的文件
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupIssue {
+    /// config / credentials / state
+    pub kind: String,
+    pub file: String,
+    pub error: String,
+}
+
+/// 启动检查中的一项
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightCheck {
+    /// accounts / config / chrome / driver / network / last_round
+    pub id: String,
+    pub label: String,
+    /// ok / warn / fail
+    pub status: String,
+    pub message: String,
+    /// 界面可执行的修复操作: open_file / download_driver / run_doctor / run_query，无修复时为空
+    pub fix_action: String,
+    /// open_file 要打开的文件
+    pub fix_target: String,
+}
+
+/// 启动检查结果，没有 fail 项时 ok 为 true
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightReport {
+    pub ok: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+/// 一次维护清理的结果
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct MaintenanceReport {
+    /// 超出保留条数而删除的检查记录
+    pub trimmed_history: usize,
+    /// 过期或超出上限而删除的告警
+    pub removed_alerts: usize,
+    /// 超过保留天数而删除的余额缓存
+    pub expired_cache: usize,
+    pub message: String,
+}
+
+/// 已触发的告警，确认前一直计入未处理数量
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AlertRecord {
+    #[serde(default)]
+    pub id: String,
+    /// failure / banned / balance_drop / low_balance / overdraft / unit_rate
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub username: String,
+    /// 触发时的账号别名，仅用于显示
+    #[serde(default)]
+    pub alias: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub created_at: String,
+    /// 未确认期间同类告警再次触发时刷新
+    #[serde(default)]
+    pub last_seen_at: String,
+    #[serde(default)]
+    pub occurrences: u32,
+    #[serde(default)]
+    pub acknowledged: bool,
+    #[serde(default)]
+    pub acknowledged_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AlertsFile {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub alerts: Vec<AlertRecord>,
+}
+
+/// 某个部署目标最近一次写入的账号
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DeploymentRecord {
+    /// claude / codex / codex_wsl:<发行版> / ssh:<主机>
+    #[serde(default)]
+    pub target: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub deployed_at: String,
+}
+
+/// 本程序在站点上新建的令牌，记录它属于哪台机器、哪个工具
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TokenLabel {
+    #[serde(default)]
+    pub username: String,
+    /// 站点控制台中显示的令牌名称
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub hostname: String,
+    #[serde(default)]
+    pub tool: String,
+    /// Key 的首尾几位，便于与控制台核对
+    #[serde(default)]
+    pub key_hint: String,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DeploymentsFile {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: String,
+    /// 按目标索引
+    #[serde(default)]
+    pub deployments: BTreeMap<String, DeploymentRecord>,
+    #[serde(default)]
+    pub tokens: Vec<TokenLabel>,
+}
+
+/// 实时进度日志事件载荷
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    /// 日志级别: "info" / "warn" / "error" / "success"
+    pub level: String,
+    /// 关联的账号名，批次级日志为空字符串
     pub username: String,
     /// 日志正文
     pub message: String,
 }
+
+/// 浏览器池或驱动故障事件载荷（与具体账号无关，附处理建议）
+#[derive(Debug, Clone, Serialize)]
+pub struct InfrastructureErrorEvent {
+    /// pool_exhausted / driver_spawn_failed / driver_unavailable
+    pub kind: String,
+    pub message: String,
+    pub hints: Vec<String>,
+}
+
+/// 一轮检查中某个结果来源的统计
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct SourceSummary {
+    pub count: usize,
+    pub success_count: usize,
+    /// 该来源结果的平均检查耗时(含之前失败的尝试)，毫秒
+    pub avg_duration_ms: u64,
+}
+
+/// 一轮检查的汇总，按结果来源(api / web_hook / web_only / cache 等)拆分，用于性能调优
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub success_count: usize,
+    pub fail_count: usize,
+    pub elapsed_secs: f64,
+    pub sources: BTreeMap<String, SourceSummary>,
+    /// 首个来源失败后改用其他来源的账号
+    #[serde(serialize_with = "crate::privacy::serialize_usernames")]
+    pub fallback_accounts: Vec<String>,
+    /// 超出本轮最长时间而取消检查的账号
+    #[serde(serialize_with = "crate::privacy::serialize_usernames")]
+    pub shed_accounts: Vec<String>,
+}
+
+/// 驱动下载进度事件载荷
+#[derive(Debug, Clone, Serialize)]
+pub struct DriverDownloadProgress {
+    pub version: String,
+    pub downloaded_bytes: u64,
+    /// 服务器未返回长度时为 0
+    pub total_bytes: u64,
+    pub bytes_per_sec: f64,
+    /// 下载结束(完成、失败或取消)
+    pub finished: bool,
+}
+
+/// 慢操作事件载荷（操作耗时超过所匹配阈值时发送）
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowOperationEvent {
+    /// 操作名
+    pub operation_name: String,
+    /// 账号名（操作未关联账号时为空）
+    pub username: String,
+    /// 实际耗时(秒)
+    pub duration_secs: f64,
+    /// 命中的阈值(秒)
+    pub threshold_secs: f64,
+    /// 操作是否成功
+    pub success: bool,
+}
+
+/// 一轮查询结束后的通知，推送到前端 `notification` 事件
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct NotificationEvent {
+    pub title: String,
+    pub body: String,
+    /// info / warn / error
+    pub level: String,
+    /// 触发原因: failure / banned / balance_drop / low_balance / round
+    pub reasons: Vec<String>,
+}
+
+/// 告警列表变化事件载荷，前端据此刷新角标
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertsChangedEvent {
+    pub active_count: usize,
+}
+
+/// 余额变动事件载荷（仅在缓存中已有旧值且数值发生变化时发送）
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceChangedEvent {
+    /// 账号名
+    pub username: String,
+    /// 变动前余额文本
+    pub old_balance: String,
+    /// 变动后余额文本
+    pub new_balance: String,
+    /// 变动值（新 - 旧），任一侧无法解析为数字时为 None
+    pub delta: Option<f64>,
+    /// 本次结果来源
+    pub source: String,
+}
+
+/// 状态变化事件载荷，界面据此只在版本前进时按类别重新读取
+#[derive(Debug, Clone, Serialize)]
+pub struct StateChangedEvent {
+    /// 变化后的状态版本，与快照的 version 一致
+    pub version: u64,
+    /// accounts / config / results / alerts / deployments / startup
+    pub kind: &'static str,
+}
@@ -1,14 +1,24 @@
 use crate::api_client::{ApiBalanceClient, ApiBalanceResult};
-use crate::models::{Account, AppConfig, CheckResult, ProgressEvent};
+use crate::currency;
+use crate::models::{
+    Account, AlertRecord, AlertsChangedEvent, AppConfig, AttemptRecord, BalanceChangedEvent,
+    BatchSummary, CheckResult, ProgressEvent, SimulationConfig, SourceSummary,
+};
 use crate::performance_monitor::{PerformanceMonitor, get_performance_monitor};
-use crate::state::StateStore;
-use crate::web_check::run_web_check;
+use crate::privacy;
+use crate::simulation;
+use crate::state::{SharedStateStore, SourcePreference};
+use crate::utils::is_banned_message;
+use crate::web_check::{WebCheckResult, run_web_check};
+use crate::web_native::{
+    QUOTA_PROVIDER, run_interactive_login, run_quota_resync, set_known_unit_rate,
+};
 use futures::stream::{FuturesUnordered, StreamExt};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::Semaphore;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum QueryMode {
@@ -16,22 +26,108 @@ enum QueryMode {
     WebOnly,
 }
 
+/// 单轮批量检查的时间预算，超出后不再发起新的网页登录；
+/// 超出最长时间后仍在排队的账号直接取消
+#[derive(Debug, Clone, Copy)]
+struct BatchBudget {
+    deadline: Option<Instant>,
+    shed_at: Option<Instant>,
+}
+
+impl BatchBudget {
+    fn new(started: Instant, budget_secs: u64, max_secs: u64) -> Self {
+        Self {
+            deadline: (budget_secs > 0).then(|| started + Duration::from_secs(budget_secs)),
+            shed_at: (max_secs > 0).then(|| started + Duration::from_secs(max_secs)),
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.deadline
+            .map(|deadline| Instant::now() >= deadline)
+            .unwrap_or(false)
+    }
+}
+
+/// 单个账号本次检查依次经过的路径，最终写入 CheckResult.attempts
+#[derive(Debug, Default)]
+struct AttemptChain(Vec<AttemptRecord>);
+
+impl AttemptChain {
+    fn record(&mut self, source: &str, outcome: &str, started: Instant, message: &str) {
+        self.0.push(AttemptRecord {
+            source: source.to_string(),
+            outcome: outcome.to_string(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            message: message.to_string(),
+        });
+    }
+
+    fn record_api(&mut self, source: &str, started: Instant, result: &ApiBalanceResult) {
+        let outcome = if result.success { "success" } else { "failed" };
+        self.record(source, outcome, started, &result.message);
+    }
+
+    fn record_web(
+        &mut self,
+        default_source: &'static str,
+        started: Instant,
+        outcome: &anyhow::Result<WebCheckResult>,
+    ) {
+        match outcome {
+            Ok(web_result) => {
+                let source = web_source(web_result, default_source);
+                if web_result.success && web_result.balance.is_some() {
+                    self.record(source, "success", started, &web_result.message);
+                } else if web_result.success {
+                    self.record(source, "failed", started, "登录成功但未提取到余额");
+                } else {
+                    self.record(source, "failed", started, &web_result.message);
+                }
+            }
+            Err(err) => self.record(default_source, "failed", started, &err.to_string()),
+        }
+    }
+
+    fn finish(self, mut result: CheckResult) -> CheckResult {
+        result.attempts = self.0;
+        result
+    }
+}
+
 /// 向前端发送实时进度日志
 fn emit_progress(app: &AppHandle, level: &str, username: &str, message: &str) {
     let payload = ProgressEvent {
         level: level.to_string(),
-        username: username.to_string(),
-        message: message.to_string(),
+        username: privacy::mask_username(username),
+        message: privacy::mask_text(message),
     };
     if let Err(e) = app.emit("progress-log", &payload) {
         tracing::warn!("发送进度事件失败: {}", e);
     }
 }
 
+/// 向前端发送余额变动事件
+fn emit_balance_changed(app: &AppHandle, payload: &BalanceChangedEvent) {
+    tracing::info!(
+        "账号 {} 余额变动: {} -> {}",
+        payload.username,
+        payload.old_balance,
+        payload.new_balance
+    );
+    let payload = BalanceChangedEvent {
+        username: privacy::mask_username(&payload.username),
+        ..payload.clone()
+    };
+    if let Err(e) = app.emit("balance-changed", &payload) {
+        tracing::warn!("发送余额变动事件失败: {}", e);
+    }
+}
+
 pub async fn check_accounts(
     accounts: Vec<Account>,
     config: AppConfig,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
     target_username: Option<String>,
     app: AppHandle,
 ) -> Vec<CheckResult> {
@@ -41,17 +137,134 @@ pub async fn check_accounts(
 pub async fn check_accounts_web_only(
     accounts: Vec<Account>,
     config: AppConfig,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
     target_username: Option<String>,
     app: AppHandle,
 ) -> Vec<CheckResult> {
     check_accounts_by_mode(accounts, config, state, target_username, QueryMode::WebOnly, app).await
 }
 
+/// 仅 API 快速刷新: 不做网页登录、不发逐账号进度，只刷新有 API Key 且未封禁的账号。
+/// 成功的结果写入余额缓存并覆盖该账号最近一次结果(保留健康度等字段)，失败的账号保持原结果，
+/// 返回本次更新过的行
+pub async fn refresh_balances_api_only(
+    accounts: Vec<Account>,
+    config: AppConfig,
+    state: SharedStateStore,
+    app: AppHandle,
+) -> Vec<CheckResult> {
+    let api_client = match ApiBalanceClient::new(&config.api.base_url, config.api.timeout) {
+        Ok(item) => item,
+        Err(e) => {
+            tracing::warn!("快速刷新初始化API客户端失败: {}", e);
+            return Vec::new();
+        }
+    };
+    let balance_key = config.api.balance_key.trim();
+    let selected: Vec<Account> = {
+        let guard = state.read().await;
+        accounts
+            .iter()
+            .filter(|item| !guard.is_banned(&item.username))
+            .map(|item| {
+                if balance_key.is_empty() {
+                    item.clone()
+                } else {
+                    item.with_active_key(balance_key)
+                }
+            })
+            .filter(|item| {
+                !item.api_key.trim().is_empty() || config.simulation.is_simulated(&item.username)
+            })
+            .collect()
+    };
+    if selected.is_empty() {
+        return Vec::new();
+    }
+
+    let started = Instant::now();
+    let semaphore = Arc::new(Semaphore::new(config.performance.max_workers.max(1)));
+    let mut jobs: FuturesUnordered<_> = selected
+        .iter()
+        .map(|account| {
+            let semaphore = semaphore.clone();
+            let api_client = &api_client;
+            let simulation_config = &config.simulation;
+            async move {
+                let _permit = semaphore.acquire_owned().await.ok()?;
+                let result = if simulation_config.is_simulated(&account.username) {
+                    simulation::query_balance(&account.username, simulation_config).await
+                } else {
+                    api_client.query_balance(&account.api_key).await
+                };
+                if !result.success {
+                    tracing::debug!("账号 {} 快速刷新失败: {}", account.username, result.message);
+                    return None;
+                }
+                Some((
+                    account.username.clone(),
+                    account.alias.trim().to_string(),
+                    result,
+                ))
+            }
+        })
+        .collect();
+    let mut fetched = Vec::new();
+    while let Some(item) = jobs.next().await {
+        if let Some(item) = item {
+            fetched.push(item);
+        }
+    }
+    drop(jobs);
+
+    let mut updated = Vec::new();
+    let mut changes = Vec::new();
+    {
+        let mut guard = state.write().await;
+        for (username, alias, api_result) in fetched {
+            let currency = api_result.currency.clone();
+            let balance_text =
+                currency::format_money(api_result.balance.unwrap_or_default(), &currency);
+            if let Some(change) = guard.balance_change(&username, &balance_text, &api_result.source)
+            {
+                changes.push(change);
+            }
+            if let Err(e) = guard.update_balance_cache(&username, &balance_text, None, None) {
+                tracing::warn!("账号 {} 保存余额缓存失败: {}", username, e);
+            }
+            let mut row = guard.last_round_result(&username).unwrap_or_default();
+            row.username = username;
+            row.alias = alias;
+            row.success = true;
+            row.balance_text = balance_text;
+            row.currency = currency;
+            row.source = api_result.source;
+            row.message = api_result.message;
+            row.refresher = "fast".to_string();
+            row.deferred = false;
+            row.hung = false;
+            row.attempts = Vec::new();
+            guard.apply_health(&mut row);
+            updated.push(row);
+        }
+        guard.record_round_results(&updated);
+    }
+    for change in &changes {
+        emit_balance_changed(&app, change);
+    }
+    tracing::debug!(
+        "快速刷新完成: {}/{} 个账号, 耗时={:.2}s",
+        updated.len(),
+        selected.len(),
+        started.elapsed().as_secs_f64()
+    );
+    updated
+}
+
 async fn check_accounts_by_mode(
     accounts: Vec<Account>,
     config: AppConfig,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
     target_username: Option<String>,
     mode: QueryMode,
     app: AppHandle,
@@ -81,6 +294,11 @@ async fn check_accounts_by_mode(
     );
 
     let started = Instant::now();
+    let budget = BatchBudget::new(
+        started,
+        config.performance.batch_time_budget_secs,
+        config.performance.max_batch_seconds,
+    );
     let api_client = if mode == QueryMode::Normal {
         match ApiBalanceClient::new(&config.api.base_url, config.api.timeout) {
             Ok(item) => Some(Arc::new(item)),
@@ -94,6 +312,7 @@ async fn check_accounts_by_mode(
                     balance_text: "错误".to_string(),
                     source: "init".to_string(),
                     message: msg,
+                    ..Default::default()
                 }];
             }
         }
@@ -103,7 +322,7 @@ async fn check_accounts_by_mode(
 
     let max_workers = config.performance.max_workers.max(1);
     let semaphore = Arc::new(Semaphore::new(max_workers));
-    let selected: Vec<Account> = accounts
+    let mut selected: Vec<Account> = accounts
         .into_iter()
         .filter(|item| {
             target_username
@@ -112,6 +331,38 @@ async fn check_accounts_by_mode(
                 .unwrap_or(true)
         })
         .collect();
+    // 别名只用于显示，结果仍按用户名匹配
+    let aliases: HashMap<String, String> = selected
+        .iter()
+        .map(|item| (item.username.clone(), item.alias.trim().to_string()))
+        .collect();
+    // 按 api.balance_key 选择查询余额使用的 Key
+    let balance_key = config.api.balance_key.trim();
+    if !balance_key.is_empty() {
+        for item in &mut selected {
+            *item = item.with_active_key(balance_key);
+        }
+    }
+    // 已封禁的账号只在单独查询时检查，避免每轮重复登录失败
+    if target_username.is_none() {
+        let guard = state.read().await;
+        let banned: Vec<String> = selected
+            .iter()
+            .filter(|item| guard.is_banned(&item.username))
+            .map(|item| item.username.clone())
+            .collect();
+        drop(guard);
+        if !banned.is_empty() {
+            selected.retain(|item| !banned.contains(&item.username));
+            let msg = format!(
+                "跳过 {} 个已封禁账号(可单独查询确认是否恢复): {}",
+                banned.len(),
+                banned.join(", ")
+            );
+            tracing::warn!("{}", msg);
+            emit_progress(&app, "warn", "", &msg);
+        }
+    }
 
     if mode == QueryMode::WebOnly {
         let msg = format!("开始仅网页登录检查 {} 个账号", selected.len());
@@ -153,13 +404,23 @@ async fn check_accounts_by_mode(
                 account_meta,
             );
 
-            let permit = semaphore
-                .acquire_owned()
-                .await
-                .map_err(|e| format!("信号量获取失败: {e}"))?;
+            // 超出本轮最长时间时仍未轮到的账号不再检查，避免站点异常缓慢时长时间占用查询锁
+            let permit = match budget.shed_at {
+                Some(shed_at) => tokio::select! {
+                    permit = semaphore.acquire_owned() => permit,
+                    _ = tokio::time::sleep_until(shed_at.into()) => {
+                        let result = shed_account(&account.username, &state, &app).await;
+                        timer.finish(false, Some(result.message.clone()));
+                        return Ok(result);
+                    }
+                },
+                None => semaphore.acquire_owned().await,
+            }
+            .map_err(|e| format!("信号量获取失败: {e}"))?;
             let _guard = permit;
 
-            let result = check_single_account(account, config, api_client, state, mode, &app).await;
+            let result =
+                check_single_account(account, config, api_client, state, mode, budget, &app).await;
             if result.success {
                 timer.finish(true, None);
             } else {
@@ -179,6 +440,7 @@ async fn check_accounts_by_mode(
                 balance_text: "错误".to_string(),
                 source: "task".to_string(),
                 message: err,
+                ..Default::default()
             }),
             Err(err) => results.push(CheckResult {
                 username: "SYSTEM".to_string(),
@@ -186,19 +448,83 @@ async fn check_accounts_by_mode(
                 balance_text: "错误".to_string(),
                 source: "task".to_string(),
                 message: format!("任务Join失败: {err}"),
+                ..Default::default()
             }),
         }
     }
     results.sort_by(|a, b| a.username.cmp(&b.username));
-    let success_count = results.iter().filter(|item| item.success).count();
-    let fail_count = results.len().saturating_sub(success_count);
-    let elapsed = started.elapsed().as_secs_f64();
-    let summary = format!(
-        "检查完成: 总数={}, 成功={}, 失败={}, 耗时={:.2}s",
-        results.len(), success_count, fail_count, elapsed
+    for item in &mut results {
+        item.refresher = "round".to_string();
+        item.alias = aliases.get(&item.username).cloned().unwrap_or_default();
+        if !item.success && item.username != "SYSTEM" && is_banned_message(&item.message) {
+            item.banned = true;
+        }
+    }
+    let pending = {
+        let mut guard = state.write().await;
+        guard.record_outcomes(&results);
+        let extracted: Vec<String> = results
+            .iter()
+            .filter(|item| !item.extraction.is_empty())
+            .map(|item| item.username.clone())
+            .collect();
+        if !extracted.is_empty() && guard.selector_health(&extracted).degraded {
+            let msg = "本轮网页提取余额均只命中兜底策略，站点页面可能已改版，请检查余额选择器";
+            tracing::warn!("{}", msg);
+            emit_progress(&app, "warn", "", msg);
+        }
+        for item in &mut results {
+            guard.apply_health(item);
+        }
+        guard.record_round_results(&results);
+        guard.take_pending_writes()
+    };
+    // 释放状态锁后再写文件，写入失败时留给后台 flush 重试
+    if let Err(e) = pending.write() {
+        tracing::warn!("保存本地状态失败，稍后重试: {}", e);
+        state.write().await.mark_dirty();
+    }
+    let deferred: Vec<&str> = results
+        .iter()
+        .filter(|item| item.deferred && !item.shed)
+        .map(|item| item.username.as_str())
+        .collect();
+    if !deferred.is_empty() {
+        let msg = format!(
+            "超出本轮时间预算({}s)，以下 {} 个账号推迟网页登录: {}",
+            config.performance.batch_time_budget_secs,
+            deferred.len(),
+            deferred.join(", ")
+        );
+        tracing::warn!("{}", msg);
+        emit_progress(&app, "warn", "", &msg);
+    }
+    let batch = summarize_batch(&results, started.elapsed().as_secs_f64());
+    let success_count = batch.success_count;
+    let fail_count = batch.fail_count;
+    let by_source = batch
+        .sources
+        .iter()
+        .map(|(source, item)| format!("{}={}({}ms)", source, item.count, item.avg_duration_ms))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut summary = format!(
+        "检查完成: 总数={}, 成功={}, 失败={}, 耗时={:.2}s, 来源: {}",
+        batch.total, success_count, fail_count, batch.elapsed_secs, by_source
     );
+    if !batch.shed_accounts.is_empty() {
+        summary.push_str(&format!(
+            "; 超出本轮最长时间({}s)，取消 {} 个账号: {}",
+            config.performance.max_batch_seconds,
+            batch.shed_accounts.len(),
+            batch.shed_accounts.join(", ")
+        ));
+    }
     tracing::info!("{}", summary);
     emit_progress(&app, "success", "", &summary);
+    if let Err(e) = app.emit("batch-summary", &batch) {
+        tracing::warn!("发送批次汇总事件失败: {}", e);
+    }
 
     if success_count == results.len() {
         batch_timer.finish(true, None);
@@ -211,18 +537,152 @@ async fn check_accounts_by_mode(
     results
 }
 
+/// 按结果来源汇总一轮检查，首个尝试失败后改用其他来源的账号记为回退
+pub fn summarize_batch(results: &[CheckResult], elapsed_secs: f64) -> BatchSummary {
+    let mut durations: BTreeMap<String, (SourceSummary, u64)> = BTreeMap::new();
+    let mut fallback_accounts = Vec::new();
+    let mut shed_accounts = Vec::new();
+    for item in results {
+        if item.shed {
+            shed_accounts.push(item.username.clone());
+        }
+        let source = if item.source.is_empty() {
+            "unknown"
+        } else {
+            item.source.as_str()
+        };
+        let entry = durations.entry(source.to_string()).or_default();
+        entry.0.count += 1;
+        if item.success {
+            entry.0.success_count += 1;
+        }
+        entry.1 += item
+            .attempts
+            .iter()
+            .map(|attempt| attempt.duration_ms)
+            .sum::<u64>();
+        if item.attempts.len() > 1 && item.attempts[0].outcome == "failed" {
+            fallback_accounts.push(item.username.clone());
+        }
+    }
+    let success_count = results.iter().filter(|item| item.success).count();
+    BatchSummary {
+        total: results.len(),
+        success_count,
+        fail_count: results.len().saturating_sub(success_count),
+        elapsed_secs,
+        sources: durations
+            .into_iter()
+            .map(|(source, (mut summary, total_ms))| {
+                summary.avg_duration_ms = total_ms / summary.count as u64;
+                (source, summary)
+            })
+            .collect(),
+        fallback_accounts,
+        shed_accounts,
+    }
+}
+
 async fn check_single_account(
     account: Account,
     config: AppConfig,
     api_client: Option<Arc<ApiBalanceClient>>,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
     mode: QueryMode,
+    budget: BatchBudget,
     app: &AppHandle,
 ) -> CheckResult {
-    if mode == QueryMode::WebOnly {
-        return check_single_account_web_only(account, config, state, app).await;
+    let mut attempts = AttemptChain::default();
+    if config.simulation.is_simulated(&account.username) {
+        let result = check_simulated_account(
+            &account.username,
+            &config.simulation,
+            state,
+            &mut attempts,
+            app,
+        )
+        .await;
+        return attempts.finish(result);
     }
+    let result = if mode == QueryMode::WebOnly {
+        if budget.exhausted() {
+            defer_web_login(&account, None, state, &mut attempts, app).await
+        } else {
+            check_single_account_web_only(account, config, state, &mut attempts, app).await
+        }
+    } else {
+        check_single_account_api_first(
+            account,
+            config,
+            api_client,
+            state,
+            budget,
+            &mut attempts,
+            app,
+        )
+        .await
+    };
+    attempts.finish(result)
+}
 
+/// 模拟账号不访问站点，按 simulation 配置生成余额或失败结果
+async fn check_simulated_account(
+    username: &str,
+    config: &SimulationConfig,
+    state: SharedStateStore,
+    attempts: &mut AttemptChain,
+    app: &AppHandle,
+) -> CheckResult {
+    emit_progress(app, "info", username, "模拟账号，不访问站点");
+    let started = Instant::now();
+    let api_result = simulation::query_balance(username, config).await;
+    attempts.record_api("simulated", started, &api_result);
+    if api_result.success {
+        return on_api_success(username, api_result, state, app).await;
+    }
+    emit_progress(app, "error", username, &api_result.message);
+    CheckResult {
+        username: username.to_string(),
+        success: false,
+        balance_text: "模拟失败".to_string(),
+        source: "simulated".to_string(),
+        message: api_result.message,
+        ..Default::default()
+    }
+}
+
+/// 手动登录、重新同步额度等单账号网页操作遇到模拟账号时，改为生成一次模拟结果
+async fn simulate_single_web_check(
+    account: &Account,
+    config: &AppConfig,
+    state: SharedStateStore,
+    app: &AppHandle,
+) -> CheckResult {
+    let mut attempts = AttemptChain::default();
+    let result = check_simulated_account(
+        &account.username,
+        &config.simulation,
+        state.clone(),
+        &mut attempts,
+        app,
+    )
+    .await;
+    let mut result = attempts.finish(result);
+    result.alias = account.alias.trim().to_string();
+    state.read().await.apply_health(&mut result);
+    result
+}
+
+/// 常规查询: 优先API秒查，失败或当日首查时走网页登录
+async fn check_single_account_api_first(
+    account: Account,
+    config: AppConfig,
+    api_client: Option<Arc<ApiBalanceClient>>,
+    state: SharedStateStore,
+    budget: BatchBudget,
+    attempts: &mut AttemptChain,
+    app: &AppHandle,
+) -> CheckResult {
     let username = account.username.clone();
     tracing::info!("开始检查账号: {}", username);
     emit_progress(app, "info", &username, "开始检查");
@@ -234,22 +694,45 @@ async fn check_single_account(
             balance_text: "错误".to_string(),
             source: "init".to_string(),
             message: "API客户端未初始化".to_string(),
+            ..Default::default()
         };
     };
 
-    let force_web = {
-        let guard = state.lock().await;
-        guard.should_force_web_query(&username)
+    let (force_web, preference) = {
+        let guard = state.read().await;
+        let preference = if config.api.adaptive_sources {
+            guard.source_preference(&username)
+        } else {
+            SourcePreference::default()
+        };
+        (guard.should_force_web_query(&username), preference)
     };
     if force_web {
         tracing::info!("账号 {} 当前周期首次查询，需执行网页登录签到", username);
         emit_progress(app, "info", &username, "当前周期首次查询，需执行网页登录签到");
     }
+    let has_key = !account.api_key.trim().is_empty();
+    if !force_web && has_key && preference.skip_api {
+        tracing::info!(
+            "账号 {} 最近多次 API 查询鉴权失败，本次直接网页登录",
+            username
+        );
+        emit_progress(
+            app,
+            "info",
+            &username,
+            "最近多次 API 查询鉴权失败，本次直接网页登录",
+        );
+    }
 
     // 非强制网页时优先走API秒查
-    if !force_web && !account.api_key.trim().is_empty() {
+    let mut api_tried = false;
+    if !force_web && has_key && !preference.skip_api {
+        api_tried = true;
         emit_progress(app, "info", &username, "尝试API秒查...");
+        let api_started = Instant::now();
         let api_result = api_client.query_balance(&account.api_key).await;
+        attempts.record_api("api", api_started, &api_result);
         if api_result.success {
             return on_api_success(&username, api_result, state, app).await;
         }
@@ -257,39 +740,56 @@ async fn check_single_account(
         tracing::warn!("账号 {} {}", username, msg);
         emit_progress(app, "warn", &username, &msg);
         if !config.api.fallback_to_web {
-            return on_api_fail_without_web_fallback(&username, api_result, state, app).await;
+            return on_api_fail_without_web_fallback(&username, api_result, state, attempts, app)
+                .await;
+        }
+        if preference.skip_web_fallback {
+            emit_progress(
+                app,
+                "info",
+                &username,
+                "最近多次网页登录失败而 API 可用，本次不回退网页",
+            );
+            return on_api_fail_without_web_fallback(&username, api_result, state, attempts, app)
+                .await;
         }
         emit_progress(app, "info", &username, "回退到网页登录...");
     }
 
+    if budget.exhausted() {
+        let api_client = (!api_tried && !preference.skip_api).then_some(api_client.as_ref());
+        return defer_web_login(&account, api_client, state, attempts, app).await;
+    }
+
     // 执行网页签到钩子
     emit_progress(app, "info", &username, "执行网页登录签到...");
-    match run_web_check(
+    let web_started = Instant::now();
+    let hook_progress = |level: &str, message: &str| emit_progress(app, level, &username, message);
+    let web_outcome = run_web_check(
         &account,
         &config.web_check,
         &config.browser,
         config.performance.retry_times,
         config.performance.retry_delay,
+        &hook_progress,
     )
-    .await
-    {
+    .await;
+    attempts.record_web("web_hook", web_started, &web_outcome);
+    match web_outcome {
         Ok(web_result) if web_result.success => {
+            let source = web_source(&web_result, "web_hook");
             if let Some(balance) = web_result.balance {
-                let balance_text = format_balance(balance);
-                let mark_result = {
-                    let mut guard = state.lock().await;
-                    let mark = guard.mark_web_query_success(&username);
-                    let save = guard.update_balance_cache(&username, &balance_text, None, None);
-                    mark.and(save)
-                };
-                if let Err(e) = mark_result {
-                    tracing::warn!("账号 {} 更新本地状态失败: {}", username, e);
-                }
+                let currency = web_currency(&web_result);
+                let balance_text = currency::format_money(balance, &currency);
+                record_web_success(&username, &balance_text, source, &web_result, &state, app)
+                    .await;
 
                 // 网页成功后，同轮再尝试API秒刷新（成功则覆盖）
-                if !account.api_key.trim().is_empty() {
+                if has_key {
                     emit_progress(app, "info", &username, "网页签到成功，尝试同轮API秒刷新...");
+                    let api_started = Instant::now();
                     let post_api = api_client.query_balance(&account.api_key).await;
+                    attempts.record_api("api_refresh", api_started, &post_api);
                     if post_api.success {
                         tracing::info!("账号 {} 同轮API秒刷新成功", username);
                         emit_progress(app, "success", &username, "同轮API秒刷新成功");
@@ -300,15 +800,29 @@ async fn check_single_account(
                     emit_progress(app, "warn", &username, &msg);
                 }
 
-                let msg = format!("网页签到成功，余额 {}", balance_text);
+                let msg = if web_result.already_checked_in {
+                    format!("今日已签到，余额 {}", balance_text)
+                } else if let Some(credited) = web_result.credited {
+                    format!("网页签到成功，到账 {}，余额 {}", credited, balance_text)
+                } else {
+                    format!("网页签到成功，余额 {}", balance_text)
+                };
                 tracing::info!("账号 {} {}", username, msg);
                 emit_progress(app, "success", &username, &msg);
                 CheckResult {
                     username,
                     success: true,
                     balance_text,
-                    source: "web_hook".to_string(),
-                    message: "网页登录签到成功".to_string(),
+                    currency,
+                    source: source.to_string(),
+                    extraction: web_result.extraction_strategy.clone(),
+                    message: if web_result.already_checked_in {
+                        web_result.message
+                    } else {
+                        "网页登录签到成功".to_string()
+                    },
+                    credited: web_result.credited,
+                    ..Default::default()
                 }
             } else {
                 tracing::warn!("账号 {} 网页签到返回成功但没有余额字段", username);
@@ -320,12 +834,15 @@ async fn check_single_account(
                         balance_text: "错误".to_string(),
                         source: "web_hook".to_string(),
                         message: "每日首查要求网页登录并成功提取余额，当前未提取到余额".to_string(),
+                        ..Default::default()
                     };
                 }
                 // 没有余额值时，尝试API兜底返回
                 if !account.api_key.trim().is_empty() {
                     emit_progress(app, "info", &username, "尝试API兜底查询余额...");
+                    let api_started = Instant::now();
                     let api_result = api_client.query_balance(&account.api_key).await;
+                    attempts.record_api("api_fallback", api_started, &api_result);
                     if api_result.success {
                         return on_api_success(&username, api_result, state, app).await;
                     }
@@ -336,6 +853,7 @@ async fn check_single_account(
                     balance_text: "错误".to_string(),
                     source: "web_hook".to_string(),
                     message: "网页登录成功但未提取到余额".to_string(),
+                    ..Default::default()
                 }
             }
         }
@@ -349,9 +867,11 @@ async fn check_single_account(
             CheckResult {
                 username,
                 success: false,
-                balance_text: "错误".to_string(),
+                balance_text: web_failure_text(&web_result),
                 source: "web_hook".to_string(),
                 message: msg,
+                hung: web_result.hung,
+                ..Default::default()
             }
         }
         Err(err) => {
@@ -364,11 +884,14 @@ async fn check_single_account(
                     balance_text: "错误".to_string(),
                     source: "web_hook".to_string(),
                     message: format!("每日首查网页登录不可用: {err}"),
+                    ..Default::default()
                 };
             }
             if !account.api_key.trim().is_empty() {
                 emit_progress(app, "info", &username, "网页不可用，尝试API查询...");
+                let api_started = Instant::now();
                 let api_result = api_client.query_balance(&account.api_key).await;
+                attempts.record_api("api_fallback", api_started, &api_result);
                 if api_result.success {
                     return on_api_success(&username, api_result, state, app).await;
                 }
@@ -379,6 +902,7 @@ async fn check_single_account(
                 balance_text: "错误".to_string(),
                 source: "web_hook".to_string(),
                 message: format!("网页登录不可用: {err}"),
+                ..Default::default()
             }
         }
     }
@@ -387,34 +911,34 @@ async fn check_single_account(
 async fn check_single_account_web_only(
     account: Account,
     config: AppConfig,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
+    attempts: &mut AttemptChain,
     app: &AppHandle,
 ) -> CheckResult {
     let username = account.username.clone();
     tracing::info!("开始仅网页登录账号: {}", username);
     emit_progress(app, "info", &username, "开始仅网页登录");
 
-    match run_web_check(
+    let web_started = Instant::now();
+    let hook_progress = |level: &str, message: &str| emit_progress(app, level, &username, message);
+    let web_outcome = run_web_check(
         &account,
         &config.web_check,
         &config.browser,
         config.performance.retry_times,
         config.performance.retry_delay,
+        &hook_progress,
     )
-    .await
-    {
+    .await;
+    attempts.record_web("web_only", web_started, &web_outcome);
+    match web_outcome {
         Ok(web_result) if web_result.success => {
+            let source = web_source(&web_result, "web_only");
             if let Some(balance) = web_result.balance {
-                let balance_text = format_balance(balance);
-                let mark_result = {
-                    let mut guard = state.lock().await;
-                    let mark = guard.mark_web_query_success(&username);
-                    let save = guard.update_balance_cache(&username, &balance_text, None, None);
-                    mark.and(save)
-                };
-                if let Err(e) = mark_result {
-                    tracing::warn!("账号 {} 更新本地状态失败: {}", username, e);
-                }
+                let currency = web_currency(&web_result);
+                let balance_text = currency::format_money(balance, &currency);
+                record_web_success(&username, &balance_text, source, &web_result, &state, app)
+                    .await;
 
                 let msg = format!("仅网页登录成功，余额 {}", balance_text);
                 tracing::info!("账号 {} {}", username, msg);
@@ -423,12 +947,16 @@ async fn check_single_account_web_only(
                     username,
                     success: true,
                     balance_text,
-                    source: "web_only".to_string(),
+                    currency,
+                    source: source.to_string(),
+                    extraction: web_result.extraction_strategy.clone(),
+                    credited: web_result.credited,
                     message: if web_result.message.trim().is_empty() {
                         "仅网页登录成功".to_string()
                     } else {
                         web_result.message
                     },
+                    ..Default::default()
                 }
             } else {
                 emit_progress(app, "warn", &username, "网页登录成功但未提取到余额");
@@ -438,6 +966,7 @@ async fn check_single_account_web_only(
                     balance_text: "错误".to_string(),
                     source: "web_only".to_string(),
                     message: "网页登录成功但未提取到余额".to_string(),
+                    ..Default::default()
                 }
             }
         }
@@ -447,9 +976,11 @@ async fn check_single_account_web_only(
             CheckResult {
                 username,
                 success: false,
-                balance_text: "错误".to_string(),
+                balance_text: web_failure_text(&web_result),
                 source: "web_only".to_string(),
                 message: msg,
+                hung: web_result.hung,
+                ..Default::default()
             }
         }
         Err(err) => {
@@ -461,24 +992,317 @@ async fn check_single_account_web_only(
                 balance_text: "错误".to_string(),
                 source: "web_only".to_string(),
                 message: msg,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// 手动接管登录单个账号，成功后与网页登录一样记录当日状态与余额
+pub async fn check_account_interactive(
+    account: Account,
+    config: AppConfig,
+    state: SharedStateStore,
+    wait_secs: u64,
+    app: &AppHandle,
+) -> CheckResult {
+    if config.simulation.is_simulated(&account.username) {
+        return simulate_single_web_check(&account, &config, state, app).await;
+    }
+    emit_progress(
+        app,
+        "info",
+        &account.username,
+        "已打开浏览器，请在窗口中手动完成登录",
+    );
+    let started = Instant::now();
+    let outcome =
+        run_interactive_login(&account, &config.web_check, &config.browser, wait_secs).await;
+    let mut result = finish_single_web_check(
+        account.username,
+        "interactive",
+        "手动登录",
+        started,
+        outcome,
+        &state,
+        app,
+    )
+    .await;
+    result.alias = account.alias.trim().to_string();
+    result
+}
+
+/// 登录账号并重新同步 API Key 额度，用于上次同步失败的账号
+pub async fn resync_account_quota(
+    account: Account,
+    config: AppConfig,
+    state: SharedStateStore,
+    app: &AppHandle,
+) -> CheckResult {
+    if config.simulation.is_simulated(&account.username) {
+        return simulate_single_web_check(&account, &config, state, app).await;
+    }
+    emit_progress(app, "info", &account.username, "重新同步 API Key 额度...");
+    let started = Instant::now();
+    let outcome = run_quota_resync(
+        &account,
+        &config.web_check,
+        &config.browser,
+        config.performance.retry_times,
+        config.performance.retry_delay,
+    )
+    .await;
+    let mut result = finish_single_web_check(
+        account.username,
+        "quota_resync",
+        "重新同步额度",
+        started,
+        outcome,
+        &state,
+        app,
+    )
+    .await;
+    result.alias = account.alias.trim().to_string();
+    state.read().await.apply_health(&mut result);
+    result
+}
+
+/// 单账号网页操作(手动登录、重新同步额度)的结果处理: 记录状态并生成检查结果
+async fn finish_single_web_check(
+    username: String,
+    source: &'static str,
+    label: &str,
+    started: Instant,
+    outcome: anyhow::Result<WebCheckResult>,
+    state: &SharedStateStore,
+    app: &AppHandle,
+) -> CheckResult {
+    let mut attempts = AttemptChain::default();
+    attempts.record_web(source, started, &outcome);
+    let result = match outcome {
+        Ok(web_result) => match web_result.balance {
+            Some(balance) => {
+                let currency = web_currency(&web_result);
+                let balance_text = currency::format_money(balance, &currency);
+                record_web_success(&username, &balance_text, source, &web_result, state, app).await;
+                let msg = format!("{label}成功，余额 {balance_text}");
+                tracing::info!("账号 {} {}", username, msg);
+                emit_progress(app, "success", &username, &msg);
+                CheckResult {
+                    username,
+                    success: true,
+                    balance_text,
+                    currency,
+                    source: source.to_string(),
+                    extraction: web_result.extraction_strategy.clone(),
+                    message: web_result.message,
+                    ..Default::default()
+                }
+            }
+            None => {
+                let msg = format!("{label}成功但未提取到余额");
+                emit_progress(app, "warn", &username, &msg);
+                CheckResult {
+                    username,
+                    success: false,
+                    balance_text: "错误".to_string(),
+                    source: source.to_string(),
+                    message: msg,
+                    ..Default::default()
+                }
+            }
+        },
+        Err(err) => {
+            let msg = format!("{label}失败: {err}");
+            emit_progress(app, "error", &username, &msg);
+            CheckResult {
+                username,
+                success: false,
+                balance_text: "错误".to_string(),
+                source: source.to_string(),
+                message: msg,
+                ..Default::default()
+            }
+        }
+    };
+    attempts.finish(result)
+}
+
+/// 网页登录拿到余额后: 记录余额变动、标记当日网页登录完成、更新余额缓存
+async fn record_web_success(
+    username: &str,
+    balance_text: &str,
+    source: &str,
+    web_result: &WebCheckResult,
+    state: &SharedStateStore,
+    app: &AppHandle,
+) {
+    let (sync_success, sync_message) = quota_sync_outcome(web_result);
+    let (mark_result, change) = {
+        let mut guard = state.write().await;
+        let change = guard.balance_change(username, balance_text, source);
+        let mark = guard.mark_web_query_success(username);
+        let save = guard.update_balance_cache(
+            username,
+            balance_text,
+            sync_success,
+            sync_message.as_deref(),
+        );
+        (mark.and(save), change)
+    };
+    if let Err(e) = mark_result {
+        tracing::warn!("账号 {} 更新本地状态失败: {}", username, e);
+    }
+    if let Some(change) = change {
+        emit_balance_changed(app, &change);
+    }
+    record_unit_rate_outcome(username, web_result, state, app).await;
+}
+
+/// 保存本次识别到的额度比例；与已记录比例冲突时不更新，转为告警等待人工确认
+async fn record_unit_rate_outcome(
+    username: &str,
+    web_result: &WebCheckResult,
+    state: &SharedStateStore,
+    app: &AppHandle,
+) {
+    if let Some(conflict) = &web_result.unit_rate_conflict {
+        emit_progress(app, "warn", username, conflict);
+        let mut guard = state.write().await;
+        let alert = AlertRecord {
+            kind: "unit_rate".to_string(),
+            username: username.to_string(),
+            message: conflict.clone(),
+            ..Default::default()
+        };
+        guard.record_alerts(vec![alert]);
+        let event = AlertsChangedEvent {
+            active_count: guard.active_alert_count(),
+        };
+        if let Err(e) = app.emit("alerts-changed", &event) {
+            tracing::warn!("发送告警变化事件失败: {}", e);
+        }
+        return;
+    }
+    if let Some(rate) = web_result.detected_unit_rate {
+        set_known_unit_rate(QUOTA_PROVIDER, rate);
+        state.write().await.record_unit_rate(QUOTA_PROVIDER, rate);
+    }
+}
+
+/// 超出本轮最长时间而取消检查的账号: 有缓存时返回缓存余额，均标记为推迟
+async fn shed_account(username: &str, state: &SharedStateStore, app: &AppHandle) -> CheckResult {
+    emit_progress(app, "warn", username, "超出本轮最长时间，取消检查");
+    let mut attempts = AttemptChain::default();
+    let started = Instant::now();
+    attempts.record("shed", "skipped", started, "超出本轮最长时间");
+    let cached = {
+        let guard = state.read().await;
+        guard.get_cached_balance_text(username)
+    };
+    let result = match cached {
+        Some(balance_text) => {
+            attempts.record("cache", "success", started, "使用缓存余额");
+            CheckResult {
+                username: username.to_string(),
+                success: true,
+                balance_text,
+                source: "cache".to_string(),
+                message: "超出本轮最长时间，检查已取消，使用缓存".to_string(),
+                deferred: true,
+                shed: true,
+                ..Default::default()
             }
         }
+        None => CheckResult {
+            username: username.to_string(),
+            success: false,
+            balance_text: "推迟".to_string(),
+            source: "deferred".to_string(),
+            message: "超出本轮最长时间，检查已取消".to_string(),
+            deferred: true,
+            shed: true,
+            ..Default::default()
+        },
+    };
+    attempts.finish(result)
+}
+
+/// 超出时间预算时跳过网页登录: 未试过API时先尝试API，否则回退缓存结果
+async fn defer_web_login(
+    account: &Account,
+    api_client: Option<&ApiBalanceClient>,
+    state: SharedStateStore,
+    attempts: &mut AttemptChain,
+    app: &AppHandle,
+) -> CheckResult {
+    let username = account.username.as_str();
+    emit_progress(app, "warn", username, "超出本轮时间预算，推迟网页登录");
+    let started = Instant::now();
+    attempts.record("web_hook", "skipped", started, "超出本轮时间预算");
+    if let Some(api_client) = api_client
+        && !account.api_key.trim().is_empty()
+    {
+        let api_started = Instant::now();
+        let api_result = api_client.query_balance(&account.api_key).await;
+        attempts.record_api("api", api_started, &api_result);
+        if api_result.success {
+            let mut result = on_api_success(username, api_result, state, app).await;
+            result.deferred = true;
+            return result;
+        }
+    }
+
+    let cache_started = Instant::now();
+    let cached = {
+        let guard = state.read().await;
+        guard.get_cached_balance_text(username)
+    };
+    match &cached {
+        Some(_) => attempts.record("cache", "success", cache_started, "使用缓存余额"),
+        None => attempts.record("cache", "failed", cache_started, "无缓存余额"),
+    }
+    match cached {
+        Some(balance_text) => CheckResult {
+            username: username.to_string(),
+            success: true,
+            balance_text,
+            source: "cache".to_string(),
+            message: "超出本轮时间预算，网页登录已推迟，使用缓存".to_string(),
+            deferred: true,
+            ..Default::default()
+        },
+        None => CheckResult {
+            username: username.to_string(),
+            success: false,
+            balance_text: "推迟".to_string(),
+            source: "deferred".to_string(),
+            message: "超出本轮时间预算，网页登录已推迟".to_string(),
+            deferred: true,
+            ..Default::default()
+        },
     }
 }
 
 async fn on_api_success(
     username: &str,
     api_result: ApiBalanceResult,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
     app: &AppHandle,
 ) -> CheckResult {
     let balance = api_result.balance.unwrap_or_default();
-    let balance_text = format_balance(balance);
-    {
-        let mut guard = state.lock().await;
+    let currency = api_result.currency.clone();
+    let balance_text = currency::format_money(balance, &currency);
+    let change = {
+        let mut guard = state.write().await;
+        let change = guard.balance_change(username, &balance_text, &api_result.source);
         if let Err(e) = guard.update_balance_cache(username, &balance_text, None, None) {
             tracing::warn!("账号 {} 保存余额缓存失败: {}", username, e);
         }
+        change
+    };
+    if let Some(change) = change {
+        emit_balance_changed(app, &change);
     }
     let msg = format!("API秒查成功: {} (source={})", balance_text, api_result.source);
     tracing::info!("账号 {} {}", username, msg);
@@ -487,21 +1311,29 @@ async fn on_api_success(
         username: username.to_string(),
         success: true,
         balance_text,
+        currency,
         source: api_result.source,
         message: api_result.message,
+        ..Default::default()
     }
 }
 
 async fn on_api_fail_without_web_fallback(
     username: &str,
     api_result: ApiBalanceResult,
-    state: Arc<Mutex<StateStore>>,
+    state: SharedStateStore,
+    attempts: &mut AttemptChain,
     app: &AppHandle,
 ) -> CheckResult {
+    let cache_started = Instant::now();
     let cached = {
-        let guard = state.lock().await;
+        let guard = state.read().await;
         guard.get_cached_balance_text(username)
     };
+    match &cached {
+        Some(_) => attempts.record("cache", "success", cache_started, "使用缓存余额"),
+        None => attempts.record("cache", "failed", cache_started, "无缓存余额"),
+    }
     if let Some(balance_text) = cached {
         let msg = format!("API秒查失败，回退缓存结果: {}", api_result.message);
         tracing::warn!("账号 {} {}", username, msg);
@@ -512,6 +1344,7 @@ async fn on_api_fail_without_web_fallback(
             balance_text,
             source: "cache".to_string(),
             message: format!("API失败，使用缓存: {}", api_result.message),
+            ..Default::default()
         };
     }
 
@@ -522,9 +1355,53 @@ async fn on_api_fail_without_web_fallback(
         balance_text: "API失败".to_string(),
         source: "api".to_string(),
         message: api_result.message,
+        ..Default::default()
     }
 }
 
-fn format_balance(value: f64) -> String {
-    format!("${value:.1}")
+/// 今日已签到时使用独立的结果来源，便于与正常网页签到区分
+fn web_source(web_result: &WebCheckResult, default: &'static str) -> &'static str {
+    if web_result.already_checked_in {
+        "already_checked_in"
+    } else {
+        default
+    }
+}
+
+/// 网页结果中的额度同步结果；外部钩子未返回结构化字段时从消息文本识别
+fn quota_sync_outcome(web_result: &WebCheckResult) -> (Option<bool>, Option<String>) {
+    if web_result.quota_sync_success.is_some() {
+        let message = if web_result.quota_sync_message.is_empty() {
+            web_result.message.clone()
+        } else {
+            web_result.quota_sync_message.clone()
+        };
+        return (web_result.quota_sync_success, Some(message));
+    }
+    let message = web_result.message.as_str();
+    if message.contains("额度已同步") {
+        (Some(true), Some(message.to_string()))
+    } else if message.contains("同步额度失败") || message.contains("额度同步已跳过") {
+        (Some(false), Some(message.to_string()))
+    } else {
+        (None, None)
+    }
+}
+
+/// 网页流程失败时结果表中显示的余额文本
+fn web_failure_text(web_result: &WebCheckResult) -> String {
+    if web_result.hung {
+        "挂起".to_string()
+    } else {
+        "错误".to_string()
+    }
+}
+
+/// 网页结果的余额货币: 页面显示的货币，未带符号时按站点配置
+fn web_currency(web_result: &WebCheckResult) -> String {
+    if web_result.currency.is_empty() {
+        currency::provider_currency(QUOTA_PROVIDER)
+    } else {
+        web_result.currency.clone()
+    }
 }
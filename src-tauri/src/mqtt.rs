@@ -0,0 +1,207 @@
+use crate::currency;
+use crate::models::{CheckResult, MqttConfig};
+use crate::utils::parse_first_number;
+use chrono::Local;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// 重连失败后的等待间隔
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+struct Publisher {
+    client: AsyncClient,
+    config: MqttConfig,
+}
+
+/// 启动时按配置创建一次，配置修改后需重启生效
+static PUBLISHER: OnceLock<Publisher> = OnceLock::new();
+
+#[derive(Serialize)]
+struct AccountState<'a> {
+    username: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    alias: &'a str,
+    success: bool,
+    balance: Option<f64>,
+    balance_text: &'a str,
+    currency: String,
+    source: &'a str,
+    message: &'a str,
+    updated_at: &'a str,
+}
+
+#[derive(Serialize)]
+struct RoundSummary<'a> {
+    total_balance: f64,
+    success_count: usize,
+    fail_count: usize,
+    updated_at: &'a str,
+}
+
+fn qos(level: u8) -> QoS {
+    match level {
+        1 => QoS::AtLeastOnce,
+        2 => QoS::ExactlyOnce,
+        _ => QoS::AtMostOnce,
+    }
+}
+
+fn availability_topic(config: &MqttConfig) -> String {
+    format!("{}/status", config.topic_prefix.trim_end_matches('/'))
+}
+
+/// 用户名中的通配符与层级分隔符替换为下划线，避免生成非法主题
+fn topic_segment(username: &str) -> String {
+    username
+        .chars()
+        .map(|ch| match ch {
+            '+' | '#' | '/' => '_',
+            _ => ch,
+        })
+        .collect()
+}
+
+/// 连接 MQTT 服务器并在后台维持事件循环。
+/// 遗嘱消息在异常断开时把可用性主题置为 offline，每次连上后重新发布 online
+pub fn start(config: &MqttConfig) {
+    if !config.enabled || config.host.trim().is_empty() {
+        return;
+    }
+    let mut options = MqttOptions::new(config.client_id.trim(), config.host.trim(), config.port);
+    options.set_keep_alive(Duration::from_secs(config.keep_alive_secs.max(5)));
+    if !config.username.is_empty() {
+        options.set_credentials(config.username.clone(), config.password.clone());
+    }
+    let availability = availability_topic(config);
+    options.set_last_will(LastWill::new(
+        availability.clone(),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+
+    let (client, mut event_loop) = AsyncClient::new(options, 64);
+    if PUBLISHER
+        .set(Publisher {
+            client: client.clone(),
+            config: config.clone(),
+        })
+        .is_err()
+    {
+        return;
+    }
+    tracing::info!(
+        "MQTT 已启用: {}:{} 主题前缀 {}",
+        config.host.trim(),
+        config.port,
+        config.topic_prefix
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let mut connected = false;
+        loop {
+            match event_loop.poll().await {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    connected = true;
+                    tracing::info!("MQTT 已连接");
+                    if let Err(e) = client
+                        .publish(availability.as_str(), QoS::AtLeastOnce, true, "online")
+                        .await
+                    {
+                        tracing::warn!("MQTT 发布在线状态失败: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    // 只在状态变化时记录，避免服务器长期不可达时刷屏
+                    if connected {
+                        tracing::warn!("MQTT 连接断开: {}", e);
+                    } else {
+                        tracing::debug!("MQTT 连接失败: {}", e);
+                    }
+                    connected = false;
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        }
+    });
+}
+
+/// 发布本轮各账号状态与汇总；未启用 MQTT 时什么也不做
+pub fn publish_round(results: &[CheckResult]) {
+    let Some(publisher) = PUBLISHER.get() else {
+        return;
+    };
+    let prefix = publisher.config.topic_prefix.trim_end_matches('/');
+    let updated_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut messages = Vec::new();
+    let mut total_balance = 0.0_f64;
+    let mut success_count = 0_usize;
+    let mut fail_count = 0_usize;
+    for item in results {
+        if item.username == "SYSTEM" || item.deferred {
+            continue;
+        }
+        let balance = parse_first_number(&item.balance_text);
+        let code = currency::result_currency(item);
+        if item.success {
+            success_count += 1;
+            total_balance += balance
+                .and_then(|value| currency::to_canonical(value, &code))
+                .unwrap_or(0.0);
+        } else {
+            fail_count += 1;
+        }
+        let payload = AccountState {
+            username: &item.username,
+            alias: &item.alias,
+            success: item.success,
+            balance,
+            balance_text: &item.balance_text,
+            currency: code,
+            source: &item.source,
+            message: &item.message,
+            updated_at: &updated_at,
+        };
+        if let Ok(body) = serde_json::to_vec(&payload) {
+            let topic = format!("{prefix}/accounts/{}/state", topic_segment(&item.username));
+            messages.push((topic, body));
+        }
+    }
+    let summary = RoundSummary {
+        total_balance,
+        success_count,
+        fail_count,
+        updated_at: &updated_at,
+    };
+    if let Ok(body) = serde_json::to_vec(&summary) {
+        messages.push((format!("{prefix}/summary"), body));
+    }
+
+    let client = publisher.client.clone();
+    let qos = qos(publisher.config.qos);
+    let retain = publisher.config.retain;
+    tauri::async_runtime::spawn(async move {
+        for (topic, body) in messages {
+            if let Err(e) = client.publish(topic.as_str(), qos, retain, body).await {
+                tracing::warn!("MQTT 发布失败 {}: {}", topic, e);
+                return;
+            }
+        }
+    });
+}
+
+/// 退出前主动发布 offline 并断开，避免等待服务器判定遗嘱
+pub async fn shutdown() {
+    let Some(publisher) = PUBLISHER.get() else {
+        return;
+    };
+    let topic = availability_topic(&publisher.config);
+    let _ = publisher
+        .client
+        .publish(topic.as_str(), QoS::AtLeastOnce, true, "offline")
+        .await;
+    let _ = publisher.client.disconnect().await;
+}
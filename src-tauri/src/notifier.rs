@@ -0,0 +1,212 @@
+use crate::models::{AlertRecord, CheckResult, NotificationEvent, NotifyConfig};
+use crate::privacy;
+use crate::utils::parse_first_number;
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter};
+
+/// 从一轮查询结果中找出需要告警的账号: 查询失败、余额下降、余额透支、余额低于阈值。
+/// before 为本轮开始前各账号的缓存余额文本，用于判断余额是否下降
+pub fn collect_alerts(
+    results: &[CheckResult],
+    before: &BTreeMap<String, String>,
+    config: &NotifyConfig,
+) -> Vec<AlertRecord> {
+    let alert = |kind: &str, item: &CheckResult, message: String| AlertRecord {
+        kind: kind.to_string(),
+        username: item.username.clone(),
+        alias: item.alias.clone(),
+        message,
+        ..Default::default()
+    };
+    let mut alerts = Vec::new();
+    for item in results {
+        if item.username == "SYSTEM" || item.deferred {
+            continue;
+        }
+        if !item.success {
+            let kind = if item.banned { "banned" } else { "failure" };
+            alerts.push(alert(kind, item, item.message.clone()));
+            continue;
+        }
+        let Some(current) = parse_first_number(&item.balance_text) else {
+            continue;
+        };
+        let previous = before
+            .get(&item.username)
+            .and_then(|text| parse_first_number(text));
+        if let Some(previous) = previous
+            && current < previous - 1e-9
+        {
+            alerts.push(alert(
+                "balance_drop",
+                item,
+                format!("{previous:.2} -> {current:.2}"),
+            ));
+        }
+        // 透支单独告警，不再重复报余额偏低
+        if current < 0.0 {
+            alerts.push(alert(
+                "overdraft",
+                item,
+                format!("余额为负 {}，账号已透支", item.balance_text),
+            ));
+        } else if config.low_balance_threshold > 0.0 && current < config.low_balance_threshold {
+            alerts.push(alert(
+                "low_balance",
+                item,
+                format!(
+                    "${:.2} 低于阈值 ${:.2}",
+                    current, config.low_balance_threshold
+                ),
+            ));
+        }
+    }
+    alerts
+}
+
+/// 按通知策略评估本轮告警，不需要通知时返回 None
+pub fn evaluate_round(
+    results: &[CheckResult],
+    alerts: &[AlertRecord],
+    config: &NotifyConfig,
+) -> Option<NotificationEvent> {
+    if !config.enabled {
+        return None;
+    }
+    let policy = config.policy.trim().to_ascii_lowercase();
+    if policy == "off" {
+        return None;
+    }
+
+    let pick = |kind: &str, with_message: bool| -> Vec<String> {
+        alerts
+            .iter()
+            .filter(|item| item.kind == kind)
+            .map(|item| {
+                // 通知中优先显示别名，隐私模式下用户名打码
+                let name = if item.alias.trim().is_empty() {
+                    privacy::mask_username(&item.username)
+                } else {
+                    privacy::mask_text(item.alias.trim())
+                };
+                if with_message {
+                    format!("{} {}", name, privacy::mask_text(&item.message))
+                } else {
+                    name
+                }
+            })
+            .collect()
+    };
+    let failures = pick("failure", false);
+    let banned = pick("banned", false);
+    let overdrawn = pick("overdraft", true);
+    let drops = pick("balance_drop", true);
+    let low = pick("low_balance", true);
+
+    let mut reasons = Vec::new();
+    let mut lines = Vec::new();
+    if !failures.is_empty() {
+        reasons.push("failure".to_string());
+        lines.push(format!(
+            "失败 {} 个: {}",
+            failures.len(),
+            failures.join(", ")
+        ));
+    }
+    if !banned.is_empty() {
+        reasons.push("banned".to_string());
+        lines.push(format!(
+            "封禁/停用 {} 个: {}",
+            banned.len(),
+            banned.join(", ")
+        ));
+    }
+    if !overdrawn.is_empty() {
+        reasons.push("overdraft".to_string());
+        lines.push(format!(
+            "透支 {} 个: {}",
+            overdrawn.len(),
+            overdrawn.join("; ")
+        ));
+    }
+    if policy != "failures_only" {
+        if !drops.is_empty() {
+            reasons.push("balance_drop".to_string());
+            lines.push(format!("余额下降: {}", drops.join("; ")));
+        }
+        if !low.is_empty() {
+            reasons.push("low_balance".to_string());
+            lines.push(format!("余额偏低: {}", low.join(", ")));
+        }
+    }
+
+    if reasons.is_empty() {
+        if policy != "always" {
+            return None;
+        }
+        reasons.push("round".to_string());
+        lines.push(format!("全部 {} 个账号查询成功，余额无下降", results.len()));
+    }
+
+    let level = if !failures.is_empty() || !banned.is_empty() || !overdrawn.is_empty() {
+        "error"
+    } else if reasons.iter().any(|item| item != "round") {
+        "warn"
+    } else {
+        "info"
+    };
+    Some(NotificationEvent {
+        title: "AnyRouter 余额查询".to_string(),
+        body: lines.join("\n"),
+        level: level.to_string(),
+        reasons,
+    })
+}
+
+/// 推送 notification 事件；Windows 上按配置再弹出系统通知
+pub fn deliver(app: &AppHandle, event: &NotificationEvent, config: &NotifyConfig) {
+    tracing::info!("发送通知: {}", event.body.replace('\n', " | "));
+    if let Err(e) = app.emit("notification", event) {
+        tracing::warn!("发送通知事件失败: {}", e);
+    }
+    if config.system_toast && cfg!(windows) {
+        let title = event.title.clone();
+        let body = event.body.clone();
+        std::thread::spawn(move || show_windows_toast(&title, &body));
+    }
+}
+
+/// 通过 PowerShell 调用 Windows.UI.Notifications 弹出系统通知
+fn show_windows_toast(title: &str, body: &str) {
+    let escape = |text: &str| {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('\'', "''")
+    };
+    let script = format!(
+        "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null;\
+         [Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] > $null;\
+         $xml = New-Object Windows.Data.Xml.Dom.XmlDocument;\
+         $xml.LoadXml('<toast><visual><binding template=\"ToastGeneric\"><text>{}</text><text>{}</text></binding></visual></toast>');\
+         $toast = New-Object Windows.UI.Notifications.ToastNotification $xml;\
+         [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('ART-rs').Show($toast);",
+        escape(title),
+        escape(body)
+    );
+    let mut command = std::process::Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    match command.output() {
+        Ok(output) if !output.status.success() => tracing::warn!(
+            "弹出系统通知失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("弹出系统通知失败: {}", e),
+    }
+}
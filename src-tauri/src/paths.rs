@@ -0,0 +1,197 @@
+use crate::config;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+pub fn resolve_config_dir() -> PathBuf {
+    if let Some(dir) = explicit_config_dir(
+        parse_arg_value("--config-dir"),
+        std::env::var("ART_RS_CONFIG_DIR").ok(),
+    ) {
+        return dir;
+    }
+
+    let mut seeds = Vec::new();
+    if let Ok(cwd) = std::env::current_dir() {
+        seeds.push(cwd);
+    }
+    if let Ok(exe) = std::env::current_exe()
+        && let Some(parent) = exe.parent()
+    {
+        seeds.push(parent.to_path_buf());
+    }
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    seeds.push(manifest_dir.clone());
+    if let Some(parent) = manifest_dir.parent() {
+        seeds.push(parent.to_path_buf());
+    }
+
+    if let Some(dir) = locate_config_dir(seeds) {
+        tracing::info!("已自动定位配置目录: {}", dir.display());
+        return dir;
+    }
+
+    let fallback = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    tracing::warn!(
+        "未定位到配置目录（config.json/credentials.txt），回退当前目录: {}",
+        fallback.display()
+    );
+    fallback
+}
+
+/// --config-dir 优先于 ART_RS_CONFIG_DIR；命令行参数原样使用，环境变量为空白时忽略
+fn explicit_config_dir(arg: Option<String>, env: Option<String>) -> Option<PathBuf> {
+    if let Some(arg) = arg {
+        return Some(PathBuf::from(arg));
+    }
+    env.map(|raw| raw.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 依次检查候选目录，返回第一个含有配置文件或账号文件的目录；重复的候选只检查一次
+fn locate_config_dir(seeds: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    let mut visited = std::collections::HashSet::new();
+    seeds
+        .into_iter()
+        .filter(|dir| visited.insert(dir.to_string_lossy().to_string()))
+        .find(|dir| has_runtime_files(dir))
+}
+
+fn has_runtime_files(dir: &Path) -> bool {
+    dir.join("credentials.txt").exists() || dir.join("config.json").exists()
+}
+
+fn parse_arg_value(name: &str) -> Option<String> {
+    arg_value(std::env::args().skip(1), name)
+}
+
+/// 取参数 name 之后的一项，name 是最后一项时视为未指定
+fn arg_value(args: impl IntoIterator<Item = String>, name: &str) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// 配置集优先级: --profile > ART_RS_PROFILE > 上次切换保存的配置集 > default。
+/// 返回(配置集, 是否由命令行或环境变量指定)
+pub fn resolve_profile(base_dir: &Path) -> (String, bool) {
+    let requested = parse_arg_value("--profile")
+        .or_else(|| std::env::var("ART_RS_PROFILE").ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    if let Some(name) = requested {
+        if config::validate_profile_name(&name).is_ok() {
+            return (name, true);
+        }
+        eprintln!("忽略无效的配置集名称: {name}");
+    }
+    let name = config::load_active_profile(base_dir)
+        .unwrap_or_else(|| config::DEFAULT_PROFILE.to_string());
+    (name, false)
+}
+
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .with_context(|| "未找到用户目录")
+}
+
+pub fn claude_settings_path() -> Result<PathBuf> {
+    Ok(home_dir()?.join(".claude").join("settings.json"))
+}
+
+pub fn codex_auth_path() -> Result<PathBuf> {
+    Ok(home_dir()?.join(".codex").join("auth.json"))
+}
+
+/// 修改前的副本，restore_tool_config_command 据此回滚最近一次修改
+pub fn tool_config_backup_path(target: &Path) -> PathBuf {
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    target.with_file_name(format!("{file_name}.bak"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// 每个测试独占的临时目录，重复运行前先清空
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("art_rs_paths_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn arg_value_takes_next_item() {
+        let list = args(&["--profile", "work", "--config-dir", "/data/art"]);
+        assert_eq!(
+            arg_value(list.clone(), "--config-dir").as_deref(),
+            Some("/data/art")
+        );
+        assert_eq!(arg_value(list, "--profile").as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn arg_value_missing_or_trailing_flag() {
+        assert_eq!(
+            arg_value(args(&["--profile", "work"]), "--config-dir"),
+            None
+        );
+        assert_eq!(arg_value(args(&["--config-dir"]), "--config-dir"), None);
+    }
+
+    #[test]
+    fn explicit_dir_prefers_argument_over_env() {
+        let dir = explicit_config_dir(Some("/from/arg".into()), Some("/from/env".into()));
+        assert_eq!(dir, Some(PathBuf::from("/from/arg")));
+        let dir = explicit_config_dir(None, Some("  /from/env  ".into()));
+        assert_eq!(dir, Some(PathBuf::from("/from/env")));
+    }
+
+    #[test]
+    fn explicit_dir_ignores_blank_env() {
+        assert_eq!(explicit_config_dir(None, Some("   ".into())), None);
+        assert_eq!(explicit_config_dir(None, None), None);
+    }
+
+    #[test]
+    fn locate_picks_first_dir_with_runtime_files() {
+        let root = scratch_dir("locate");
+        let empty = root.join("empty");
+        let with_config = root.join("with_config");
+        let with_credentials = root.join("with_credentials");
+        for dir in [&empty, &with_config, &with_credentials] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(with_config.join("config.json"), "{}").unwrap();
+        fs::write(with_credentials.join("credentials.txt"), "").unwrap();
+
+        let found = locate_config_dir([empty.clone(), with_credentials.clone(), with_config]);
+        assert_eq!(found, Some(with_credentials));
+        assert_eq!(locate_config_dir([empty.clone(), empty]), None);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn backup_path_appends_suffix() {
+        let target = Path::new("/home/user/.claude/settings.json");
+        assert_eq!(
+            tool_config_backup_path(target),
+            PathBuf::from("/home/user/.claude/settings.json.bak")
+        );
+    }
+}
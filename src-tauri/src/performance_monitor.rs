@@ -1,8 +1,12 @@
+use crate::browser_pool::tracked_driver_pids;
+use crate::models::SlowOperationEvent;
 use chrono::{DateTime, Local};
-use std::collections::{HashMap, VecDeque};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Instant;
-use sysinfo::System;
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessesToUpdate, System};
 
 #[derive(Debug, Clone)]
 pub struct PerformanceMetrics {
@@ -31,13 +35,50 @@ pub struct SystemMetrics {
     pub total_memory_mb: f64,
     pub used_memory_mb: f64,
     pub total_threads: usize,
+    /// 本程序进程
+    pub app: ProcessUsage,
+    /// 浏览器池启动的 chromedriver 进程
+    pub chromedriver: ProcessUsage,
+    /// chromedriver 派生的 Chrome 进程
+    pub chrome: ProcessUsage,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct PoolSnapshot {
+    pub taken_at: DateTime<Local>,
+    pub stats: HashMap<String, f64>,
+}
+
+const POOL_HISTORY_SIZE: usize = 240;
+
+/// 网页流程分阶段计时的操作名前缀
+pub const WEB_STAGE_PREFIX: &str = "网页阶段_";
+
+const DEFAULT_SLOW_THRESHOLD_SECS: f64 = 10.0;
+
+type SlowListener = Arc<dyn Fn(&SlowOperationEvent) + Send + Sync>;
+
 pub struct PerformanceMonitor {
     history_size: usize,
     history: VecDeque<PerformanceMetrics>,
     stats: HashMap<String, PerfStat>,
+    /// Tauri 命令的调用统计，与业务操作分开展示
+    command_stats: HashMap<String, PerfStat>,
+    pool_history: VecDeque<PoolSnapshot>,
+    slow_threshold_secs: f64,
+    slow_thresholds: Vec<(String, f64)>,
+    slow_listener: Option<SlowListener>,
+}
+
+impl std::fmt::Debug for PerformanceMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PerformanceMonitor")
+            .field("history_size", &self.history_size)
+            .field("history", &self.history.len())
+            .field("slow_threshold_secs", &self.slow_threshold_secs)
+            .field("slow_thresholds", &self.slow_thresholds)
+            .finish()
+    }
 }
 
 impl PerformanceMonitor {
@@ -46,9 +87,47 @@ impl PerformanceMonitor {
             history_size: history_size.max(1),
             history: VecDeque::with_capacity(history_size.max(1)),
             stats: HashMap::new(),
+            command_stats: HashMap::new(),
+            pool_history: VecDeque::with_capacity(POOL_HISTORY_SIZE),
+            slow_threshold_secs: DEFAULT_SLOW_THRESHOLD_SECS,
+            slow_thresholds: Vec::new(),
+            slow_listener: None,
         }
     }
 
+    /// 设置慢操作阈值: default_secs 为默认值，by_prefix 按操作名前缀覆盖
+    pub fn set_slow_thresholds(&mut self, default_secs: f64, by_prefix: &BTreeMap<String, f64>) {
+        self.slow_threshold_secs = if default_secs > 0.0 {
+            default_secs
+        } else {
+            DEFAULT_SLOW_THRESHOLD_SECS
+        };
+        let mut items: Vec<(String, f64)> = by_prefix
+            .iter()
+            .filter(|(prefix, secs)| !prefix.is_empty() && **secs > 0.0)
+            .map(|(prefix, secs)| (prefix.clone(), *secs))
+            .collect();
+        // 长前缀优先匹配
+        items.sort_by_key(|item| std::cmp::Reverse(item.0.len()));
+        self.slow_thresholds = items;
+    }
+
+    /// 注册慢操作回调，用于向前端发送事件
+    pub fn set_slow_listener(
+        &mut self,
+        listener: impl Fn(&SlowOperationEvent) + Send + Sync + 'static,
+    ) {
+        self.slow_listener = Some(Arc::new(listener));
+    }
+
+    fn slow_threshold_for(&self, operation_name: &str) -> f64 {
+        self.slow_thresholds
+            .iter()
+            .find(|(prefix, _)| operation_name.starts_with(prefix.as_str()))
+            .map(|(_, secs)| *secs)
+            .unwrap_or(self.slow_threshold_secs)
+    }
+
     pub fn start_operation(
         monitor: Arc<Mutex<Self>>,
         operation_name: impl Into<String>,
@@ -69,7 +148,17 @@ impl PerformanceMonitor {
             self.history.pop_front();
         }
         self.update_stats(&item);
-        if item.duration_secs > 10.0 {
+        let threshold_secs = self.slow_threshold_for(&item.operation_name);
+        if item.duration_secs > threshold_secs {
+            if let Some(listener) = &self.slow_listener {
+                listener(&SlowOperationEvent {
+                    operation_name: item.operation_name.clone(),
+                    username: item.metadata.get("username").cloned().unwrap_or_default(),
+                    duration_secs: item.duration_secs,
+                    threshold_secs,
+                    success: item.success,
+                });
+            }
             if item.success {
                 tracing::warn!(
                     "[性能] {}: {:.2}秒",
@@ -113,6 +202,22 @@ impl PerformanceMonitor {
     }
 
     pub fn get_system_metrics(&self) -> SystemMetrics {
+        let total_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        // 后台采样已运行时直接使用最新样本，避免每次全量刷新
+        if let Some(sample) = latest_system_sample() {
+            return SystemMetrics {
+                cpu_percent: sample.cpu_percent,
+                total_memory_mb: sample.total_memory_mb,
+                used_memory_mb: sample.used_memory_mb,
+                total_threads,
+                app: sample.app,
+                chromedriver: sample.chromedriver,
+                chrome: sample.chrome,
+            };
+        }
+
         let mut sys = System::new_all();
         sys.refresh_all();
 
@@ -125,15 +230,16 @@ impl PerformanceMonitor {
             let sum: f32 = sys.cpus().iter().map(|item| item.cpu_usage()).sum();
             sum / (sys.cpus().len() as f32)
         };
+        let usage = attribute_processes(&sys, &tracked_driver_pids());
 
-        let total_threads = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(1);
         SystemMetrics {
             cpu_percent,
             total_memory_mb,
             used_memory_mb,
             total_threads,
+            app: usage.app,
+            chromedriver: usage.chromedriver,
+            chrome: usage.chrome,
         }
     }
 
@@ -170,9 +276,31 @@ impl PerformanceMonitor {
             format!("内存总量: {:.1}MB", system.total_memory_mb),
             format!("内存占用: {:.1}MB", system.used_memory_mb),
             format!("可用并发线程: {}", system.total_threads),
-            String::new(),
-            "操作统计:".to_string(),
         ];
+        lines.push(format!(
+            "本程序: CPU {:.1}%, 内存 {:.1}MB",
+            system.app.cpu_percent, system.app.memory_mb
+        ));
+        if let Some(sample) = latest_system_sample() {
+            lines.push(format!(
+                "子进程: {} 个, CPU {:.1}%, 内存 {:.1}MB",
+                sample.children.process_count,
+                sample.children.cpu_percent,
+                sample.children.memory_mb
+            ));
+        }
+        lines.push(format!(
+            "chromedriver: {} 个, CPU {:.1}%, 内存 {:.1}MB",
+            system.chromedriver.process_count,
+            system.chromedriver.cpu_percent,
+            system.chromedriver.memory_mb
+        ));
+        lines.push(format!(
+            "Chrome: {} 个, CPU {:.1}%, 内存 {:.1}MB",
+            system.chrome.process_count, system.chrome.cpu_percent, system.chrome.memory_mb
+        ));
+        lines.push(String::new());
+        lines.push("操作统计:".to_string());
 
         for (name, stat) in &self.stats {
             let success_rate = if stat.count > 0 {
@@ -189,29 +317,147 @@ impl PerformanceMonitor {
             lines.push(format!("    最短耗时: {:.2}秒", stat.min_duration));
             lines.push(format!("    最长耗时: {:.2}秒", stat.max_duration));
         }
+
+        let stages: Vec<(&String, &PerfStat)> = self
+            .stats
+            .iter()
+            .filter(|(name, _)| name.starts_with(WEB_STAGE_PREFIX))
+            .collect();
+        let stage_total: f64 = stages.iter().map(|(_, stat)| stat.total_duration).sum();
+        if stage_total > 0.0 {
+            lines.push(String::new());
+            lines.push("网页流程阶段耗时占比:".to_string());
+            for (name, stat) in stages {
+                lines.push(format!(
+                    "  {}: 累计 {:.1}秒, 占比 {:.1}%",
+                    name.trim_start_matches(WEB_STAGE_PREFIX),
+                    stat.total_duration,
+                    stat.total_duration / stage_total * 100.0
+                ));
+            }
+        }
+        if !self.command_stats.is_empty() {
+            let mut commands: Vec<(&String, &PerfStat)> = self.command_stats.iter().collect();
+            commands.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+            lines.push(String::new());
+            lines.push("命令调用统计(按调用次数):".to_string());
+            for (name, stat) in commands {
+                lines.push(format!(
+                    "  {}: 调用 {} 次, 失败 {} 次({:.1}%), 平均 {:.3}秒, 最长 {:.3}秒",
+                    name,
+                    stat.count,
+                    stat.fail_count,
+                    stat.fail_count as f64 / stat.count as f64 * 100.0,
+                    stat.avg_duration,
+                    stat.max_duration
+                ));
+            }
+        }
+        lines.push("============================================================".to_string());
+        lines.join("\n")
+    }
+
+    pub fn record_pool_snapshot(&mut self, stats: HashMap<String, f64>) {
+        if self.pool_history.len() >= POOL_HISTORY_SIZE {
+            self.pool_history.pop_front();
+        }
+        self.pool_history.push_back(PoolSnapshot {
+            taken_at: Local::now(),
+            stats,
+        });
+    }
+
+    pub fn generate_pool_report(&self, current: Option<&HashMap<String, f64>>) -> String {
+        let value =
+            |stats: &HashMap<String, f64>, key: &str| stats.get(key).copied().unwrap_or(0.0);
+        let mut lines = vec![
+            "============================================================".to_string(),
+            "浏览器池报告".to_string(),
+            "============================================================".to_string(),
+            String::new(),
+        ];
+        match current {
+            Some(stats) => {
+                lines.push(format!("实例数: {:.0}", value(stats, "pool_size")));
+                lines.push(format!("使用中: {:.0}", value(stats, "busy_count")));
+                lines.push(format!("累计请求: {:.0}", value(stats, "total_requests")));
+                lines.push(format!("复用率: {:.1}%", value(stats, "reuse_rate")));
+                lines.push(format!("平均等待: {:.2}秒", value(stats, "avg_wait_secs")));
+                lines.push(format!("累计创建: {:.0}", value(stats, "total_created")));
+                lines.push(format!(
+                    "创建失败: {:.0}",
+                    value(stats, "creation_failures")
+                ));
+                lines.push(format!("失效移除: {:.0}", value(stats, "evictions")));
+            }
+            None => lines.push("浏览器池尚未创建".to_string()),
+        }
+
+        lines.push(String::new());
+        lines.push(format!("历史快照 (最近 {} 条):", self.pool_history.len()));
+        for item in &self.pool_history {
+            lines.push(format!(
+                "  {}  实例={:.0} 使用中={:.0} 复用率={:.1}% 平均等待={:.2}秒 创建失败={:.0} 失效移除={:.0}",
+                item.taken_at.format("%m-%d %H:%M:%S"),
+                value(&item.stats, "pool_size"),
+                value(&item.stats, "busy_count"),
+                value(&item.stats, "reuse_rate"),
+                value(&item.stats, "avg_wait_secs"),
+                value(&item.stats, "creation_failures"),
+                value(&item.stats, "evictions"),
+            ));
+        }
         lines.push("============================================================".to_string());
         lines.join("\n")
     }
 
     fn update_stats(&mut self, item: &PerformanceMetrics) {
-        let entry = self
-            .stats
-            .entry(item.operation_name.clone())
-            .or_insert_with(|| PerfStat {
-                min_duration: f64::MAX,
-                ..PerfStat::default()
-            });
-        entry.count += 1;
-        if item.success {
-            entry.success_count += 1;
-        } else {
-            entry.fail_count += 1;
+        accumulate(
+            &mut self.stats,
+            &item.operation_name,
+            item.duration_secs,
+            item.success,
+        );
+    }
+
+    /// 记录一次 Tauri 命令调用，操作名为命令名。只计入命令统计，不写入历史也不触发慢操作提醒，
+    /// 命令内部的查询等操作另有计时
+    pub fn record_command(&mut self, name: &str, duration_secs: f64, error: Option<&str>) {
+        match error {
+            Some(error) => {
+                tracing::debug!("[命令] {}: {:.3}秒 (失败: {})", name, duration_secs, error)
+            }
+            None => tracing::debug!("[命令] {}: {:.3}秒", name, duration_secs),
         }
-        entry.total_duration += item.duration_secs;
-        entry.min_duration = entry.min_duration.min(item.duration_secs);
-        entry.max_duration = entry.max_duration.max(item.duration_secs);
-        entry.avg_duration = entry.total_duration / entry.count as f64;
+        accumulate(
+            &mut self.command_stats,
+            name,
+            duration_secs,
+            error.is_none(),
+        );
+    }
+}
+
+fn accumulate(
+    stats: &mut HashMap<String, PerfStat>,
+    name: &str,
+    duration_secs: f64,
+    success: bool,
+) {
+    let entry = stats.entry(name.to_string()).or_insert_with(|| PerfStat {
+        min_duration: f64::MAX,
+        ..PerfStat::default()
+    });
+    entry.count += 1;
+    if success {
+        entry.success_count += 1;
+    } else {
+        entry.fail_count += 1;
     }
+    entry.total_duration += duration_secs;
+    entry.min_duration = entry.min_duration.min(duration_secs);
+    entry.max_duration = entry.max_duration.max(duration_secs);
+    entry.avg_duration = entry.total_duration / entry.count as f64;
 }
 
 pub struct OperationTimer {
@@ -263,6 +509,202 @@ impl Drop for OperationTimer {
     }
 }
 
+/// 一组进程的资源占用合计
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessUsage {
+    pub process_count: usize,
+    pub cpu_percent: f32,
+    pub memory_mb: f64,
+}
+
+/// 一次系统资源采样
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemSample {
+    pub taken_at: String,
+    pub cpu_percent: f32,
+    pub total_memory_mb: f64,
+    pub used_memory_mb: f64,
+    /// 本程序进程
+    pub app: ProcessUsage,
+    /// 本程序派生的全部子孙进程(chromedriver 与 Chrome)
+    pub children: ProcessUsage,
+    /// 浏览器池启动的 chromedriver 进程
+    pub chromedriver: ProcessUsage,
+    /// chromedriver 派生的 Chrome 进程
+    pub chrome: ProcessUsage,
+}
+
+/// 采样窗口汇总，供前端查询
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemMetricsReport {
+    pub interval_secs: u64,
+    pub sample_count: usize,
+    pub avg_cpu_percent: f32,
+    pub peak_used_memory_mb: f64,
+    pub peak_children_memory_mb: f64,
+    pub samples: Vec<SystemSample>,
+}
+
+const SYSTEM_SAMPLE_HISTORY: usize = 720;
+
+struct SystemSampler {
+    system: System,
+    interval: Duration,
+    samples: VecDeque<SystemSample>,
+}
+
+impl SystemSampler {
+    fn sample(&mut self) -> SystemSample {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        self.system.refresh_processes(ProcessesToUpdate::All, true);
+        let usage = attribute_processes(&self.system, &tracked_driver_pids());
+
+        SystemSample {
+            taken_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            cpu_percent: self.system.global_cpu_usage(),
+            total_memory_mb: self.system.total_memory() as f64 / 1024.0 / 1024.0,
+            used_memory_mb: self.system.used_memory() as f64 / 1024.0 / 1024.0,
+            app: usage.app,
+            children: usage.children,
+            chromedriver: usage.chromedriver,
+            chrome: usage.chrome,
+        }
+    }
+
+    fn push(&mut self, sample: SystemSample) {
+        if self.samples.len() >= SYSTEM_SAMPLE_HISTORY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+}
+
+#[derive(Default)]
+struct ProcessAttribution {
+    app: ProcessUsage,
+    children: ProcessUsage,
+    chromedriver: ProcessUsage,
+    chrome: ProcessUsage,
+}
+
+/// 按进程归属汇总资源: 本程序、全部子孙进程，以及按 PID 跟踪的 chromedriver 和其派生的 Chrome
+fn attribute_processes(system: &System, driver_pids: &[u32]) -> ProcessAttribution {
+    let mut usage = ProcessAttribution::default();
+    let app_pid = sysinfo::get_current_pid().ok();
+    let driver_pids: Vec<Pid> = driver_pids.iter().map(|pid| Pid::from_u32(*pid)).collect();
+    for (pid, process) in system.processes() {
+        if process.thread_kind().is_some() {
+            continue;
+        }
+        let cpu_percent = process.cpu_usage();
+        let memory_mb = process.memory() as f64 / 1024.0 / 1024.0;
+        let add = |target: &mut ProcessUsage| {
+            target.process_count += 1;
+            target.cpu_percent += cpu_percent;
+            target.memory_mb += memory_mb;
+        };
+        if Some(*pid) == app_pid {
+            add(&mut usage.app);
+            continue;
+        }
+        if app_pid.is_some_and(|app_pid| is_descendant_of(system, *pid, &[app_pid])) {
+            add(&mut usage.children);
+        }
+        if driver_pids.contains(pid) {
+            add(&mut usage.chromedriver);
+        } else if is_descendant_of(system, *pid, &driver_pids) {
+            add(&mut usage.chrome);
+        }
+    }
+    usage
+}
+
+fn is_descendant_of(system: &System, pid: Pid, ancestors: &[Pid]) -> bool {
+    if ancestors.is_empty() {
+        return false;
+    }
+    let mut current = system.process(pid).and_then(|item| item.parent());
+    // 限制向上查找深度，防止异常的父进程环
+    for _ in 0..32 {
+        match current {
+            Some(parent) if ancestors.contains(&parent) => return true,
+            Some(parent) => current = system.process(parent).and_then(|item| item.parent()),
+            None => return false,
+        }
+    }
+    false
+}
+
+static SYSTEM_SAMPLER: OnceLock<Arc<Mutex<SystemSampler>>> = OnceLock::new();
+
+/// 启动后台系统资源采样线程，重复调用只会启动一次
+pub fn start_system_sampler(interval_secs: u64) {
+    let interval = Duration::from_secs(interval_secs.max(1));
+    let mut created = false;
+    let sampler = SYSTEM_SAMPLER.get_or_init(|| {
+        created = true;
+        Arc::new(Mutex::new(SystemSampler {
+            system: System::new(),
+            interval,
+            samples: VecDeque::with_capacity(SYSTEM_SAMPLE_HISTORY),
+        }))
+    });
+    if !created {
+        return;
+    }
+    let sampler = sampler.clone();
+    let spawned = thread::Builder::new()
+        .name("system-sampler".to_string())
+        .spawn(move || {
+            loop {
+                if let Ok(mut guard) = sampler.lock() {
+                    let sample = guard.sample();
+                    guard.push(sample);
+                }
+                thread::sleep(interval);
+            }
+        });
+    if let Err(err) = spawned {
+        tracing::warn!("启动系统资源采样线程失败: {}", err);
+    }
+}
+
+fn latest_system_sample() -> Option<SystemSample> {
+    let sampler = SYSTEM_SAMPLER.get()?;
+    let guard = sampler.lock().ok()?;
+    guard.samples.back().cloned()
+}
+
+/// 返回最近 limit 条采样及窗口汇总；采样线程未启动时返回 None
+pub fn system_metrics_report(limit: usize) -> Option<SystemMetricsReport> {
+    let sampler = SYSTEM_SAMPLER.get()?;
+    let guard = sampler.lock().ok()?;
+    let skip = guard.samples.len().saturating_sub(limit.max(1));
+    let samples: Vec<SystemSample> = guard.samples.iter().skip(skip).cloned().collect();
+    let avg_cpu_percent = if samples.is_empty() {
+        0.0
+    } else {
+        samples.iter().map(|item| item.cpu_percent).sum::<f32>() / samples.len() as f32
+    };
+    let peak_used_memory_mb = samples
+        .iter()
+        .map(|item| item.used_memory_mb)
+        .fold(0.0, f64::max);
+    let peak_children_memory_mb = samples
+        .iter()
+        .map(|item| item.children.memory_mb)
+        .fold(0.0, f64::max);
+    Some(SystemMetricsReport {
+        interval_secs: guard.interval.as_secs(),
+        sample_count: samples.len(),
+        avg_cpu_percent,
+        peak_used_memory_mb,
+        peak_children_memory_mb,
+        samples,
+    })
+}
+
 pub fn get_performance_monitor() -> Arc<Mutex<PerformanceMonitor>> {
     static GLOBAL_MONITOR: OnceLock<Arc<Mutex<PerformanceMonitor>>> = OnceLock::new();
     GLOBAL_MONITOR
@@ -0,0 +1,317 @@
+use crate::models::{Account, AlertRecord, CheckResult, PrivacyConfig};
+use anyhow::{Context, Result};
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// 打码名称中保留的哈希位数
+const LABEL_HASH_LEN: usize = 12;
+
+#[derive(Debug, Default)]
+struct Registry {
+    enabled: bool,
+    /// 已知的登录用户名，按长度从长到短排列，替换文本时避免短名先命中长名的一部分
+    usernames: Vec<String>,
+    /// 本机安装的随机密钥，打码哈希以此加盐，拿到截图也无法按用户名列表逐个比对
+    secret: Vec<u8>,
+}
+
+/// 隐私模式开关与账号列表；启动时按配置初始化，界面可随时切换
+static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<Registry> {
+    REGISTRY.get_or_init(|| RwLock::new(Registry::default()))
+}
+
+pub fn configure(config: &PrivacyConfig) {
+    set_enabled(config.enabled);
+}
+
+pub fn set_enabled(enabled: bool) {
+    if let Ok(mut guard) = registry().write() {
+        guard.enabled = enabled;
+    }
+}
+
+pub fn enabled() -> bool {
+    registry()
+        .read()
+        .map(|guard| guard.enabled)
+        .unwrap_or(false)
+}
+
+/// 读取状态目录中的打码密钥，不存在或内容无效时生成新的并保存
+pub fn load_secret(path: &Path) -> Result<()> {
+    let stored = fs::read_to_string(path)
+        .ok()
+        .and_then(|text| hex::decode(text.trim()).ok())
+        .filter(|bytes| bytes.len() >= 16);
+    let fresh = stored.is_none();
+    let secret = stored.unwrap_or_else(random_secret);
+    // 保存失败时本次运行仍使用新密钥，下次启动重新生成
+    if let Ok(mut guard) = registry().write() {
+        guard.secret = secret.clone();
+    }
+    if fresh {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("创建目录失败: {}", parent.display()))?;
+        }
+        fs::write(path, hex::encode(&secret))
+            .with_context(|| format!("写入打码密钥失败: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// 标准库 RandomState 的种子来自系统随机数，再混入时间与进程号
+fn random_secret() -> Vec<u8> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|item| item.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    for round in 0..4_u8 {
+        let mut item = RandomState::new().build_hasher();
+        item.write_u8(round);
+        item.write_u128(nanos);
+        hasher.update(item.finish().to_le_bytes());
+    }
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// 记录当前账号列表，日志与说明文本中出现的用户名据此打码
+pub fn set_usernames(accounts: &[Account]) {
+    let mut usernames: Vec<String> = accounts
+        .iter()
+        .map(|item| item.username.clone())
+        .filter(|item| !item.is_empty())
+        .collect();
+    usernames.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+    usernames.dedup();
+    if let Ok(mut guard) = registry().write() {
+        guard.usernames = usernames;
+    }
+}
+
+/// 打码后的用户名: 首字符加以安装密钥计算的用户名 HMAC 前几位，同一用户名始终相同，便于截图中区分账号
+fn label(secret: &[u8], username: &str) -> String {
+    let first = username.chars().next().unwrap_or('-');
+    let digest = match Hmac::<Sha256>::new_from_slice(secret) {
+        Ok(mut mac) => {
+            mac.update(username.as_bytes());
+            hex::encode(mac.finalize().into_bytes())
+        }
+        Err(_) => hex::encode(Sha256::digest(username.as_bytes())),
+    };
+    format!("{first}***#{}", &digest[..LABEL_HASH_LEN])
+}
+
+pub fn mask_username(username: &str) -> String {
+    if username.is_empty() || username == "SYSTEM" {
+        return username.to_string();
+    }
+    match registry().read() {
+        Ok(guard) if guard.enabled => label(&guard.secret, username),
+        _ => username.to_string(),
+    }
+}
+
+/// 把文本中出现的已知用户名替换为打码后的名称
+pub fn mask_text(text: &str) -> String {
+    let Ok(guard) = registry().read() else {
+        return text.to_string();
+    };
+    if !guard.enabled
+        || !guard
+            .usernames
+            .iter()
+            .any(|username| text.contains(username.as_str()))
+    {
+        return text.to_string();
+    }
+    // 单次扫描替换，避免打码后的名称再被较短的用户名命中
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = text;
+    'scan: while let Some(ch) = rest.chars().next() {
+        for username in &guard.usernames {
+            if let Some(tail) = rest.strip_prefix(username.as_str()) {
+                masked.push_str(&label(&guard.secret, username));
+                rest = tail;
+                continue 'scan;
+            }
+        }
+        masked.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    masked
+}
+
+/// 界面传回打码后的名称时还原为登录用户名；不是打码名称时原样返回。
+/// 切换隐私模式前后界面上的名称都能识别，因此不判断当前是否启用
+pub fn resolve_username(name: &str) -> String {
+    let usernames = match registry().read() {
+        Ok(guard) => guard.usernames.clone(),
+        Err(_) => return name.trim().to_string(),
+    };
+    resolve_among(name, usernames.iter().map(String::as_str))
+}
+
+/// 在给定的用户名中还原打码名称，用于已不在账号列表中的账号(如已删除的账号)
+pub fn resolve_among<'a>(name: &str, usernames: impl IntoIterator<Item = &'a str>) -> String {
+    let name = name.trim();
+    if !name.contains("***#") {
+        return name.to_string();
+    }
+    let secret = registry()
+        .read()
+        .map(|guard| guard.secret.clone())
+        .unwrap_or_default();
+    usernames
+        .into_iter()
+        .find(|username| label(&secret, username) == name)
+        .unwrap_or(name)
+        .to_string()
+}
+
+pub fn mask_result(row: &CheckResult) -> CheckResult {
+    let mut row = row.clone();
+    row.username = mask_username(&row.username);
+    row.alias = mask_text(&row.alias);
+    row.message = mask_text(&row.message);
+    row.apikey_sync_message = mask_text(&row.apikey_sync_message);
+    for attempt in &mut row.attempts {
+        attempt.message = mask_text(&attempt.message);
+    }
+    row
+}
+
+pub fn mask_results(rows: &[CheckResult]) -> Vec<CheckResult> {
+    if !enabled() {
+        return rows.to_vec();
+    }
+    rows.iter().map(mask_result).collect()
+}
+
+/// 把任意报告序列化后打码其中的字符串与对象键
+pub fn mask_json<T: Serialize>(report: &T) -> Result<Value, String> {
+    let value = serde_json::to_value(report).map_err(|e| format!("序列化报告失败: {e}"))?;
+    if !enabled() {
+        return Ok(value);
+    }
+    Ok(mask_value(value))
+}
+
+fn mask_value(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(mask_text(&text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(mask_value).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, item)| (mask_text(&key), mask_value(item)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// 命令响应中结果列表的序列化: 隐私模式下输出打码后的副本
+pub fn serialize_results<S: Serializer>(
+    rows: &[CheckResult],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if !enabled() {
+        return rows.serialize(serializer);
+    }
+    mask_results(rows).serialize(serializer)
+}
+
+pub fn serialize_accounts<S: Serializer>(
+    accounts: &[Account],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if !enabled() {
+        return accounts.serialize(serializer);
+    }
+    accounts
+        .iter()
+        .map(|item| Account {
+            username: mask_username(&item.username),
+            alias: mask_text(&item.alias),
+            ..item.clone()
+        })
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
+
+pub fn serialize_alerts<S: Serializer>(
+    alerts: &[AlertRecord],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if !enabled() {
+        return alerts.serialize(serializer);
+    }
+    alerts
+        .iter()
+        .map(|item| AlertRecord {
+            username: mask_username(&item.username),
+            alias: mask_text(&item.alias),
+            message: mask_text(&item.message),
+            ..item.clone()
+        })
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
+
+pub fn serialize_text<S: Serializer>(text: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&mask_text(text))
+}
+
+pub fn serialize_username<S: Serializer>(username: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&mask_username(username))
+}
+
+pub fn serialize_usernames<S: Serializer>(
+    usernames: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(usernames.iter().map(|item| mask_username(item)))
+}
+
+/// 日志输出的包装: 隐私模式下把每次写入中的用户名打码后再交给下层
+pub struct MaskedMakeWriter<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for MaskedMakeWriter<M> {
+    type Writer = MaskedWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        MaskedWriter(self.0.make_writer())
+    }
+}
+
+pub struct MaskedWriter<W>(W);
+
+impl<W: Write> Write for MaskedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !enabled() {
+            return self.0.write(buf);
+        }
+        let text = String::from_utf8_lossy(buf);
+        self.0.write_all(mask_text(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
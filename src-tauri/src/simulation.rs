@@ -0,0 +1,45 @@
+use crate::api_client::ApiBalanceResult;
+use crate::currency;
+use crate::models::SimulationConfig;
+use chrono::{Local, Timelike};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// 用户名哈希的前 8 字节，决定模拟余额与耗时
+fn seed(username: &str) -> u64 {
+    let digest = Sha256::digest(username.as_bytes());
+    let mut bytes = [0_u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// 模拟余额: 每个账号有固定的初始余额(20~200)与每小时消耗(0.5~3.5)，
+/// 按当天已过的小时数递减，同一小时内多次查询结果相同；部分账号傍晚会透支，便于测试余额告警
+pub fn simulated_balance(username: &str, hour: u32) -> f64 {
+    let seed = seed(username);
+    let base = 20.0 + (seed % 18_000) as f64 / 100.0;
+    let hourly = 0.5 + ((seed >> 16) % 300) as f64 / 100.0;
+    ((base - hourly * f64::from(hour)) * 100.0).round() / 100.0
+}
+
+fn simulated_delay(username: &str, config: &SimulationConfig) -> Duration {
+    let min = config.min_delay_ms.min(config.max_delay_ms);
+    let span = config.max_delay_ms.max(config.min_delay_ms) - min;
+    Duration::from_millis(min + (seed(username) >> 32) % (span + 1))
+}
+
+/// 代替 API 查询的模拟结果，按配置的耗时等待后返回
+pub async fn query_balance(username: &str, config: &SimulationConfig) -> ApiBalanceResult {
+    tokio::time::sleep(simulated_delay(username, config)).await;
+    if config.is_failing(username) {
+        return ApiBalanceResult {
+            source: "simulated".to_string(),
+            ..ApiBalanceResult::fail("模拟查询失败")
+        };
+    }
+    let balance = simulated_balance(username, Local::now().hour());
+    ApiBalanceResult {
+        currency: currency::canonical(),
+        ..ApiBalanceResult::ok(balance, "simulated", "模拟余额，未访问站点")
+    }
+}
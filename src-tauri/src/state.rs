@@ -1,42 +1,352 @@
-use crate::models::{BalanceCacheFile, BalanceCacheRecord, DailyWebStateFile};
+use crate::config::RuntimeFiles;
+use crate::models::{
+    Account, AccountHistoryFile, AccountOutcome, AccountSchedule, AccountSelectorHealth,
+    AccountStats, AccountTombstone, AlertRecord, AlertsFile, AttemptRecord, BalanceCacheFile,
+    BalanceCacheRecord, BalanceChangedEvent, CheckResult, DailyStatusEntry, DailyStatusReport,
+    DailyWebStateFile, DeploymentRecord, DeploymentsFile, MaintenanceReport, RetentionConfig,
+    ScheduleReport, SelectorHealthReport, StateDump, StateRepairReport, TokenLabel, UnitRateRecord,
+};
+use crate::utils::parse_first_number;
 use anyhow::{Context, Result};
-use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::RwLock;
+
+/// 健康度评估窗口（最近N次）
+const HEALTH_WINDOW: usize = 5;
+/// 连续失败达到该次数视为隔离
+const QUARANTINE_THRESHOLD: usize = 5;
+/// 最近一次成功距今超过该小时数视为过期
+const STALE_HOURS: i64 = 24;
+/// 状态文件 schema 版本，升级时在此追加一行，并在各 load_* 中按旧版本迁移后重写:
+/// - v0: 无 version 字段，余额缓存与每日网页状态为 {用户名: 值} 的扁平结构，由 parse_*_accounts 兼容读取
+/// - v1: 各文件带 version 与 updated_at，账号数据放在 accounts 字段下
+/// - v2: 每日网页状态改为按 daily_rollover_hour 切日，旧文件由 migrate_midnight_rollover
+///   回拨切日前写入的“当天”；其余文件结构未变，读到旧版本时仅按当前结构重写
+const STATE_SCHEMA_VERSION: u32 = 2;
+/// 保留的已删除账号记录数
+const MAX_TOMBSTONES: usize = 10;
+/// 调整查询来源所需的最近同类结果数
+const ADAPTIVE_SAMPLES: usize = 3;
+
+/// 按最近检查历史得出的查询来源偏好
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SourcePreference {
+    /// 最近几次 API 查询均鉴权失败，直接网页登录；网页成功后的 API 刷新仍会执行，Key 修复后自动恢复
+    pub skip_api: bool,
+    /// 最近几次网页登录均失败而 API 仍有成功，API 失败时不再回退网页
+    pub skip_web_fallback: bool,
+}
+
+/// 共享的状态存储: 查询结果构建等读操作可并发，仅写入时互斥
+pub type SharedStateStore = Arc<RwLock<StateStore>>;
 
 #[derive(Debug)]
 pub struct StateStore {
     balance_cache_file: PathBuf,
     daily_web_state_file: PathBuf,
+    account_history_file: PathBuf,
+    alerts_file: PathBuf,
+    deployments_file: PathBuf,
     balance_cache: BTreeMap<String, BalanceCacheRecord>,
+    /// 各站点已记录的额度换算比例，随余额缓存保存
+    unit_rates: BTreeMap<String, UnitRateRecord>,
     daily_web_state: BTreeMap<String, String>,
+    account_history: BTreeMap<String, VecDeque<AccountOutcome>>,
+    /// 最近删除的账号(旧的在前)，随账号历史保存
+    removed_accounts: VecDeque<AccountTombstone>,
+    /// 手动排序与置顶的账号，随账号历史保存
+    account_order: Vec<String>,
+    pinned_accounts: BTreeSet<String>,
+    alerts: Vec<AlertRecord>,
+    /// 各部署目标最近一次写入的账号
+    deployments: BTreeMap<String, DeploymentRecord>,
+    /// 本程序新建的令牌及其归属
+    token_labels: Vec<TokenLabel>,
+    /// 各账号最近一次检查结果(仅内存)，用于重试上一轮失败的账号
+    last_round: BTreeMap<String, CheckResult>,
     daily_rollover_hour: u32,
+    /// 切日所在时区，None 表示本机时区
+    rollover_timezone: Option<Tz>,
+    /// 各状态文件有未落盘的修改，由后台定时 flush 在释放状态锁后合并写入。
+    /// 只有启动时的版本迁移与处理启动问题时的重置仍直接写文件: 前者发生在状态存储共享之前，
+    /// 后者需要把写入结果立即反馈给用户
+    balance_cache_dirty: bool,
+    daily_web_state_dirty: bool,
+    account_history_dirty: bool,
+    alerts_dirty: bool,
+    deployments_dirty: bool,
+    /// 变更游标: 启动时取当前毫秒时间戳，每次账号缓存结果变化递增，
+    /// 重启后旧游标必然小于新的起点，据此要求调用方全量同步
+    change_base: u64,
+    change_seq: u64,
+    /// 该游标之前的变化无法逐个追溯(如重新加载账号、状态修复)，需要全量同步
+    full_resync_seq: u64,
+    account_changed_seq: BTreeMap<String, u64>,
+    retention: RetentionConfig,
+    /// 启动时解析失败的状态文件及错误；处理前不写这些文件，避免覆盖用户数据
+    blocked_files: BTreeMap<PathBuf, String>,
 }
 
 impl StateStore {
     pub fn load(
-        balance_cache_file: PathBuf,
-        daily_web_state_file: PathBuf,
+        files: &RuntimeFiles,
         daily_rollover_hour: u32,
+        daily_rollover_timezone: &str,
+        retention: RetentionConfig,
     ) -> Result<Self> {
+        let rollover_timezone = match daily_rollover_timezone.trim() {
+            "" => None,
+            name => match name.parse::<Tz>() {
+                Ok(tz) => Some(tz),
+                Err(err) => {
+                    tracing::warn!("无效的切日时区 {}，改用本机时区: {}", name, err);
+                    None
+                }
+            },
+        };
         let mut store = Self {
-            balance_cache_file,
-            daily_web_state_file,
+            balance_cache_file: files.balance_cache_file.clone(),
+            daily_web_state_file: files.daily_web_state_file.clone(),
+            account_history_file: files.account_history_file.clone(),
+            alerts_file: files.alerts_file.clone(),
+            deployments_file: files.deployments_file.clone(),
             balance_cache: BTreeMap::new(),
+            unit_rates: BTreeMap::new(),
             daily_web_state: BTreeMap::new(),
+            account_history: BTreeMap::new(),
+            removed_accounts: VecDeque::new(),
+            account_order: Vec::new(),
+            pinned_accounts: BTreeSet::new(),
+            alerts: Vec::new(),
+            deployments: BTreeMap::new(),
+            token_labels: Vec::new(),
+            last_round: BTreeMap::new(),
             daily_rollover_hour: if daily_rollover_hour <= 23 {
                 daily_rollover_hour
             } else {
                 8
             },
+            rollover_timezone,
+            balance_cache_dirty: false,
+            daily_web_state_dirty: false,
+            account_history_dirty: false,
+            alerts_dirty: false,
+            deployments_dirty: false,
+            change_base: 0,
+            change_seq: 0,
+            full_resync_seq: 0,
+            account_changed_seq: BTreeMap::new(),
+            retention,
+            blocked_files: BTreeMap::new(),
         };
-        store.load_balance_cache()?;
-        store.load_daily_web_state()?;
+        store.change_base = u64::try_from(Local::now().timestamp_millis()).unwrap_or_default();
+        store.change_seq = store.change_base;
+        store.full_resync_seq = store.change_base;
+        for path in store.state_files() {
+            if let Err(e) = store.load_file(&path) {
+                tracing::error!("状态文件加载失败，进入安全模式: {:#}", e);
+                store.blocked_files.insert(path, format!("{e:#}"));
+            }
+        }
         Ok(store)
     }
 
+    fn state_files(&self) -> [PathBuf; 5] {
+        [
+            self.balance_cache_file.clone(),
+            self.daily_web_state_file.clone(),
+            self.account_history_file.clone(),
+            self.alerts_file.clone(),
+            self.deployments_file.clone(),
+        ]
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<()> {
+        if path == self.balance_cache_file {
+            self.load_balance_cache()
+        } else if path == self.daily_web_state_file {
+            self.load_daily_web_state()
+        } else if path == self.account_history_file {
+            self.load_account_history()
+        } else if path == self.alerts_file {
+            self.load_alerts()
+        } else if path == self.deployments_file {
+            self.load_deployments()
+        } else {
+            anyhow::bail!("不是状态文件: {}", path.display())
+        }
+    }
+
+    /// 启动时解析失败、尚未处理的状态文件
+    pub fn load_failures(&self) -> Vec<(PathBuf, String)> {
+        self.blocked_files
+            .iter()
+            .map(|(path, error)| (path.clone(), error.clone()))
+            .collect()
+    }
+
+    /// 处理解析失败的状态文件: reset 时把原文件备份改名后以内存中的数据重写，
+    /// 否则重新读取(用户已手动修复)。成功后恢复对该文件的写入
+    pub fn resolve_load_failure(&mut self, path: &Path, reset: bool) -> Result<Option<PathBuf>> {
+        if !self.blocked_files.contains_key(path) {
+            anyhow::bail!("该文件没有待处理的加载错误: {}", path.display());
+        }
+        let backup = if reset {
+            Some(crate::config::backup_broken_file(path)?)
+        } else {
+            self.load_file(path)?;
+            None
+        };
+        self.blocked_files.remove(path);
+        if reset {
+            if path == self.balance_cache_file {
+                self.save_balance_cache()?;
+            } else if path == self.daily_web_state_file {
+                self.save_daily_web_state()?;
+            } else if path == self.account_history_file {
+                self.save_account_history()?;
+            } else if path == self.deployments_file {
+                self.save_deployments()?;
+            } else {
+                self.save_alerts()?;
+            }
+        }
+        self.touch_all();
+        Ok(backup)
+    }
+
+    /// 写状态文件；加载失败尚未处理的文件跳过写入
+    fn write_state_file<T: serde::Serialize>(&self, path: &Path, value: &T) -> Result<()> {
+        if self.blocked_files.contains_key(path) {
+            tracing::debug!("状态文件待处理，跳过写入: {}", path.display());
+            return Ok(());
+        }
+        write_json_file(path, value, next_write_seq())
+    }
+
+    fn load_alerts(&mut self) -> Result<()> {
+        if !self.alerts_file.exists() {
+            return Ok(());
+        }
+        let raw = fs::read_to_string(&self.alerts_file)
+            .with_context(|| format!("读取告警文件失败: {}", self.alerts_file.display()))?;
+        let parsed: AlertsFile =
+            serde_json::from_str(&raw).with_context(|| "解析 alerts.json 失败")?;
+        ensure_supported_version(parsed.version, &self.alerts_file)?;
+        self.alerts = parsed.alerts;
+        if parsed.version < STATE_SCHEMA_VERSION {
+            self.save_alerts()?;
+            log_migration(&self.alerts_file, parsed.version);
+        }
+        Ok(())
+    }
+
+    fn load_deployments(&mut self) -> Result<()> {
+        if !self.deployments_file.exists() {
+            return Ok(());
+        }
+        let raw = fs::read_to_string(&self.deployments_file)
+            .with_context(|| format!("读取部署记录失败: {}", self.deployments_file.display()))?;
+        let parsed: DeploymentsFile =
+            serde_json::from_str(&raw).with_context(|| "解析 deployments.json 失败")?;
+        ensure_supported_version(parsed.version, &self.deployments_file)?;
+        self.deployments = parsed.deployments;
+        self.token_labels = parsed.tokens;
+        Ok(())
+    }
+
+    fn deployments_payload(&self) -> DeploymentsFile {
+        DeploymentsFile {
+            version: STATE_SCHEMA_VERSION,
+            updated_at: Local::now().to_rfc3339(),
+            deployments: self.deployments.clone(),
+            tokens: self.token_labels.clone(),
+        }
+    }
+
+    fn save_deployments(&self) -> Result<()> {
+        self.write_state_file(&self.deployments_file, &self.deployments_payload())
+    }
+
+    /// 记录某个目标最近一次写入的账号
+    pub fn record_deployment(&mut self, target: &str, username: &str) {
+        self.deployments.insert(
+            target.to_string(),
+            DeploymentRecord {
+                target: target.to_string(),
+                username: username.to_string(),
+                deployed_at: Local::now().to_rfc3339(),
+            },
+        );
+        self.bump_version("deployments");
+        self.deployments_dirty = true;
+    }
+
+    pub fn list_deployments(&self) -> Vec<DeploymentRecord> {
+        self.deployments.values().cloned().collect()
+    }
+
+    pub fn record_token_label(&mut self, label: TokenLabel) {
+        self.token_labels.push(label);
+        self.bump_version("deployments");
+        self.deployments_dirty = true;
+    }
+
+    /// 本程序新建的令牌；指定账号时只返回该账号的
+    pub fn list_token_labels(&self, username: Option<&str>) -> Vec<TokenLabel> {
+        self.token_labels
+            .iter()
+            .filter(|item| username.is_none_or(|name| item.username == name))
+            .cloned()
+            .collect()
+    }
+
+    /// 目标最近一次写入的账号
+    pub fn deployed_username(&self, target: &str) -> Option<String> {
+        self.deployments
+            .get(target)
+            .map(|item| item.username.clone())
+    }
+
+    fn load_account_history(&mut self) -> Result<()> {
+        if !self.account_history_file.exists() {
+            return Ok(());
+        }
+        let raw = fs::read_to_string(&self.account_history_file).with_context(|| {
+            format!(
+                "读取账号历史文件失败: {}",
+                self.account_history_file.display()
+            )
+        })?;
+        let parsed: AccountHistoryFile =
+            serde_json::from_str(&raw).with_context(|| "解析 account_history.json 失败")?;
+        ensure_supported_version(parsed.version, &self.account_history_file)?;
+        let version = parsed.version;
+        self.account_history = parsed
+            .accounts
+            .into_iter()
+            .map(|(username, items)| {
+                let skip = items.len().saturating_sub(self.history_limit());
+                (username, items.into_iter().skip(skip).collect())
+            })
+            .collect();
+        self.removed_accounts = parsed.removed.into_iter().collect();
+        self.account_order = parsed.order;
+        self.pinned_accounts = parsed.pinned.into_iter().collect();
+        if version < STATE_SCHEMA_VERSION {
+            self.save_account_history()?;
+            log_migration(&self.account_history_file, version);
+        }
+        Ok(())
+    }
+
     fn load_balance_cache(&mut self) -> Result<()> {
         if !self.balance_cache_file.exists() {
             return Ok(());
@@ -49,7 +359,18 @@ impl StateStore {
         })?;
         let parsed: Value =
             serde_json::from_str(&raw).with_context(|| "解析 balance_cache.json 失败")?;
+        let version = file_version(&parsed);
+        ensure_supported_version(version, &self.balance_cache_file)?;
         self.balance_cache = parse_balance_cache_accounts(&parsed);
+        self.unit_rates = parsed
+            .get("unit_rates")
+            .cloned()
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+        if version < STATE_SCHEMA_VERSION {
+            self.save_balance_cache()?;
+            log_migration(&self.balance_cache_file, version);
+        }
         Ok(())
     }
 
@@ -65,63 +386,661 @@ impl StateStore {
         })?;
         let parsed: Value =
             serde_json::from_str(&raw).with_context(|| "解析 daily_web_login_state.json 失败")?;
+        let version = file_version(&parsed);
+        ensure_supported_version(version, &self.daily_web_state_file)?;
         let (state_map, updated_at) = parse_daily_web_accounts(&parsed);
         self.daily_web_state = state_map;
 
-        // 兼容修正：旧版按00:00切日，若记录写入时间在切日前且值为“当天”，回拨一天
-        if !updated_at.is_empty() {
-            if let Some((hour, old_day)) = parse_updated_time(&updated_at) {
-                if hour < self.daily_rollover_hour {
-                    let new_day = old_day - Duration::days(1);
-                    let mut corrected = 0usize;
-                    for value in self.daily_web_state.values_mut() {
-                        if *value == old_day.to_string() {
-                            *value = new_day.to_string();
-                            corrected += 1;
-                        }
-                    }
-                    if corrected > 0 {
-                        tracing::warn!(
-                            "检测到旧版午夜切日状态，已按{:02}:00规则修正 {} 条",
-                            self.daily_rollover_hour,
-                            corrected
-                        );
-                        self.save_daily_web_state()?;
-                    }
-                }
-            }
+        if version < 2 {
+            self.migrate_midnight_rollover(&updated_at);
+        }
+        if version < STATE_SCHEMA_VERSION {
+            self.save_daily_web_state()?;
+            log_migration(&self.daily_web_state_file, version);
         }
-
         Ok(())
     }
 
-    pub fn save_balance_cache(&self) -> Result<()> {
-        let payload = BalanceCacheFile {
-            version: 1,
+    /// v1 → v2: 旧版按00:00切日，若记录写入时间在切日前且值为“当天”，回拨一天
+    fn migrate_midnight_rollover(&mut self, updated_at: &str) {
+        let Some((hour, old_day)) = parse_updated_time(updated_at) else {
+            return;
+        };
+        if hour >= self.daily_rollover_hour {
+            return;
+        }
+        let new_day = old_day - Duration::days(1);
+        let mut corrected = 0usize;
+        for value in self.daily_web_state.values_mut() {
+            if *value == old_day.to_string() {
+                *value = new_day.to_string();
+                corrected += 1;
+            }
+        }
+        if corrected > 0 {
+            tracing::warn!(
+                "检测到旧版午夜切日状态，已按{:02}:00规则修正 {} 条",
+                self.daily_rollover_hour,
+                corrected
+            );
+        }
+    }
+
+    fn balance_cache_payload(&self) -> BalanceCacheFile {
+        BalanceCacheFile {
+            version: STATE_SCHEMA_VERSION,
             updated_at: Local::now().to_rfc3339(),
             accounts: self.balance_cache.clone(),
-        };
-        write_json_file(&self.balance_cache_file, &payload)
+            unit_rates: self.unit_rates.clone(),
+        }
     }
 
-    pub fn save_daily_web_state(&self) -> Result<()> {
-        let payload = DailyWebStateFile {
-            version: 1,
+    fn daily_web_state_payload(&self) -> DailyWebStateFile {
+        DailyWebStateFile {
+            version: STATE_SCHEMA_VERSION,
             updated_at: Local::now().to_rfc3339(),
             accounts: self.daily_web_state.clone(),
+        }
+    }
+
+    fn account_history_payload(&self) -> AccountHistoryFile {
+        AccountHistoryFile {
+            version: STATE_SCHEMA_VERSION,
+            updated_at: Local::now().to_rfc3339(),
+            accounts: self
+                .account_history
+                .iter()
+                .map(|(username, items)| (username.clone(), items.iter().cloned().collect()))
+                .collect(),
+            removed: self.removed_accounts.iter().cloned().collect(),
+            order: self.account_order.clone(),
+            pinned: self.pinned_accounts.iter().cloned().collect(),
+        }
+    }
+
+    pub fn save_balance_cache(&self) -> Result<()> {
+        self.write_state_file(&self.balance_cache_file, &self.balance_cache_payload())
+    }
+
+    pub fn save_daily_web_state(&self) -> Result<()> {
+        self.write_state_file(&self.daily_web_state_file, &self.daily_web_state_payload())
+    }
+
+    pub fn save_account_history(&self) -> Result<()> {
+        self.write_state_file(&self.account_history_file, &self.account_history_payload())
+    }
+
+    /// 保存手动排序与置顶的账号
+    pub fn set_account_order(&mut self, order: Vec<String>, pinned: BTreeSet<String>) {
+        self.account_order = order;
+        self.pinned_accounts = pinned;
+        self.account_history_dirty = true;
+    }
+
+    pub fn pinned_accounts(&self) -> Vec<String> {
+        self.pinned_accounts.iter().cloned().collect()
+    }
+
+    /// 按置顶与手动顺序稳定排序，其余账号保持原有顺序排在后面
+    pub fn sort_by_account_order<T>(&self, items: &mut [T], username: impl Fn(&T) -> &str) {
+        let positions: BTreeMap<&str, usize> = self
+            .account_order
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.as_str(), index))
+            .collect();
+        items.sort_by_key(|item| {
+            let name = username(item);
+            (
+                !self.pinned_accounts.contains(name),
+                positions.get(name).copied().unwrap_or(usize::MAX),
+            )
+        });
+    }
+
+    /// 记录被删除的账号，只保留最近 MAX_TOMBSTONES 条
+    pub fn record_removed_account(&mut self, account: Account) {
+        self.removed_accounts.push_back(AccountTombstone {
+            account,
+            removed_at: Local::now().to_rfc3339(),
+        });
+        while self.removed_accounts.len() > MAX_TOMBSTONES {
+            self.removed_accounts.pop_front();
+        }
+        self.account_history_dirty = true;
+    }
+
+    /// 最近删除的账号，新的在前
+    pub fn removed_accounts(&self) -> Vec<AccountTombstone> {
+        self.removed_accounts.iter().rev().cloned().collect()
+    }
+
+    /// 取出该用户名最近一次删除的记录，恢复后不再保留
+    pub fn take_removed_account(&mut self, username: &str) -> Option<Account> {
+        let index = self
+            .removed_accounts
+            .iter()
+            .rposition(|item| item.account.username == username)?;
+        let tombstone = self.removed_accounts.remove(index)?;
+        self.account_history_dirty = true;
+        Some(tombstone.account)
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.balance_cache_dirty
+            || self.daily_web_state_dirty
+            || self.account_history_dirty
+            || self.alerts_dirty
+            || self.deployments_dirty
+    }
+
+    /// 写入失败后重新标记，等待下一次 flush
+    pub fn mark_dirty(&mut self) {
+        self.balance_cache_dirty = true;
+        self.daily_web_state_dirty = true;
+        self.account_history_dirty = true;
+        self.alerts_dirty = true;
+        self.deployments_dirty = true;
+    }
+
+    /// 把延迟写入的状态文件落盘
+    pub fn flush(&mut self) -> Result<()> {
+        self.take_pending_writes().write()
+    }
+
+    /// 取出待落盘的内容并清除脏标记，调用方可在释放锁之后再写文件
+    pub fn take_pending_writes(&mut self) -> PendingWrites {
+        let mut pending = PendingWrites::default();
+        if self.blocked_files.contains_key(&self.balance_cache_file) {
+            self.balance_cache_dirty = false;
+        }
+        if self.blocked_files.contains_key(&self.daily_web_state_file) {
+            self.daily_web_state_dirty = false;
+        }
+        if self.blocked_files.contains_key(&self.account_history_file) {
+            self.account_history_dirty = false;
+        }
+        if self.blocked_files.contains_key(&self.alerts_file) {
+            self.alerts_dirty = false;
+        }
+        if self.blocked_files.contains_key(&self.deployments_file) {
+            self.deployments_dirty = false;
+        }
+        if self.balance_cache_dirty {
+            pending.balance_cache = Some((
+                self.balance_cache_file.clone(),
+                next_write_seq(),
+                self.balance_cache_payload(),
+            ));
+            self.balance_cache_dirty = false;
+        }
+        if self.daily_web_state_dirty {
+            pending.daily_web_state = Some((
+                self.daily_web_state_file.clone(),
+                next_write_seq(),
+                self.daily_web_state_payload(),
+            ));
+            self.daily_web_state_dirty = false;
+        }
+        if self.account_history_dirty {
+            pending.account_history = Some((
+                self.account_history_file.clone(),
+                next_write_seq(),
+                self.account_history_payload(),
+            ));
+            self.account_history_dirty = false;
+        }
+        if self.alerts_dirty {
+            pending.alerts = Some((
+                self.alerts_file.clone(),
+                next_write_seq(),
+                self.alerts_payload(),
+            ));
+            self.alerts_dirty = false;
+        }
+        if self.deployments_dirty {
+            pending.deployments = Some((
+                self.deployments_file.clone(),
+                next_write_seq(),
+                self.deployments_payload(),
+            ));
+            self.deployments_dirty = false;
+        }
+        pending
+    }
+
+    fn alerts_payload(&self) -> AlertsFile {
+        AlertsFile {
+            version: STATE_SCHEMA_VERSION,
+            updated_at: Local::now().to_rfc3339(),
+            alerts: self.alerts.clone(),
+        }
+    }
+
+    pub fn save_alerts(&self) -> Result<()> {
+        self.write_state_file(&self.alerts_file, &self.alerts_payload())
+    }
+
+    /// 记录新触发的告警；同一账号同类告警未确认时只刷新内容与次数，不重复新增
+    pub fn record_alerts(&mut self, fired: Vec<AlertRecord>) -> usize {
+        if fired.is_empty() {
+            return 0;
+        }
+        let now = Local::now().to_rfc3339();
+        let count = fired.len();
+        for alert in fired {
+            let existing = self.alerts.iter_mut().find(|item| {
+                !item.acknowledged && item.kind == alert.kind && item.username == alert.username
+            });
+            match existing {
+                Some(item) => {
+                    item.message = alert.message;
+                    item.alias = alert.alias;
+                    item.last_seen_at = now.clone();
+                    item.occurrences = item.occurrences.saturating_add(1);
+                }
+                None => self.alerts.push(AlertRecord {
+                    id: format!(
+                        "{}-{}-{}",
+                        alert.kind,
+                        alert.username,
+                        Local::now().timestamp_millis()
+                    ),
+                    created_at: now.clone(),
+                    last_seen_at: now.clone(),
+                    occurrences: 1,
+                    acknowledged: false,
+                    acknowledged_at: String::new(),
+                    ..alert
+                }),
+            }
+        }
+        self.cap_alerts();
+        self.bump_version("alerts");
+        self.alerts_dirty = true;
+        count
+    }
+
+    /// 按时间倒序返回告警，include_acknowledged=false 时只返回未确认的
+    pub fn list_alerts(&self, include_acknowledged: bool) -> Vec<AlertRecord> {
+        self.alerts
+            .iter()
+            .rev()
+            .filter(|item| include_acknowledged || !item.acknowledged)
+            .cloned()
+            .collect()
+    }
+
+    pub fn active_alert_count(&self) -> usize {
+        self.alerts.iter().filter(|item| !item.acknowledged).count()
+    }
+
+    /// 确认指定告警，id 为空时确认全部未处理告警，返回本次确认的数量
+    pub fn acknowledge_alerts(&mut self, id: Option<&str>) -> usize {
+        let now = Local::now().to_rfc3339();
+        let mut count = 0usize;
+        for item in self
+            .alerts
+            .iter_mut()
+            .filter(|item| !item.acknowledged && id.is_none_or(|id| item.id == id))
+        {
+            item.acknowledged = true;
+            item.acknowledged_at = now.clone();
+            count += 1;
+        }
+        if count > 0 {
+            self.bump_version("alerts");
+            self.alerts_dirty = true;
+        }
+        count
+    }
+
+    pub fn dump(&self) -> StateDump {
+        let failure_counters = self
+            .account_history
+            .iter()
+            .map(|(username, items)| {
+                let count = items.iter().rev().take_while(|item| !item.success).count();
+                (username.clone(), count)
+            })
+            .collect();
+        StateDump {
+            cycle_day: self.current_cycle_day().to_string(),
+            balance_cache: self.balance_cache.clone(),
+            daily_web_state: self.daily_web_state.clone(),
+            failure_counters,
+            active_alerts: self.active_alert_count(),
+            deployments: self.deployments.clone(),
+        }
+    }
+
+    fn history_limit(&self) -> usize {
+        self.retention.history_per_account.max(1)
+    }
+
+    /// 告警数量超出上限时优先丢弃最早的已确认告警，返回丢弃数量
+    fn cap_alerts(&mut self) -> usize {
+        let limit = self.retention.max_alerts.max(1);
+        let mut removed = 0usize;
+        while self.alerts.len() > limit {
+            let index = self
+                .alerts
+                .iter()
+                .position(|item| item.acknowledged)
+                .unwrap_or(0);
+            self.alerts.remove(index);
+            removed += 1;
+        }
+        removed
+    }
+
+    /// 按保留策略清理检查记录、已确认告警与过期余额缓存，只重写有变化的文件
+    pub fn run_maintenance(&mut self) -> MaintenanceReport {
+        let mut report = MaintenanceReport::default();
+        let now = Local::now();
+
+        let limit = self.history_limit();
+        for items in self.account_history.values_mut() {
+            while items.len() > limit {
+                items.pop_front();
+                report.trimmed_history += 1;
+            }
+        }
+
+        let before = self.alerts.len();
+        if self.retention.acknowledged_alert_days > 0 {
+            let cutoff = days_ago(now, self.retention.acknowledged_alert_days);
+            self.alerts.retain(|item| {
+                !item.acknowledged
+                    || DateTime::parse_from_rfc3339(&item.acknowledged_at)
+                        .map(|at| at >= cutoff)
+                        .unwrap_or(true)
+            });
+        }
+        report.removed_alerts = before - self.alerts.len() + self.cap_alerts();
+
+        if self.retention.cache_ttl_days > 0 {
+            let cutoff = days_ago(now, self.retention.cache_ttl_days);
+            let before = self.balance_cache.len();
+            self.balance_cache.retain(|_, record| {
+                DateTime::parse_from_rfc3339(&record.updated_at)
+                    .map(|at| at >= cutoff)
+                    .unwrap_or(true)
+            });
+            report.expired_cache = before - self.balance_cache.len();
+        }
+
+        if report.trimmed_history > 0 || report.expired_cache > 0 {
+            self.touch_all();
+        }
+        if report.trimmed_history > 0 {
+            self.account_history_dirty = true;
+        }
+        if report.removed_alerts > 0 {
+            self.bump_version("alerts");
+            self.alerts_dirty = true;
+        }
+        if report.expired_cache > 0 {
+            self.balance_cache_dirty = true;
+        }
+        report.message = format!(
+            "维护完成: 清理检查记录 {} 条, 告警 {} 条, 过期余额缓存 {} 个",
+            report.trimmed_history, report.removed_alerts, report.expired_cache
+        );
+        report
+    }
+
+    /// 按当前账号列表清理已删除账号的遗留条目，并修正格式不规范的日期，只重写有变化的文件
+    pub fn repair(&mut self, usernames: &BTreeSet<String>) -> StateRepairReport {
+        let mut report = StateRepairReport::default();
+
+        let before = self.balance_cache.len();
+        self.balance_cache
+            .retain(|name, _| usernames.contains(name));
+        let mut cache_changed = self.balance_cache.len() != before;
+        report.removed_orphans += before - self.balance_cache.len();
+        for record in self.balance_cache.values_mut() {
+            if record.updated_at.is_empty()
+                || DateTime::parse_from_rfc3339(&record.updated_at).is_ok()
+            {
+                continue;
+            }
+            record.updated_at = normalize_timestamp(&record.updated_at).unwrap_or_default();
+            if record.updated_at.is_empty() {
+                report.dropped_invalid += 1;
+            } else {
+                report.fixed_dates += 1;
+            }
+            cache_changed = true;
+        }
+
+        let before = self.daily_web_state.len();
+        self.daily_web_state
+            .retain(|name, _| usernames.contains(name));
+        let mut daily_changed = self.daily_web_state.len() != before;
+        report.removed_orphans += before - self.daily_web_state.len();
+        let mut invalid_days = Vec::new();
+        for (name, day) in self.daily_web_state.iter_mut() {
+            if NaiveDate::parse_from_str(day, "%Y-%m-%d").is_ok() {
+                continue;
+            }
+            match normalize_day(day) {
+                Some(fixed) => {
+                    *day = fixed.to_string();
+                    report.fixed_dates += 1;
+                }
+                None => invalid_days.push(name.clone()),
+            }
+            daily_changed = true;
+        }
+        for name in invalid_days {
+            self.daily_web_state.remove(&name);
+            report.dropped_invalid += 1;
+        }
+
+        let before = self.account_history.len();
+        self.account_history
+            .retain(|name, _| usernames.contains(name));
+        let mut history_changed = self.account_history.len() != before;
+        report.removed_orphans += before - self.account_history.len();
+        for items in self.account_history.values_mut() {
+            let before = items.len();
+            items.retain(|item| DateTime::parse_from_rfc3339(&item.at).is_ok());
+            if items.len() != before {
+                report.dropped_invalid += before - items.len();
+                history_changed = true;
+            }
+        }
+
+        let before = self.alerts.len();
+        self.alerts
+            .retain(|item| item.username.is_empty() || usernames.contains(&item.username));
+        let alerts_changed = self.alerts.len() != before;
+        report.removed_orphans += before - self.alerts.len();
+        self.last_round.retain(|name, _| usernames.contains(name));
+
+        if cache_changed || history_changed {
+            self.touch_all();
+        }
+        self.balance_cache_dirty |= cache_changed;
+        self.daily_web_state_dirty |= daily_changed;
+        self.account_history_dirty |= history_changed;
+        if alerts_changed {
+            self.bump_version("alerts");
+            self.alerts_dirty = true;
+        }
+        report.message = format!(
+            "状态修复完成: 清理遗留条目 {} 个, 修正日期 {} 个, 丢弃无效条目 {} 个",
+            report.removed_orphans, report.fixed_dates, report.dropped_invalid
+        );
+        report
+    }
+
+    /// 记录一批检查结果到账号历史，并统一落盘一次
+    /// 记录本轮各账号的检查结果；账号历史由后台 flush 落盘，不在持锁期间写文件
+    pub fn record_outcomes(&mut self, results: &[CheckResult]) {
+        let now = Local::now().to_rfc3339();
+        let limit = self.history_limit();
+        let mut changed = false;
+        for item in results {
+            if item.username == "SYSTEM" || item.username.trim().is_empty() {
+                continue;
+            }
+            let entries = self
+                .account_history
+                .entry(item.username.clone())
+                .or_default();
+            entries.push_back(AccountOutcome {
+                at: now.clone(),
+                success: item.success,
+                source: item.source.clone(),
+                message: item.message.clone(),
+                banned: item.banned,
+                extraction: item.extraction.clone(),
+                duration_ms: item
+                    .attempts
+                    .iter()
+                    .map(|attempt| attempt.duration_ms)
+                    .sum(),
+                api_outcome: attempt_outcome(&item.attempts, "api"),
+                web_outcome: attempt_outcome(&item.attempts, "web"),
+            });
+            while entries.len() > limit {
+                entries.pop_front();
+            }
+            changed = true;
+            self.touch_account(&item.username);
+        }
+        if changed {
+            self.account_history_dirty = true;
+        }
+    }
+
+    /// 记录本轮检查结果，覆盖同名账号的上一次结果
+    pub fn record_round_results(&mut self, results: &[CheckResult]) {
+        for item in results {
+            if item.username == "SYSTEM" || item.username.trim().is_empty() {
+                continue;
+            }
+            self.last_round.insert(item.username.clone(), item.clone());
+        }
+    }
+
+    /// 最近一次检查失败的账号
+    pub fn last_round_failures(&self) -> Vec<String> {
+        self.last_round
+            .values()
+            .filter(|item| !item.success)
+            .map(|item| item.username.clone())
+            .collect()
+    }
+
+    /// 最近一次检查判定为封禁的账号，自动查询时跳过
+    pub fn is_banned(&self, username: &str) -> bool {
+        self.account_history
+            .get(username)
+            .and_then(|history| history.back())
+            .is_some_and(|item| item.banned)
+    }
+
+    /// 所有账号中最近一次检查的时间
+    pub fn latest_check_at(&self) -> Option<DateTime<Local>> {
+        self.account_history
+            .values()
+            .filter_map(|items| items.back())
+            .filter_map(|item| DateTime::parse_from_rfc3339(&item.at).ok())
+            .map(|item| item.with_timezone(&Local))
+            .max()
+    }
+
+    pub fn last_round_result(&self, username: &str) -> Option<CheckResult> {
+        self.last_round.get(username).cloned()
+    }
+
+    /// 根据最近检查历史与缓存时间评估账号健康状态，返回 (状态, 健康分)
+    pub fn account_health(&self, username: &str) -> (String, u32) {
+        let Some(history) = self.account_history.get(username).filter(|v| !v.is_empty()) else {
+            return ("stale".to_string(), 0);
         };
-        write_json_file(&self.daily_web_state_file, &payload)
+        let window: Vec<&AccountOutcome> = history.iter().rev().take(HEALTH_WINDOW).collect();
+        let success_count = window.iter().filter(|item| item.success).count();
+        let score = ((success_count * 100) / window.len()) as u32;
+
+        if window[0].banned {
+            return ("banned".to_string(), score);
+        }
+        let consecutive_failures = history
+            .iter()
+            .rev()
+            .take_while(|item| !item.success)
+            .count();
+        if consecutive_failures >= QUARANTINE_THRESHOLD {
+            return ("quarantined".to_string(), score);
+        }
+        let latest = window[0];
+        if !latest.success && is_auth_failure(&latest.message) {
+            return ("auth_failed".to_string(), score);
+        }
+        let last_success_at = history
+            .iter()
+            .rev()
+            .find(|item| item.success)
+            .and_then(|item| DateTime::parse_from_rfc3339(&item.at).ok());
+        match last_success_at {
+            Some(at) if Local::now().signed_duration_since(at) <= Duration::hours(STALE_HOURS) => {}
+            _ => return ("stale".to_string(), score),
+        }
+        if success_count < window.len() {
+            return ("degraded".to_string(), score);
+        }
+        ("healthy".to_string(), score)
+    }
+
+    /// 为结果填充健康状态、透支标记与最近一次额度同步结果
+    pub fn apply_health(&self, result: &mut CheckResult) {
+        let (health, score) = self.account_health(&result.username);
+        result.health = health;
+        result.health_score = score;
+        result.overdrawn = result.success
+            && parse_first_number(&result.balance_text).is_some_and(|value| value < 0.0);
+        if let Some(record) = self.balance_cache.get(&result.username) {
+            result.apikey_sync_success = record.apikey_sync_success;
+            result.apikey_sync_message = record.apikey_sync_message.clone();
+        }
+    }
+
+    /// 切日时区下的当前时间
+    fn rollover_now(&self) -> NaiveDateTime {
+        match self.rollover_timezone {
+            Some(tz) => Utc::now().with_timezone(&tz).naive_local(),
+            None => Local::now().naive_local(),
+        }
     }
 
     pub fn current_cycle_day(&self) -> NaiveDate {
-        let now = Local::now();
+        let now = self.rollover_now();
         if now.hour() < self.daily_rollover_hour {
-            (now - Duration::days(1)).date_naive()
+            (now - Duration::days(1)).date()
         } else {
-            now.date_naive()
+            now.date()
         }
     }
 
+    /// 距离下一次切日(daily_rollover_hour 整点)的秒数
+    pub fn seconds_until_rollover(&self) -> i64 {
+        let now = self.rollover_now();
+        let today_rollover = now
+            .date()
+            .and_hms_opt(self.daily_rollover_hour, 0, 0)
+            .unwrap_or_default();
+        let next_rollover = if now < today_rollover {
+            today_rollover
+        } else {
+            today_rollover + Duration::days(1)
+        };
+        (next_rollover - now).num_seconds()
+    }
+
     pub fn should_force_web_query(&self, username: &str) -> bool {
         let cycle_day = self.current_cycle_day().to_string();
         let last_day = self
@@ -132,15 +1051,239 @@ impl StateStore {
         cycle_day != last_day
     }
 
+    /// 汇总当前周期各账号网页登录完成情况，未完成的附带最近一次检查的失败原因
+    pub fn daily_status(&self, usernames: &[String]) -> DailyStatusReport {
+        let cycle_day = self.current_cycle_day().to_string();
+        let accounts: Vec<DailyStatusEntry> = usernames
+            .iter()
+            .map(|username| {
+                let completed_day = self
+                    .daily_web_state
+                    .get(username)
+                    .cloned()
+                    .unwrap_or_default();
+                let last = self
+                    .account_history
+                    .get(username)
+                    .and_then(|items| items.back());
+                DailyStatusEntry {
+                    username: username.clone(),
+                    completed: completed_day == cycle_day,
+                    completed_day,
+                    last_attempt_at: last.map(|item| item.at.clone()).unwrap_or_default(),
+                    last_error: last
+                        .filter(|item| !item.success)
+                        .map(|item| item.message.clone())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+        let completed_count = accounts.iter().filter(|item| item.completed).count();
+        DailyStatusReport {
+            cycle_day,
+            rollover_hour: self.daily_rollover_hour,
+            seconds_until_rollover: self.seconds_until_rollover(),
+            completed_count,
+            pending_count: accounts.len() - completed_count,
+            accounts,
+        }
+    }
+
+    /// 账号检查历史的成功率、耗时与来源统计，recent 为返回的最近结果条数
+    pub fn account_stats(&self, username: &str, recent: usize) -> AccountStats {
+        let empty = VecDeque::new();
+        let history = self.account_history.get(username).unwrap_or(&empty);
+        let successes = history.iter().filter(|item| item.success).count();
+        let timed: Vec<u64> = history
+            .iter()
+            .map(|item| item.duration_ms)
+            .filter(|ms| *ms > 0)
+            .collect();
+        let web_count = history
+            .iter()
+            .filter(|item| item.source.starts_with("web"))
+            .count();
+        let api_count = history
+            .iter()
+            .filter(|item| item.source.starts_with("api"))
+            .count();
+        let ratio = |part: usize, whole: usize| {
+            if whole == 0 {
+                0.0
+            } else {
+                part as f64 / whole as f64
+            }
+        };
+        AccountStats {
+            username: username.to_string(),
+            total: history.len(),
+            successes,
+            success_rate: ratio(successes, history.len()),
+            avg_duration_ms: if timed.is_empty() {
+                0
+            } else {
+                timed.iter().sum::<u64>() / timed.len() as u64
+            },
+            api_count,
+            web_count,
+            web_ratio: ratio(web_count, web_count + api_count),
+            first_at: history
+                .front()
+                .map(|item| item.at.clone())
+                .unwrap_or_default(),
+            last_at: history
+                .back()
+                .map(|item| item.at.clone())
+                .unwrap_or_default(),
+            recent: history.iter().rev().take(recent).cloned().collect(),
+        }
+    }
+
+    pub fn source_preference(&self, username: &str) -> SourcePreference {
+        let Some(history) = self.account_history.get(username) else {
+            return SourcePreference::default();
+        };
+        let recent = |pick: fn(&AccountOutcome) -> &str| -> Vec<&str> {
+            history
+                .iter()
+                .rev()
+                .map(pick)
+                .filter(|outcome| !outcome.is_empty())
+                .take(ADAPTIVE_SAMPLES)
+                .collect()
+        };
+        let api = recent(|item| item.api_outcome.as_str());
+        let web = recent(|item| item.web_outcome.as_str());
+        SourcePreference {
+            skip_api: api.len() == ADAPTIVE_SAMPLES
+                && api.iter().all(|outcome| *outcome == "auth_failed"),
+            skip_web_fallback: web.len() == ADAPTIVE_SAMPLES
+                && web.iter().all(|outcome| *outcome != "success")
+                && api.contains(&"success"),
+        }
+    }
+
+    /// 按账号历史汇总网页提取余额命中的策略
+    pub fn selector_health(&self, usernames: &[String]) -> SelectorHealthReport {
+        let mut totals: BTreeMap<String, u32> = BTreeMap::new();
+        let accounts: Vec<AccountSelectorHealth> = usernames
+            .iter()
+            .map(|username| {
+                let extractions: Vec<&AccountOutcome> = self
+                    .account_history
+                    .get(username)
+                    .map(|items| {
+                        items
+                            .iter()
+                            .filter(|item| item.success && !item.extraction.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+                for item in &extractions {
+                    *counts.entry(item.extraction.clone()).or_default() += 1;
+                    *totals.entry(item.extraction.clone()).or_default() += 1;
+                }
+                let recent = &extractions[extractions.len().saturating_sub(HEALTH_WINDOW)..];
+                let last = extractions.last();
+                AccountSelectorHealth {
+                    username: username.clone(),
+                    last_strategy: last.map(|item| item.extraction.clone()).unwrap_or_default(),
+                    last_at: last.map(|item| item.at.clone()).unwrap_or_default(),
+                    counts,
+                    fragile_only: !recent.is_empty()
+                        && recent
+                            .iter()
+                            .all(|item| is_fragile_strategy(&item.extraction)),
+                }
+            })
+            .collect();
+        let total: u32 = totals.values().sum();
+        let fragile: u32 = totals
+            .iter()
+            .filter(|(name, _)| is_fragile_strategy(name))
+            .map(|(_, count)| count)
+            .sum();
+        let observed: Vec<&AccountSelectorHealth> = accounts
+            .iter()
+            .filter(|item| !item.last_strategy.is_empty())
+            .collect();
+        SelectorHealthReport {
+            totals,
+            fragile_ratio: if total == 0 {
+                0.0
+            } else {
+                f64::from(fragile) / f64::from(total)
+            },
+            degraded: !observed.is_empty() && observed.iter().all(|item| item.fragile_only),
+            accounts,
+        }
+    }
+
+    /// 按各账号的间隔(用户名, 秒)与最近一次检查时间计算自动查询计划
+    pub fn schedule(&self, intervals: &[(String, u64)], default_interval: u64) -> ScheduleReport {
+        let now = Local::now();
+        let accounts: Vec<AccountSchedule> = intervals
+            .iter()
+            .filter(|(username, _)| !self.is_banned(username))
+            .map(|(username, interval_secs)| {
+                let last_checked_at = self
+                    .account_history
+                    .get(username)
+                    .and_then(|items| items.back())
+                    .map(|item| item.at.clone())
+                    .unwrap_or_default();
+                let due_in_secs = DateTime::parse_from_rfc3339(&last_checked_at)
+                    .map(|at| {
+                        let elapsed = (now - at.with_timezone(&Local)).num_seconds().max(0);
+                        interval_secs.saturating_sub(elapsed as u64)
+                    })
+                    .unwrap_or(0);
+                AccountSchedule {
+                    username: username.clone(),
+                    interval_secs: *interval_secs,
+                    last_checked_at,
+                    due_in_secs,
+                }
+            })
+            .collect();
+        let next_due_in_secs = accounts
+            .iter()
+            .map(|item| item.due_in_secs)
+            .min()
+            .unwrap_or(default_interval)
+            .max(1);
+        ScheduleReport {
+            accounts,
+            next_due_in_secs,
+        }
+    }
+
     pub fn mark_web_query_success(&mut self, username: &str) -> Result<()> {
         let cycle_day = self.current_cycle_day().to_string();
         self.daily_web_state
             .insert(username.to_string(), cycle_day.clone());
-        self.save_daily_web_state()?;
+        self.daily_web_state_dirty = true;
         tracing::debug!("账号 {} 已记录网页登录成功周期日: {}", username, cycle_day);
         Ok(())
     }
 
+    /// 清除每日网页登录记录，使下次查询重新强制网页登录；username 为空时清除全部
+    pub fn reset_web_state(&mut self, username: Option<&str>) -> usize {
+        let removed = match username {
+            Some(name) => usize::from(self.daily_web_state.remove(name).is_some()),
+            None => {
+                let count = self.daily_web_state.len();
+                self.daily_web_state.clear();
+                count
+            }
+        };
+        if removed > 0 {
+            self.daily_web_state_dirty = true;
+        }
+        removed
+    }
+
     pub fn update_balance_cache(
         &mut self,
         username: &str,
@@ -155,12 +1298,129 @@ impl StateStore {
             .unwrap_or_default();
         record.balance = balance.to_string();
         record.updated_at = Local::now().to_rfc3339();
-        record.apikey_sync_success = apikey_sync_success;
+        // 本次未同步额度时保留上一次的同步结果
+        if apikey_sync_success.is_some() {
+            record.apikey_sync_success = apikey_sync_success;
+        }
         if let Some(msg) = apikey_sync_message {
             record.apikey_sync_message = msg.to_string();
         }
         self.balance_cache.insert(username.to_string(), record);
-        self.save_balance_cache()
+        self.balance_cache_dirty = true;
+        self.touch_account(username);
+        Ok(())
+    }
+
+    /// 已记录的各站点额度换算比例
+    pub fn unit_rates(&self) -> BTreeMap<String, f64> {
+        self.unit_rates
+            .iter()
+            .map(|(provider, record)| (provider.clone(), record.rate))
+            .collect()
+    }
+
+    pub fn record_unit_rate(&mut self, provider: &str, rate: f64) {
+        if !rate.is_finite() || rate <= 0.0 {
+            return;
+        }
+        self.unit_rates.insert(
+            provider.to_string(),
+            UnitRateRecord {
+                rate,
+                updated_at: Local::now().to_rfc3339(),
+            },
+        );
+        self.balance_cache_dirty = true;
+    }
+
+    /// 记录账号 Key 写入 Claude 配置的时间；没有余额缓存的账号不记录
+    pub fn mark_claude_deployed(&mut self, username: &str) {
+        if let Some(record) = self.balance_cache.get_mut(username) {
+            record.claude_deployed_at = Local::now().to_rfc3339();
+            self.balance_cache_dirty = true;
+        }
+    }
+
+    /// 与缓存中的旧余额比较，数值有变化时返回变动事件；无旧缓存视为首次写入，不算变动
+    pub fn balance_change(
+        &self,
+        username: &str,
+        new_balance: &str,
+        source: &str,
+    ) -> Option<BalanceChangedEvent> {
+        let old_balance = self.get_cached_balance_text(username)?;
+        let delta = match (
+            parse_first_number(&old_balance),
+            parse_first_number(new_balance),
+        ) {
+            (Some(old), Some(new)) => {
+                let diff = new - old;
+                if diff.abs() < 1e-9 {
+                    return None;
+                }
+                Some(diff)
+            }
+            _ => {
+                if old_balance.trim() == new_balance.trim() {
+                    return None;
+                }
+                None
+            }
+        };
+        Some(BalanceChangedEvent {
+            username: username.to_string(),
+            old_balance,
+            new_balance: new_balance.to_string(),
+            delta,
+            source: source.to_string(),
+        })
+    }
+
+    /// 当前变更游标，同时作为状态版本: 缓存结果或告警有变化时递增
+    pub fn change_cursor(&self) -> u64 {
+        self.change_seq
+    }
+
+    /// 状态有变化但不涉及具体账号的缓存结果(如告警)，kind 为变化类别
+    fn bump_version(&mut self, kind: &'static str) {
+        self.change_seq += 1;
+        self.notify_change(kind);
+    }
+
+    /// 标记账号的缓存结果已变化
+    pub fn touch_account(&mut self, username: &str) {
+        self.change_seq += 1;
+        self.account_changed_seq
+            .insert(username.to_string(), self.change_seq);
+        self.notify_change("results");
+    }
+
+    /// 标记全部账号需要重新同步
+    pub fn touch_all(&mut self) {
+        self.change_seq += 1;
+        self.full_resync_seq = self.change_seq;
+        self.account_changed_seq.clear();
+        self.notify_change("results");
+    }
+
+    fn notify_change(&self, kind: &'static str) {
+        if let Some(listener) = CHANGE_LISTENER.get() {
+            listener(self.change_seq, kind);
+        }
+    }
+
+    /// 游标之后有变化的账号；游标早于本次启动、早于全量失效点或超出当前游标时返回 None，调用方应全量同步
+    pub fn changed_since(&self, cursor: u64) -> Option<BTreeSet<String>> {
+        if cursor < self.full_resync_seq || cursor > self.change_seq {
+            return None;
+        }
+        Some(
+            self.account_changed_seq
+                .iter()
+                .filter(|(_, seq)| **seq > cursor)
+                .map(|(name, _)| name.clone())
+                .collect(),
+        )
     }
 
     pub fn get_cached_balance_text(&self, username: &str) -> Option<String> {
@@ -175,14 +1435,183 @@ impl StateStore {
     }
 }
 
-fn write_json_file<T: serde::Serialize>(path: &Path, value: &T) -> Result<()> {
-    let tmp = path.with_extension("tmp");
+type ChangeListener = Box<dyn Fn(u64, &'static str) + Send + Sync>;
+
+/// 变更游标递增时的回调，参数为新游标与变化类别，启动时注册一次
+static CHANGE_LISTENER: OnceLock<ChangeListener> = OnceLock::new();
+
+/// 注册变更回调；回调在持有状态存储写锁时调用，不能再次获取状态存储
+pub fn set_change_listener(listener: impl Fn(u64, &'static str) + Send + Sync + 'static) {
+    let _ = CHANGE_LISTENER.set(Box::new(listener));
+}
+
+/// 从 StateStore 取出的待写入快照，写文件时不占用状态锁
+#[derive(Debug, Default)]
+pub struct PendingWrites {
+    balance_cache: Option<(PathBuf, u64, BalanceCacheFile)>,
+    daily_web_state: Option<(PathBuf, u64, DailyWebStateFile)>,
+    account_history: Option<(PathBuf, u64, AccountHistoryFile)>,
+    alerts: Option<(PathBuf, u64, AlertsFile)>,
+    deployments: Option<(PathBuf, u64, DeploymentsFile)>,
+}
+
+impl PendingWrites {
+    pub fn write(self) -> Result<()> {
+        if let Some((path, seq, payload)) = &self.balance_cache {
+            write_json_file(path, payload, *seq)?;
+        }
+        if let Some((path, seq, payload)) = &self.daily_web_state {
+            write_json_file(path, payload, *seq)?;
+        }
+        if let Some((path, seq, payload)) = &self.account_history {
+            write_json_file(path, payload, *seq)?;
+        }
+        if let Some((path, seq, payload)) = &self.alerts {
+            write_json_file(path, payload, *seq)?;
+        }
+        if let Some((path, seq, payload)) = &self.deployments {
+            write_json_file(path, payload, *seq)?;
+        }
+        Ok(())
+    }
+}
+
+fn file_version(root: &Value) -> u32 {
+    root.get("version")
+        .and_then(Value::as_u64)
+        .map(|item| item as u32)
+        .unwrap_or(0)
+}
+
+/// 拒绝读取由更新版本程序写入的状态文件，避免按旧结构覆盖后丢失数据
+fn ensure_supported_version(version: u32, path: &Path) -> Result<()> {
+    if version > STATE_SCHEMA_VERSION {
+        anyhow::bail!(
+            "状态文件 {} 的版本({})高于当前程序支持的版本({})，请升级程序后再使用",
+            path.display(),
+            version,
+            STATE_SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+fn log_migration(path: &Path, from_version: u32) {
+    tracing::info!(
+        "已将状态文件 {} 从版本 {} 迁移到 {}",
+        path.display(),
+        from_version,
+        STATE_SCHEMA_VERSION
+    );
+}
+
+/// 状态快照的写入序号，在持有状态锁取快照时分配，序号越大内容越新
+static WRITE_SEQ: AtomicU64 = AtomicU64::new(0);
+/// 临时文件名计数，同一进程内并发写入也不会共用临时文件
+static TEMP_SEQ: AtomicU64 = AtomicU64::new(0);
+/// 每个状态文件一把写锁，记录已落盘内容的序号
+static FILE_WRITES: OnceLock<Mutex<BTreeMap<PathBuf, Arc<Mutex<u64>>>>> = OnceLock::new();
+
+fn next_write_seq() -> u64 {
+    WRITE_SEQ.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+fn file_write_lock(path: &Path) -> Arc<Mutex<u64>> {
+    let mut locks = FILE_WRITES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    locks.entry(path.to_path_buf()).or_default().clone()
+}
+
+/// 原子写入状态文件。后台 flush 在释放状态锁后才写文件，可能与直接保存交错，
+/// 因此同一文件的序列化与替换串行进行，且序号不比已落盘内容新的快照直接丢弃
+fn write_json_file<T: serde::Serialize>(path: &Path, value: &T, seq: u64) -> Result<()> {
+    let lock = file_write_lock(path);
+    let mut written = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if *written >= seq {
+        tracing::debug!("已有更新的内容落盘，跳过旧快照: {}", path.display());
+        return Ok(());
+    }
+    if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("创建目录失败: {}", parent.display()))?;
+    }
     let content = serde_json::to_string_pretty(value).with_context(|| "序列化JSON失败")?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(
+        "{file_name}.{}.{}.tmp",
+        std::process::id(),
+        TEMP_SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
     fs::write(&tmp, content).with_context(|| format!("写入临时文件失败: {}", tmp.display()))?;
-    fs::rename(&tmp, path).with_context(|| format!("原子替换失败: {}", path.display()))?;
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("原子替换失败: {}", path.display()));
+    }
+    *written = seq;
     Ok(())
 }
 
+/// 本次检查中最后一次以 prefix 开头的来源的结果，鉴权失败单独标记
+fn attempt_outcome(attempts: &[AttemptRecord], prefix: &str) -> String {
+    attempts
+        .iter()
+        .rev()
+        .find(|item| item.source.starts_with(prefix) && item.outcome != "skipped")
+        .map(|item| match item.outcome.as_str() {
+            "failed" if is_auth_failure(&item.message) => "auth_failed".to_string(),
+            other => other.to_string(),
+        })
+        .unwrap_or_default()
+}
+
+/// 启发式兜底的提取策略: 站点改版后往往只剩这些能命中，结果也更容易取错
+fn is_fragile_strategy(strategy: &str) -> bool {
+    matches!(strategy, "large_text" | "container_text" | "regex_fallback")
+}
+
+fn is_auth_failure(message: &str) -> bool {
+    let lower = message.to_ascii_lowercase();
+    [
+        "401",
+        "403",
+        "unauthorized",
+        "invalid api key",
+        "用户名或密码",
+        "账号或密码",
+        "密码错误",
+    ]
+    .iter()
+    .any(|key| lower.contains(key))
+}
+
+/// 把常见的非 RFC3339 时间文本转为本地时区 RFC3339
+fn normalize_timestamp(text: &str) -> Option<String> {
+    let text = text.trim();
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y/%m/%d %H:%M:%S",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .and_then(|naive| naive.and_local_timezone(Local).single())
+    .map(|value| value.to_rfc3339())
+}
+
+/// 识别带时间或使用其他分隔符的日期，取其中的日期部分
+fn normalize_day(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    if let Ok(value) = DateTime::parse_from_rfc3339(text) {
+        return Some(value.date_naive());
+    }
+    let head = text.get(..10).unwrap_or(text);
+    ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(head, format).ok())
+}
+
 fn parse_updated_time(text: &str) -> Option<(u32, NaiveDate)> {
     if let Ok(with_tz) = DateTime::parse_from_rfc3339(text) {
         return Some((with_tz.hour(), with_tz.date_naive()));
@@ -212,6 +1641,9 @@ fn parse_balance_cache_accounts(root: &Value) -> BTreeMap<String, BalanceCacheRe
                     record.apikey_sync_message = value_to_text(obj.get("apikey_sync_message"))
                         .trim()
                         .to_string();
+                    record.claude_deployed_at = value_to_text(obj.get("claude_deployed_at"))
+                        .trim()
+                        .to_string();
                 }
                 _ => {
                     record.balance = value_to_text(Some(item)).trim().to_string();
@@ -271,3 +1703,85 @@ fn is_valid_day_text(text: &str) -> bool {
             .enumerate()
             .all(|(idx, ch)| idx == 4 || idx == 7 || ch.is_ascii_digit())
 }
+
+/// 保留天数对应的截止时间，天数上限 100 年
+fn days_ago(now: DateTime<Local>, days: u64) -> DateTime<Local> {
+    now - Duration::days(days.min(36_500) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("art_rs_state_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn load_store(files: &RuntimeFiles) -> StateStore {
+        StateStore::load(files, 8, "", RetentionConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn claude_deployed_at_survives_reload() {
+        let dir = scratch_dir("claude_deployed");
+        let files = RuntimeFiles::new(dir.clone());
+        let mut store = load_store(&files);
+        store
+            .update_balance_cache("alice", "$3.00", Some(true), Some("已同步"))
+            .unwrap();
+        store.mark_claude_deployed("alice");
+        let deployed_at = store.balance_cache["alice"].claude_deployed_at.clone();
+        assert!(!deployed_at.is_empty());
+        store.flush().unwrap();
+
+        let reloaded = load_store(&files);
+        let record = &reloaded.balance_cache["alice"];
+        assert_eq!(record.claude_deployed_at, deployed_at);
+        assert_eq!(record.balance, "$3.00");
+        assert_eq!(record.apikey_sync_success, Some(true));
+        assert_eq!(record.apikey_sync_message, "已同步");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn outcomes_are_written_by_flush() {
+        let dir = scratch_dir("outcomes");
+        let files = RuntimeFiles::new(dir.clone());
+        let mut store = load_store(&files);
+        store.record_outcomes(&[CheckResult {
+            username: "alice".to_string(),
+            success: true,
+            source: "api".to_string(),
+            ..Default::default()
+        }]);
+        assert!(store.is_dirty());
+        assert!(!files.account_history_file.exists());
+        store.flush().unwrap();
+        assert!(!store.is_dirty());
+
+        let reloaded = load_store(&files);
+        assert_eq!(reloaded.account_history["alice"].len(), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn older_snapshot_does_not_overwrite_newer_write() {
+        let dir = scratch_dir("write_seq");
+        let path = dir.join("balance_cache.json");
+        let older = next_write_seq();
+        let newer = next_write_seq();
+        write_json_file(&path, &"newer", newer).unwrap();
+        write_json_file(&path, &"older", older).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "\"newer\"");
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
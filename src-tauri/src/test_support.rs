@@ -0,0 +1,28 @@
+//! 单元测试共用的辅助工具
+
+/// 固定种子的伪随机数(xorshift)，生成可复现的性质测试输入
+pub struct Rng(u64);
+
+impl Rng {
+    /// 种子不能为 0，否则序列恒为 0
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// [-limit, limit] 内保留两位小数的金额
+    pub fn amount(&mut self, limit: u64) -> f64 {
+        let cents = (self.next() % (limit * 100 + 1)) as f64 / 100.0;
+        if self.next().is_multiple_of(2) {
+            cents
+        } else {
+            -cents
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use crate::config;
+use crate::models::WslConfig;
+use crate::paths::{claude_settings_path, codex_auth_path, tool_config_backup_path};
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub fn read_current_claude_token() -> Result<String> {
+    let target = claude_settings_path()?;
+    if !target.exists() {
+        return Ok(String::new());
+    }
+    let raw = std::fs::read_to_string(&target)
+        .with_context(|| format!("读取配置失败: {}", target.display()))?;
+    let root: Value = serde_json::from_str(&raw).unwrap_or(Value::Null);
+    let token = root
+        .get("env")
+        .and_then(|env| env.get("ANTHROPIC_AUTH_TOKEN"))
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    Ok(token)
+}
+
+/// 读取工具配置并在其 JSON 对象上应用修改后写回。
+/// 写入前把原文件复制为 .bak；原文件不是合法的 JSON 对象时改名为 .broken-<时间> 保留，
+/// 再从空对象开始，不静默丢弃用户内容
+fn patch_tool_config(target: &Path, patch: impl FnOnce(&mut Map<String, Value>)) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("创建目录失败: {}", parent.display()))?;
+    }
+    let backup = tool_config_backup_path(target);
+    let mut root = Map::new();
+    if target.exists() {
+        let raw = std::fs::read_to_string(target)
+            .with_context(|| format!("读取配置失败: {}", target.display()))?;
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(Value::Object(obj)) => {
+                std::fs::copy(target, &backup)
+                    .with_context(|| format!("备份配置失败: {}", target.display()))?;
+                root = obj;
+            }
+            _ => {
+                let broken = config::backup_broken_file(target)?;
+                tracing::warn!(
+                    "配置文件不是合法的 JSON 对象，已备份为 {} 后重新生成",
+                    broken.display()
+                );
+                std::fs::copy(&broken, &backup)
+                    .with_context(|| format!("备份配置失败: {}", broken.display()))?;
+            }
+        }
+    } else if backup.exists() {
+        // 原文件不存在时清掉旧备份，避免回滚到更早的内容
+        std::fs::remove_file(&backup)
+            .with_context(|| format!("删除旧备份失败: {}", backup.display()))?;
+    }
+
+    patch(&mut root);
+    let content =
+        serde_json::to_string_pretty(&Value::Object(root)).with_context(|| "序列化配置失败")?;
+    std::fs::write(target, content)
+        .with_context(|| format!("写入配置失败: {}", target.display()))?;
+    Ok(())
+}
+
+pub fn save_claude_token(token: &str) -> Result<PathBuf> {
+    let target = claude_settings_path()?;
+    patch_tool_config(&target, |obj| {
+        let env_value = obj
+            .entry("env".to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !env_value.is_object() {
+            *env_value = Value::Object(Map::new());
+        }
+        let env_obj = env_value.as_object_mut().expect("env object");
+        env_obj.insert(
+            "ANTHROPIC_AUTH_TOKEN".to_string(),
+            Value::String(token.to_string()),
+        );
+    })?;
+    Ok(target)
+}
+
+fn save_openai_key_local(token: &str) -> Result<PathBuf> {
+    let target = codex_auth_path()?;
+    patch_tool_config(&target, |obj| {
+        obj.insert(
+            "OPENAI_API_KEY".to_string(),
+            Value::String(token.to_string()),
+        );
+    })?;
+    Ok(target)
+}
+
+fn decode_wsl_output(bytes: &[u8]) -> String {
+    if bytes.len() >= 2 && bytes[1] == 0 {
+        let mut u16s = Vec::with_capacity(bytes.len() / 2);
+        for chunk in bytes.chunks_exact(2) {
+            u16s.push(u16::from_le_bytes([chunk[0], chunk[1]]));
+        }
+        if let Ok(text) = String::from_utf16(&u16s) {
+            return text;
+        }
+    }
+    String::from_utf8_lossy(bytes).to_string()
+}
+
+/// 已发现的 WSL 发行版，首次使用或刷新时才调用 wsl.exe
+static WSL_DISTROS: std::sync::Mutex<Option<Vec<String>>> = std::sync::Mutex::new(None);
+
+pub fn cached_wsl_distros(refresh: bool) -> Vec<String> {
+    let mut guard = WSL_DISTROS.lock().unwrap_or_else(|e| e.into_inner());
+    if refresh || guard.is_none() {
+        *guard = Some(discover_wsl_distros());
+    }
+    guard.clone().unwrap_or_default()
+}
+
+/// 本次要写入的发行版: 参数优先，其次 config.wsl；exclude 始终生效
+pub fn select_wsl_distros(
+    config: &WslConfig,
+    requested: Option<Vec<String>>,
+    skip: Option<bool>,
+) -> Vec<String> {
+    if skip.unwrap_or(config.skip) {
+        return Vec::new();
+    }
+    let normalize = |items: &[String]| -> Vec<String> {
+        items
+            .iter()
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    };
+    let mut selected = match requested {
+        Some(items) => normalize(&items),
+        None if !config.distros.is_empty() => normalize(&config.distros),
+        None => cached_wsl_distros(false),
+    };
+    selected.retain(|item| {
+        !config
+            .exclude
+            .iter()
+            .any(|excluded| excluded.trim().eq_ignore_ascii_case(item))
+    });
+    selected.dedup();
+    selected
+}
+
+fn discover_wsl_distros() -> Vec<String> {
+    if !cfg!(windows) {
+        return Vec::new();
+    }
+    let mut command = Command::new("wsl.exe");
+    command.args(["-l", "-q"]);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = match command.output() {
+        Ok(item) => item,
+        Err(_) => return Vec::new(),
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    let decoded = decode_wsl_output(&output.stdout).replace('\0', "");
+    decoded
+        .lines()
+        .map(|item| item.trim().trim_start_matches('\u{feff}').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn save_openai_key_to_wsl(distro: &str, token: &str) -> Result<String> {
+    let json_text = serde_json::to_string_pretty(&serde_json::json!({
+        "OPENAI_API_KEY": token
+    }))
+    .with_context(|| "构建OpenAI配置内容失败")?;
+    let script = format!(
+        "mkdir -p \"$HOME/.codex\" && cat <<'EOF' > \"$HOME/.codex/auth.json\"\n{}\nEOF\nprintf %s \"$HOME/.codex/auth.json\"",
+        json_text
+    );
+    let output = {
+        let mut c = Command::new("wsl.exe");
+        c.args(["-d", distro, "-e", "sh", "-lc", &script]);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            c.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+        c.output()
+            .with_context(|| format!("执行 wsl.exe 失败: {}", distro))?
+    };
+    if !output.status.success() {
+        let stderr = decode_wsl_output(&output.stderr).replace('\0', "");
+        anyhow::bail!("WSL[{}] 写入失败: {}", distro, stderr.trim());
+    }
+    let stdout = decode_wsl_output(&output.stdout).replace('\0', "");
+    let path = stdout.trim();
+    if path.is_empty() {
+        Ok("$HOME/.codex/auth.json".to_string())
+    } else {
+        Ok(path.to_string())
+    }
+}
+
+/// 把 Key 写入单个部署目标，返回写入位置
+pub fn deploy_key(target: &str, token: &str) -> Result<String> {
+    if let Some(distro) = target.strip_prefix("codex_wsl:") {
+        let distro = distro.trim();
+        if distro.is_empty() {
+            anyhow::bail!("codex_wsl 目标缺少发行版名称");
+        }
+        let path = save_openai_key_to_wsl(distro, token)?;
+        return Ok(format!("WSL[{distro}]: {path}"));
+    }
+    match target {
+        "claude" => Ok(save_claude_token(token)?.display().to_string()),
+        "codex" => Ok(save_openai_key_local(token)?.display().to_string()),
+        _ => anyhow::bail!("不支持的部署目标: {target}"),
+    }
+}
+
+/// 写入本机与指定的 WSL 发行版，返回摘要与写入成功的部署目标。
+/// 各发行版并行写入，单个发行版启动慢不拖累其余
+pub fn save_openai_key(token: &str, distros: &[String]) -> Result<(String, Vec<String>)> {
+    let local = save_openai_key_local(token)?;
+    let mut summary = vec![format!("Windows: {}", local.display())];
+    let mut targets = vec!["codex".to_string()];
+    if distros.is_empty() {
+        return Ok((summary.join(" | "), targets));
+    }
+    let outcomes: Vec<(String, Result<String>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = distros
+            .iter()
+            .map(|distro| {
+                (
+                    distro.clone(),
+                    scope.spawn(move || save_openai_key_to_wsl(distro, token)),
+                )
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(distro, handle)| {
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("写入线程异常退出")));
+                (distro, result)
+            })
+            .collect()
+    });
+    let mut wsl_success = 0_usize;
+    let mut wsl_fail = 0_usize;
+    for (distro, result) in outcomes {
+        match result {
+            Ok(path) => {
+                wsl_success += 1;
+                summary.push(format!("WSL[{distro}]: {path}"));
+                targets.push(format!("codex_wsl:{distro}"));
+            }
+            Err(err) => {
+                wsl_fail += 1;
+                summary.push(format!("WSL[{distro}]失败: {err}"));
+            }
+        }
+    }
+    summary.push(format!("WSL成功 {wsl_success}，失败 {wsl_fail}"));
+    Ok((summary.join(" | "), targets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("art_rs_deploy_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_json(path: &Path) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn patch_keeps_other_fields_and_backs_up() {
+        let dir = scratch_dir("patch");
+        let target = dir.join("settings.json");
+        std::fs::write(&target, r#"{"model":"opus","env":{"OTHER":"1"}}"#).unwrap();
+        patch_tool_config(&target, |obj| {
+            obj.insert("OPENAI_API_KEY".to_string(), Value::String("sk-new".into()));
+        })
+        .unwrap();
+
+        let patched = read_json(&target);
+        assert_eq!(patched["model"], "opus");
+        assert_eq!(patched["env"]["OTHER"], "1");
+        assert_eq!(patched["OPENAI_API_KEY"], "sk-new");
+        let backup = read_json(&tool_config_backup_path(&target));
+        assert!(backup.get("OPENAI_API_KEY").is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn patch_replaces_invalid_file_and_drops_stale_backup() {
+        let dir = scratch_dir("invalid");
+        let target = dir.join("auth.json");
+        std::fs::write(&target, "not json").unwrap();
+        patch_tool_config(&target, |obj| {
+            obj.insert("key".to_string(), Value::Bool(true));
+        })
+        .unwrap();
+        assert_eq!(read_json(&target)["key"], true);
+        let backup = tool_config_backup_path(&target);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "not json");
+
+        // 原文件不存在时不保留旧备份，避免回滚到更早的内容
+        std::fs::remove_file(&target).unwrap();
+        patch_tool_config(&target, |_| {}).unwrap();
+        assert!(!backup.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn decode_wsl_output_handles_utf16_and_utf8() {
+        let utf16: Vec<u8> = "Ubuntu\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(decode_wsl_output(&utf16), "Ubuntu\r\n");
+        assert_eq!(decode_wsl_output("Debian\n".as_bytes()), "Debian\n");
+    }
+}
@@ -26,3 +26,90 @@ pub fn to_f64(value: Option<&Value>) -> Option<f64> {
 pub fn value_to_f64(value: &Value) -> Option<f64> {
     to_f64(Some(value))
 }
+
+/// 登录或接口返回的提示是否表明账号已被封禁、停用
+pub fn is_banned_message(text: &str) -> bool {
+    let lower = text.to_ascii_lowercase();
+    [
+        "封禁",
+        "已被封",
+        "已被禁用",
+        "账号已禁用",
+        "账户已禁用",
+        "用户已禁用",
+        "已停用",
+        "banned",
+        "suspended",
+        "account disabled",
+        "user disabled",
+        "account has been disabled",
+    ]
+    .iter()
+    .any(|key| lower.contains(key))
+}
+
+/// 本机主机名，取不到时为 unknown
+pub fn hostname() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Rng;
+
+    /// 千分位格式，如 1234567.5 -> "1,234,567.50"
+    fn with_separators(value: f64) -> String {
+        let text = format!("{:.2}", value.abs());
+        let (int_part, frac) = text.split_once('.').unwrap();
+        let mut grouped = String::new();
+        for (index, ch) in int_part.chars().enumerate() {
+            if index > 0 && (int_part.len() - index) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(ch);
+        }
+        let sign = if value < 0.0 { "-" } else { "" };
+        format!("{sign}{grouped}.{frac}")
+    }
+
+    #[test]
+    fn parse_round_trips_formatted_amounts() {
+        let mut rng = Rng::new(0x5eed_1234);
+        for _ in 0..500 {
+            let value = rng.amount(100_000_000);
+            for text in [
+                format!("{value:.2}"),
+                with_separators(value),
+                format!("余额: ${} USD", with_separators(value)),
+            ] {
+                let parsed = parse_first_number(&text).unwrap();
+                assert!((parsed - value).abs() < 1e-6, "{text} -> {parsed}");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_takes_first_number() {
+        assert_eq!(parse_first_number("剩余 12.5 / 总额 100"), Some(12.5));
+        assert_eq!(parse_first_number("$-3.25"), Some(-3.25));
+        assert_eq!(parse_first_number("¥1,024"), Some(1024.0));
+        assert_eq!(parse_first_number("暂无余额"), None);
+        assert_eq!(parse_first_number(""), None);
+    }
+
+    #[test]
+    fn to_f64_accepts_numbers_and_strings() {
+        assert_eq!(to_f64(Some(&Value::from(8))), Some(8.0));
+        assert_eq!(to_f64(Some(&Value::from("1,234.56"))), Some(1234.56));
+        assert_eq!(to_f64(Some(&Value::from("N/A"))), None);
+        assert_eq!(to_f64(Some(&Value::Bool(true))), None);
+        assert_eq!(to_f64(None), None);
+    }
+}
@@ -1,35 +1,115 @@
+use crate::currency;
+use crate::hooks::resolve_hook;
 use crate::models::BrowserConfig;
-use crate::models::{Account, WebCheckConfig};
-use crate::utils::parse_first_number;
+use crate::models::{Account, HookDefinition, WebCheckConfig};
 use crate::web_native::run_native_web_check;
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
 use tokio::process::Command;
 use tokio::time::{Duration, timeout};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct WebCheckResult {
     pub success: bool,
     pub balance: Option<f64>,
+    /// 余额文本中识别到的货币代码，未带符号时为空
+    pub currency: String,
+    /// 内置网页流程命中的余额提取策略，外部钩子为空
+    pub extraction_strategy: String,
     pub message: String,
+    /// 会话被看门狗判定为无响应并强制结束
+    pub hung: bool,
+    /// 控制台显示今日已签到(如已在其他浏览器手动签到)
+    pub already_checked_in: bool,
+    /// 点击签到按钮后到账的额度
+    pub credited: Option<f64>,
+    /// 本次 API Key 额度同步是否成功，未尝试同步为空
+    pub quota_sync_success: Option<bool>,
+    pub quota_sync_message: String,
+    /// 额度同步时从编辑弹窗识别到的换算比例
+    pub detected_unit_rate: Option<f64>,
+    /// 识别到的比例与已记录比例相差过大时的说明
+    pub unit_rate_conflict: Option<String>,
 }
 
+/// 外部钩子返回的 JSON 结果；v2 协议额外支持签到与额度同步等字段
 #[derive(Debug, Deserialize)]
 struct HookJsonResult {
     #[serde(default)]
     success: bool,
     #[serde(default)]
-    balance: Option<f64>,
+    balance: Option<serde_json::Value>,
+    /// 余额货币代码；省略时从带符号的 balance 文本识别
+    #[serde(default)]
+    currency: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    already_checked_in: bool,
+    #[serde(default)]
+    credited: Option<f64>,
+    #[serde(default)]
+    quota_sync_success: Option<bool>,
+    #[serde(default)]
+    quota_sync_message: String,
+}
+
+impl HookJsonResult {
+    /// balance 允许为数字或带货币符号的文本
+    fn balance(&self) -> Option<f64> {
+        match self.balance.as_ref()? {
+            serde_json::Value::Number(number) => number.as_f64(),
+            serde_json::Value::String(text) => currency::parse_amount(text),
+            _ => None,
+        }
+    }
+
+    fn currency(&self) -> String {
+        if !self.currency.trim().is_empty() {
+            return self.currency.trim().to_ascii_uppercase();
+        }
+        match &self.balance {
+            Some(serde_json::Value::String(text)) => currency::detect_currency(text)
+                .unwrap_or_default()
+                .to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// 钩子运行中输出的进度行: {"type":"progress","level":"info","message":"..."}
+#[derive(Debug, Deserialize)]
+struct HookProgressLine {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    level: String,
     #[serde(default)]
     message: String,
 }
 
+/// 钩子进度回调，参数为 (level, message)
+pub type HookProgress<'a> = &'a (dyn Fn(&str, &str) + Send + Sync);
+
+/// v2 协议通过 stdin 传给钩子的 JSON 载荷
+#[derive(Debug, Serialize)]
+struct HookPayload<'a> {
+    protocol: u32,
+    username: &'a str,
+    password: &'a str,
+    api_key: &'a str,
+    timeout_seconds: u64,
+}
+
 pub async fn run_web_check(
     account: &Account,
     config: &WebCheckConfig,
     browser_config: &BrowserConfig,
     retry_times: u32,
     retry_delay_secs: u64,
+    progress: HookProgress<'_>,
 ) -> Result<WebCheckResult> {
     if !config.enabled {
         tracing::debug!("web_check.enabled=false，回退到原生网页登录流程");
@@ -43,8 +123,8 @@ pub async fn run_web_check(
         .await;
     }
 
-    // command 为空时走原生Rust网页登录流程
-    if config.command.trim().is_empty() {
+    // 账号未选择外部钩子(或选择 native)时走原生Rust网页登录流程
+    let Some(hook) = resolve_hook(&account.username, config)? else {
         return run_native_web_check(
             account,
             config,
@@ -53,62 +133,265 @@ pub async fn run_web_check(
             retry_delay_secs,
         )
         .await;
-    }
+    };
+    tracing::debug!("账号 {} 使用网页签到钩子: {}", account.username, hook.name);
+    run_hook(account, &hook, config.timeout_seconds, progress).await
+}
 
-    let mut command = Command::new(config.command.trim());
-    for raw_arg in &config.args {
-        let arg = raw_arg
-            .replace("{username}", &account.username)
-            .replace("{password}", &account.password)
-            .replace("{api_key}", &account.api_key);
-        command.arg(arg);
+/// 执行外部钩子(命令或 HTTP)；hook.timeout_seconds 为 0 时使用 default_timeout_secs
+async fn run_hook(
+    account: &Account,
+    hook: &HookDefinition,
+    default_timeout_secs: u64,
+    progress: HookProgress<'_>,
+) -> Result<WebCheckResult> {
+    let protocol = hook.hook_protocol.max(1);
+    let timeout_secs = if hook.timeout_seconds > 0 {
+        hook.timeout_seconds
+    } else {
+        default_timeout_secs
+    }
+    .max(5);
+    if !hook.url.trim().is_empty() {
+        return run_http_hook(account, hook, timeout_secs).await;
+    }
+    let mut command = Command::new(hook.command.trim());
+    for raw_arg in &hook.args {
+        command.arg(expand_hook_arg(raw_arg, account, protocol));
+    }
+    let working_dir = hook.working_dir.trim();
+    if !working_dir.is_empty() {
+        command.current_dir(working_dir);
     }
+    if protocol >= 2 {
+        // v2: 凭据只经 stdin 与环境变量传递，不出现在进程命令行中
+        command
+            .env("ART_HOOK_PROTOCOL", protocol.to_string())
+            .env("ART_USERNAME", &account.username)
+            .env("ART_PASSWORD", &account.password)
+            .env("ART_API_KEY", &account.api_key)
+            .stdin(Stdio::piped());
+    } else {
+        command.stdin(Stdio::null());
+    }
+    command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
     #[cfg(windows)]
     {
         command.creation_flags(0x08000000); // CREATE_NO_WINDOW
     }
 
-    let timeout_secs = config.timeout_seconds.max(5);
-    let output = timeout(Duration::from_secs(timeout_secs), command.output())
+    let mut child = command.spawn().with_context(|| {
+        format!(
+            "启动网页签到钩子失败: {} ({})",
+            hook.name,
+            hook.command.trim()
+        )
+    })?;
+    if protocol >= 2
+        && let Some(mut stdin) = child.stdin.take()
+    {
+        let payload = serde_json::to_vec(&HookPayload {
+            protocol,
+            username: &account.username,
+            password: &account.password,
+            api_key: &account.api_key,
+            timeout_seconds: timeout_secs,
+        })?;
+        // 钩子可能不读取 stdin，写入失败只记录不中断
+        if let Err(e) = stdin.write_all(&payload).await {
+            tracing::debug!("写入网页签到命令 stdin 失败: {}", e);
+        }
+        drop(stdin);
+    }
+
+    // 边运行边读取输出，进度行即时转发，其余内容留作结果解析
+    let stdout_pipe = child
+        .stdout
+        .take()
+        .context("获取网页签到命令 stdout 失败")?;
+    let stderr_pipe = child
+        .stderr
+        .take()
+        .context("获取网页签到命令 stderr 失败")?;
+    let run = async {
+        let (stdout, stderr) = tokio::join!(
+            collect_hook_output(stdout_pipe, progress),
+            collect_hook_output(stderr_pipe, progress)
+        );
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>((status, stdout, stderr))
+    };
+    let (status, stdout, stderr) = timeout(Duration::from_secs(timeout_secs), run)
         .await
         .with_context(|| format!("网页签到命令执行超时({timeout_secs}s)"))?
         .with_context(|| "网页签到命令执行失败")?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-
-    if !output.status.success() {
+    if !status.success() {
         anyhow::bail!(
             "网页签到命令返回非0: code={:?}, stderr={}",
-            output.status.code(),
+            status.code(),
             stderr
         );
     }
 
-    if stdout.is_empty() {
-        return Ok(WebCheckResult {
+    Ok(interpret_hook_output(&stdout, protocol, "网页签到命令"))
+}
+
+/// HTTP 钩子: 向 hook.url POST 账号载荷(与 v2 stdin 载荷相同)，按命令钩子的规则解析响应
+async fn run_http_hook(
+    account: &Account,
+    hook: &HookDefinition,
+    timeout_secs: u64,
+) -> Result<WebCheckResult> {
+    let url = hook.url.trim();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout_secs))
+        .build()
+        .with_context(|| "创建 HTTP 钩子客户端失败")?;
+    let mut request = client.post(url).json(&HookPayload {
+        protocol: 2,
+        username: &account.username,
+        password: &account.password,
+        api_key: &account.api_key,
+        timeout_seconds: timeout_secs,
+    });
+    for (name, value) in &hook.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("请求 HTTP 钩子失败: {} ({url})", hook.name))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .with_context(|| "读取 HTTP 钩子响应失败")?;
+    if !status.is_success() {
+        let snippet: String = body.trim().chars().take(200).collect();
+        anyhow::bail!("HTTP 钩子返回 {}: {}", status.as_u16(), snippet);
+    }
+    Ok(interpret_hook_output(body.trim(), 2, "HTTP 钩子"))
+}
+
+/// 解析钩子输出: 空输出视为成功但无余额，JSON 按结构化字段映射，其余文本取首个数字作为余额
+fn interpret_hook_output(output: &str, protocol: u32, label: &str) -> WebCheckResult {
+    if output.is_empty() {
+        return WebCheckResult {
             success: true,
             balance: None,
-            message: "网页签到命令执行成功，但未返回余额".to_string(),
-        });
+            message: format!("{label}执行成功，但未返回余额"),
+            ..Default::default()
+        };
     }
 
-    if let Ok(json_result) = serde_json::from_str::<HookJsonResult>(&stdout) {
-        return Ok(WebCheckResult {
+    if let Some(json_result) = parse_hook_json(output, protocol) {
+        let balance = json_result.balance();
+        return WebCheckResult {
             success: json_result.success,
-            balance: json_result.balance,
+            balance,
+            currency: json_result.currency(),
             message: if json_result.message.is_empty() {
-                "网页签到命令返回JSON".to_string()
+                format!("{label}返回JSON")
             } else {
                 json_result.message
             },
-        });
+            already_checked_in: json_result.already_checked_in,
+            credited: json_result.credited,
+            quota_sync_success: json_result.quota_sync_success,
+            quota_sync_message: json_result.quota_sync_message,
+            ..Default::default()
+        };
     }
 
-    let balance = parse_first_number(&stdout);
-    Ok(WebCheckResult {
+    let (balance, currency) = match currency::parse_money(output) {
+        Some((amount, code)) => (Some(amount), code.unwrap_or_default().to_string()),
+        None => (None, String::new()),
+    };
+    WebCheckResult {
         success: true,
         balance,
-        message: "网页签到命令返回文本".to_string(),
-    })
+        currency,
+        message: format!("{label}返回文本"),
+        ..Default::default()
+    }
+}
+
+/// 逐行读取钩子输出: 进度行交给回调，其余行拼接返回
+async fn collect_hook_output<R: AsyncRead + Unpin>(pipe: R, progress: HookProgress<'_>) -> String {
+    let mut reader = BufReader::new(pipe);
+    let mut buf = Vec::new();
+    let mut kept = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::debug!("读取网页签到命令输出失败: {}", e);
+                break;
+            }
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if let Some((level, message)) = parse_progress_line(line) {
+            progress(level, &message);
+            continue;
+        }
+        kept.push(line.to_string());
+    }
+    kept.join("\n").trim().to_string()
+}
+
+fn parse_progress_line(line: &str) -> Option<(&'static str, String)> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+    let item = serde_json::from_str::<HookProgressLine>(line).ok()?;
+    if item.kind != "progress" || item.message.trim().is_empty() {
+        return None;
+    }
+    let level = match item.level.trim().to_ascii_lowercase().as_str() {
+        "warn" | "warning" => "warn",
+        "error" => "error",
+        "success" => "success",
+        _ => "info",
+    };
+    Some((level, item.message.trim().to_string()))
+}
+
+/// 展开命令参数占位符；v2 协议只替换 {username}，密码与 API Key 改由 stdin/环境变量传递
+fn expand_hook_arg(raw_arg: &str, account: &Account, protocol: u32) -> String {
+    let arg = raw_arg.replace("{username}", &account.username);
+    if protocol < 2 {
+        return arg
+            .replace("{password}", &account.password)
+            .replace("{api_key}", &account.api_key);
+    }
+    if arg.contains("{password}") || arg.contains("{api_key}") {
+        tracing::warn!(
+            "hook_protocol=2 不在命令行传递 {{password}}/{{api_key}}，请改从 stdin 或 ART_PASSWORD/ART_API_KEY 读取"
+        );
+    }
+    arg.replace("{password}", "").replace("{api_key}", "")
+}
+
+/// 解析钩子的 JSON 输出；v2 协议允许先输出日志行，取最后一行 JSON 对象作为结果
+fn parse_hook_json(stdout: &str, protocol: u32) -> Option<HookJsonResult> {
+    if let Ok(result) = serde_json::from_str::<HookJsonResult>(stdout) {
+        return Some(result);
+    }
+    if protocol < 2 {
+        return None;
+    }
+    stdout
+        .lines()
+        .rev()
+        .map(str::trim)
+        .filter(|line| line.starts_with('{'))
+        .find_map(|line| serde_json::from_str::<HookJsonResult>(line).ok())
 }
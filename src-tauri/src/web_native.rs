@@ -1,18 +1,735 @@
-use crate::browser_pool::get_global_pool;
-use crate::models::{Account, BrowserConfig, WebCheckConfig};
+use crate::browser_pool::{
+    BrowserPool, PoolTicket, classify_driver_error, get_global_pool, prewarm_global_pool,
+    report_infrastructure_error,
+};
+use crate::cookies::{StoredCookie, load_account_cookies};
+use crate::currency;
+use crate::driver_manager::{resolve_browser_binary, sandbox_profile_root};
+use crate::models::{Account, BrowserConfig, CheckinProfile, QuotaGuardConfig, WebCheckConfig};
+use crate::performance_monitor::{
+    OperationTimer, PerformanceMonitor, WEB_STAGE_PREFIX, get_performance_monitor,
+};
 use crate::utils::{parse_first_number, value_to_f64 as to_f64};
 use crate::web_check::WebCheckResult;
 use anyhow::{Context, Result};
+use serde::Serialize;
 use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use thirtyfour::common::capabilities::chromium::ChromiumLikeCapabilities;
+use thirtyfour::extensions::cdp::ChromeDevTools;
 use thirtyfour::prelude::*;
+use thirtyfour::{ChromeCapabilities, PageLoadStrategy};
 use tokio::task;
 use tokio::time::sleep as async_sleep;
 
 const CONSOLE_URL: &str = "https://anyrouter.top/console";
+/// 额度换算比例按站点记录
+pub const QUOTA_PROVIDER: &str = "anyrouter.top";
+/// 识别到的比例与已记录比例的相对偏差超过该值时视为解析有误，不写入额度
+const UNIT_RATE_MAX_DEVIATION: f64 = 0.2;
+const PERSONAL_URL: &str = "https://anyrouter.top/console/personal";
 const QUOTA_UNIT_PER_DOLLAR: f64 = 500000.0;
 
+/// 已建立的浏览器会话，登录一次后可依次执行多个控制台操作(余额、额度同步、令牌管理)
+pub struct WebSession {
+    driver: WebDriver,
+    pool: Arc<Mutex<BrowserPool>>,
+    ticket: PoolTicket,
+    browser_config: BrowserConfig,
+    logged_in_as: Option<String>,
+    heartbeat: Heartbeat,
+    /// web_check.quota_sync_key，登录时据此选出要同步额度的 Key
+    quota_key_name: String,
+    quota_key: Option<String>,
+    quota_guard: QuotaGuardConfig,
+    /// web_check.watchdog_quit_secs，正常关闭会话的等待上限
+    quit_timeout: Duration,
+    /// Snap/Flatpak 浏览器使用的临时用户数据目录，会话结束后删除
+    profile_dir: Option<PathBuf>,
+}
+
+/// 各站点已记录的额度换算比例，启动时从本地状态载入，识别成功后更新
+static KNOWN_UNIT_RATES: OnceLock<Mutex<BTreeMap<String, f64>>> = OnceLock::new();
+
+pub fn set_known_unit_rate(provider: &str, rate: f64) {
+    let rates = KNOWN_UNIT_RATES.get_or_init(|| Mutex::new(BTreeMap::new()));
+    if let Ok(mut guard) = rates.lock() {
+        guard.insert(provider.to_string(), rate);
+    }
+}
+
+fn known_unit_rate(provider: &str) -> Option<f64> {
+    KNOWN_UNIT_RATES
+        .get()
+        .and_then(|rates| rates.lock().ok()?.get(provider).copied())
+}
+
+/// 会话阶段心跳，供看门狗判断会话是否仍在推进
+#[derive(Debug, Clone)]
+struct Heartbeat {
+    inner: Arc<Mutex<(String, Instant)>>,
+}
+
+impl Heartbeat {
+    fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(("连接".to_string(), Instant::now()))),
+        }
+    }
+
+    fn beat(&self, stage: &str) {
+        if let Ok(mut guard) = self.inner.lock() {
+            *guard = (stage.to_string(), Instant::now());
+        }
+    }
+
+    fn snapshot(&self) -> (String, Duration) {
+        match self.inner.lock() {
+            Ok(guard) => (guard.0.clone(), guard.1.elapsed()),
+            Err(_) => (String::new(), Duration::ZERO),
+        }
+    }
+}
+
+/// 控制台 API 令牌列表中的一行
+#[derive(Debug, Clone, Serialize)]
+pub struct WebToken {
+    pub name: String,
+    pub status: String,
+    pub cells: Vec<String>,
+}
+
+/// 额度同步预演中的一个令牌
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaPreviewToken {
+    pub name: String,
+    /// 站点返回的 Key，可能已打码
+    pub key: String,
+    pub remain_quota: i64,
+    pub unlimited: bool,
+    /// 实际同步时会修改的令牌
+    pub selected: bool,
+}
+
+/// 额度同步预演: 按当前余额与识别到的换算比例计算目标额度，不提交修改
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaSyncPreview {
+    pub target: String,
+    pub balance: f64,
+    pub unit_rate: f64,
+    /// false 表示未能从编辑弹窗识别比例，使用的是已记录的比例或默认值
+    pub rate_detected: bool,
+    /// 本地已记录的比例
+    pub stored_rate: Option<f64>,
+    /// 识别到的比例与已记录比例相差过大，实际同步时会跳过写入
+    pub rate_conflict: bool,
+    /// 目标额度超出 web_check.quota_guard 边界，实际同步时会拒绝写入
+    pub guard_rejection: Option<String>,
+    pub target_quota: i64,
+    pub tokens: Vec<QuotaPreviewToken>,
+}
+
+/// 从控制台读取的余额
+#[derive(Debug, Clone)]
+pub struct BalanceReading {
+    pub amount: f64,
+    /// 页面显示的货币代码，未带货币符号时为空
+    pub currency: String,
+    /// 命中的提取策略，见 extract_balance
+    pub strategy: String,
+}
+
+/// 管理员账号在站点用户列表中看到的一个子账号
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredUser {
+    pub username: String,
+    pub display_name: String,
+    pub email: String,
+    pub quota: i64,
+    pub enabled: bool,
+    /// 本地账号列表中已存在同名账号
+    pub exists: bool,
+}
+
+/// 管理员账号自己的一个令牌；站点只返回打码 Key 时 masked 为 true，无法导入
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredToken {
+    pub name: String,
+    pub key: String,
+    pub masked: bool,
+    /// 管理员账号已记录该 Key
+    pub exists: bool,
+}
+
+/// 从管理员账号发现的子账号与令牌
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDiscovery {
+    pub admin: String,
+    pub users: Vec<DiscoveredUser>,
+    /// 读取用户列表失败的原因(通常是账号没有管理员权限)，令牌列表仍可使用
+    pub users_error: Option<String>,
+    pub tokens: Vec<DiscoveredToken>,
+}
+
+/// 新建的令牌；站点未返回明文 Key 时 key 为空
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedToken {
+    pub name: String,
+    pub key: String,
+}
+
+/// 点击签到按钮的结果
+#[derive(Debug, Clone, Default)]
+pub struct CheckinOutcome {
+    /// 点击后提示今日已签到，或按钮处于已签到/禁用状态
+    pub already: bool,
+    /// 提示中识别到的到账额度
+    pub credited: Option<f64>,
+    pub message: String,
+}
+
+/// 额度同步的一次策略失败
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaStrategyFailure {
+    pub strategy: String,
+    pub error: String,
+}
+
+/// 额度同步报告: 最终生效的策略(全部失败时为空，表示已跳过)与之前各策略的失败原因
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuotaSyncReport {
+    /// 同步的令牌: 首个 API Key 或 API Key[名称]
+    pub target: String,
+    pub applied_by: Option<String>,
+    pub balance: f64,
+    pub target_quota: i64,
+    pub unit_rate: f64,
+    /// 本次从编辑弹窗识别到的比例，未识别时为空
+    pub detected_rate: Option<f64>,
+    /// 识别到的比例与已记录比例相差过大时的说明，此时不写入额度
+    pub rate_conflict: Option<String>,
+    /// 目标额度超出 web_check.quota_guard 边界时的说明，此时不写入额度
+    pub guard_rejection: Option<String>,
+    pub failures: Vec<QuotaStrategyFailure>,
+}
+
+impl QuotaSyncReport {
+    fn failure_text(&self) -> String {
+        self.failures
+            .iter()
+            .map(|item| format!("{}: {}", item.strategy, item.error))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    pub fn summary(&self) -> String {
+        match &self.applied_by {
+            Some(_) if self.failures.is_empty() => format!(
+                "{} 额度已同步: 余额=${:.2}, 额度值={}, 比例={:.2}",
+                self.target, self.balance, self.target_quota, self.unit_rate
+            ),
+            Some(strategy) => format!(
+                "{} 额度已同步(策略={}): 余额=${:.2}, 额度值={}, 比例={:.2}; 已失败策略: {}",
+                self.target,
+                strategy,
+                self.balance,
+                self.target_quota,
+                self.unit_rate,
+                self.failure_text()
+            ),
+            None if self.rate_conflict.is_some() => format!(
+                "额度同步已跳过: {}",
+                self.rate_conflict.as_deref().unwrap_or_default()
+            ),
+            None if self.guard_rejection.is_some() => format!(
+                "额度同步已拒绝: {}",
+                self.guard_rejection.as_deref().unwrap_or_default()
+            ),
+            None => format!("额度同步已跳过，所有策略均失败: {}", self.failure_text()),
+        }
+    }
+}
+
+impl WebSession {
+    /// 从浏览器池获取实例并建立 WebDriver 会话(尚未登录)
+    pub async fn connect(
+        web_config: &WebCheckConfig,
+        browser_config: &BrowserConfig,
+    ) -> Result<Self> {
+        // 等待浏览器池实例的同时准备启动参数，拿到实例后立即建立会话
+        let (ticket_result, caps_result) = tokio::join!(
+            acquire_pool_ticket(web_config, &browser_config.binary_path),
+            async { build_chrome_capabilities(browser_config) }
+        );
+        let (pool, ticket) = ticket_result?;
+        let mut profile_dir = None;
+        let connected = match caps_result {
+            Ok((caps, dir)) => {
+                profile_dir = dir;
+                WebDriver::new(&ticket.url, caps)
+                    .await
+                    .with_context(|| "连接 chromedriver 失败")
+            }
+            Err(err) => Err(err),
+        };
+        if let Ok(driver) = &connected {
+            apply_session_overrides(driver, browser_config).await;
+        }
+        match connected {
+            Ok(driver) => Ok(Self {
+                driver,
+                pool,
+                ticket,
+                browser_config: browser_config.clone(),
+                logged_in_as: None,
+                heartbeat: Heartbeat::new(),
+                quota_key_name: web_config.quota_sync_key.trim().to_string(),
+                quota_key: None,
+                quota_guard: web_config.quota_guard.clone(),
+                quit_timeout: Duration::from_secs(web_config.watchdog_quit_secs.max(1)),
+                profile_dir,
+            }),
+            Err(err) => {
+                release_pool_ticket(&pool, ticket);
+                remove_profile_dir(profile_dir.as_ref());
+                Err(err)
+            }
+        }
+    }
+
+    /// 登录指定账号，失败按配置重试；同一账号已登录时直接返回
+    pub async fn login(
+        &mut self,
+        account: &Account,
+        retry_times: u32,
+        retry_delay_secs: u64,
+    ) -> Result<()> {
+        if self.logged_in_as.as_deref() == Some(account.username.as_str()) {
+            return Ok(());
+        }
+        self.quota_key = None;
+        if !self.quota_key_name.is_empty() {
+            self.quota_key = account.key(&self.quota_key_name).map(str::to_string);
+            if self.quota_key.is_none() {
+                tracing::warn!(
+                    "账号 {} 没有名为 {} 的 Key，额度同步改为首个令牌",
+                    account.username,
+                    self.quota_key_name
+                );
+            }
+        }
+        let waits = WaitCaps::from_config(&self.browser_config);
+        let cookies = load_account_cookies(&account.username);
+        if !cookies.is_empty() {
+            self.heartbeat.beat(STAGE_LOGIN);
+            match login_with_cookies(&self.driver, &cookies, waits).await {
+                Ok(true) => {
+                    tracing::info!("账号 {} 使用已保存的 Cookie 登录", account.username);
+                    self.logged_in_as = Some(account.username.clone());
+                    return Ok(());
+                }
+                Ok(false) => tracing::info!(
+                    "账号 {} 已保存的 Cookie 已失效，改用账号密码登录",
+                    account.username
+                ),
+                Err(err) => tracing::warn!(
+                    "账号 {} 使用 Cookie 登录失败，改用账号密码登录: {}",
+                    account.username,
+                    err
+                ),
+            }
+        }
+
+        let retry_times = retry_times.max(1);
+        let retry_delay_secs = retry_delay_secs.max(1);
+        let mut last_error = String::new();
+        for attempt in 0..retry_times {
+            self.heartbeat.beat(STAGE_LOGIN);
+            let timer = start_stage_timer(STAGE_LOGIN, &account.username);
+            match ensure_logged_in(&self.driver, account, waits).await {
+                Ok(()) => {
+                    timer.finish(true, None);
+                    self.logged_in_as = Some(account.username.clone());
+                    return Ok(());
+                }
+                Err(err) => {
+                    last_error = err.to_string();
+                    timer.finish(false, Some(last_error.clone()));
+                    tracing::warn!(
+                        "登录失败 (尝试 {}/{}): {}",
+                        attempt + 1,
+                        retry_times,
+                        last_error
+                    );
+                    if attempt + 1 < retry_times {
+                        async_sleep(Duration::from_secs(retry_delay_secs)).await;
+                    }
+                }
+            }
+        }
+        anyhow::bail!("登录失败，已重试{}次: {}", retry_times, last_error)
+    }
+
+    /// 读取当前站点 Cookie
+    pub async fn site_cookies(&self) -> Result<Vec<StoredCookie>> {
+        self.require_login()?;
+        let cookies = self.driver.get_all_cookies().await?;
+        Ok(cookies
+            .into_iter()
+            .map(|item| StoredCookie {
+                name: item.name,
+                value: item.value,
+                domain: item.domain.unwrap_or_default(),
+                path: item.path.unwrap_or_default(),
+                secure: item.secure.unwrap_or(false),
+                http_only: false,
+                expiry: item.expiry,
+            })
+            .collect())
+    }
+
+    /// 控制台是否显示今日已签到
+    pub async fn is_already_checked_in(&self, texts: &[String]) -> Result<bool> {
+        self.require_login()?;
+        if texts.is_empty() {
+            return Ok(false);
+        }
+        let value = self
+            .driver
+            .execute(ALREADY_CHECKED_IN_SCRIPT, vec![serde_json::json!(texts)])
+            .await?;
+        Ok(value.json().as_bool().unwrap_or(false))
+    }
+
+    /// 定位并点击签到按钮，等待成功提示并识别到账额度
+    pub async fn click_checkin(&self, profile: &CheckinProfile) -> Result<CheckinOutcome> {
+        self.require_login()?;
+        self.heartbeat.beat(STAGE_CHECKIN);
+        let timer = start_stage_timer(STAGE_CHECKIN, self.username());
+        let waits = WaitCaps::from_config(&self.browser_config);
+        let result = click_checkin_button(&self.driver, profile, waits).await;
+        match &result {
+            Ok(_) => timer.finish(true, None),
+            Err(err) => timer.finish(false, Some(err.to_string())),
+        }
+        result
+    }
+
+    /// 读取控制台余额，不在控制台首页时先导航回去
+    pub async fn read_balance(&self) -> Result<BalanceReading> {
+        self.require_login()?;
+        let current_url = self.driver.current_url().await?.to_string();
+        if !current_url.trim_end_matches('/').ends_with("/console") {
+            self.driver
+                .get(CONSOLE_URL)
+                .await
+                .with_context(|| "导航到控制台失败")?;
+        }
+        let step_started = Instant::now();
+        self.heartbeat.beat(STAGE_EXTRACT);
+        let timer = start_stage_timer(STAGE_EXTRACT, self.username());
+        let waits = WaitCaps::from_config(&self.browser_config);
+        let extracted = extract_balance(&self.driver, self.browser_config.timeout.max(3), waits)
+            .await
+            .with_context(|| "余额提取失败")
+            .and_then(|(balance, strategy)| {
+                currency::parse_money(&balance)
+                    .map(|money| (balance.clone(), strategy, money))
+                    .with_context(|| format!("余额格式无法解析: {balance}"))
+            });
+        let (balance, strategy, (balance_num, balance_currency)) = match extracted {
+            Ok(item) => {
+                timer.finish(true, None);
+                item
+            }
+            Err(err) => {
+                timer.finish(false, Some(err.to_string()));
+                return Err(err);
+            }
+        };
+        tracing::debug!(
+            "[flow] 余额提取耗时={:.1}s, balance={}, strategy={}",
+            step_started.elapsed().as_secs_f64(),
+            balance,
+            strategy
+        );
+        Ok(BalanceReading {
+            amount: balance_num,
+            currency: balance_currency.unwrap_or_default().to_string(),
+            strategy,
+        })
+    }
+
+    /// 将 API Key 的额度同步为当前余额，按策略顺序依次尝试。
+    /// 未指定 quota_sync_key 时同步首个令牌
+    pub async fn sync_apikey_limit(&self, balance: f64) -> Result<QuotaSyncReport> {
+        self.require_login()?;
+        self.heartbeat.beat(STAGE_QUOTA_SYNC);
+        let timer = start_stage_timer(STAGE_QUOTA_SYNC, self.username());
+        let waits = WaitCaps::from_config(&self.browser_config);
+        let selected = self
+            .quota_key
+            .as_deref()
+            .map(|key| (self.quota_key_name.as_str(), key));
+        let report =
+            sync_apikey_limit(&self.driver, balance, waits, selected, &self.quota_guard).await;
+        if report.applied_by.is_some() {
+            timer.finish(true, None);
+        } else {
+            tracing::warn!("[sync_quota] 账号 {} {}", self.username(), report.summary());
+            timer.finish(false, Some(report.failure_text()));
+        }
+        Ok(report)
+    }
+
+    /// 计算额度同步将写入的值并列出各令牌当前额度，只打开编辑弹窗读取比例，不提交
+    pub async fn preview_quota_sync(&self, balance: f64) -> Result<QuotaSyncPreview> {
+        self.require_login()?;
+        self.heartbeat.beat(STAGE_QUOTA_SYNC);
+        let waits = WaitCaps::from_config(&self.browser_config);
+        open_apikey_page(&self.driver, waits).await?;
+        let detected = match open_first_token_editor(&self.driver, waits).await {
+            Ok(()) => detect_quota_unit_rate(&self.driver).await.ok(),
+            Err(err) => {
+                tracing::debug!("[preview_quota] 编辑弹窗未打开: {}", err);
+                None
+            }
+        };
+        let stored_rate = known_unit_rate(QUOTA_PROVIDER);
+        let unit_rate = detected.or(stored_rate).unwrap_or(QUOTA_UNIT_PER_DOLLAR);
+        let rate_conflict = matches!(
+            (detected, stored_rate),
+            (Some(detected), Some(stored))
+                if (detected - stored).abs() / stored > UNIT_RATE_MAX_DEVIATION
+        );
+        let mut tokens = fetch_token_quotas(&self.driver).await?;
+        let target_index = target_token_index(&tokens, self.quota_key.as_deref());
+        if let Some(index) = target_index {
+            tokens[index].selected = true;
+        }
+        let guard_rejection = check_quota_guard(
+            &self.quota_guard,
+            balance,
+            unit_rate,
+            target_index.map(|index| &tokens[index]),
+        );
+        Ok(QuotaSyncPreview {
+            target: match &self.quota_key {
+                Some(_) => format!("API Key[{}]", self.quota_key_name),
+                None => "首个 API Key".to_string(),
+            },
+            balance,
+            unit_rate,
+            rate_detected: detected.is_some(),
+            stored_rate,
+            rate_conflict,
+            guard_rejection,
+            target_quota: (balance * unit_rate).round().max(0.0) as i64,
+            tokens,
+        })
+    }
+
+    /// 列出 API令牌 页面中的令牌
+    pub async fn list_tokens(&self) -> Result<Vec<WebToken>> {
+        self.require_login()?;
+        self.heartbeat.beat("令牌列表");
+        let waits = WaitCaps::from_config(&self.browser_config);
+        open_apikey_page(&self.driver, waits).await?;
+        list_token_rows(&self.driver).await
+    }
+
+    /// 以当前登录会话调用站点接口新建不限额度、永不过期的令牌，再按名称取回其 Key
+    pub async fn create_token(&self, name: &str) -> Result<CreatedToken> {
+        self.require_login()?;
+        self.heartbeat.beat("新建令牌");
+        let script = r#"
+            const done = arguments[arguments.length - 1];
+            const name = arguments[0];
+            (async () => {
+                let userId = '';
+                try {
+                    const user = JSON.parse(localStorage.getItem('user') || '{}');
+                    userId = String(user.id || '');
+                } catch (e) {}
+                const headers = { 'Content-Type': 'application/json' };
+                if (userId) headers['New-Api-User'] = userId;
+                const resp = await fetch('/api/token/', {
+                    method: 'POST',
+                    credentials: 'include',
+                    headers,
+                    body: JSON.stringify({ name, remain_quota: 0, unlimited_quota: true, expired_time: -1 })
+                });
+                const data = await resp.json();
+                if (!data.success) {
+                    return done({ ok: false, reason: data.message || String(resp.status) });
+                }
+                const listResp = await fetch('/api/token/?p=0&size=100', { credentials: 'include', headers });
+                const list = await listResp.json();
+                const items = Array.isArray(list.data) ? list.data : ((list.data && list.data.items) || []);
+                const created = items.find((item) => item.name === name);
+                let key = created ? String(created.key || '') : '';
+                if (key && !key.includes('*') && !key.startsWith('sk-')) key = 'sk-' + key;
+                done({ ok: true, key: key.includes('*') ? '' : key });
+            })().catch((e) => done({ ok: false, reason: String(e) }));
+        "#;
+        let value = self
+            .driver
+            .execute_async(script, vec![Value::from(name)])
+            .await?;
+        let result = value.json();
+        if !result.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+            let reason = result
+                .get("reason")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            anyhow::bail!("新建令牌失败: {}", reason);
+        }
+        Ok(CreatedToken {
+            name: name.to_string(),
+            key: result
+                .get("key")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string(),
+        })
+    }
+
+    /// 通过站点管理接口列出子账号，并读取当前账号的令牌。
+    /// 用户列表需要管理员权限，读取失败时只返回令牌
+    pub async fn discover_accounts(&self) -> Result<AccountDiscovery> {
+        self.require_login()?;
+        self.heartbeat.beat("发现账号");
+        let (users, users_error) = match fetch_site_users(&self.driver).await {
+            Ok(users) => (users, None),
+            Err(e) => (Vec::new(), Some(format!("{e:#}"))),
+        };
+        let tokens = fetch_token_quotas(&self.driver)
+            .await?
+            .into_iter()
+            .map(|item| DiscoveredToken {
+                masked: item.key.contains('*'),
+                key: if item.key.contains('*') || item.key.starts_with("sk-") {
+                    item.key
+                } else {
+                    format!("sk-{}", item.key)
+                },
+                name: item.name,
+                exists: false,
+            })
+            .collect();
+        Ok(AccountDiscovery {
+            admin: self.username().to_string(),
+            users,
+            users_error,
+            tokens,
+        })
+    }
+
+    /// 修改登录密码: 先在个人设置页填写修改密码表单，失败时改用站点接口。
+    /// 返回实际生效的方式
+    pub async fn change_password(&self, old_password: &str, new_password: &str) -> Result<String> {
+        self.require_login()?;
+        self.heartbeat.beat(STAGE_PASSWORD);
+        let timer = start_stage_timer(STAGE_PASSWORD, self.username());
+        let waits = WaitCaps::from_config(&self.browser_config);
+        let result =
+            match change_password_via_form(&self.driver, old_password, new_password, waits).await {
+                Ok(()) => Ok("表单".to_string()),
+                Err(form_err) => {
+                    tracing::warn!(
+                        "账号 {} 修改密码表单失败，改用接口: {}",
+                        self.username(),
+                        form_err
+                    );
+                    change_password_via_api(&self.driver, old_password, new_password)
+                        .await
+                        .map(|()| "接口".to_string())
+                        .map_err(|api_err| anyhow::anyhow!("表单: {form_err}; 接口: {api_err}"))
+                }
+            };
+        match &result {
+            Ok(_) => timer.finish(true, None),
+            Err(err) => timer.finish(false, Some(err.to_string())),
+        }
+        result
+    }
+
+    /// 清除当前登录态后只用账号密码重新登录，不使用已保存的 Cookie
+    async fn relogin_with_password(&mut self, account: &Account) -> Result<()> {
+        self.logged_in_as = None;
+        self.heartbeat.beat(STAGE_LOGIN);
+        let waits = WaitCaps::from_config(&self.browser_config);
+        self.driver.delete_all_cookies().await?;
+        let _ = self
+            .driver
+            .execute(
+                "localStorage.clear(); sessionStorage.clear();",
+                Vec::<Value>::new(),
+            )
+            .await;
+        ensure_logged_in(&self.driver, account, waits).await?;
+        self.logged_in_as = Some(account.username.clone());
+        Ok(())
+    }
+
+    /// 结束会话并归还浏览器池实例
+    pub async fn close(self) {
+        let quit_timeout = self.quit_timeout;
+        self.shutdown(quit_timeout, false).await;
+    }
+
+    /// 在 quit_timeout 内正常关闭会话；force 或关闭超时时直接结束 chromedriver 进程
+    async fn shutdown(self, quit_timeout: Duration, force: bool) {
+        let quit_ok = if force {
+            false
+        } else {
+            matches!(
+                tokio::time::timeout(quit_timeout, self.driver.quit()).await,
+                Ok(Ok(()))
+            )
+        };
+        if quit_ok {
+            release_pool_ticket(&self.pool, self.ticket);
+        } else {
+            match self.pool.lock() {
+                Ok(mut guard) => guard.discard(self.ticket),
+                Err(_) => tracing::warn!("浏览器池锁获取失败，无法结束无响应的实例"),
+            }
+        }
+        remove_profile_dir(self.profile_dir.as_ref());
+    }
+
+    fn username(&self) -> &str {
+        self.logged_in_as.as_deref().unwrap_or("")
+    }
+
+    fn require_login(&self) -> Result<()> {
+        if self.logged_in_as.is_none() {
+            anyhow::bail!("浏览器会话尚未登录");
+        }
+        Ok(())
+    }
+}
+
+/// 网页流程各阶段在性能监控中的操作名，账号记录在 metadata.username
+const STAGE_LOGIN: &str = "登录";
+const STAGE_EXTRACT: &str = "余额提取";
+const STAGE_QUOTA_SYNC: &str = "额度同步";
+const STAGE_CHECKIN: &str = "签到";
+const STAGE_PASSWORD: &str = "修改密码";
+
+fn start_stage_timer(stage: &str, username: &str) -> OperationTimer {
+    PerformanceMonitor::start_operation(
+        get_performance_monitor(),
+        format!("{WEB_STAGE_PREFIX}{stage}"),
+        HashMap::from([("username".to_string(), username.to_string())]),
+    )
+}
+
 pub async fn run_native_web_check(
     account: &Account,
     web_config: &WebCheckConfig,
@@ -20,34 +737,652 @@ pub async fn run_native_web_check(
     retry_times: u32,
     retry_delay_secs: u64,
 ) -> Result<WebCheckResult> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let heartbeat = session.heartbeat.clone();
+
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    // 阶段心跳只在进入阶段时更新，挂起判定不能短于单个阶段内合法的等待时长
+    let stall = Duration::from_secs(web_config.watchdog_stall_secs.max(5))
+        .max(WaitCaps::from_config(browser_config).stage_budget(browser_config));
+    let result = tokio::select! {
+        result = tokio::time::timeout(
+            Duration::from_secs(timeout_secs),
+            run_login_flow(
+                &mut session,
+                account,
+                &web_config.checkin,
+                retry_times,
+                retry_delay_secs,
+            ),
+        ) => Some(result),
+        _ = watch_heartbeat(&heartbeat, stall) => None,
+    };
+
+    let final_result = match result {
+        Some(Ok(Ok(data))) => data,
+        Some(Ok(Err(err))) => WebCheckResult {
+            success: false,
+            balance: None,
+            message: format!("网页流程失败: {err}"),
+            ..Default::default()
+        },
+        Some(Err(_)) => WebCheckResult {
+            success: false,
+            balance: None,
+            message: format!("网页流程超时({timeout_secs}s)"),
+            ..Default::default()
+        },
+        None => {
+            let (stage, idle) = heartbeat.snapshot();
+            tracing::warn!(
+                "账号 {} 网页会话挂起: 阶段={}, 已 {:.0}s 无进展",
+                account.username,
+                stage,
+                idle.as_secs_f64()
+            );
+            WebCheckResult {
+                success: false,
+                balance: None,
+                message: format!(
+                    "网页会话挂起(阶段: {stage}, 已 {:.0}s 无进展)，已强制结束",
+                    idle.as_secs_f64()
+                ),
+                hung: true,
+                ..Default::default()
+            }
+        }
+    };
+
+    let quit_timeout = session.quit_timeout;
+    session.shutdown(quit_timeout, final_result.hung).await;
+    Ok(final_result)
+}
+
+/// 登录账号(优先已保存的 Cookie)并返回当前站点 Cookie
+pub async fn fetch_account_cookies(
+    account: &Account,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<Vec<StoredCookie>> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        session.site_cookies().await
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("读取 Cookie 超时({timeout_secs}s)"),
+    }
+}
+
+/// 登录账号后只重新同步 API Key 的额度，不执行签到
+pub async fn run_quota_resync(
+    account: &Account,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<WebCheckResult> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        let reading = session.read_balance().await?;
+        let report = session.sync_apikey_limit(reading.amount).await?;
+        Ok::<_, anyhow::Error>(WebCheckResult {
+            success: true,
+            balance: Some(reading.amount),
+            currency: reading.currency,
+            extraction_strategy: reading.strategy,
+            message: report.summary(),
+            quota_sync_success: Some(report.applied_by.is_some()),
+            quota_sync_message: report.summary(),
+            detected_unit_rate: report.detected_rate,
+            unit_rate_conflict: report.rate_conflict,
+            ..Default::default()
+        })
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("重新同步额度超时({timeout_secs}s)"),
+    }
+}
+
+/// 写入已保存的 Cookie 后打开控制台，未被重定向到登录页即视为登录成功
+async fn login_with_cookies(
+    driver: &WebDriver,
+    cookies: &[StoredCookie],
+    waits: WaitCaps,
+) -> Result<bool> {
+    driver
+        .get(CONSOLE_URL)
+        .await
+        .with_context(|| "导航到控制台失败")?;
+    driver.delete_all_cookies().await?;
+    for item in cookies {
+        let mut cookie = Cookie::new(item.name.clone(), item.value.clone());
+        if !item.domain.is_empty() {
+            cookie.set_domain(item.domain.clone());
+        }
+        if !item.path.is_empty() {
+            cookie.set_path(item.path.clone());
+        }
+        if item.secure {
+            cookie.set_secure(true);
+        }
+        if let Some(expiry) = item.expiry {
+            cookie.set_expiry(expiry);
+        }
+        if let Err(err) = driver.add_cookie(cookie).await {
+            tracing::debug!("写入 Cookie 失败: name={}, err={}", item.name, err);
+        }
+    }
+    driver
+        .get(CONSOLE_URL)
+        .await
+        .with_context(|| "导航到控制台失败")?;
+    wait_for_console_or_login(driver, waits).await?;
+    let state = driver
+        .execute(CONSOLE_OR_LOGIN_SCRIPT, Vec::<Value>::new())
+        .await?;
+    let current_url = driver.current_url().await?.to_string();
+    Ok(state.json().as_str() != Some("login") && current_url.contains("/console"))
+}
+
+/// 手动接管登录: 打开可见浏览器到登录页，等待用户自行完成登录(含验证码)，
+/// 检测到进入控制台后自动提取余额并同步额度
+pub async fn run_interactive_login(
+    account: &Account,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    wait_secs: u64,
+) -> Result<WebCheckResult> {
+    let mut visible = browser_config.clone();
+    visible.headless = false;
+    let mut session = WebSession::connect(web_config, &visible).await?;
+    let result = async {
+        session
+            .driver
+            .get(CONSOLE_URL)
+            .await
+            .with_context(|| "打开登录页失败")?;
+        let _ = session
+            .driver
+            .execute(
+                PREFILL_USERNAME_SCRIPT,
+                vec![Value::from(account.username.clone())],
+            )
+            .await;
+        tracing::info!(
+            "账号 {} 等待手动登录，上限 {}s",
+            account.username,
+            wait_secs
+        );
+        session.heartbeat.beat("手动登录");
+        let started = Instant::now();
+        loop {
+            let url = session.driver.current_url().await?.to_string();
+            if url.contains("/console") && !url.contains("/login") {
+                break;
+            }
+            if started.elapsed() >= Duration::from_secs(wait_secs) {
+                anyhow::bail!("等待手动登录超时({wait_secs}s)");
+            }
+            async_sleep(Duration::from_secs(1)).await;
+        }
+        session.logged_in_as = Some(account.username.clone());
+        let reading = session.read_balance().await?;
+        let report = session.sync_apikey_limit(reading.amount).await?;
+        Ok(WebCheckResult {
+            success: true,
+            balance: Some(reading.amount),
+            currency: reading.currency,
+            extraction_strategy: reading.strategy,
+            message: format!("手动登录完成; {}", report.summary()),
+            quota_sync_success: Some(report.applied_by.is_some()),
+            quota_sync_message: report.summary(),
+            detected_unit_rate: report.detected_rate,
+            unit_rate_conflict: report.rate_conflict,
+            ..Default::default()
+        })
+    }
+    .await;
+    session.close().await;
+    result
+}
+
+/// 预热浏览器池；navigate=true 时再打开一次登录页，使浏览器程序与域名解析进入缓存
+pub async fn prewarm_browser_pool(
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    navigate: bool,
+) -> Result<String> {
+    let started = Instant::now();
+    let web_cfg = web_config.clone();
+    let binary_path = browser_config.binary_path.clone();
+    let created = task::spawn_blocking(move || prewarm_global_pool(&web_cfg, &binary_path))
+        .await
+        .map_err(|e| anyhow::anyhow!("预热浏览器池任务失败: {e}"))?
+        .with_context(|| "预热浏览器池失败")?;
+
+    if navigate {
+        let session = WebSession::connect(web_config, browser_config).await?;
+        let waits = WaitCaps::from_config(browser_config);
+        let visited = match session.driver.get(CONSOLE_URL).await {
+            Ok(()) => wait_for_console_or_login(&session.driver, waits).await,
+            Err(err) => Err(err.into()),
+        };
+        session.close().await;
+        if let Err(err) = visited {
+            tracing::warn!("预热打开登录页失败: {}", err);
+        }
+    }
+
+    Ok(format!(
+        "浏览器池预热完成: 新建实例={}, 耗时={:.1}s",
+        created,
+        started.elapsed().as_secs_f64()
+    ))
+}
+
+/// 看门狗: 会话在 stall 时间内没有新的阶段心跳时返回
+async fn watch_heartbeat(heartbeat: &Heartbeat, stall: Duration) {
+    loop {
+        async_sleep(Duration::from_secs(1)).await;
+        if heartbeat.snapshot().1 >= stall {
+            return;
+        }
+    }
+}
+
+/// 登录后列出账号的 API 令牌，整个过程复用同一浏览器会话
+pub async fn list_account_tokens(
+    account: &Account,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<Vec<WebToken>> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        session.list_tokens().await
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("获取令牌列表超时({timeout_secs}s)"),
+    }
+}
+
+/// 登录账号并预演额度同步，不修改任何令牌
+pub async fn run_quota_preview(
+    account: &Account,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<QuotaSyncPreview> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        let reading = session.read_balance().await?;
+        session.preview_quota_sync(reading.amount).await
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("额度同步预演超时({timeout_secs}s)"),
+    }
+}
+
+/// 登录账号并新建一个令牌
+pub async fn create_account_token(
+    account: &Account,
+    name: &str,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<CreatedToken> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        session.create_token(name).await
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("新建令牌超时({timeout_secs}s)"),
+    }
+}
+
+/// 登录管理员账号并列出可导入的子账号与令牌
+pub async fn run_account_discovery(
+    account: &Account,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<AccountDiscovery> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20);
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        session.discover_accounts().await
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("发现账号超时({timeout_secs}s)"),
+    }
+}
+
+/// 冒烟测试中的一个阶段
+#[derive(Debug, Clone, Serialize)]
+pub struct SmokeStage {
+    pub name: String,
+    /// passed / failed / skipped
+    pub status: String,
+    pub duration_ms: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmokeTestReport {
+    pub success: bool,
+    pub stages: Vec<SmokeStage>,
+    pub elapsed_secs: f64,
+}
+
+/// 内置的余额页面，结构与控制台的余额卡片一致；提取结果应为 SMOKE_FIXTURE_BALANCE
+const SMOKE_FIXTURE_HTML: &str = r#"<!DOCTYPE html>
+<html><head><meta charset="utf-8"><title>ART-rs smoke test</title></head>
+<body><div id="app"><main class="console">
+<div class="semi-card"><span>当前余额</span><div class="text-2xl">$1,234.56</div></div>
+<div class="semi-card"><span>历史消耗</span><div class="text-2xl">$78.90</div></div>
+</main></div></body></html>"#;
+const SMOKE_FIXTURE_BALANCE: f64 = 1234.56;
+
+/// 在本机随机端口提供内置页面，返回地址与服务任务；测试结束后中止任务即可关闭
+async fn serve_smoke_fixture() -> Result<(String, tokio::task::JoinHandle<()>)> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .context("启动内置测试页面失败")?;
+    let addr = listener.local_addr().context("读取内置测试页面端口失败")?;
+    let handle = tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0_u8; 2048];
+            let _ = stream.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                SMOKE_FIXTURE_HTML.len(),
+                SMOKE_FIXTURE_HTML
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+    Ok((format!("http://{addr}/console"), handle))
+}
+
+fn smoke_stage(name: &str, started: Instant, outcome: Result<String>) -> SmokeStage {
+    let (status, message) = match outcome {
+        Ok(message) => ("passed", message),
+        Err(err) => ("failed", format!("{err:#}")),
+    };
+    SmokeStage {
+        name: name.to_string(),
+        status: status.to_string(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        message,
+    }
+}
+
+/// 安装自检: 依次验证驱动与浏览器能否启动、登录页能否打开、余额提取脚本能否识别内置页面。
+/// 不登录任何账号，适合在 Chrome 更新后确认环境仍可用
+pub async fn run_smoke_test(
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+) -> SmokeTestReport {
+    let started = Instant::now();
+    let waits = WaitCaps::from_config(browser_config);
+    let mut stages = Vec::new();
+
+    let stage_started = Instant::now();
+    let session = match WebSession::connect(web_config, browser_config).await {
+        Ok(session) => session,
+        Err(err) => {
+            stages.push(smoke_stage("驱动与浏览器", stage_started, Err(err)));
+            for name in ["登录页", "余额提取脚本"] {
+                stages.push(SmokeStage {
+                    name: name.to_string(),
+                    status: "skipped".to_string(),
+                    duration_ms: 0,
+                    message: "浏览器未启动，跳过".to_string(),
+                });
+            }
+            return SmokeTestReport {
+                success: false,
+                stages,
+                elapsed_secs: started.elapsed().as_secs_f64(),
+            };
+        }
+    };
+    let user_agent = session
+        .driver
+        .execute("return navigator.userAgent;", Vec::<Value>::new())
+        .await
+        .map(|value| value.json().as_str().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    let browser = user_agent
+        .split_whitespace()
+        .find(|item| item.starts_with("Chrome/") || item.starts_with("HeadlessChrome/"))
+        .unwrap_or("版本未知");
+    stages.push(smoke_stage(
+        "驱动与浏览器",
+        stage_started,
+        Ok(format!("会话已建立: {browser}")),
+    ));
+
+    let stage_started = Instant::now();
+    let login_page = async {
+        session.driver.get(CONSOLE_URL).await?;
+        wait_for_console_or_login(&session.driver, waits).await?;
+        let value = session
+            .driver
+            .execute(CONSOLE_OR_LOGIN_SCRIPT, Vec::<Value>::new())
+            .await?;
+        match value.json().as_str().unwrap_or("") {
+            "login" => Ok("已打开登录页".to_string()),
+            "console" => Ok("已打开控制台(浏览器中已有登录状态)".to_string()),
+            _ => {
+                let url = session
+                    .driver
+                    .current_url()
+                    .await
+                    .map(|item| item.to_string());
+                anyhow::bail!("页面未出现登录表单: {}", url.unwrap_or_default())
+            }
+        }
+    };
+    let outcome = match tokio::time::timeout(Duration::from_secs(60), login_page).await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(anyhow::anyhow!("打开登录页超时(60s)")),
+    };
+    stages.push(smoke_stage("登录页", stage_started, outcome));
+
+    let stage_started = Instant::now();
+    let extraction = async {
+        let (url, server) = serve_smoke_fixture().await?;
+        let extracted = async {
+            session.driver.get(url.as_str()).await?;
+            extract_balance(&session.driver, 5, WaitCaps::from_config(browser_config)).await
+        }
+        .await;
+        server.abort();
+        let (text, strategy) = extracted?;
+        match currency::parse_amount(&text) {
+            Some(value) if (value - SMOKE_FIXTURE_BALANCE).abs() < 1e-6 => {
+                Ok(format!("提取结果 {text} 与预期一致(策略 {strategy})"))
+            }
+            _ => anyhow::bail!("提取结果 {text} 与预期 {SMOKE_FIXTURE_BALANCE} 不一致"),
+        }
+    };
+    let outcome = match tokio::time::timeout(Duration::from_secs(30), extraction).await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(anyhow::anyhow!("余额提取超时(30s)")),
+    };
+    stages.push(smoke_stage("余额提取脚本", stage_started, outcome));
+    session.close().await;
+
+    SmokeTestReport {
+        success: stages.iter().all(|item| item.status == "passed"),
+        stages,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// 修改密码的结果；verified 为 false 表示新旧密码都无法重新登录，修改结果未知
+#[derive(Debug, Clone)]
+pub struct PasswordChangeOutcome {
+    pub verified: bool,
+    pub message: String,
+    /// 新密码登录后的站点 Cookie，用于替换已失效的旧 Cookie
+    pub cookies: Vec<StoredCookie>,
+}
+
+/// 登录后修改密码，再用新密码重新登录验证。新密码登录失败而旧密码仍可登录时
+/// 视为修改未生效并返回错误
+pub async fn run_password_change(
+    account: &Account,
+    new_password: &str,
+    web_config: &WebCheckConfig,
+    browser_config: &BrowserConfig,
+    retry_times: u32,
+    retry_delay_secs: u64,
+) -> Result<PasswordChangeOutcome> {
+    let mut session = WebSession::connect(web_config, browser_config).await?;
+    let timeout_secs = web_config.timeout_seconds.max(20) * 2;
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        session
+            .login(account, retry_times, retry_delay_secs)
+            .await?;
+        let method = session
+            .change_password(&account.password, new_password)
+            .await?;
+        let updated = Account {
+            password: new_password.to_string(),
+            ..account.clone()
+        };
+        match session.relogin_with_password(&updated).await {
+            Ok(()) => Ok(PasswordChangeOutcome {
+                verified: true,
+                message: format!("密码已修改(方式={method})并通过重新登录验证"),
+                cookies: session.site_cookies().await.unwrap_or_default(),
+            }),
+            Err(new_err) => {
+                if session.relogin_with_password(account).await.is_ok() {
+                    anyhow::bail!("新密码无法登录而旧密码仍有效，修改未生效: {new_err}");
+                }
+                Ok(PasswordChangeOutcome {
+                    verified: false,
+                    message: format!(
+                        "密码已提交修改(方式={method})，但新旧密码均无法重新登录: {new_err}"
+                    ),
+                    cookies: Vec::new(),
+                })
+            }
+        }
+    })
+    .await;
+    session.close().await;
+    match result {
+        Ok(data) => data,
+        Err(_) => anyhow::bail!("修改密码超时({timeout_secs}s)"),
+    }
+}
+
+async fn acquire_pool_ticket(
+    web_config: &WebCheckConfig,
+    binary_path: &str,
+) -> Result<(Arc<Mutex<BrowserPool>>, PoolTicket)> {
     let web_cfg = web_config.clone();
-    let pool = task::spawn_blocking(move || get_global_pool(&web_cfg))
+    let binary_path = binary_path.to_string();
+    let pool = task::spawn_blocking(move || get_global_pool(&web_cfg, &binary_path))
         .await
         .map_err(|e| anyhow::anyhow!("初始化浏览器池任务失败: {e}"))?
+        .inspect_err(|err| report_infrastructure_error(classify_driver_error(err), err))
         .with_context(|| "初始化浏览器池失败")?;
-    let ticket = {
-        let acquire_timeout = Duration::from_secs(20);
-        let started = Instant::now();
-        loop {
+    let acquire_timeout = Duration::from_secs(20);
+    let started = Instant::now();
+    loop {
+        {
+            let mut guard = pool
+                .lock()
+                .map_err(|_| anyhow::anyhow!("浏览器池锁获取失败"))?;
+            // 当前无可用实例时释放锁后等待重试
+            if let Some(ticket) = guard
+                .try_acquire()
+                .inspect_err(|err| report_infrastructure_error(classify_driver_error(err), err))
+                .with_context(|| "从浏览器池获取可用实例失败")?
             {
-                let mut guard = pool
-                    .lock()
-                    .map_err(|_| anyhow::anyhow!("浏览器池锁获取失败"))?;
-                match guard
-                    .try_acquire()
-                    .with_context(|| "从浏览器池获取可用实例失败")?
-                {
-                    Some(ticket) => break ticket,
-                    None => {} // 当前无可用实例，释放锁后等待重试
-                }
-            } // guard 在此处 drop，释放锁
-            if started.elapsed() >= acquire_timeout {
-                anyhow::bail!("等待浏览器池可用实例超时({}s)", acquire_timeout.as_secs());
+                guard.record_wait(started.elapsed().as_secs_f64());
+                return Ok((pool.clone(), ticket));
             }
-            async_sleep(Duration::from_millis(120)).await;
+        } // guard 在此处 drop，释放锁
+        if started.elapsed() >= acquire_timeout {
+            let err = anyhow::anyhow!("等待浏览器池可用实例超时({}s)", acquire_timeout.as_secs());
+            report_infrastructure_error("pool_exhausted", &err);
+            return Err(err);
         }
-    };
+        async_sleep(Duration::from_millis(120)).await;
+    }
+}
 
+fn release_pool_ticket(pool: &Arc<Mutex<BrowserPool>>, ticket: PoolTicket) {
+    if let Ok(mut guard) = pool.lock() {
+        guard.release(ticket);
+        let stats = guard.get_stats();
+        tracing::debug!(
+            "浏览器池统计: 复用率={:.1}%, 可用实例={}",
+            stats.get("reuse_rate").copied().unwrap_or(0.0),
+            stats.get("available_count").copied().unwrap_or(0.0)
+        );
+    }
+}
+
+/// 构建启动参数；沙箱浏览器额外返回为本次会话创建的用户数据目录
+fn build_chrome_capabilities(
+    browser_config: &BrowserConfig,
+) -> Result<(ChromeCapabilities, Option<PathBuf>)> {
     let mut caps = DesiredCapabilities::chrome();
     caps.add_arg("--disable-gpu")?;
     caps.add_arg("--no-sandbox")?;
@@ -63,6 +1398,24 @@ pub async fn run_native_web_check(
     caps.add_arg("--log-level=3")?;
     caps.add_arg("--disable-logging")?;
     caps.add_experimental_option("excludeSwitches", serde_json::json!(["enable-logging"]))?;
+    // eager: DOMContentLoaded 即返回，登录表单准备与页面剩余资源加载并行进行
+    caps.set_page_load_strategy(parse_page_load_strategy(&browser_config.page_load_strategy))?;
+    let mut profile_dir = None;
+    if let Some(binary) = resolve_browser_binary(&browser_config.binary_path) {
+        caps.set_binary(&binary)?;
+        if let Some(root) = sandbox_profile_root(&binary) {
+            static PROFILE_SEQ: AtomicU64 = AtomicU64::new(0);
+            let dir = root.join(format!(
+                "profile_{}_{}",
+                std::process::id(),
+                PROFILE_SEQ.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("创建浏览器用户数据目录失败: {}", dir.display()))?;
+            caps.add_arg(&format!("--user-data-dir={}", dir.display()))?;
+            profile_dir = Some(dir);
+        }
+    }
     if browser_config.headless {
         caps.add_arg("--headless=new")?;
     }
@@ -77,142 +1430,487 @@ pub async fn run_native_web_check(
             caps.add_arg(&user_agent_arg)?;
         }
     }
-    if browser_config.disable_images {
-        caps.add_experimental_option(
-            "prefs",
-            serde_json::json!({"profile.managed_default_content_settings.images": 2}),
-        )?;
+    if !browser_config.lang.trim().is_empty() {
+        let lang_arg = format!("--lang={}", browser_config.lang.trim());
+        caps.add_arg(&lang_arg)?;
+    }
+    for raw_arg in &browser_config.extra_args {
+        let arg = raw_arg.trim();
+        if !arg.is_empty() {
+            caps.add_arg(arg)?;
+        }
     }
 
-    let driver = WebDriver::new(&ticket.url, caps)
-        .await
-        .with_context(|| "连接 chromedriver 失败")?;
+    // 用户自定义 prefs 覆盖内置项，两者合并后一次写入
+    let mut prefs = serde_json::Map::new();
+    if browser_config.disable_images {
+        prefs.insert(
+            "profile.managed_default_content_settings.images".to_string(),
+            serde_json::json!(2),
+        );
+    }
+    if !browser_config.accept_language.trim().is_empty() {
+        prefs.insert(
+            "intl.accept_languages".to_string(),
+            serde_json::json!(browser_config.accept_language.trim()),
+        );
+    }
+    for (key, value) in &browser_config.prefs {
+        prefs.insert(key.clone(), value.clone());
+    }
+    if !prefs.is_empty() {
+        caps.add_experimental_option("prefs", Value::Object(prefs))?;
+    }
+    Ok((caps, profile_dir))
+}
 
-    let timeout_secs = web_config.timeout_seconds.max(20);
-    let result = tokio::time::timeout(
-        Duration::from_secs(timeout_secs),
-        run_login_flow_with_retry(
-            &driver,
-            account,
-            browser_config,
-            retry_times,
-            retry_delay_secs,
-        ),
-    )
-    .await;
+fn remove_profile_dir(dir: Option<&PathBuf>) {
+    if let Some(dir) = dir
+        && let Err(err) = std::fs::remove_dir_all(dir)
+    {
+        tracing::debug!("删除浏览器用户数据目录失败: {}, err={}", dir.display(), err);
+    }
+}
 
-    let final_result = match result {
-        Ok(Ok(data)) => data,
-        Ok(Err(err)) => WebCheckResult {
-            success: false,
-            balance: None,
-            message: format!("网页流程失败: {err}"),
-        },
-        Err(_) => WebCheckResult {
-            success: false,
-            balance: None,
-            message: format!("网页流程超时({timeout_secs}s)"),
-        },
-    };
+/// 通过 CDP 设置会话级的请求语言与模拟时区，失败只记录日志不中断流程
+async fn apply_session_overrides(driver: &WebDriver, browser_config: &BrowserConfig) {
+    let devtools = ChromeDevTools::new(driver.handle.clone());
+    let accept_language = browser_config.accept_language.trim();
+    if !accept_language.is_empty() {
+        let params = serde_json::json!({"headers": {"Accept-Language": accept_language}});
+        let enabled = devtools.execute_cdp("Network.enable").await;
+        let applied = match enabled {
+            Ok(_) => {
+                devtools
+                    .execute_cdp_with_params("Network.setExtraHTTPHeaders", params)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        if let Err(err) = applied {
+            tracing::warn!("设置 Accept-Language 失败: {}", err);
+        }
+    }
+    let timezone = browser_config.timezone.trim();
+    if !timezone.is_empty() {
+        let params = serde_json::json!({"timezoneId": timezone});
+        if let Err(err) = devtools
+            .execute_cdp_with_params("Emulation.setTimezoneOverride", params)
+            .await
+        {
+            tracing::warn!("设置模拟时区失败: timezone={}, err={}", timezone, err);
+        }
+    }
+}
 
-    let _ = driver.quit().await;
-    if let Ok(mut guard) = pool.lock() {
-        guard.release(ticket);
-        let stats = guard.get_stats();
-        tracing::debug!(
-            "浏览器池统计: 复用率={:.1}%, 可用实例={}",
-            stats.get("reuse_rate").copied().unwrap_or(0.0),
-            stats.get("available_count").copied().unwrap_or(0.0)
-        );
+fn parse_page_load_strategy(text: &str) -> PageLoadStrategy {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "normal" => PageLoadStrategy::Normal,
+        "none" => PageLoadStrategy::None,
+        _ => PageLoadStrategy::Eager,
     }
-    Ok(final_result)
 }
 
-async fn run_login_flow_with_retry(
-    driver: &WebDriver,
+async fn run_login_flow(
+    session: &mut WebSession,
     account: &Account,
-    browser_config: &BrowserConfig,
+    checkin: &CheckinProfile,
     retry_times: u32,
     retry_delay_secs: u64,
 ) -> Result<WebCheckResult> {
-    let retry_times = retry_times.max(1);
-    let retry_delay_secs = retry_delay_secs.max(1);
-    let mut last_error = String::new();
-    for attempt in 0..retry_times {
-        match run_login_flow_once(driver, account, browser_config).await {
-            Ok(result) => return Ok(result),
+    let flow_started = Instant::now();
+    session
+        .login(account, retry_times, retry_delay_secs)
+        .await?;
+    let already_checked_in = session
+        .is_already_checked_in(&checkin.already_texts)
+        .await
+        .unwrap_or_else(|err| {
+            tracing::debug!("检测今日签到状态失败: {}", err);
+            false
+        });
+    let mut credited = None;
+    let mut checkin_note = String::new();
+    let already_checked_in = if checkin.click_button && !already_checked_in {
+        match session.click_checkin(checkin).await {
+            Ok(outcome) => {
+                credited = outcome.credited;
+                checkin_note = outcome.message;
+                outcome.already
+            }
             Err(err) => {
-                last_error = err.to_string();
-                tracing::warn!(
-                    "登录失败 (尝试 {}/{}): {}",
-                    attempt + 1,
-                    retry_times,
-                    last_error
-                );
-                if attempt + 1 < retry_times {
-                    async_sleep(Duration::from_secs(retry_delay_secs)).await;
-                }
+                tracing::warn!("账号 {} 点击签到失败: {}", account.username, err);
+                checkin_note = format!("点击签到失败: {err}");
+                false
             }
         }
+    } else {
+        already_checked_in
+    };
+    let reading = session.read_balance().await?;
+    let balance_num = reading.amount;
+    if already_checked_in {
+        tracing::info!("账号 {} 今日已签到，跳过额度同步", account.username);
+        return Ok(WebCheckResult {
+            success: true,
+            balance: Some(balance_num),
+            currency: reading.currency,
+            extraction_strategy: reading.strategy,
+            message: "今日已签到，跳过额度同步".to_string(),
+            already_checked_in: true,
+            ..Default::default()
+        });
     }
-    anyhow::bail!("登录失败，已重试{}次: {}", retry_times, last_error)
+
+    let step_started = Instant::now();
+    let (sync_ok, sync_msg, detected_rate, rate_conflict) =
+        match session.sync_apikey_limit(balance_num).await {
+            Ok(report) => (
+                report.applied_by.is_some(),
+                report.summary(),
+                report.detected_rate,
+                report.rate_conflict,
+            ),
+            Err(err) => {
+                tracing::warn!("同步 API Key 额度失败: {}", err);
+                (false, format!("同步额度失败: {err}"), None, None)
+            }
+        };
+    tracing::debug!(
+        "[flow] sync_apikey_limit 耗时={:.1}s",
+        step_started.elapsed().as_secs_f64()
+    );
+    tracing::debug!(
+        "[flow] run_login_flow 总耗时={:.1}s",
+        flow_started.elapsed().as_secs_f64()
+    );
+
+    Ok(WebCheckResult {
+        success: true,
+        balance: Some(balance_num),
+        currency: reading.currency,
+        extraction_strategy: reading.strategy,
+        message: if checkin_note.is_empty() {
+            sync_msg.clone()
+        } else {
+            format!("{checkin_note}; {sync_msg}")
+        },
+        credited,
+        quota_sync_success: Some(sync_ok),
+        quota_sync_message: sync_msg,
+        detected_unit_rate: detected_rate,
+        unit_rate_conflict: rate_conflict,
+        ..Default::default()
+    })
 }
 
-async fn run_login_flow_once(
+async fn click_checkin_button(
     driver: &WebDriver,
-    account: &Account,
-    browser_config: &BrowserConfig,
-) -> Result<WebCheckResult> {
-    let flow_started = Instant::now();
+    profile: &CheckinProfile,
+    waits: WaitCaps,
+) -> Result<CheckinOutcome> {
+    let page_url = profile.page_url.trim();
+    if !page_url.is_empty() {
+        driver
+            .get(page_url)
+            .await
+            .with_context(|| format!("导航到签到页面失败: {page_url}"))?;
+        wait_for_network_idle(driver, waits.network_idle).await?;
+    }
+
+    let started = Instant::now();
+    let clicked = loop {
+        let value = driver
+            .execute(
+                CHECKIN_BUTTON_SCRIPT,
+                vec![
+                    serde_json::json!(profile.button_selector.trim()),
+                    serde_json::json!(profile.button_texts),
+                    serde_json::json!(profile.already_texts),
+                ],
+            )
+            .await?;
+        let state = value.json().as_str().unwrap_or("").to_string();
+        if !state.is_empty() || started.elapsed() >= waits.element {
+            break state;
+        }
+        async_sleep(Duration::from_millis(200)).await;
+    };
+    match clicked.as_str() {
+        "clicked" => {}
+        "already" => {
+            return Ok(CheckinOutcome {
+                already: true,
+                message: "签到按钮显示今日已签到".to_string(),
+                ..Default::default()
+            });
+        }
+        _ => anyhow::bail!("未找到签到按钮"),
+    }
+
+    let started = Instant::now();
+    while started.elapsed() < waits.element {
+        let value = driver
+            .execute(
+                CHECKIN_TOAST_SCRIPT,
+                vec![
+                    serde_json::json!(profile.success_texts),
+                    serde_json::json!(profile.already_texts),
+                ],
+            )
+            .await?;
+        let obj = value.json();
+        let text = obj
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        match obj.get("state").and_then(Value::as_str).unwrap_or("") {
+            "success" => {
+                let credited = parse_first_number(&text);
+                tracing::info!("签到成功: {}", text);
+                return Ok(CheckinOutcome {
+                    already: false,
+                    credited,
+                    message: format!("签到成功: {text}"),
+                });
+            }
+            "already" => {
+                return Ok(CheckinOutcome {
+                    already: true,
+                    message: format!("今日已签到: {text}"),
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+        async_sleep(Duration::from_millis(200)).await;
+    }
+    anyhow::bail!("已点击签到按钮，但未出现签到成功提示")
+}
+
+async fn ensure_logged_in(driver: &WebDriver, account: &Account, waits: WaitCaps) -> Result<()> {
+    driver.get(CONSOLE_URL).await.with_context(|| "导航到控制台失败")?;
+    wait_for_console_or_login(driver, waits).await?;
+
+    let current_url = driver.current_url().await?.to_string();
+    if current_url.contains("/login") {
+        let step_started = Instant::now();
+        wait_for_script(driver, LOGIN_FORM_READY_SCRIPT, waits.element).await?;
+        close_announcement_popup(driver).await?;
+        switch_to_email_login(driver, waits).await?;
+        submit_login(driver, account, waits).await?;
+        driver.get(CONSOLE_URL).await.with_context(|| "登录后导航到控制台失败")?;
+        wait_for_console_or_login(driver, waits).await?;
+        tracing::debug!("[flow] 登录流程耗时={:.1}s", step_started.elapsed().as_secs_f64());
+    }
+
+    let logged_url = driver.current_url().await?.to_string();
+    tracing::info!("[flow] 登录后URL: {}", logged_url);
+    if !logged_url.contains("/console") || logged_url.contains("/login") {
+        if let Some(error_text) = check_login_error_message(driver).await {
+            anyhow::bail!("登录失败: {} (当前URL: {})", error_text, logged_url);
+        }
+        anyhow::bail!("登录失败，当前URL: {logged_url}");
+    }
+    Ok(())
+}
+
+const LOGIN_FORM_READY_SCRIPT: &str = r#"
+    return !!document.querySelector("input[name='username'], button[type='button'] span.semi-icon-mail");
+"#;
+
+const LOGIN_SUBMITTED_SCRIPT: &str = r#"
+    const href = (window.location && window.location.href) || '';
+    if (!href.includes('/login')) return true;
+    const selectors = ['.error-message', '.alert-danger', '.toast-error', '[role="alert"]', '.semi-toast-content'];
+    return selectors.some((selector) => {
+        const node = document.querySelector(selector);
+        return !!node && !!(node.innerText || node.textContent || '').trim();
+    });
+"#;
+
+const CONSOLE_OR_LOGIN_SCRIPT: &str = r#"
+    const href = (window.location && window.location.href) || '';
+    if (href.includes('/login') || document.querySelector("input[name='username']")) return 'login';
+    if (href.includes('/console') && document.readyState === 'complete') return 'console';
+    return '';
+"#;
+
+/// 手动登录时预填用户名，页面结构不符时静默跳过
+const PREFILL_USERNAME_SCRIPT: &str = r#"
+    const input = document.querySelector("input[name='username'], input[type='email']");
+    if (!input) return false;
+    const descriptor = Object.getOwnPropertyDescriptor(window.HTMLInputElement.prototype, 'value');
+    if (descriptor && descriptor.set) descriptor.set.call(input, arguments[0]); else input.value = arguments[0];
+    input.dispatchEvent(new Event('input', { bubbles: true }));
+    return true;
+"#;
+
+const ALREADY_CHECKED_IN_SCRIPT: &str = r#"
+    const texts = arguments[0] || [];
+    const body = document.body ? document.body.innerText : '';
+    const lower = body.toLowerCase();
+    return texts.some((text) => !!text && (body.includes(text) || lower.includes(String(text).toLowerCase())));
+"#;
+
+/// 返回 clicked / already / 空字符串(未找到)
+const CHECKIN_BUTTON_SCRIPT: &str = r#"
+    const selector = arguments[0] || '';
+    const texts = (arguments[1] || []).map((text) => String(text).toLowerCase());
+    const alreadyTexts = (arguments[2] || []).map((text) => String(text).toLowerCase());
+    function normalizeText(text) {
+        return String(text || '').replace(/\s+/g, ' ').trim().toLowerCase();
+    }
+    function isVisible(node) {
+        if (!node) return false;
+        const style = window.getComputedStyle(node);
+        if (style.display === 'none' || style.visibility === 'hidden') return false;
+        const rect = node.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0;
+    }
+    let button = null;
+    if (selector) {
+        button = document.querySelector(selector);
+    } else {
+        const nodes = Array.from(document.querySelectorAll('button, [role="button"], a'));
+        button = nodes.find((node) => {
+            if (!isVisible(node)) return false;
+            const text = normalizeText(node.innerText || node.textContent || '');
+            return texts.some((item) => text === item) ||
+                alreadyTexts.some((item) => item && text.includes(item));
+        }) || nodes.find((node) => {
+            if (!isVisible(node)) return false;
+            const text = normalizeText(node.innerText || node.textContent || '');
+            return text.length <= 12 && texts.some((item) => item && text.includes(item));
+        });
+    }
+    if (!button || !isVisible(button)) return '';
+    const text = normalizeText(button.innerText || button.textContent || '');
+    if (alreadyTexts.some((item) => item && text.includes(item))) return 'already';
+    if (button.disabled || button.getAttribute('aria-disabled') === 'true') return 'already';
+    button.click();
+    return 'clicked';
+"#;
+
+/// 在提示框(没有提示框时为整页)中查找签到成功/已签到文本
+const CHECKIN_TOAST_SCRIPT: &str = r#"
+    const successTexts = arguments[0] || [];
+    const alreadyTexts = arguments[1] || [];
+    const toasts = Array.from(document.querySelectorAll(
+        '.semi-toast, .semi-toast-content, .semi-notification, [role="alert"], [class*="toast"], [class*="message"]'
+    )).map((node) => String(node.innerText || node.textContent || '').trim()).filter((text) => !!text);
+    const candidates = toasts.length ? toasts : [document.body ? document.body.innerText : ''];
+    for (const text of candidates) {
+        const lower = text.toLowerCase();
+        if (successTexts.some((item) => item && lower.includes(String(item).toLowerCase()))) {
+            return { state: 'success', text: text.slice(0, 200) };
+        }
+        if (alreadyTexts.some((item) => item && lower.includes(String(item).toLowerCase()))) {
+            return { state: 'already', text: text.slice(0, 200) };
+        }
+    }
+    return { state: '', text: '' };
+"#;
+
+/// 网页流程各类条件等待的上限
+#[derive(Debug, Clone, Copy)]
+struct WaitCaps {
+    navigation: Duration,
+    element: Duration,
+    network_idle: Duration,
+}
 
-    driver.get(CONSOLE_URL).await.with_context(|| "导航到控制台失败")?;
-    async_sleep(Duration::from_millis(800)).await;
+impl WaitCaps {
+    fn from_config(config: &BrowserConfig) -> Self {
+        Self {
+            navigation: Duration::from_millis(config.navigation_wait_ms.max(500)),
+            element: Duration::from_millis(config.element_wait_ms.max(500)),
+            network_idle: Duration::from_millis(config.network_idle_wait_ms),
+        }
+    }
 
-    let current_url = driver.current_url().await?.to_string();
-    if current_url.contains("/login") {
-        let step_started = Instant::now();
-        async_sleep(Duration::from_millis(500)).await;
-        close_announcement_popup(driver).await?;
-        switch_to_email_login(driver).await?;
-        submit_login(driver, account).await?;
-        driver.get(CONSOLE_URL).await.with_context(|| "登录后导航到控制台失败")?;
-        async_sleep(Duration::from_millis(800)).await;
-        tracing::debug!("[flow] 登录流程耗时={:.1}s", step_started.elapsed().as_secs_f64());
+    /// 单个阶段内可能依次经历的等待上限之和: 页面加载、导航、元素、网络空闲与余额提取
+    fn stage_budget(&self, config: &BrowserConfig) -> Duration {
+        Duration::from_secs(config.page_load_timeout + config.timeout.max(3))
+            + self.navigation
+            + self.element
+            + self.network_idle
     }
+}
 
-    let logged_url = driver.current_url().await?.to_string();
-    tracing::info!("[flow] 登录后URL: {}", logged_url);
-    if !logged_url.contains("/console") || logged_url.contains("/login") {
-        if let Some(error_text) = check_login_error_message(driver).await {
-            anyhow::bail!("登录失败: {} (当前URL: {})", error_text, logged_url);
+/// 轮询执行返回布尔值的脚本，直到为真或超过上限；超时返回 false
+async fn wait_for_script(driver: &WebDriver, script: &str, cap: Duration) -> Result<bool> {
+    let started = Instant::now();
+    loop {
+        let value = driver.execute(script, Vec::<Value>::new()).await?;
+        if value.json().as_bool().unwrap_or(false) {
+            return Ok(true);
         }
-        anyhow::bail!("登录失败，当前URL: {logged_url}");
+        if started.elapsed() >= cap {
+            return Ok(false);
+        }
+        async_sleep(Duration::from_millis(100)).await;
     }
+}
 
-    let step_started = Instant::now();
-    let balance = extract_balance(driver, browser_config.timeout.max(3))
-        .await
-        .with_context(|| "余额提取失败")?;
-    let balance_num =
-        parse_first_number(&balance).with_context(|| format!("余额格式无法解析: {balance}"))?;
-    tracing::debug!("[flow] 余额提取耗时={:.1}s, balance={}", step_started.elapsed().as_secs_f64(), balance);
-
-    let step_started = Instant::now();
-    let sync_msg = match sync_first_apikey_limit(driver, balance_num).await {
-        Ok(msg) => msg,
-        Err(err) => {
-            tracing::warn!("同步首个 API Key 额度失败: {}", err);
-            format!("同步额度失败: {err}")
+/// 等待控制台路由稳定: 已跳转登录页，或控制台页面加载完成且请求静默
+async fn wait_for_console_or_login(driver: &WebDriver, waits: WaitCaps) -> Result<()> {
+    let started = Instant::now();
+    loop {
+        let value = driver
+            .execute(CONSOLE_OR_LOGIN_SCRIPT, Vec::<Value>::new())
+            .await?;
+        match value.json().as_str().unwrap_or("") {
+            "login" => return Ok(()),
+            "console" => {
+                // 前端路由守卫可能在资源加载后才跳转登录页
+                wait_for_network_idle(driver, waits.network_idle).await?;
+                return Ok(());
+            }
+            _ => {}
         }
-    };
-    tracing::debug!("[flow] sync_first_apikey_limit 耗时={:.1}s", step_started.elapsed().as_secs_f64());
-    tracing::debug!("[flow] run_login_flow_once 总耗时={:.1}s", flow_started.elapsed().as_secs_f64());
+        if started.elapsed() >= waits.navigation {
+            tracing::debug!(
+                "[flow] 等待控制台路由稳定超时({}ms)",
+                waits.navigation.as_millis()
+            );
+            return Ok(());
+        }
+        async_sleep(Duration::from_millis(100)).await;
+    }
+}
 
-    Ok(WebCheckResult {
-        success: true,
-        balance: Some(balance_num),
-        message: sync_msg,
-    })
+/// 通过 Performance API 判断网络静默: 资源条目数在 400ms 内不再增长。
+/// WebDriver 协议无法订阅 CDP 网络事件，这里以资源计时条目作为近似。
+async fn wait_for_network_idle(driver: &WebDriver, cap: Duration) -> Result<bool> {
+    if cap.is_zero() {
+        return Ok(true);
+    }
+    let count_script = r#"
+        const entries = (window.performance && performance.getEntriesByType)
+            ? performance.getEntriesByType('resource').length
+            : 0;
+        return document.readyState === 'loading' ? -1 : entries;
+    "#;
+    let quiet_window = Duration::from_millis(400);
+    let started = Instant::now();
+    let mut last_count = i64::MIN;
+    let mut stable_since = Instant::now();
+    loop {
+        let value = driver.execute(count_script, Vec::<Value>::new()).await?;
+        let count = value.json().as_i64().unwrap_or(-1);
+        if count != last_count || count < 0 {
+            last_count = count;
+            stable_since = Instant::now();
+        } else if stable_since.elapsed() >= quiet_window {
+            return Ok(true);
+        }
+        if started.elapsed() >= cap {
+            return Ok(false);
+        }
+        async_sleep(Duration::from_millis(100)).await;
+    }
 }
 
 async fn check_login_error_message(driver: &WebDriver) -> Option<String> {
@@ -241,7 +1939,10 @@ async fn close_announcement_popup(driver: &WebDriver) -> Result<()> {
         const buttons = Array.from(document.querySelectorAll('button'));
         for (const btn of buttons) {
             const text = (btn.textContent || '').trim();
-            if (text.includes('今日关闭') || text.includes('关闭公告') || text.includes('关闭')) {
+            if (
+                text.includes('今日关闭') || text.includes('关闭公告') || text.includes('关闭') ||
+                /^(close|close today|close notice)$/i.test(text)
+            ) {
                 btn.click();
                 return true;
             }
@@ -252,7 +1953,7 @@ async fn close_announcement_popup(driver: &WebDriver) -> Result<()> {
     Ok(())
 }
 
-async fn switch_to_email_login(driver: &WebDriver) -> Result<()> {
+async fn switch_to_email_login(driver: &WebDriver, waits: WaitCaps) -> Result<()> {
     if let Ok(btn) = driver
         .find(By::Css("button[type='button'] span.semi-icon-mail"))
         .await
@@ -260,24 +1961,27 @@ async fn switch_to_email_login(driver: &WebDriver) -> Result<()> {
         let _ = driver
             .execute("arguments[0].click();", vec![btn.to_json()?])
             .await;
-        async_sleep(Duration::from_millis(1500)).await;
+        wait_for_script(
+            driver,
+            "return !!document.querySelector(\"input[name='username']\");",
+            waits.element,
+        )
+        .await?;
     }
     Ok(())
 }
 
-async fn submit_login(driver: &WebDriver, account: &Account) -> Result<()> {
-    let username = driver
+async fn submit_login(driver: &WebDriver, account: &Account, waits: WaitCaps) -> Result<()> {
+    // 用户名与密码输入框并行定位，减少表单准备时间
+    let username_query = driver
         .query(By::Name("username"))
-        .wait(Duration::from_secs(5), Duration::from_millis(200))
-        .first()
-        .await
-        .with_context(|| "未找到用户名输入框")?;
-    let password = driver
+        .wait(waits.element, Duration::from_millis(200));
+    let password_query = driver
         .query(By::Name("password"))
-        .wait(Duration::from_secs(5), Duration::from_millis(200))
-        .first()
-        .await
-        .with_context(|| "未找到密码输入框")?;
+        .wait(waits.element, Duration::from_millis(200));
+    let (username, password) = tokio::join!(username_query.first(), password_query.first());
+    let username = username.with_context(|| "未找到用户名输入框")?;
+    let password = password.with_context(|| "未找到密码输入框")?;
 
     username.clear().await?;
     username.send_keys(&account.username).await?;
@@ -293,27 +1997,62 @@ async fn submit_login(driver: &WebDriver, account: &Account) -> Result<()> {
         .await
         .with_context(|| "点击提交按钮失败")?;
 
-    async_sleep(Duration::from_millis(2000)).await;
+    // 等待跳离登录页或出现错误提示，而不是固定等待
+    wait_for_script(driver, LOGIN_SUBMITTED_SCRIPT, waits.navigation).await?;
     Ok(())
 }
 
-async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
+/// 提取余额文本，同时返回命中的提取策略:
+/// known_selector / xpath_label / large_text / container_text / regex_fallback
+async fn extract_balance(
+    driver: &WebDriver,
+    wait_time: u64,
+    waits: WaitCaps,
+) -> Result<(String, String)> {
     // 等待骨架屏消失(参考Python版BalanceExtractor，确保数据已渲染)
     let skeleton_script = r#"
         return !document.querySelector('.semi-skeleton');
     "#;
-    let skeleton_started = Instant::now();
-    while skeleton_started.elapsed() < Duration::from_secs(10) {
-        match driver.execute(skeleton_script, Vec::<Value>::new()).await {
-            Ok(result) if result.json().as_bool().unwrap_or(false) => break,
-            _ => {}
-        }
-        async_sleep(Duration::from_millis(300)).await;
+    if !wait_for_script(driver, skeleton_script, waits.navigation).await? {
+        tracing::debug!("[extract_balance] 骨架屏等待超时，继续尝试提取");
     }
-    // 骨架屏消失后再等1s确保数据完全渲染
-    async_sleep(Duration::from_millis(1000)).await;
+    // 骨架屏消失后等待请求静默，确保数据完全渲染
+    wait_for_network_idle(driver, waits.network_idle).await?;
 
     let extract_script = r#"
+        // 金额可带 $ ¥ ￥ € £ 前缀或 元 € USD CNY EUR 等后缀，数值支持 1,234.56 与 1.234,56 两种写法；
+        // 负号可在符号前后(-$12、$-12)，透支余额原样保留
+        const moneyPattern = /[-−]?\s*(?:US\$|\$|¥|￥|€|£)\s*[-−]?\d[\d.,]*|[-−]?\d[\d.,]*\s*(?:元|¥|￥|€|£|USD|CNY|RMB|EUR|GBP)/i;
+        const plainPattern = /[-−]?\d[\d.,]*/;
+        const balanceTexts = ['当前余额', 'Current Balance', '余额', 'Balance', 'Crédit', 'Credit', 'Solde', 'Saldo'];
+
+        function parseAmount(raw) {
+            const text = String(raw || '');
+            const sign = /[-−]/.test(text.split(/\d/)[0]) ? -1 : 1;
+            let s = text.replace(/[^\d.,]/g, '').replace(/[.,]+$/, '');
+            const comma = s.lastIndexOf(',');
+            const dot = s.lastIndexOf('.');
+            if (comma >= 0 && dot >= 0) {
+                s = comma > dot ? s.replace(/\./g, '').replace(',', '.') : s.replace(/,/g, '');
+            } else if (comma >= 0) {
+                const single = s.indexOf(',') === comma;
+                s = single && s.length - comma - 1 !== 3 ? s.replace(',', '.') : s.replace(/,/g, '');
+            } else if (s.indexOf('.') !== dot) {
+                s = s.replace(/\./g, '');
+            }
+            return sign * parseFloat(s);
+        }
+
+        // 返回文本中的金额原文，allowPlain 为 true 时接受不带货币符号的数字(仅用于标签旁的文本)
+        function findMoney(text, allowPlain) {
+            const value = String(text || '');
+            const match = value.match(moneyPattern) || (allowPlain ? value.match(plainPattern) : null);
+            if (!match) return '';
+            const amount = parseAmount(match[0]);
+            // 0 多为数据未加载完时的占位，继续等待
+            return Number.isFinite(amount) && amount !== 0 ? match[0].trim() : '';
+        }
+
         function extractBalance() {
             const knownSelectors = [
                 '.balance-amount',
@@ -330,20 +2069,12 @@ async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
                 try {
                     const elems = document.querySelectorAll(selector);
                     for (const elem of elems) {
-                        const text = String(elem.textContent || '');
-                        if (!text.includes('$')) continue;
-                        const match = text.match(/\$([\d,]+\.?\d*)/);
-                        if (match) {
-                            const value = parseFloat(String(match[1] || '').replace(/,/g, ''));
-                            if (Number.isFinite(value) && value > 0) {
-                                return '$' + value.toFixed(1);
-                            }
-                        }
+                        const found = findMoney(elem.textContent, false);
+                        if (found) return { text: found, strategy: 'known_selector' };
                     }
                 } catch (e) {}
             }
 
-            const balanceTexts = ['当前余额', 'Current Balance', '余额', 'Balance'];
             for (const key of balanceTexts) {
                 try {
                     const xpath = `//*[contains(text(), '${key}')]`;
@@ -361,25 +2092,17 @@ async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
                     if (parent) {
                         const siblings = Array.from(parent.children);
                         for (const item of siblings) {
-                            const m = String(item.textContent || '').match(/\$([\d,]+\.?\d*)/);
-                            if (m) {
-                                const value = parseFloat(String(m[1] || '').replace(/,/g, ''));
-                                if (Number.isFinite(value) && value > 0) {
-                                    return '$' + value.toFixed(1);
-                                }
-                            }
-                        }
-                        const p = String(parent.textContent || '').match(/\$([\d,]+\.?\d*)/);
-                        if (p) {
-                            const value = parseFloat(String(p[1] || '').replace(/,/g, ''));
-                            if (Number.isFinite(value) && value > 0) {
-                                return '$' + value.toFixed(1);
-                            }
+                            const found = findMoney(item.textContent, false);
+                            if (found) return { text: found, strategy: 'xpath_label' };
                         }
+                        const labelled = String(parent.textContent || '').split(key).slice(1).join(key);
+                        const found = findMoney(parent.textContent, false) || findMoney(labelled, true);
+                        if (found) return { text: found, strategy: 'xpath_label' };
                     }
                 } catch (e) {}
             }
 
+            const standalone = /^(?:[-−]?\s*(?:US\$|\$|¥|￥|€|£)\s*[-−]?\d[\d.,\s]*|[-−]?\d[\d.,\s]*\s*(?:元|¥|￥|€|£))$/;
             const largeTextSelectors = [
                 '.text-lg', '.text-xl', '.text-2xl', '.text-3xl',
                 'h1', 'h2', 'h3',
@@ -389,11 +2112,9 @@ async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
                 const elems = document.querySelectorAll(selector);
                 for (const elem of elems) {
                     const text = String(elem.textContent || '').trim();
-                    if (!/^\$\s*[\d,]+\.?\d*$/.test(text)) continue;
-                    const value = parseFloat(text.replace(/[$,\s]/g, ''));
-                    if (Number.isFinite(value) && value > 0) {
-                        return '$' + value.toFixed(1);
-                    }
+                    if (!standalone.test(text)) continue;
+                    const found = findMoney(text, false);
+                    if (found) return { text: found, strategy: 'large_text' };
                 }
             }
 
@@ -408,30 +2129,24 @@ async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
                 for (const node of nodes) {
                     const text = String(node.textContent || '').trim();
                     if (node.childElementCount !== 0) continue;
-                    if (!/^\$\s*[\d,]+\.?\d*$/.test(text)) continue;
-                    const value = parseFloat(text.replace(/[$,\s]/g, ''));
-                    if (Number.isFinite(value) && value > 0) {
-                        return '$' + value.toFixed(1);
-                    }
+                    if (!standalone.test(text)) continue;
+                    const found = findMoney(text, false);
+                    if (found) return { text: found, strategy: 'container_text' };
                 }
             }
 
             const bodyText = (document.body && document.body.innerText) ? document.body.innerText : '';
-            const patterns = [
-                /当前余额[：:\s]*\$([\d,]+\.?\d*)/,
-                /余额[：:\s]*\$([\d,]+\.?\d*)/,
-                /Balance[：:\s]*\$([\d,]+\.?\d*)/i
-            ];
-            for (const pattern of patterns) {
-                const match = bodyText.match(pattern);
-                if (match) {
-                    const value = parseFloat(String(match[1] || '').replace(/,/g, ''));
-                    if (Number.isFinite(value) && value > 0) {
-                        return '$' + value.toFixed(1);
-                    }
-                }
+            for (const key of balanceTexts) {
+                const index = bodyText.toLowerCase().indexOf(key.toLowerCase());
+                if (index < 0) continue;
+                const tail = bodyText.substring(index + key.length, index + key.length + 40);
+                const line = tail.split('\n').find((item) => item.trim() && !/^[：:\s]*$/.test(item)) || '';
+                // 标签后紧跟金额才采用，避免把 "Balance history" 之类后面的日期当作余额
+                if (!/^[：:\s]*[-−]?\s*(?:US\$|\$|¥|￥|€|£)?\s*[-−]?\d/.test(line)) continue;
+                const found = findMoney(line, true);
+                if (found) return { text: found, strategy: 'regex_fallback' };
             }
-            return '';
+            return null;
         }
 
         return extractBalance();
@@ -442,9 +2157,20 @@ async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
     let started = Instant::now();
     loop {
         let result = driver.execute(extract_script, Vec::<Value>::new()).await?;
-        let text = result.json().as_str().unwrap_or("").trim().to_string();
+        let found = result.json();
+        let text = found
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .trim()
+            .to_string();
         if !text.is_empty() {
-            return Ok(text);
+            let strategy = found
+                .get("strategy")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            return Ok((text, strategy));
         }
         if started.elapsed() >= timeout {
             break;
@@ -469,44 +2195,479 @@ async fn extract_balance(driver: &WebDriver, wait_time: u64) -> Result<String> {
     anyhow::bail!("未提取到余额文本")
 }
 
-async fn sync_first_apikey_limit(driver: &WebDriver, balance: f64) -> Result<String> {
+const QUOTA_STRATEGY_MODAL: &str = "modal";
+const QUOTA_STRATEGY_KEYBOARD: &str = "keyboard";
+const QUOTA_STRATEGY_API: &str = "api";
+
+/// 依次尝试: 编辑弹窗填写 -> 键盘定位输入框 -> 直接调用站点接口；全部失败时跳过并返回报告。
+/// selected 为 (名称, Key) 时弹窗只能定位首行，改为只经站点接口按 Key 匹配令牌
+async fn sync_apikey_limit(
+    driver: &WebDriver,
+    balance: f64,
+    waits: WaitCaps,
+    selected: Option<(&str, &str)>,
+    guard: &QuotaGuardConfig,
+) -> QuotaSyncReport {
     let total_started = Instant::now();
+    let mut report = QuotaSyncReport {
+        target: match selected {
+            Some((name, _)) => format!("API Key[{name}]"),
+            None => "首个 API Key".to_string(),
+        },
+        balance,
+        unit_rate: known_unit_rate(QUOTA_PROVIDER).unwrap_or(QUOTA_UNIT_PER_DOLLAR),
+        ..Default::default()
+    };
+    if let Some((_, key)) = selected {
+        report.target_quota = (balance * report.unit_rate).round().max(0.0) as i64;
+        if let Some(reason) = guard_quota_write(driver, guard, &report, Some(key)).await {
+            report.guard_rejection = Some(reason);
+            return report;
+        }
+        match set_quota_via_api(driver, report.target_quota, Some(key)).await {
+            Ok(()) => report.applied_by = Some(QUOTA_STRATEGY_API.to_string()),
+            Err(err) => report.failures.push(QuotaStrategyFailure {
+                strategy: QUOTA_STRATEGY_API.to_string(),
+                error: err.to_string(),
+            }),
+        }
+        return report;
+    }
 
     let step_started = Instant::now();
-    open_apikey_page(driver).await?;
-    tracing::debug!("[sync_quota] open_apikey_page 耗时={:.1}s", step_started.elapsed().as_secs_f64());
+    let editor = open_quota_editor(driver, waits).await;
+    let known = known_unit_rate(QUOTA_PROVIDER);
+    match (&editor, known) {
+        (Ok(Some(detected)), Some(known))
+            if (detected - known).abs() / known > UNIT_RATE_MAX_DEVIATION =>
+        {
+            let conflict =
+                format!("识别到的额度比例 {detected} 与已记录的 {known} 相差过大，可能解析有误");
+            tracing::warn!("[sync_quota] {}", conflict);
+            report.detected_rate = Some(*detected);
+            report.rate_conflict = Some(conflict);
+            return report;
+        }
+        (Ok(Some(detected)), _) => {
+            report.unit_rate = *detected;
+            report.detected_rate = Some(*detected);
+        }
+        _ if known.is_none() => {
+            tracing::warn!(
+                "[sync_quota] 未识别到额度比例且没有已记录的比例，使用默认值 {}",
+                QUOTA_UNIT_PER_DOLLAR
+            );
+        }
+        _ => {}
+    }
+    tracing::debug!(
+        "[sync_quota] open_quota_editor 耗时={:.1}s, rate={}",
+        step_started.elapsed().as_secs_f64(),
+        report.unit_rate
+    );
+    report.target_quota = (balance * report.unit_rate).round().max(0.0) as i64;
+    if let Some(reason) = guard_quota_write(driver, guard, &report, None).await {
+        report.guard_rejection = Some(reason);
+        return report;
+    }
 
-    let step_started = Instant::now();
-    open_first_token_editor(driver).await?;
-    tracing::debug!("[sync_quota] open_first_token_editor 耗时={:.1}s", step_started.elapsed().as_secs_f64());
+    for strategy in [
+        QUOTA_STRATEGY_MODAL,
+        QUOTA_STRATEGY_KEYBOARD,
+        QUOTA_STRATEGY_API,
+    ] {
+        let step_started = Instant::now();
+        let outcome = match (strategy, &editor) {
+            (QUOTA_STRATEGY_API, _) => set_quota_via_api(driver, report.target_quota, None).await,
+            (_, Err(err)) => Err(anyhow::anyhow!("编辑弹窗未打开: {err}")),
+            (QUOTA_STRATEGY_MODAL, Ok(_)) => {
+                match set_modal_quota_value(driver, report.target_quota).await {
+                    Ok(()) => submit_quota_modal(driver).await,
+                    Err(err) => Err(err),
+                }
+            }
+            (_, Ok(_)) => set_quota_via_keyboard(driver, report.target_quota).await,
+        };
+        tracing::debug!(
+            "[sync_quota] 策略 {} 耗时={:.1}s",
+            strategy,
+            step_started.elapsed().as_secs_f64()
+        );
+        match outcome {
+            Ok(()) => {
+                report.applied_by = Some(strategy.to_string());
+                break;
+            }
+            Err(err) => {
+                tracing::debug!("[sync_quota] 策略 {} 失败: {}", strategy, err);
+                report.failures.push(QuotaStrategyFailure {
+                    strategy: strategy.to_string(),
+                    error: err.to_string(),
+                });
+            }
+        }
+    }
 
-    let step_started = Instant::now();
-    let unit_rate = detect_quota_unit_rate(driver)
-        .await
-        .unwrap_or(QUOTA_UNIT_PER_DOLLAR);
-    tracing::debug!("[sync_quota] detect_quota_unit_rate 耗时={:.1}s, rate={}", step_started.elapsed().as_secs_f64(), unit_rate);
+    tracing::debug!("[sync_quota] 总耗时={:.1}s", total_started.elapsed().as_secs_f64());
+    report
+}
 
-    let target_quota = (balance * unit_rate).round().max(0.0) as i64;
+/// 写入前读取目标令牌的当前额度并检查边界，返回拒绝原因；读取失败时只检查上下限
+async fn guard_quota_write(
+    driver: &WebDriver,
+    guard: &QuotaGuardConfig,
+    report: &QuotaSyncReport,
+    key: Option<&str>,
+) -> Option<String> {
+    if !guard.enabled {
+        return None;
+    }
+    let tokens = match fetch_token_quotas(driver).await {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            tracing::debug!(
+                "[sync_quota] 读取令牌当前额度失败，跳过变化幅度检查: {}",
+                err
+            );
+            Vec::new()
+        }
+    };
+    let current = target_token_index(&tokens, key).map(|index| &tokens[index]);
+    let rejection = check_quota_guard(guard, report.balance, report.unit_rate, current);
+    if let Some(reason) = &rejection {
+        tracing::warn!("[sync_quota] 拒绝写入 {}: {}", report.target, reason);
+    }
+    rejection
+}
 
-    let step_started = Instant::now();
-    set_modal_quota_value(driver, target_quota).await?;
-    tracing::debug!("[sync_quota] set_modal_quota_value 耗时={:.1}s", step_started.elapsed().as_secs_f64());
+/// 指定 key 时按 Key 匹配令牌，否则为首个令牌
+fn target_token_index(tokens: &[QuotaPreviewToken], key: Option<&str>) -> Option<usize> {
+    match key {
+        Some(key) => tokens
+            .iter()
+            .position(|item| token_key_matches(&item.key, key)),
+        None => (!tokens.is_empty()).then_some(0),
+    }
+}
 
-    let step_started = Instant::now();
-    submit_quota_modal(driver).await?;
-    tracing::debug!("[sync_quota] submit_quota_modal 耗时={:.1}s", step_started.elapsed().as_secs_f64());
+/// 按 web_check.quota_guard 检查目标金额，超出边界时返回原因。
+/// 当前额度较大而目标接近清零时多半是余额提取出错，按降幅拦截
+fn check_quota_guard(
+    guard: &QuotaGuardConfig,
+    amount: f64,
+    unit_rate: f64,
+    current: Option<&QuotaPreviewToken>,
+) -> Option<String> {
+    if !guard.enabled {
+        return None;
+    }
+    if guard.min_amount > 0.0 && amount < guard.min_amount {
+        return Some(format!(
+            "目标额度 ${amount:.2} 低于下限 ${:.2}",
+            guard.min_amount
+        ));
+    }
+    if guard.max_amount > 0.0 && amount > guard.max_amount {
+        return Some(format!(
+            "目标额度 ${amount:.2} 超过上限 ${:.2}",
+            guard.max_amount
+        ));
+    }
+    let current = current.filter(|item| !item.unlimited)?;
+    if guard.max_change_ratio <= 0.0 || unit_rate <= 0.0 {
+        return None;
+    }
+    let current_amount = current.remain_quota as f64 / unit_rate;
+    if current_amount < guard.protect_above.max(f64::EPSILON) {
+        return None;
+    }
+    let drop = (current_amount - amount) / current_amount;
+    (drop > guard.max_change_ratio).then(|| {
+        format!(
+            "令牌当前额度 ${current_amount:.2}，目标 ${amount:.2}，降幅 {:.0}% 超过上限 {:.0}%，可能是余额提取异常",
+            drop * 100.0,
+            guard.max_change_ratio * 100.0
+        )
+    })
+}
 
-    tracing::debug!("[sync_quota] 总耗时={:.1}s", total_started.elapsed().as_secs_f64());
+/// 打开 API令牌 页面与首个令牌的编辑弹窗，返回识别到的额度换算比例，未识别时为空
+async fn open_quota_editor(driver: &WebDriver, waits: WaitCaps) -> Result<Option<f64>> {
+    open_apikey_page(driver, waits).await?;
+    open_first_token_editor(driver, waits).await?;
+    Ok(detect_quota_unit_rate(driver).await.ok())
+}
 
-    Ok(format!(
-        "首个 API Key 额度已同步: 余额=${:.2}, 额度值={}, 比例={:.2}",
-        balance, target_quota, unit_rate
-    ))
+/// 从弹窗第一个输入框开始按 Tab 逐个检查，定位到额度输入框后以真实按键写入
+async fn set_quota_via_keyboard(driver: &WebDriver, quota_value: i64) -> Result<()> {
+    let focus_script = r#"
+        function isVisible(node) {
+            if (!node) return false;
+            const style = window.getComputedStyle(node);
+            if (style.display === 'none' || style.visibility === 'hidden') return false;
+            const rect = node.getBoundingClientRect();
+            return rect.width > 0 && rect.height > 0;
+        }
+        const roots = Array.from(document.querySelectorAll('[role="dialog"], [class*="modal"], [class*="sidesheet"], [class*="sideSheet"]'))
+            .filter((item) => isVisible(item));
+        const root = roots[0] || document.body;
+        const input = Array.from(root.querySelectorAll('input, textarea')).find((item) => isVisible(item) && !item.disabled);
+        if (!input) return false;
+        input.focus();
+        return document.activeElement === input;
+    "#;
+    let is_quota_script = r#"
+        const input = arguments[0];
+        if (!input || !/^(input|textarea)$/i.test(input.tagName || '')) return false;
+        const attrs = [
+            input.getAttribute('placeholder') || '',
+            input.getAttribute('name') || '',
+            input.getAttribute('id') || '',
+            input.getAttribute('aria-label') || ''
+        ].join(' ').toLowerCase();
+        if (attrs.includes('额度') || attrs.includes('quota')) return true;
+        let node = input.parentElement;
+        for (let i = 0; i < 5 && node; i += 1) {
+            const label = node.querySelector('label, [class*="label"]');
+            const text = label ? String(label.textContent || '').replace(/\s+/g, ' ').trim().toLowerCase() : '';
+            if (text === '额度' || text === 'quota' || text.startsWith('额度') || text.startsWith('quota')) return true;
+            node = node.parentElement;
+        }
+        return false;
+    "#;
+
+    let focused = driver.execute(focus_script, Vec::<Value>::new()).await?;
+    if !focused.json().as_bool().unwrap_or(false) {
+        anyhow::bail!("弹窗内没有可聚焦的输入框");
+    }
+    for _ in 0..12 {
+        let active = driver.active_element().await?;
+        let matched = driver
+            .execute(is_quota_script, vec![active.to_json()?])
+            .await?;
+        if matched.json().as_bool().unwrap_or(false) {
+            active.send_keys(Key::Control + "a").await?;
+            active.send_keys(Key::Backspace).await?;
+            active.send_keys(quota_value.to_string()).await?;
+            let written = active.prop("value").await?.unwrap_or_default();
+            let digits: String = written.chars().filter(char::is_ascii_digit).collect();
+            if digits != quota_value.to_string() {
+                anyhow::bail!("键盘写入后额度校验失败: {written}");
+            }
+            return submit_quota_modal(driver).await;
+        }
+        active.send_keys(Key::Tab).await?;
+    }
+    anyhow::bail!("按 Tab 未定位到额度输入框")
+}
+
+/// 绕过页面，直接以当前登录会话调用站点令牌接口修改令牌的剩余额度。
+/// 指定 key 时修改与之匹配的令牌(列表中的 Key 可能带 sk- 前缀或被部分打码)，否则修改首个令牌
+async fn set_quota_via_api(driver: &WebDriver, quota_value: i64, key: Option<&str>) -> Result<()> {
+    let script = r#"
+        const done = arguments[arguments.length - 1];
+        const target = arguments[0];
+        const wanted = String(arguments[1] || '').replace(/^sk-/, '');
+        function keyMatches(raw) {
+            const value = String(raw || '').replace(/^sk-/, '');
+            if (!value || !wanted) return false;
+            if (!value.includes('*')) return value === wanted;
+            const parts = value.split(/\*+/);
+            return wanted.startsWith(parts[0]) && wanted.endsWith(parts[parts.length - 1]);
+        }
+        (async () => {
+            let userId = '';
+            try {
+                const user = JSON.parse(localStorage.getItem('user') || '{}');
+                userId = String(user.id || '');
+            } catch (e) {}
+            const headers = { 'Content-Type': 'application/json' };
+            if (userId) headers['New-Api-User'] = userId;
+            const size = wanted ? 100 : 10;
+            const listResp = await fetch('/api/token/?p=0&size=' + size, { credentials: 'include', headers });
+            const list = await listResp.json();
+            if (!list.success) {
+                return done({ ok: false, reason: 'list_failed: ' + (list.message || listResp.status) });
+            }
+            const items = Array.isArray(list.data) ? list.data : ((list.data && list.data.items) || []);
+            const matched = wanted ? items.find((item) => keyMatches(item.key)) : items[0];
+            if (!matched) {
+                return done({ ok: false, reason: wanted ? 'key_not_found' : 'no_token' });
+            }
+            const token = Object.assign({}, matched, { remain_quota: target, unlimited_quota: false });
+            const resp = await fetch('/api/token/', {
+                method: 'PUT',
+                credentials: 'include',
+                headers,
+                body: JSON.stringify(token)
+            });
+            const data = await resp.json();
+            done({ ok: !!data.success, reason: data.message || String(resp.status) });
+        })().catch((e) => done({ ok: false, reason: String(e) }));
+    "#;
+    let value = driver
+        .execute_async(
+            script,
+            vec![Value::from(quota_value), Value::from(key.unwrap_or_default())],
+        )
+        .await?;
+    let result = value.json();
+    if result.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+        return Ok(());
+    }
+    let reason = result
+        .get("reason")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown");
+    anyhow::bail!("接口修改额度失败: {}", reason)
+}
+
+/// 与 set_quota_via_api 中的脚本一致: 忽略 sk- 前缀，打码的 Key 按首尾比对
+fn token_key_matches(raw: &str, wanted: &str) -> bool {
+    let value = raw.trim().trim_start_matches("sk-");
+    let wanted = wanted.trim().trim_start_matches("sk-");
+    if value.is_empty() || wanted.is_empty() {
+        return false;
+    }
+    if !value.contains('*') {
+        return value == wanted;
+    }
+    let head = value.split('*').next().unwrap_or("");
+    let tail = value.rsplit('*').next().unwrap_or("");
+    wanted.starts_with(head) && wanted.ends_with(tail)
+}
+
+/// 通过站点接口读取令牌及其剩余额度
+async fn fetch_token_quotas(driver: &WebDriver) -> Result<Vec<QuotaPreviewToken>> {
+    let script = r#"
+        const done = arguments[arguments.length - 1];
+        (async () => {
+            let userId = '';
+            try {
+                const user = JSON.parse(localStorage.getItem('user') || '{}');
+                userId = String(user.id || '');
+            } catch (e) {}
+            const headers = { 'Content-Type': 'application/json' };
+            if (userId) headers['New-Api-User'] = userId;
+            const resp = await fetch('/api/token/?p=0&size=100', { credentials: 'include', headers });
+            const list = await resp.json();
+            if (!list.success) {
+                return done({ ok: false, reason: list.message || String(resp.status) });
+            }
+            const items = Array.isArray(list.data) ? list.data : ((list.data && list.data.items) || []);
+            done({ ok: true, items: items.map((item) => ({
+                name: String(item.name || ''),
+                key: String(item.key || ''),
+                remain_quota: Number(item.remain_quota || 0),
+                unlimited: !!item.unlimited_quota
+            })) });
+        })().catch((e) => done({ ok: false, reason: String(e) }));
+    "#;
+    let value = driver.execute_async(script, Vec::<Value>::new()).await?;
+    let result = value.json();
+    if !result.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+        let reason = result
+            .get("reason")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        anyhow::bail!("读取令牌列表失败: {}", reason);
+    }
+    let items = result
+        .get("items")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    Ok(items
+        .iter()
+        .map(|item| QuotaPreviewToken {
+            name: item
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string(),
+            key: item
+                .get("key")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .to_string(),
+            remain_quota: item
+                .get("remain_quota")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0) as i64,
+            unlimited: item
+                .get("unlimited")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            selected: false,
+        })
+        .collect())
+}
+
+/// 通过站点管理接口读取用户列表(需要管理员权限)
+async fn fetch_site_users(driver: &WebDriver) -> Result<Vec<DiscoveredUser>> {
+    let script = r#"
+        const done = arguments[arguments.length - 1];
+        (async () => {
+            let userId = '';
+            try {
+                const user = JSON.parse(localStorage.getItem('user') || '{}');
+                userId = String(user.id || '');
+            } catch (e) {}
+            const headers = { 'Content-Type': 'application/json' };
+            if (userId) headers['New-Api-User'] = userId;
+            const resp = await fetch('/api/user/?p=0&size=100', { credentials: 'include', headers });
+            const list = await resp.json();
+            if (!list.success) {
+                return done({ ok: false, reason: list.message || String(resp.status) });
+            }
+            const items = Array.isArray(list.data) ? list.data : ((list.data && list.data.items) || []);
+            done({ ok: true, items: items.map((item) => ({
+                username: String(item.username || ''),
+                display_name: String(item.display_name || ''),
+                email: String(item.email || ''),
+                quota: Number(item.quota || 0),
+                enabled: Number(item.status || 1) === 1
+            })) });
+        })().catch((e) => done({ ok: false, reason: String(e) }));
+    "#;
+    let value = driver.execute_async(script, Vec::<Value>::new()).await?;
+    let result = value.json();
+    if !result.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+        let reason = result
+            .get("reason")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        anyhow::bail!("读取用户列表失败: {}", reason);
+    }
+    let text = |item: &Value, key: &str| {
+        item.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string()
+    };
+    Ok(result
+        .get("items")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| DiscoveredUser {
+                    username: text(item, "username"),
+                    display_name: text(item, "display_name"),
+                    email: text(item, "email"),
+                    quota: item.get("quota").and_then(Value::as_f64).unwrap_or(0.0) as i64,
+                    enabled: item.get("enabled").and_then(Value::as_bool).unwrap_or(true),
+                    exists: false,
+                })
+                .filter(|item| !item.username.is_empty())
+                .collect()
+        })
+        .unwrap_or_default())
 }
 
-async fn open_apikey_page(driver: &WebDriver) -> Result<()> {
+async fn open_apikey_page(driver: &WebDriver, waits: WaitCaps) -> Result<()> {
     let click_menu_script = r#"
-        const xpath = "//*[self::a or self::button or self::span or self::div][normalize-space(text())='API令牌']";
+        const xpath = "//*[self::a or self::button or self::span or self::div][normalize-space(text())='API令牌' or normalize-space(text())='API Tokens' or normalize-space(text())='Tokens']";
         const node = document.evaluate(
             xpath,
             document,
@@ -548,13 +2709,14 @@ async fn open_apikey_page(driver: &WebDriver) -> Result<()> {
     {
         tracing::debug!("未找到左侧 API令牌 菜单，回退直达 token 页面");
         driver.get("https://anyrouter.top/console/token").await?;
-        async_sleep(Duration::from_millis(1200)).await;
+        wait_for_network_idle(driver, waits.network_idle).await?;
     }
 
     let wait_loaded_script = r#"
         const text = document.body && document.body.innerText ? document.body.innerText : '';
         const onTokenPage = (window.location && window.location.href || '').includes('/console/token');
-        return text.includes('添加令牌') || text.includes('复制所选令牌到剪贴板') || onTokenPage;
+        return text.includes('添加令牌') || text.includes('复制所选令牌到剪贴板') ||
+            text.includes('Add Token') || text.includes('Copy Selected') || onTokenPage;
     "#;
     let started = Instant::now();
     while started.elapsed() < Duration::from_secs(8) {
@@ -569,7 +2731,69 @@ async fn open_apikey_page(driver: &WebDriver) -> Result<()> {
     anyhow::bail!("API令牌 页面未加载完成")
 }
 
-async fn open_first_token_editor(driver: &WebDriver) -> Result<()> {
+async fn list_token_rows(driver: &WebDriver) -> Result<Vec<WebToken>> {
+    let script = r#"
+        function normalizeText(text) {
+            return String(text || '').replace(/\s+/g, ' ').trim();
+        }
+        const text = normalizeText(document.body ? document.body.innerText : '');
+        const empty = text.includes('暂无数据') || text.toLowerCase().includes('no data');
+        const rows = Array.from(document.querySelectorAll('tbody tr')).filter((row) => {
+            return row.querySelectorAll('td').length >= 2;
+        });
+        const items = rows.map((row) => {
+            const cells = Array.from(row.querySelectorAll('td')).map((td) => normalizeText(td.innerText || td.textContent || ''));
+            const status = cells.find((cell) => (
+                cell.includes('启用') || cell.includes('禁用') || cell.includes('过期') || cell.includes('耗尽') ||
+                /^(enabled|disabled|expired|exhausted)$/i.test(cell)
+            )) || '';
+            return { name: cells[0] || '', status: status, cells: cells };
+        }).filter((item) => !!item.name);
+        return { ready: items.length > 0 || empty, items: items };
+    "#;
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(8) {
+        let value = driver.execute(script, Vec::<Value>::new()).await?;
+        let obj = value.json();
+        if obj.get("ready").and_then(Value::as_bool).unwrap_or(false) {
+            let items = obj
+                .get("items")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            return Ok(items
+                .iter()
+                .map(|item| WebToken {
+                    name: item
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or("")
+                        .to_string(),
+                    status: item
+                        .get("status")
+                        .and_then(Value::as_str)
+                        .unwrap_or("")
+                        .to_string(),
+                    cells: item
+                        .get("cells")
+                        .and_then(Value::as_array)
+                        .map(|cells| {
+                            cells
+                                .iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                })
+                .collect());
+        }
+        async_sleep(Duration::from_millis(200)).await;
+    }
+    anyhow::bail!("API令牌 列表未加载完成")
+}
+
+async fn open_first_token_editor(driver: &WebDriver, waits: WaitCaps) -> Result<()> {
     // 检测编辑弹窗是否已打开的脚本
     let editor_open_script = r#"
         function isVisible(node) {
@@ -582,19 +2806,21 @@ async fn open_first_token_editor(driver: &WebDriver) -> Result<()> {
         const hasEditorHeader = Array.from(document.querySelectorAll('*')).some((node) => {
             if (!isVisible(node)) return false;
             const text = (node.textContent || '').trim();
-            return text.includes('更新令牌信息') || text.includes('额度设置') || text.includes('编辑令牌');
+            return text.includes('更新令牌信息') || text.includes('额度设置') || text.includes('编辑令牌') ||
+                /Update Token|Edit Token|Quota Settings/i.test(text);
         });
 
         const hasQuotaLabel = Array.from(document.querySelectorAll('*')).some((node) => {
             if (!isVisible(node)) return false;
-            return (node.textContent || '').trim() === '额度';
+            const text = (node.textContent || '').trim();
+            return text === '额度' || text.toLowerCase() === 'quota';
         });
 
         const hasSubmit = Array.from(document.querySelectorAll('button, [role="button"]')).some((btn) => {
             if (!isVisible(btn)) return false;
             if (btn.disabled) return false;
             const text = (btn.innerText || btn.textContent || '').trim();
-            return text.includes('提交');
+            return text.includes('提交') || text.toLowerCase().includes('submit');
         });
 
         return hasEditorHeader || (hasQuotaLabel && hasSubmit);
@@ -861,7 +3087,7 @@ async fn open_first_token_editor(driver: &WebDriver) -> Result<()> {
             if let Err(err) = driver.refresh().await {
                 tracing::debug!("刷新令牌页失败: {}", err);
             }
-            async_sleep(Duration::from_millis(1200)).await;
+            wait_for_network_idle(driver, waits.network_idle).await?;
         }
     }
     if !has_token_row {
@@ -914,7 +3140,8 @@ async fn detect_quota_unit_rate(driver: &WebDriver) -> Result<f64> {
             '.semi-modal-content, .semi-modal, .semi-sidesheet, .semi-sidesheet-content, .semi-sideSheet, [class*="sidesheet"], [class*="sideSheet"], [role="dialog"]'
         ));
         let root = roots.find((item) => item && isVisible(item) && (
-            (item.innerText || '').includes('更新令牌信息') || (item.innerText || '').includes('额度设置')
+            (item.innerText || '').includes('更新令牌信息') || (item.innerText || '').includes('额度设置') ||
+            /Update Token|Edit Token|Quota Settings/i.test(item.innerText || '')
         ));
         if (!root) {
             root = roots.find((item) => item && isVisible(item));
@@ -923,12 +3150,12 @@ async fn detect_quota_unit_rate(driver: &WebDriver) -> Result<f64> {
             root = document.body;
         }
         const text = root.innerText || '';
-        const amountMatch = text.match(/等价金额[:：]\s*\$\s*(-?[\d,.]+)/);
+        const amountMatch = text.match(/(?:等价金额|Equivalent Amount)[:：]\s*\$\s*(-?[\d,.]+)/i);
         const amountValue = amountMatch ? Number((amountMatch[1] || '').replace(/,/g, '')) : null;
 
         const labels = Array.from(root.querySelectorAll('*')).filter((el) => {
             const t = (el.textContent || '').trim();
-            return t === '额度';
+            return t === '额度' || t.toLowerCase() === 'quota';
         });
         function findInput(startNode) {
             let node = startNode;
@@ -993,7 +3220,8 @@ async fn set_modal_quota_value(driver: &WebDriver, quota_value: i64) -> Result<(
                 '.semi-modal-content, .semi-modal, .semi-sidesheet, .semi-sidesheet-content, .semi-sideSheet, [class*="sidesheet"], [class*="sideSheet"], [role="dialog"]'
             ));
             let root = roots.find((item) => item && isVisible(item) && (
-                (item.innerText || '').includes('更新令牌信息') || (item.innerText || '').includes('额度设置')
+                (item.innerText || '').includes('更新令牌信息') || (item.innerText || '').includes('额度设置') ||
+            /Update Token|Edit Token|Quota Settings/i.test(item.innerText || '')
             ));
             if (!root) {
                 root = roots.find((item) => item && isVisible(item));
@@ -1017,7 +3245,8 @@ async fn set_modal_quota_value(driver: &WebDriver, quota_value: i64) -> Result<(
         function collectCandidates(root) {
             const list = [];
             const labels = Array.from(root.querySelectorAll('*')).filter((el) => {
-                return normalizeText(el.textContent || '') === '额度';
+                const text = normalizeText(el.textContent || '');
+                return text === '额度' || text.toLowerCase() === 'quota';
             });
             for (const label of labels) {
                 let node = label;
@@ -1149,7 +3378,8 @@ async fn submit_quota_modal(driver: &WebDriver) -> Result<()> {
             '.semi-modal-content, .semi-modal, .semi-sidesheet, .semi-sidesheet-content, .semi-sideSheet, [class*="sidesheet"], [class*="sideSheet"], [role="dialog"]'
         ));
         let root = roots.find((item) => item && isVisible(item) && (
-            (item.innerText || '').includes('更新令牌信息') || (item.innerText || '').includes('额度设置')
+            (item.innerText || '').includes('更新令牌信息') || (item.innerText || '').includes('额度设置') ||
+            /Update Token|Edit Token|Quota Settings/i.test(item.innerText || '')
         ));
         if (!root) {
             root = roots.find((item) => item && isVisible(item));
@@ -1159,7 +3389,7 @@ async fn submit_quota_modal(driver: &WebDriver) -> Result<()> {
         }
         const btn = Array.from(root.querySelectorAll('button')).find((node) => {
             const text = (node.innerText || node.textContent || '').trim();
-            return text.includes('提交') && isVisible(node) && !node.disabled;
+            return (text.includes('提交') || text.toLowerCase().includes('submit')) && isVisible(node) && !node.disabled;
         });
         if (!btn) return false;
         btn.click();
@@ -1183,7 +3413,8 @@ async fn submit_quota_modal(driver: &WebDriver) -> Result<()> {
         )).filter(isVisible);
         return roots.some((root) => {
             const text = root.innerText || '';
-            return text.includes('更新令牌信息') || text.includes('额度设置') || text.includes('编辑令牌');
+            return text.includes('更新令牌信息') || text.includes('额度设置') || text.includes('编辑令牌') ||
+                /Update Token|Edit Token|Quota Settings/i.test(text);
         });
     "#;
     let started = Instant::now();
@@ -1196,3 +3427,142 @@ async fn submit_quota_modal(driver: &WebDriver) -> Result<()> {
     }
     anyhow::bail!("提交后弹窗未关闭")
 }
+
+/// 个人设置页中点击「修改密码」，在弹窗里依次填写原密码、新密码与确认密码后提交
+async fn change_password_via_form(
+    driver: &WebDriver,
+    old_password: &str,
+    new_password: &str,
+    waits: WaitCaps,
+) -> Result<()> {
+    driver
+        .get(PERSONAL_URL)
+        .await
+        .with_context(|| "导航到个人设置失败")?;
+    wait_for_network_idle(driver, waits.network_idle).await?;
+    close_announcement_popup(driver).await?;
+
+    let open_script = r#"
+        const nodes = Array.from(document.querySelectorAll('button, a, span, div'));
+        const target = nodes.find((node) => {
+            const text = (node.innerText || node.textContent || '').trim();
+            return node.children.length === 0 && (text === '修改密码' || /^change password$/i.test(text));
+        });
+        if (!target) return false;
+        (target.closest('button') || target).click();
+        return true;
+    "#;
+    let opened = driver.execute(open_script, Vec::<Value>::new()).await?;
+    if !opened.json().as_bool().unwrap_or(false) {
+        anyhow::bail!("未找到修改密码按钮");
+    }
+    let ready = wait_for_script(driver, PASSWORD_INPUTS_READY_SCRIPT, waits.element).await?;
+    if !ready {
+        anyhow::bail!("修改密码弹窗未出现");
+    }
+
+    let fill_script = r#"
+        function isVisible(node) {
+            const rect = node.getBoundingClientRect();
+            return rect.width > 0 && rect.height > 0;
+        }
+        const inputs = Array.from(document.querySelectorAll('input[type="password"]')).filter(isVisible);
+        const values = inputs.length >= 3
+            ? [arguments[0], arguments[1], arguments[1]]
+            : [arguments[1], arguments[1]];
+        if (inputs.length < 2) return false;
+        const descriptor = Object.getOwnPropertyDescriptor(window.HTMLInputElement.prototype, 'value');
+        values.forEach((value, index) => {
+            const input = inputs[index];
+            if (descriptor && descriptor.set) descriptor.set.call(input, value); else input.value = value;
+            input.dispatchEvent(new Event('input', { bubbles: true }));
+            input.dispatchEvent(new Event('change', { bubbles: true }));
+        });
+        const root = inputs[0].closest('.semi-modal-content, [role="dialog"], form') || document.body;
+        const btn = Array.from(root.querySelectorAll('button')).find((node) => {
+            const text = (node.innerText || node.textContent || '').trim();
+            return /^(确定|确认|提交|保存|OK|Confirm|Submit|Save)$/i.test(text) && !node.disabled;
+        });
+        if (!btn) return false;
+        btn.click();
+        return true;
+    "#;
+    let submitted = driver
+        .execute(
+            fill_script,
+            vec![Value::from(old_password), Value::from(new_password)],
+        )
+        .await?;
+    if !submitted.json().as_bool().unwrap_or(false) {
+        anyhow::bail!("未找到密码输入框或提交按钮");
+    }
+
+    let toast_script = r#"
+        const node = document.querySelector('.semi-toast-content');
+        return node ? (node.innerText || node.textContent || '').trim() : '';
+    "#;
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(8) {
+        let toast = driver.execute(toast_script, Vec::<Value>::new()).await?;
+        let text = toast.json().as_str().unwrap_or("").to_string();
+        if text.contains("成功") || text.to_ascii_lowercase().contains("success") {
+            return Ok(());
+        }
+        if !text.is_empty() {
+            anyhow::bail!("站点提示: {text}");
+        }
+        async_sleep(Duration::from_millis(250)).await;
+    }
+    anyhow::bail!("提交后未出现结果提示")
+}
+
+const PASSWORD_INPUTS_READY_SCRIPT: &str = r#"
+    return Array.from(document.querySelectorAll('input[type="password"]'))
+        .filter((node) => { const rect = node.getBoundingClientRect(); return rect.width > 0 && rect.height > 0; })
+        .length >= 2;
+"#;
+
+/// 表单不可用时直接调用 PUT /api/user/self 修改密码
+async fn change_password_via_api(
+    driver: &WebDriver,
+    old_password: &str,
+    new_password: &str,
+) -> Result<()> {
+    let script = r#"
+        const done = arguments[arguments.length - 1];
+        const original = arguments[0];
+        const password = arguments[1];
+        (async () => {
+            let userId = '';
+            try {
+                const user = JSON.parse(localStorage.getItem('user') || '{}');
+                userId = String(user.id || '');
+            } catch (e) {}
+            const headers = { 'Content-Type': 'application/json' };
+            if (userId) headers['New-Api-User'] = userId;
+            const resp = await fetch('/api/user/self', {
+                method: 'PUT',
+                credentials: 'include',
+                headers,
+                body: JSON.stringify({ original_password: original, password })
+            });
+            const data = await resp.json();
+            done({ ok: !!data.success, reason: data.message || String(resp.status) });
+        })().catch((e) => done({ ok: false, reason: String(e) }));
+    "#;
+    let value = driver
+        .execute_async(
+            script,
+            vec![Value::from(old_password), Value::from(new_password)],
+        )
+        .await?;
+    let result = value.json();
+    if result.get("ok").and_then(Value::as_bool).unwrap_or(false) {
+        return Ok(());
+    }
+    let reason = result
+        .get("reason")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown");
+    anyhow::bail!("接口修改密码失败: {}", reason)
+}
@@ -0,0 +1,127 @@
+use crate::models::{AlertRecord, CheckResult, WebhookConfig};
+use crate::privacy;
+use anyhow::{Context, Result};
+use chrono::Local;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    event: &'static str,
+    idempotency_key: &'a str,
+    finished_at: String,
+    /// 隐私模式下与界面一样打码用户名及说明文本
+    #[serde(serialize_with = "privacy::serialize_results")]
+    results: &'a [CheckResult],
+    #[serde(serialize_with = "privacy::serialize_alerts")]
+    alerts: &'a [AlertRecord],
+}
+
+/// 在后台把本轮结果推送到全部 Webhook，不阻塞查询流程
+pub fn spawn_result_webhooks(
+    config: &WebhookConfig,
+    results: &[CheckResult],
+    alerts: &[AlertRecord],
+) {
+    if !config.enabled || config.urls.iter().all(|url| url.trim().is_empty()) {
+        return;
+    }
+    let finished_at = Local::now().to_rfc3339();
+    let idempotency_key = idempotency_key(&finished_at, results);
+    let payload = WebhookPayload {
+        event: "round_completed",
+        idempotency_key: &idempotency_key,
+        finished_at,
+        results,
+        alerts,
+    };
+    let body = match serde_json::to_string(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("序列化 Webhook 载荷失败: {}", e);
+            return;
+        }
+    };
+    let config = config.clone();
+    tauri::async_runtime::spawn(async move {
+        for url in config.urls.iter().map(|url| url.trim()) {
+            if url.is_empty() {
+                continue;
+            }
+            match post_with_retry(&config, url, &body, &idempotency_key).await {
+                Ok(()) => tracing::info!("Webhook 推送成功: {}", url),
+                Err(e) => tracing::warn!("Webhook 推送失败: {} {:#}", url, e),
+            }
+        }
+    });
+}
+
+/// 同一轮结果的幂等键，重试与多个地址共用，接收方据此去重
+fn idempotency_key(finished_at: &str, results: &[CheckResult]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(finished_at.as_bytes());
+    for item in results {
+        hasher.update(item.username.as_bytes());
+        hasher.update(item.balance_text.as_bytes());
+        hasher.update([u8::from(item.success)]);
+    }
+    let digest = hex::encode(hasher.finalize());
+    format!("art-{}", &digest[..32])
+}
+
+/// HMAC-SHA256(secret, "<timestamp>.<body>")，返回十六进制摘要
+fn sign(secret: &str, timestamp: i64, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).context("初始化 HMAC 失败")?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn post_with_retry(
+    config: &WebhookConfig,
+    url: &str,
+    body: &str,
+    idempotency_key: &str,
+) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_seconds.max(1)))
+        .build()
+        .context("创建 Webhook 客户端失败")?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        // 每次请求重新取时间戳并签名，接收方可据此拒绝过旧的重放请求
+        let timestamp = Local::now().timestamp();
+        let mut request = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header("Idempotency-Key", idempotency_key)
+            .header("X-ART-Timestamp", timestamp.to_string())
+            .body(body.to_string());
+        if !config.secret.is_empty() {
+            let signature = sign(&config.secret, timestamp, body)?;
+            request = request.header("X-ART-Signature", format!("sha256={signature}"));
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let retryable = status.is_server_error() || status.as_u16() == 429;
+                if !retryable {
+                    anyhow::bail!("接收方返回 {}", status.as_u16());
+                }
+                anyhow::anyhow!("接收方返回 {}", status.as_u16())
+            }
+            Err(e) => anyhow::Error::new(e).context("请求失败"),
+        };
+        if attempt > config.retry_times {
+            return Err(error.context(format!("已重试 {} 次", config.retry_times)));
+        }
+        tracing::debug!("Webhook 推送第 {} 次失败，稍后重试: {:#}", attempt, error);
+        tokio::time::sleep(Duration::from_secs(2 * u64::from(attempt))).await;
+    }
+}
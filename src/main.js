@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke as tauriInvoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import "./style.css";
 
@@ -6,20 +6,27 @@ const MAX_PROGRESS_LOGS = 600;
 
 const state = {
   configDir: "",
+  profile: "default",
   queryInterval: 60,
   dailyRolloverHour: 8,
   fallbackToWeb: true,
+  prewarmLeadSecs: 0,
   autoSwitchEnabled: true,
   autoSwitchThreshold: 1.0,
+  autoSwitchStrategy: "highest_balance",
   displaySearch: "",
   displayStatus: "all",
   displaySort: "default",
   summaryCompact: false,
   selectedUsername: "",
+  pinned: [],
   accounts: [],
   results: [],
   totalBalance: 0,
   totalBalanceCount: 0,
+  totalCurrency: "USD",
+  scheduledIntervals: false,
+  currencyRates: { USD: 1 },
   statusText: "\u5c31\u7eea",
   statusState: "ok",
   lastFinished: "-",
@@ -31,11 +38,23 @@ const state = {
   progressLogs: [],
   openDropdown: null,
   editingUsername: "",
-  claudeAccount: ""
+  claudeAccount: "",
+  balanceChanges: {},
+  activeAlerts: 0,
+  driverVerbose: false,
+  locked: false,
+  privacy: false,
+  snapshotVersion: 0
 };
 
 let statusRenderPending = false;
 let logsRenderPending = false;
+// \u5408\u5e76\u77ed\u65f6\u95f4\u5185\u7684\u591a\u6b21\u72b6\u6001\u53d8\u5316\uff0c\u53ea\u91cd\u65b0\u8bfb\u53d6\u4e00\u6b21\u5feb\u7167
+let stateRefreshTimer = null;
+const stateRefreshKinds = new Set();
+// \u754c\u9762\u9700\u8981\u968f\u4e4b\u5237\u65b0\u7684\u72b6\u6001\u53d8\u5316\u7c7b\u522b
+const STATE_REFRESH_KINDS = new Set(["accounts", "config", "results", "alerts"]);
+const STATE_REFRESH_DELAY_MS = 300;
 
 const app = document.querySelector("#app");
 
@@ -67,6 +86,7 @@ app.innerHTML = `
           </div>
           <button id="btnQuery" class="primary">\u67e5\u8be2</button>
           <button id="btnWebLogin">\u4ec5\u7f51\u9875\u767b\u5f55</button>
+          <button id="btnRetryFailed" title="\u53ea\u91cd\u65b0\u68c0\u67e5\u4e0a\u4e00\u8f6e\u5931\u8d25\u7684\u8d26\u53f7">\u91cd\u8bd5\u5931\u8d25</button>
           <span class="toolbar-divider"></span>
           <div class="toolbar-group">
             <button id="btnAuto">\u5f00\u542f\u81ea\u52a8</button>
@@ -76,12 +96,17 @@ app.innerHTML = `
           </div>
           <span class="toolbar-divider"></span>
           <div class="toolbar-group">
-            <button id="btnAutoSwitch" title="\u5f53\u5f53\u524d Claude Token \u4f59\u989d\u4f4e\u4e8e\u9608\u503c\u65f6\uff0c\u81ea\u52a8\u5207\u6362\u5230\u4f59\u989d\u6700\u9ad8\u7684 Key">\u4f4e\u4f59\u989d\u6362Key</button>
+            <button id="btnAutoSwitch" title="\u5f53\u5f53\u524d Claude Token \u4f59\u989d\u4f4e\u4e8e\u9608\u503c\u65f6\uff0c\u6309\u6240\u9009\u7b56\u7565\u81ea\u52a8\u5207\u6362 Key">\u4f4e\u4f59\u989d\u6362Key</button>
             <label>\u9608\u503c</label>
             <input id="switchThresholdInput" type="number" min="0" max="99999" step="0.1" value="1.0" />
+            <select id="switchStrategySelect" title="\u5207\u6362\u5230\u54ea\u4e2a\u8d26\u53f7">
+              <option value="highest_balance">\u4f59\u989d\u6700\u9ad8</option>
+              <option value="least_recent">\u6700\u4e45\u672a\u7528</option>
+            </select>
           </div>
           <span class="toolbar-divider"></span>
           <span id="totalBadge" class="total-badge" style="display:none" title="\u70b9\u51fb\u590d\u5236">\u603b\u4f59\u989d: -</span>
+          <span id="alertBadge" class="alert-badge" style="display:none" title="\u70b9\u51fb\u67e5\u770b\u5e76\u786e\u8ba4\u544a\u8b66">\u544a\u8b66: 0</span>
         </div>
         <div class="toolbar toolbar-sub">
           <div class="toolbar-group">
@@ -93,6 +118,7 @@ app.innerHTML = `
               <option value="all">\u5168\u90e8</option>
               <option value="ok">\u6210\u529f</option>
               <option value="fail">\u5931\u8d25</option>
+              <option value="banned">\u5c01\u7981</option>
               <option value="cache">\u7f13\u5b58</option>
               <option value="idle">\u5f85\u673a</option>
             </select>
@@ -115,6 +141,31 @@ app.innerHTML = `
                 <button class="dropdown-item" data-action="copy_csv">\u590d\u5236 CSV</button>
                 <button class="dropdown-item" data-action="copy_json">\u590d\u5236 JSON</button>
                 <button class="dropdown-item" data-action="copy_fails">\u590d\u5236\u5931\u8d25\u8d26\u53f7</button>
+                <div class="dropdown-sep"></div>
+                <button class="dropdown-item" data-action="resync_failed_quota">\u91cd\u65b0\u540c\u6b65\u5931\u8d25\u989d\u5ea6</button>
+                <button class="dropdown-item" data-action="reset_all_web_state">\u91cd\u7f6e\u5168\u90e8\u7b7e\u5230\u72b6\u6001</button>
+                <button class="dropdown-item" data-action="copy_state_dump">\u590d\u5236\u72b6\u6001\u5feb\u7167</button>
+                <button class="dropdown-item" data-action="repair_state">\u6e05\u7406\u4fee\u590d\u72b6\u6001</button>
+                <button class="dropdown-item" data-action="run_maintenance">\u6309\u4fdd\u7559\u7b56\u7565\u6e05\u7406</button>
+                <button class="dropdown-item" data-action="list_hooks">\u67e5\u770b\u7b7e\u5230\u94a9\u5b50</button>
+                <button class="dropdown-item" data-action="deploy_plan">\u6267\u884c\u90e8\u7f72\u8ba1\u5212</button>
+                <button class="dropdown-item" data-action="list_deployments">\u67e5\u770b\u90e8\u7f72\u8bb0\u5f55</button>
+                <button class="dropdown-item" data-action="list_token_labels">\u67e5\u770b\u4ee4\u724c\u5f52\u5c5e</button>
+                <button class="dropdown-item" data-action="refresh_wsl">\u5237\u65b0 WSL \u53d1\u884c\u7248</button>
+                <button class="dropdown-item" data-action="restore_tool_config">\u56de\u6eda Claude/Codex \u914d\u7f6e</button>
+                <button class="dropdown-item" data-action="startup_issues">\u5904\u7406\u542f\u52a8\u95ee\u9898</button>
+                <button class="dropdown-item" data-action="selector_health">\u9009\u62e9\u5668\u5065\u5eb7\u62a5\u544a</button>
+                <button class="dropdown-item" data-action="cancel_driver_download">\u53d6\u6d88\u9a71\u52a8\u4e0b\u8f7d</button>
+                <button class="dropdown-item" data-action="preflight">\u542f\u52a8\u68c0\u67e5</button>
+                <button class="dropdown-item" data-action="smoke_test">\u5b89\u88c5\u81ea\u68c0</button>
+                <button class="dropdown-item" data-action="show_paths">\u67e5\u770b\u6570\u636e\u8def\u5f84</button>
+                <button class="dropdown-item" data-action="restore_account">\u6062\u590d\u5df2\u5220\u9664\u8d26\u53f7</button>
+                <button class="dropdown-item" data-action="export_shared">\u5bfc\u51fa\u5171\u4eab\u8d26\u53f7\u5217\u8868</button>
+                <button class="dropdown-item" data-action="import_shared">\u5bfc\u5165\u5171\u4eab\u8d26\u53f7\u5217\u8868</button>
+                <button class="dropdown-item" data-action="switch_profile">\u5207\u6362\u914d\u7f6e\u96c6</button>
+                <button class="dropdown-item" data-action="toggle_privacy">\u9690\u79c1\u6a21\u5f0f</button>
+                <button class="dropdown-item" data-action="lock_app">\u9501\u5b9a\u5e94\u7528</button>
+                <button class="dropdown-item" data-action="hash_pin">\u751f\u6210\u5e94\u7528\u9501 PIN \u54c8\u5e0c</button>
               </div>
             </div>
           </div>
@@ -150,9 +201,13 @@ app.innerHTML = `
               <label>\u5bc6\u7801</label>
               <input id="editPassword" type="password" placeholder="\u5bc6\u7801" />
             </div>
+            <div class="form-field">
+              <label>\u522b\u540d</label>
+              <input id="editAlias" type="text" placeholder="\u53ef\u9009\uff0c\u4ec5\u7528\u4e8e\u663e\u793a" />
+            </div>
             <div class="form-field">
               <label>API Key</label>
-              <input id="editApiKey" type="text" placeholder="\u53ef\u9009" />
+              <input id="editApiKey" type="text" placeholder="\u53ef\u9009\uff0c\u591a\u4e2a Key \u5199\u4f5c \u540d\u79f0=Key;\u540d\u79f0=Key" />
             </div>
             <button id="btnSaveAccount" class="primary">\u4fdd\u5b58</button>
             <button id="btnCancelEdit">\u53d6\u6d88</button>
@@ -160,6 +215,7 @@ app.innerHTML = `
         </div>
         <div class="toolbar">
           <button id="btnReload">\u91cd\u65b0\u52a0\u8f7d\u8d26\u53f7</button>
+          <button id="btnDiscover" title="\u767b\u5f55\u7ba1\u7406\u5458\u8d26\u53f7\uff0c\u5217\u51fa\u7ad9\u70b9\u4e0a\u7684\u5b50\u8d26\u53f7\u4e0e\u4ee4\u724c\u5e76\u9009\u62e9\u5bfc\u5165">\u53d1\u73b0\u8d26\u53f7</button>
           <span class="toolbar-divider"></span>
           <span id="modeLabel" style="font-size:12px;color:var(--text-muted)"></span>
         </div>
@@ -187,6 +243,9 @@ app.innerHTML = `
           <div class="logs-toolbar">
             <button id="btnClearLogs">\u6e05\u7a7a</button>
             <button id="btnPerf">\u6027\u80fd\u62a5\u544a</button>
+            <button id="btnPoolReport">\u6d4f\u89c8\u5668\u6c60\u62a5\u544a</button>
+            <button id="btnDailyStatus">\u4eca\u65e5\u7b7e\u5230\u72b6\u6001</button>
+            <button id="btnDriverVerbose">\u9a71\u52a8\u8be6\u7ec6\u65e5\u5fd7</button>
             <span class="toolbar-divider"></span>
             <span id="logCount" class="logs-count"></span>
           </div>
@@ -212,6 +271,7 @@ const refs = {
   intervalInput: el("intervalInput"),
   btnAutoSwitch: el("btnAutoSwitch"),
   switchThresholdInput: el("switchThresholdInput"),
+  switchStrategySelect: el("switchStrategySelect"),
   resultsSearch: el("resultsSearch"),
   btnClearSearch: el("btnClearSearch"),
   resultsStatus: el("resultsStatus"),
@@ -222,22 +282,29 @@ const refs = {
   summaryChips: el("summaryChips"),
   btnQuery: el("btnQuery"),
   btnWebLogin: el("btnWebLogin"),
+  btnRetryFailed: el("btnRetryFailed"),
   btnAuto: el("btnAuto"),
   totalBadge: el("totalBadge"),
+  alertBadge: el("alertBadge"),
   resultsBody: el("resultsBody"),
   formTitle: el("formTitle"),
   editUsername: el("editUsername"),
   editPassword: el("editPassword"),
   editApiKey: el("editApiKey"),
+  editAlias: el("editAlias"),
   btnSaveAccount: el("btnSaveAccount"),
   btnCancelEdit: el("btnCancelEdit"),
   btnReload: el("btnReload"),
+  btnDiscover: el("btnDiscover"),
   modeLabel: el("modeLabel"),
   accountsBody: el("accountsBody"),
   logsBody: el("logsBody"),
   logCount: el("logCount"),
   btnClearLogs: el("btnClearLogs"),
   btnPerf: el("btnPerf"),
+  btnPoolReport: el("btnPoolReport"),
+  btnDailyStatus: el("btnDailyStatus"),
+  btnDriverVerbose: el("btnDriverVerbose"),
   statusBar: el("statusBar"),
   statusText: el("statusText"),
   autoLabel: el("autoLabel")
@@ -317,6 +384,10 @@ function bindEvents() {
     saveUiPrefs();
     renderAutoSwitchControls();
   });
+  refs.switchStrategySelect.addEventListener("change", () => {
+    state.autoSwitchStrategy = refs.switchStrategySelect.value === "least_recent" ? "least_recent" : "highest_balance";
+    saveUiPrefs();
+  });
 
   // \u8d26\u53f7\u7b5b\u9009
   refs.accountSelect.addEventListener("change", () => {
@@ -410,17 +481,21 @@ function bindEvents() {
   // \u67e5\u8be2\u64cd\u4f5c
   refs.btnQuery.addEventListener("click", () => runQuery());
   refs.btnWebLogin.addEventListener("click", () => runWebLoginOnly());
+  refs.btnRetryFailed.addEventListener("click", () => runRetryFailed());
   refs.btnAuto.addEventListener("click", () => toggleAutoMode());
 
   // \u603b\u4f59\u989d\u590d\u5236
   refs.totalBadge.addEventListener("click", async () => {
     if (state.totalBalanceCount === 0) return;
-    const text = `$${state.totalBalance.toFixed(2)}`;
+    const text = formatMoney(state.totalBalance, state.totalCurrency);
     await navigator.clipboard.writeText(text);
     setStatus(`\u5df2\u590d\u5236\u603b\u4f59\u989d: ${text}`, "ok");
     pushLog(`\u5df2\u590d\u5236\u603b\u4f59\u989d: ${text}`);
   });
 
+  // \u544a\u8b66\u89d2\u6807: \u67e5\u770b\u672a\u786e\u8ba4\u544a\u8b66\u5e76\u5168\u90e8\u786e\u8ba4
+  refs.alertBadge.addEventListener("click", () => showAlerts());
+
   // \u7ed3\u679c\u8868\u683c\u4e09\u70b9\u83dc\u5355
   refs.resultsBody.addEventListener("click", onResultsAction);
   const resultsWrap = refs.resultsBody.closest(".table-wrap");
@@ -430,6 +505,7 @@ function bindEvents() {
 
   // \u8d26\u53f7\u7ba1\u7406
   refs.btnReload.addEventListener("click", () => reloadAccounts());
+  refs.btnDiscover.addEventListener("click", () => discoverAccounts());
   refs.btnSaveAccount.addEventListener("click", () => saveAccountFromEditor());
   refs.btnCancelEdit.addEventListener("click", () => cancelEdit());
   refs.accountsBody.addEventListener("click", onAccountsAction);
@@ -448,6 +524,78 @@ function bindEvents() {
       setStatus(`\u8bfb\u53d6\u6027\u80fd\u62a5\u544a\u5931\u8d25: ${toErrorMessage(error)}`, "error");
     }
   });
+  refs.btnPoolReport.addEventListener("click", async () => {
+    try {
+      const report = await invoke("browser_pool_report_command");
+      alert(report);
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u6d4f\u89c8\u5668\u6c60\u62a5\u544a\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+  });
+  refs.btnDailyStatus.addEventListener("click", async () => {
+    try {
+      const report = await invoke("get_daily_status_command");
+      const hours = (Number(report.seconds_until_rollover || 0) / 3600).toFixed(1);
+      const lines = [
+        `\u5468\u671f\u65e5 ${report.cycle_day}\uff0c\u8ddd\u5207\u65e5 ${hours} \u5c0f\u65f6`,
+        `\u5df2\u5b8c\u6210 ${report.completed_count} / \u672a\u5b8c\u6210 ${report.pending_count}`,
+        ""
+      ];
+      for (const item of report.accounts || []) {
+        const mark = item.completed ? "[\u5b8c\u6210]" : "[\u5f85\u7b7e]";
+        const error = !item.completed && item.last_error ? ` - ${item.last_error}` : "";
+        lines.push(`${mark} ${item.username}${error}`);
+      }
+      alert(lines.join("\n"));
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u7b7e\u5230\u72b6\u6001\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+  });
+  refs.btnDriverVerbose.addEventListener("click", async () => {
+    try {
+      const enabled = !state.driverVerbose;
+      const message = await invoke("set_driver_verbose_command", { enabled });
+      state.driverVerbose = enabled;
+      refs.btnDriverVerbose.classList.toggle("active", enabled);
+      pushLog(message);
+    } catch (error) {
+      setStatus(`\u5207\u6362\u9a71\u52a8\u8be6\u7ec6\u65e5\u5fd7\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+  });
+}
+
+// ========== App Lock ==========
+
+// \u547d\u4ee4\u88ab\u5e94\u7528\u9501\u62d2\u7edd\u65f6\u5f39\u51fa PIN \u8f93\u5165\u6846\uff0c\u89e3\u9501\u6210\u529f\u540e\u91cd\u8bd5\u4e00\u6b21
+async function invoke(command, args) {
+  try {
+    return await tauriInvoke(command, args);
+  } catch (error) {
+    if (errorCode(error) !== "locked" || !(await requestUnlock())) throw error;
+    return tauriInvoke(command, args);
+  }
+}
+
+async function requestUnlock() {
+  const pin = prompt("\u5e94\u7528\u5df2\u9501\u5b9a\uff0c\u8f93\u5165 PIN \u89e3\u9501:", "");
+  if (!pin) return false;
+  try {
+    await tauriInvoke("unlock_command", { pin });
+  } catch (error) {
+    setStatus(`\u89e3\u9501\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    return false;
+  }
+  await refreshLockedAccounts();
+  pushLog("\u5e94\u7528\u5df2\u89e3\u9501");
+  return true;
+}
+
+// \u9501\u5b9a\u72b6\u6001\u53d8\u5316\u540e\u91cd\u65b0\u8bfb\u53d6\u8d26\u53f7\uff0c\u9501\u5b9a\u65f6\u5feb\u7167\u4e2d\u7684\u5bc6\u7801\u4e0e Key \u5df2\u6253\u7801
+async function refreshLockedAccounts() {
+  const snapshot = await tauriInvoke("get_snapshot_command");
+  state.locked = Boolean(snapshot.locked);
+  if (Array.isArray(snapshot.accounts)) state.accounts = snapshot.accounts;
+  renderAccountsTable();
 }
 
 // ========== Boot ==========
@@ -461,6 +609,102 @@ async function boot() {
   renderAll();
   pushLog("\u521d\u59cb\u5316\u5b8c\u6210");
   setStatus("\u5c31\u7eea", "ok");
+  const issues = await invoke("get_startup_issues_command");
+  if (issues.length) {
+    await resolveStartupIssues(issues);
+  }
+  await reportQueryRunning();
+  await runPreflight(false);
+}
+
+const QUERY_KIND_TEXT = {
+  query: "\u67e5\u8be2\u4f59\u989d",
+  web_login: "\u7f51\u9875\u767b\u5f55",
+  retry_failed: "\u91cd\u8bd5\u5931\u8d25\u8d26\u53f7",
+  rollover_prerun: "\u5207\u65e5\u524d\u8865\u8dd1"
+};
+
+// \u754c\u9762\u91cd\u65b0\u52a0\u8f7d\u6216\u901a\u8fc7 HTTP \u63a5\u53e3\u89e6\u53d1\u67e5\u8be2\u65f6\uff0c\u63d0\u793a\u540e\u53f0\u4ecd\u6709\u67e5\u8be2\u5728\u8fdb\u884c
+async function reportQueryRunning() {
+  try {
+    const status = await invoke("is_query_running_command");
+    if (!status.running) return;
+    const kind = QUERY_KIND_TEXT[status.kind] || "\u5176\u4ed6\u64cd\u4f5c";
+    const detail = status.kind ? `\uff0c\u5df2\u8fdb\u884c ${status.elapsed_secs} \u79d2\uff0c\u6d89\u53ca ${status.accounts.length} \u4e2a\u8d26\u53f7` : "";
+    setStatus(`\u540e\u53f0${kind}\u8fdb\u884c\u4e2d${detail}`, "busy");
+    pushLog(`\u540e\u53f0${kind}\u8fdb\u884c\u4e2d${detail}\uff0c\u7ed3\u675f\u524d\u5220\u9664\u76f8\u5173\u8d26\u53f7\u4f1a\u88ab\u62d2\u7edd`);
+  } catch (error) {
+    pushLog(`\u83b7\u53d6\u67e5\u8be2\u72b6\u6001\u5931\u8d25: ${toErrorMessage(error)}`);
+  }
+}
+
+const PREFLIGHT_FIX_TEXT = {
+  open_file: "\u6253\u5f00\u6587\u4ef6",
+  download_driver: "\u4e0b\u8f7d\u9a71\u52a8",
+  run_doctor: "\u8fd0\u884c\u5b89\u88c5\u81ea\u68c0",
+  run_query: "\u7acb\u5373\u67e5\u8be2"
+};
+
+// \u542f\u52a8\u68c0\u67e5: \u9010\u9879\u8f93\u51fa\u7ed3\u679c\uff1bverbose \u4e3a false \u65f6\u53ea\u5bf9\u5931\u8d25\u9879\u8be2\u95ee\u662f\u5426\u6267\u884c\u4fee\u590d
+async function runPreflight(verbose) {
+  let report;
+  try {
+    report = await invoke("preflight_command");
+  } catch (error) {
+    pushLog(`\u542f\u52a8\u68c0\u67e5\u5931\u8d25: ${toErrorMessage(error)}`);
+    return;
+  }
+  const mark = { ok: "[OK]", warn: "[\u6ce8\u610f]", fail: "[\u5931\u8d25]" };
+  for (const item of report.checks || []) {
+    if (verbose || item.status !== "ok") {
+      pushLog(`\u542f\u52a8\u68c0\u67e5 ${mark[item.status] || item.status} ${item.label}: ${item.message}`);
+    }
+  }
+  const fixes = (report.checks || []).filter((item) => item.fix_action && (verbose || item.status === "fail"));
+  for (const item of fixes) {
+    const fixText = PREFLIGHT_FIX_TEXT[item.fix_action] || item.fix_action;
+    if (!confirm(`${item.label}: ${item.message}\n\n${fixText}?`)) continue;
+    await runPreflightFix(item);
+  }
+  if (!report.ok) {
+    setStatus("\u542f\u52a8\u68c0\u67e5\u672a\u901a\u8fc7\uff0c\u8be6\u89c1\u65e5\u5fd7", "error");
+  }
+}
+
+async function runPreflightFix(item) {
+  try {
+    if (item.fix_action === "open_file") {
+      await invoke("open_path_command", { path: item.fix_target });
+    } else if (item.fix_action === "download_driver") {
+      await prewarmBrowserPool();
+    } else if (item.fix_action === "run_doctor") {
+      await handleToolsAction("smoke_test");
+    } else if (item.fix_action === "run_query") {
+      await runQuery();
+    }
+  } catch (error) {
+    setStatus(`\u4fee\u590d\u64cd\u4f5c\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+// \u542f\u52a8\u65f6\u6709\u6587\u4ef6\u89e3\u6790\u5931\u8d25: \u9010\u4e2a\u8be2\u95ee\u91cd\u7f6e(\u5907\u4efd\u539f\u6587\u4ef6)\u8fd8\u662f\u624b\u52a8\u4fee\u590d\u540e\u91cd\u65b0\u8bfb\u53d6
+async function resolveStartupIssues(issues) {
+  for (const issue of issues) {
+    pushLog(`[\u5b89\u5168\u6a21\u5f0f] ${issue.file} \u89e3\u6790\u5931\u8d25: ${issue.error}`);
+    const reset = confirm(
+      `\u542f\u52a8\u65f6\u65e0\u6cd5\u89e3\u6790 ${issue.file}\uff0c\u5f53\u524d\u4f7f\u7528\u9ed8\u8ba4\u5185\u5bb9\u8fd0\u884c\u3002\n\n${issue.error}\n\n\u786e\u5b9a: \u5907\u4efd\u539f\u6587\u4ef6\u5e76\u91cd\u7f6e\n\u53d6\u6d88: \u5df2\u624b\u52a8\u4fee\u590d\uff0c\u91cd\u65b0\u8bfb\u53d6`
+    );
+    try {
+      await invoke("resolve_startup_issue_command", { file: issue.file, action: reset ? "reset" : "reload" });
+      pushLog(`${issue.file} \u5df2${reset ? "\u5907\u4efd\u5e76\u91cd\u7f6e" : "\u91cd\u65b0\u8bfb\u53d6"}`);
+    } catch (error) {
+      setStatus(`\u5904\u7406\u542f\u52a8\u95ee\u9898\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+      pushLog(`\u5904\u7406 ${issue.file} \u5931\u8d25: ${toErrorMessage(error)}`);
+    }
+  }
+  const snapshot = await invoke("get_snapshot_command");
+  hydrateFromSnapshot(snapshot);
+  renderAll();
 }
 
 async function setupProgressListener() {
@@ -469,6 +713,67 @@ async function setupProgressListener() {
     const prefix = username ? `[${username}] ` : "";
     pushLog(`${prefix}${message}`);
   });
+  await listen("state-changed", (event) => {
+    const { version, kind } = event.payload || {};
+    if (Number(version || 0) <= state.snapshotVersion || !STATE_REFRESH_KINDS.has(kind)) return;
+    scheduleStateRefresh(kind);
+  });
+  await listen("balance-changed", (event) => {
+    const { username, delta } = event.payload || {};
+    if (!username) return;
+    state.balanceChanges[username] = typeof delta === "number" ? delta : null;
+  });
+  await listen("slow-operation", (event) => {
+    const { operation_name, username, duration_secs, threshold_secs } = event.payload || {};
+    const prefix = username ? `[${username}] ` : "";
+    const duration = Number(duration_secs || 0).toFixed(1);
+    const threshold = Number(threshold_secs || 0).toFixed(0);
+    pushLog(`${prefix}\u6162\u64cd\u4f5c: ${operation_name} \u8017\u65f6 ${duration}s (\u9608\u503c ${threshold}s)`);
+  });
+  await listen("driver-download-progress", (event) => {
+    const { version, downloaded_bytes, total_bytes, bytes_per_sec, finished } = event.payload || {};
+    const mb = (value) => (Number(value || 0) / 1048576).toFixed(1);
+    const size = total_bytes ? `${mb(downloaded_bytes)}/${mb(total_bytes)} MB` : `${mb(downloaded_bytes)} MB`;
+    const text = `\u9a71\u52a8 ${version} ${size} (${mb(bytes_per_sec)} MB/s)`;
+    if (finished) {
+      pushLog(`${text} \u4e0b\u8f7d\u7ed3\u675f`);
+      return;
+    }
+    setStatus(`\u6b63\u5728\u4e0b\u8f7d${text}`, "busy");
+  });
+  await listen("infrastructure-error", (event) => {
+    const { message, hints } = event.payload || {};
+    const tips = Array.isArray(hints) && hints.length ? ` \u5efa\u8bae: ${hints.join("\uff1b")}` : "";
+    pushLog(`\u6d4f\u89c8\u5668\u6545\u969c: ${message}${tips}`);
+    setStatus(`\u6d4f\u89c8\u5668\u6545\u969c: ${message}`, "error");
+  });
+  await listen("rollover-prerun", (event) => {
+    if (!Array.isArray(event.payload)) return;
+    state.results = event.payload;
+    recalculateTotals();
+    renderResults();
+    renderTotalBadge();
+    pushLog("\u5207\u65e5\u524d\u8865\u8dd1\u7f51\u9875\u767b\u5f55\u5b8c\u6210");
+  });
+  await listen("fast-refresh", (event) => {
+    if (!Array.isArray(event.payload)) return;
+    state.results = event.payload;
+    recalculateTotals();
+    renderResults();
+    renderTotalBadge();
+  });
+  await listen("alerts-changed", (event) => {
+    state.activeAlerts = Number((event.payload || {}).active_count || 0);
+    renderAlertBadge();
+  });
+  await listen("notification", (event) => {
+    const { title, body } = event.payload || {};
+    if (!body) return;
+    pushLog(`[\u901a\u77e5] ${body.replace(/\n/g, " | ")}`);
+    if (typeof Notification !== "undefined" && Notification.permission === "granted") {
+      new Notification(title || "ART-rs", { body });
+    }
+  });
 }
 
 async function refreshClaudeAccount() {
@@ -481,16 +786,53 @@ async function refreshClaudeAccount() {
 
 function hydrateFromSnapshot(snapshot) {
   state.configDir = snapshot.config_dir || "";
+  state.profile = snapshot.profile || "default";
   state.queryInterval = Math.max(1, Number(snapshot.query_interval || 60));
+  state.scheduledIntervals = Boolean(snapshot.scheduled_intervals);
   state.dailyRolloverHour = Number(snapshot.daily_rollover_hour || 8);
   state.fallbackToWeb = Boolean(snapshot.fallback_to_web);
+  state.prewarmLeadSecs = Math.max(0, Number(snapshot.prewarm_before_round_secs || 0));
   state.accounts = Array.isArray(snapshot.accounts) ? snapshot.accounts : [];
   state.results = Array.isArray(snapshot.cached_results) ? snapshot.cached_results : [];
+  state.activeAlerts = Number(snapshot.active_alerts || 0);
+  state.locked = Boolean(snapshot.locked);
+  state.privacy = Boolean(snapshot.privacy);
+  state.pinned = Array.isArray(snapshot.pinned) ? snapshot.pinned : [];
+  state.snapshotVersion = Number(snapshot.version || 0);
   recalculateTotals();
   state.selectedUsername = "";
   refs.intervalInput.value = String(state.queryInterval);
 }
 
+// \u540e\u53f0\u67e5\u8be2\u3001HTTP \u63a5\u53e3\u6216\u91cd\u65b0\u52a0\u8f7d\u8d26\u53f7\u6587\u4ef6\u7b49\u6539\u53d8\u72b6\u6001\u540e\u91cd\u65b0\u8bfb\u53d6\u5feb\u7167\uff0c\u4fdd\u7559\u5f53\u524d\u9009\u4e2d\u7684\u8d26\u53f7\u3002
+// \u754c\u9762\u81ea\u5df1\u53d1\u8d77\u7684\u67e5\u8be2\u7ed3\u675f\u65f6\u4f1a\u6574\u4f53\u5237\u65b0\uff0c\u671f\u95f4\u4e0d\u91cd\u590d\u8bfb\u53d6
+function scheduleStateRefresh(kind) {
+  stateRefreshKinds.add(kind);
+  if (stateRefreshTimer) return;
+  stateRefreshTimer = setTimeout(async () => {
+    stateRefreshTimer = null;
+    if (state.isRunning) {
+      stateRefreshKinds.clear();
+      return;
+    }
+    const kinds = [...stateRefreshKinds];
+    stateRefreshKinds.clear();
+    try {
+      const snapshot = await invoke("get_snapshot_command");
+      if (Number(snapshot.version || 0) <= state.snapshotVersion) return;
+      const selected = state.selectedUsername;
+      hydrateFromSnapshot(snapshot);
+      if (state.accounts.some((item) => item.username === selected)) {
+        state.selectedUsername = selected;
+      }
+      renderAll();
+      if (kinds.includes("config")) pushLog("\u914d\u7f6e\u5df2\u91cd\u65b0\u52a0\u8f7d");
+    } catch (error) {
+      pushLog(`\u5237\u65b0\u72b6\u6001\u5931\u8d25: ${toErrorMessage(error)}`);
+    }
+  }, STATE_REFRESH_DELAY_MS);
+}
+
 // ========== Tab ==========
 
 function switchTab(tabId) {
@@ -518,12 +860,15 @@ function renderAll() {
   renderResults();
   renderAccountsTable();
   renderTotalBadge();
+  renderAlertBadge();
   renderAutoSwitchControls();
   scheduleStatusRender();
 }
 
 function renderMeta() {
-  refs.metaConfigDir.textContent = state.configDir || "-";
+  refs.metaConfigDir.textContent = state.profile === "default"
+    ? state.configDir || "-"
+    : `[${state.profile}] ${state.configDir || "-"}`;
   refs.metaAccountCount.textContent = `${state.accounts.length} \u4e2a\u8d26\u53f7`;
   refs.metaClaudeAccount.textContent = state.claudeAccount
     ? `Claude: ${state.claudeAccount}`
@@ -541,6 +886,7 @@ function renderAutoSwitchControls() {
     : 0;
   state.autoSwitchThreshold = threshold;
   refs.switchThresholdInput.value = String(threshold);
+  if (refs.switchStrategySelect) refs.switchStrategySelect.value = state.autoSwitchStrategy;
   refs.btnAutoSwitch.classList.toggle("primary", enabled);
   refs.btnAutoSwitch.textContent = enabled
     ? "\u4f4e\u4f59\u989d\u6362Key: \u5f00"
@@ -551,7 +897,8 @@ function renderAccountSelect() {
   const opts = [`<option value="">\u5168\u90e8\u8d26\u53f7</option>`];
   state.accounts.forEach((item) => {
     const sel = item.username === state.selectedUsername ? "selected" : "";
-    opts.push(`<option value="${esc(item.username)}" ${sel}>${esc(item.username)}</option>`);
+    const label = item.alias ? `${item.alias} (${item.username})` : item.username;
+    opts.push(`<option value="${esc(item.username)}" ${sel}>${esc(label)}</option>`);
   });
   refs.accountSelect.innerHTML = opts.join("");
 }
@@ -578,6 +925,12 @@ function renderToolsMenu() {
       ? "\u7d27\u51d1\u6c47\u603b: \u5f00"
       : "\u7d27\u51d1\u6c47\u603b: \u5173";
   }
+  const privacyItem = refs.toolsDropdown.querySelector('[data-action="toggle_privacy"]');
+  if (privacyItem) {
+    privacyItem.textContent = state.privacy
+      ? "\u9690\u79c1\u6a21\u5f0f: \u5f00"
+      : "\u9690\u79c1\u6a21\u5f0f: \u5173";
+  }
 
   const hasData = state.results.length > 0;
   const copyCsv = refs.toolsDropdown.querySelector('[data-action="copy_csv"]');
@@ -607,6 +960,284 @@ async function handleToolsAction(action) {
   }
   if (action === "copy_fails") {
     await copyDisplayedFails();
+    return;
+  }
+  if (action === "resync_failed_quota") {
+    await resyncQuota(null);
+    return;
+  }
+  if (action === "reset_all_web_state") {
+    if (!confirm("\u786e\u5b9a\u91cd\u7f6e\u5168\u90e8\u8d26\u53f7\u7684\u6bcf\u65e5\u7b7e\u5230\u72b6\u6001\uff1f\u4e0b\u6b21\u67e5\u8be2\u5c06\u91cd\u65b0\u7f51\u9875\u767b\u5f55\u3002")) return;
+    await updateWebState("reset_web_state_command", null);
+    return;
+  }
+  if (action === "copy_state_dump") {
+    try {
+      const dump = await invoke("get_state_dump_command");
+      await navigator.clipboard.writeText(JSON.stringify(dump, null, 2));
+      setStatus("\u5df2\u590d\u5236\u72b6\u6001\u5feb\u7167", "ok");
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u72b6\u6001\u5feb\u7167\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "repair_state") {
+    try {
+      const report = await invoke("repair_state_command");
+      setStatus(report.message, "ok");
+      pushLog(report.message);
+    } catch (error) {
+      setStatus(`\u4fee\u590d\u72b6\u6001\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "switch_profile") {
+    try {
+      const list = await invoke("list_profiles_command");
+      if (list.pinned) {
+        alert(`\u5f53\u524d\u914d\u7f6e\u96c6 ${list.active} \u7531\u542f\u52a8\u53c2\u6570\u6307\u5b9a\uff0c\u8bf7\u4fee\u6539 --profile \u6216 ART_RS_PROFILE \u540e\u91cd\u542f\u3002`);
+        return;
+      }
+      const input = prompt(
+        `\u5f53\u524d\u914d\u7f6e\u96c6: ${list.active}\n\u53ef\u7528: ${list.profiles.join(", ")}\n\u8f93\u5165\u8981\u5207\u6362\u7684\u914d\u7f6e\u96c6\u540d\u79f0(\u4e0d\u5b58\u5728\u65f6\u65b0\u5efa\uff0c\u5207\u6362\u540e\u5e94\u7528\u4f1a\u91cd\u542f):`,
+        ""
+      );
+      const name = (input || "").trim();
+      if (!name || name === list.active) return;
+      const create = !list.profiles.includes(name);
+      if (create && !confirm(`\u914d\u7f6e\u96c6 ${name} \u4e0d\u5b58\u5728\uff0c\u662f\u5426\u65b0\u5efa\u5e76\u5207\u6362?`)) return;
+      setStatus(`\u6b63\u5728\u5207\u6362\u5230\u914d\u7f6e\u96c6 ${name}...`, "busy");
+      const message = await invoke("switch_profile_command", { name, create });
+      setStatus(message, "ok");
+    } catch (error) {
+      setStatus(`\u5207\u6362\u914d\u7f6e\u96c6\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "selector_health") {
+    try {
+      const report = await invoke("get_selector_health_command");
+      const totals = Object.entries(report.totals || {}).map(([name, count]) => `${name} ${count}`);
+      const lines = [
+        `\u7b56\u7565\u547d\u4e2d: ${totals.join(", ") || "\u6682\u65e0\u7f51\u9875\u63d0\u53d6\u8bb0\u5f55"}`,
+        `\u515c\u5e95\u7b56\u7565\u5360\u6bd4 ${(Number(report.fragile_ratio || 0) * 100).toFixed(0)}%`,
+        ""
+      ];
+      for (const item of report.accounts || []) {
+        if (!item.last_strategy) continue;
+        const mark = item.fragile_only ? "[\u4ec5\u515c\u5e95]" : "[\u6b63\u5e38]";
+        lines.push(`${mark} ${item.username}: ${item.last_strategy} (${item.last_at})`);
+      }
+      if (report.degraded) {
+        lines.unshift("\u5168\u90e8\u8d26\u53f7\u53ea\u5269\u515c\u5e95\u7b56\u7565\u547d\u4e2d\uff0c\u7ad9\u70b9\u9875\u9762\u53ef\u80fd\u5df2\u6539\u7248", "");
+      }
+      alert(lines.join("\n"));
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u9009\u62e9\u5668\u5065\u5eb7\u62a5\u544a\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "cancel_driver_download") {
+    try {
+      pushLog(await invoke("cancel_driver_download_command"));
+    } catch (error) {
+      setStatus(`\u53d6\u6d88\u9a71\u52a8\u4e0b\u8f7d\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "preflight") {
+    await runPreflight(true);
+    return;
+  }
+  if (action === "smoke_test") {
+    setStatus("\u6b63\u5728\u8fd0\u884c\u5b89\u88c5\u81ea\u68c0...", "busy");
+    try {
+      const report = await invoke("smoke_test_command");
+      const statusText = { passed: "\u901a\u8fc7", failed: "\u5931\u8d25", skipped: "\u8df3\u8fc7" };
+      const lines = (report.stages || []).map((item) =>
+        `${item.name}: ${statusText[item.status] || item.status} (${item.duration_ms}ms) ${item.message}`);
+      const summary = report.success ? "\u5b89\u88c5\u81ea\u68c0\u901a\u8fc7" : "\u5b89\u88c5\u81ea\u68c0\u672a\u901a\u8fc7";
+      setStatus(summary, report.success ? "ok" : "error");
+      pushLog(`${summary}: ${lines.join(" | ")}`);
+      alert(`${summary}\n\n${lines.join("\n")}`);
+    } catch (error) {
+      setStatus(`\u5b89\u88c5\u81ea\u68c0\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "show_paths") {
+    try {
+      const paths = await invoke("get_paths_command");
+      const lines = Object.entries(paths).map(([key, value]) => `${key}: ${value}`);
+      alert(lines.join("\n"));
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u6570\u636e\u8def\u5f84\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "toggle_privacy") {
+    // \u5f00\u542f\u540e\u7528\u6237\u540d\u6253\u7801\uff0c\u91cd\u65b0\u8bfb\u53d6\u5feb\u7167\u4f7f\u5217\u8868\u4e0e\u64cd\u4f5c\u90fd\u4f7f\u7528\u6253\u7801\u540e\u7684\u540d\u79f0
+    const enabled = await invoke("set_privacy_mode_command", { enabled: !state.privacy });
+    hydrateFromSnapshot(await invoke("get_snapshot_command"));
+    await refreshClaudeAccount();
+    renderAll();
+    setStatus(enabled ? "\u9690\u79c1\u6a21\u5f0f\u5df2\u5f00\u542f" : "\u9690\u79c1\u6a21\u5f0f\u5df2\u5173\u95ed", "ok");
+    return;
+  }
+  if (action === "lock_app") {
+    const message = await tauriInvoke("lock_command");
+    await refreshLockedAccounts();
+    setStatus(message, "ok");
+    pushLog(message);
+    return;
+  }
+  if (action === "hash_pin") {
+    const pin = prompt("\u8f93\u5165\u65b0\u7684\u5e94\u7528\u9501 PIN(\u81f3\u5c11 4 \u4f4d):", "");
+    if (!pin) return;
+    try {
+      const hash = await tauriInvoke("hash_pin_command", { pin });
+      await navigator.clipboard.writeText(hash);
+      setStatus("PIN \u54c8\u5e0c\u5df2\u590d\u5236\uff0c\u586b\u5165 config.json \u7684 app_lock.pin_hash \u540e\u91cd\u542f\u751f\u6548", "ok");
+    } catch (error) {
+      setStatus(`\u751f\u6210 PIN \u54c8\u5e0c\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "restore_account") {
+    await restoreAccount();
+    return;
+  }
+  if (action === "export_shared") {
+    await exportSharedConfig();
+    return;
+  }
+  if (action === "import_shared") {
+    await importSharedConfig();
+    return;
+  }
+  if (action === "startup_issues") {
+    try {
+      const issues = await invoke("get_startup_issues_command");
+      if (!issues.length) {
+        setStatus("\u6ca1\u6709\u5f85\u5904\u7406\u7684\u542f\u52a8\u95ee\u9898", "ok");
+        return;
+      }
+      await resolveStartupIssues(issues);
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u542f\u52a8\u95ee\u9898\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "restore_tool_config") {
+    const input = prompt("\u56de\u6eda\u54ea\u4e2a\u914d\u7f6e\u5230\u6700\u8fd1\u4e00\u6b21\u4fee\u6539\u524d (claude / codex):", "claude");
+    const target = (input || "").trim().toLowerCase();
+    if (!target) return;
+    try {
+      const message = await invoke("restore_tool_config_command", { target });
+      if (target === "claude") {
+        await refreshClaudeAccount();
+        renderMeta();
+        renderResults();
+      }
+      setStatus(message, "ok");
+      pushLog(message);
+    } catch (error) {
+      setStatus(`\u56de\u6eda\u914d\u7f6e\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "refresh_wsl") {
+    try {
+      const distros = await invoke("list_wsl_distros_command", { refresh: true });
+      const message = distros.length ? `WSL \u53d1\u884c\u7248: ${distros.join(", ")}` : "\u672a\u53d1\u73b0 WSL \u53d1\u884c\u7248";
+      setStatus(message, "ok");
+      pushLog(message);
+    } catch (error) {
+      setStatus(`\u5237\u65b0 WSL \u53d1\u884c\u7248\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "list_token_labels") {
+    try {
+      const labels = await invoke("list_token_labels_command");
+      if (!labels.length) {
+        alert("\u6682\u65e0\u672c\u7a0b\u5e8f\u65b0\u5efa\u7684\u4ee4\u724c");
+        return;
+      }
+      const lines = labels.map((item) =>
+        `${item.username} | ${item.name} | ${item.hostname}${item.tool ? ` / ${item.tool}` : ""} | ${item.key_hint || "-"} | ${item.created_at}`
+      );
+      alert(`\u672c\u7a0b\u5e8f\u65b0\u5efa\u7684\u4ee4\u724c:\n${lines.join("\n")}`);
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u4ee4\u724c\u5f52\u5c5e\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "list_deployments") {
+    try {
+      const records = await invoke("list_deployments_command");
+      if (!records.length) {
+        alert("\u6682\u65e0\u90e8\u7f72\u8bb0\u5f55");
+        return;
+      }
+      const lines = records.map((item) => `${item.target}: ${item.username} (${item.deployed_at})`);
+      alert(`\u5404\u76ee\u6807\u6700\u8fd1\u5199\u5165\u7684\u8d26\u53f7:\n${lines.join("\n")}`);
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u90e8\u7f72\u8bb0\u5f55\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "deploy_plan") {
+    try {
+      const plans = await invoke("list_deploy_plans_command");
+      if (!plans.length) {
+        alert("\u672a\u914d\u7f6e\u90e8\u7f72\u8ba1\u5212\uff0c\u8bf7\u5728 config.json \u7684 deploy_plans \u4e2d\u6dfb\u52a0\u3002");
+        return;
+      }
+      const names = plans.map((item) => `${item.name} (${item.entries.length} \u9879)`).join("\n");
+      const input = prompt(`\u8f93\u5165\u8981\u6267\u884c\u7684\u90e8\u7f72\u8ba1\u5212\u540d\u79f0:\n${names}`, plans[0].name);
+      const plan = (input || "").trim();
+      if (!plan) return;
+      setStatus(`\u6b63\u5728\u6267\u884c\u90e8\u7f72\u8ba1\u5212 ${plan}...`, "busy");
+      const report = await invoke("deploy_plan_command", { plan });
+      const lines = report.cells.map((cell) =>
+        `${cell.success ? "\u6210\u529f" : "\u5931\u8d25"} ${cell.username} -> ${cell.target}: ${cell.message}`
+      );
+      lines.forEach((line) => pushLog(`[\u90e8\u7f72 ${report.plan}] ${line}`));
+      const summary = `\u90e8\u7f72\u8ba1\u5212 ${report.plan}: \u6210\u529f ${report.success_count}\uff0c\u5931\u8d25 ${report.fail_count}`;
+      setStatus(summary, report.fail_count ? "warn" : "ok");
+      alert(`${summary}\n\n${lines.join("\n")}`);
+    } catch (error) {
+      setStatus(`\u6267\u884c\u90e8\u7f72\u8ba1\u5212\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "run_maintenance") {
+    try {
+      const report = await invoke("run_maintenance_command");
+      setStatus(report.message, "ok");
+      pushLog(report.message);
+    } catch (error) {
+      setStatus(`\u7ef4\u62a4\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
+    return;
+  }
+  if (action === "list_hooks") {
+    try {
+      const hooks = await invoke("list_hooks_command");
+      if (!hooks.length) {
+        alert("\u672a\u914d\u7f6e\u5916\u90e8\u7b7e\u5230\u94a9\u5b50\uff0c\u5168\u90e8\u8d26\u53f7\u4f7f\u7528\u539f\u751f\u7f51\u9875\u767b\u5f55\u3002");
+        return;
+      }
+      const lines = hooks.map((item) => {
+        const users = item.accounts.length ? item.accounts.join(", ") : "(\u65e0\u8d26\u53f7\u4f7f\u7528)";
+        const desc = item.description ? ` - ${item.description}` : "";
+        return `${item.name} [${item.source}, ${item.kind}, v${item.hook_protocol}]${desc}\n  ${item.command}\n  \u8d26\u53f7: ${users}`;
+      });
+      alert(lines.join("\n\n"));
+    } catch (error) {
+      setStatus(`\u8bfb\u53d6\u94a9\u5b50\u5217\u8868\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    }
   }
 }
 
@@ -684,23 +1315,38 @@ function renderResults() {
     const claudeBadge = isCurrentClaude ? ' <span class="badge badge-claude">Claude</span>' : "";
     const claudeActionLabel = isCurrentClaude ? "\u5df2\u662f Claude Token" : "\u8bbe\u4e3a Claude Token";
     const claudeDisabledAttr = isCurrentClaude ? "disabled" : "";
+    const isPinned = state.pinned.includes(item.username);
     const dotClass = getRowStatusKey(item);
     const dotText = dotClass === "cache" ? "\u7f13\u5b58"
       : dotClass === "idle" ? "\u5f85\u673a"
       : dotClass === "ok" ? "\u6210\u529f" : "\u5931\u8d25";
     return `
       <tr class="${isCurrentClaude ? "row-current" : ""}">
-        <td><span class="account-name">${esc(item.username)}</span>${claudeBadge}</td>
+        <td>${isPinned ? '<span class="badge badge-pin">\u7f6e\u9876</span> ' : ""}${renderAccountName(item)}${claudeBadge}${renderHealthBadge(item)}${renderSyncBadge(item)}${renderOverdraftBadge(item)}</td>
         <td><span class="status-dot ${dotClass}">${dotText}</span></td>
-        <td class="balance-value">${esc(item.balance_text || "-")}</td>
-        <td>${esc(item.source || "-")}</td>
+        <td class="balance-value${getBalanceChangeClass(item.username)}">${esc(item.balance_text || "-")}${renderBalanceDelta(item.username)}</td>
+        <td title="${escAttr(formatAttempts(item))}">${esc(item.source || "-")}${renderRefresherBadge(item)}</td>
         <td>${esc(item.message || "-")}</td>
         <td class="cell-actions">
           <button class="btn-more" data-username="${escAttr(item.username)}" title="\u64cd\u4f5c">\u00b7\u00b7\u00b7</button>
           <div class="dropdown" data-menu="${escAttr(item.username)}">
+            <button class="dropdown-item" data-action="toggle_pin" data-username="${escAttr(item.username)}">${isPinned ? "\u53d6\u6d88\u7f6e\u9876" : "\u7f6e\u9876"}</button>
+            <button class="dropdown-item" data-action="move_up" data-username="${escAttr(item.username)}">\u4e0a\u79fb</button>
+            <button class="dropdown-item" data-action="move_down" data-username="${escAttr(item.username)}">\u4e0b\u79fb</button>
+            <button class="dropdown-item" data-action="account_stats" data-username="${escAttr(item.username)}">\u68c0\u67e5\u7edf\u8ba1</button>
             <button class="dropdown-item" data-action="copy_key" data-username="${escAttr(item.username)}">\u590d\u5236 API Key</button>
             <button class="dropdown-item" data-action="set_claude" data-username="${escAttr(item.username)}" ${claudeDisabledAttr}>${claudeActionLabel}</button>
             <button class="dropdown-item" data-action="set_openai" data-username="${escAttr(item.username)}">\u8bbe\u4e3a OpenAI Key</button>
+            <button class="dropdown-item" data-action="export_env" data-username="${escAttr(item.username)}">\u590d\u5236\u73af\u5883\u53d8\u91cf</button>
+            <button class="dropdown-item" data-action="create_token" data-username="${escAttr(item.username)}">\u65b0\u5efa\u4ee4\u724c</button>
+            <button class="dropdown-item" data-action="interactive_login" data-username="${escAttr(item.username)}">\u624b\u52a8\u767b\u5f55</button>
+            <button class="dropdown-item" data-action="resync_quota" data-username="${escAttr(item.username)}">\u91cd\u65b0\u540c\u6b65\u989d\u5ea6</button>
+            <button class="dropdown-item" data-action="preview_quota" data-username="${escAttr(item.username)}">\u9884\u6f14\u989d\u5ea6\u540c\u6b65</button>
+            <button class="dropdown-item" data-action="mark_web_done" data-username="${escAttr(item.username)}">\u6807\u8bb0\u4eca\u65e5\u5df2\u7b7e\u5230</button>
+            <button class="dropdown-item" data-action="reset_web_state" data-username="${escAttr(item.username)}">\u91cd\u7f6e\u7b7e\u5230\u72b6\u6001</button>
+            <button class="dropdown-item" data-action="export_cookies" data-username="${escAttr(item.username)}">\u5bfc\u51fa Cookie</button>
+            <button class="dropdown-item" data-action="import_cookies" data-username="${escAttr(item.username)}">\u5bfc\u5165 Cookie</button>
+            <button class="dropdown-item" data-action="change_password" data-username="${escAttr(item.username)}">\u4fee\u6539\u5bc6\u7801</button>
             <div class="dropdown-sep"></div>
             <button class="dropdown-item danger" data-action="delete_account" data-username="${escAttr(item.username)}">\u5220\u9664\u8d26\u53f7</button>
           </div>
@@ -710,6 +1356,52 @@ function renderResults() {
   }).join("");
 }
 
+function renderHealthBadge(item) {
+  const health = String((item && item.health) || "");
+  const labels = {
+    degraded: "\u964d\u7ea7",
+    auth_failed: "\u8ba4\u8bc1\u5931\u8d25",
+    banned: "\u5c01\u7981",
+    quarantined: "\u9694\u79bb",
+    stale: "\u8fc7\u671f"
+  };
+  if (!labels[health]) return "";
+  const score = Number(item.health_score || 0);
+  return ` <span class="badge badge-health-${health}" title="\u5065\u5eb7\u5206: ${score}">${labels[health]}</span>`;
+}
+
+// \u989d\u5ea6\u540c\u6b65\u5931\u8d25\u65f6\u63d0\u793a\uff0c\u6210\u529f\u65f6\u4e0d\u5360\u4f4d
+function renderSyncBadge(item) {
+  if (!item || item.apikey_sync_success !== false) return "";
+  const title = item.apikey_sync_message || "\u989d\u5ea6\u540c\u6b65\u5931\u8d25";
+  return ` <span class="badge badge-sync-failed" title="${escAttr(title)}">\u540c\u6b65\u5931\u8d25</span>`;
+}
+
+function renderOverdraftBadge(item) {
+  if (!item || !item.overdrawn) return "";
+  return ` <span class="badge badge-overdrawn" title="\u4f59\u989d\u4e3a\u8d1f\uff0c\u8d26\u53f7\u5df2\u900f\u652f">\u900f\u652f</span>`;
+}
+
+// \u7531\u4ec5 API \u5feb\u901f\u5237\u65b0\u66f4\u65b0\u7684\u884c\uff0c\u4e0e\u5b8c\u6574\u67e5\u8be2\u8f6e\u6b21\u7684\u7ed3\u679c\u533a\u5206
+function renderRefresherBadge(item) {
+  if (!item || item.refresher !== "fast") return "";
+  return ` <span class="badge badge-fast" title="\u7531\u4ec5 API \u5feb\u901f\u5237\u65b0\u66f4\u65b0">\u5feb\u901f</span>`;
+}
+
+function getBalanceChangeClass(username) {
+  if (!(username in state.balanceChanges)) return "";
+  const delta = state.balanceChanges[username];
+  if (delta === null) return " balance-changed";
+  return delta > 0 ? " balance-up" : " balance-down";
+}
+
+function renderBalanceDelta(username) {
+  const delta = state.balanceChanges[username];
+  if (typeof delta !== "number") return "";
+  const sign = delta > 0 ? "+" : "";
+  return ` <span class="balance-delta">${sign}${delta.toFixed(2)}</span>`;
+}
+
 function renderVisibleBadge(displayedRows) {
   if (!refs.visibleBadge) return;
   const total = state.results.length;
@@ -727,12 +1419,52 @@ function renderVisibleBadge(displayedRows) {
 function renderTotalBadge() {
   if (state.totalBalanceCount > 0) {
     refs.totalBadge.style.display = "";
-    refs.totalBadge.textContent = `\u603b\u4f59\u989d: $${state.totalBalance.toFixed(2)} (${state.totalBalanceCount}\u4e2a)`;
+    refs.totalBadge.textContent = `\u603b\u4f59\u989d: ${formatMoney(state.totalBalance, state.totalCurrency)} (${state.totalBalanceCount}\u4e2a)`;
   } else {
     refs.totalBadge.style.display = "none";
   }
 }
 
+function renderAlertBadge() {
+  if (state.activeAlerts > 0) {
+    refs.alertBadge.style.display = "";
+    refs.alertBadge.textContent = `\u544a\u8b66: ${state.activeAlerts}`;
+  } else {
+    refs.alertBadge.style.display = "none";
+  }
+}
+
+const ALERT_KIND_LABELS = {
+  failure: "\u67e5\u8be2\u5931\u8d25",
+  balance_drop: "\u4f59\u989d\u4e0b\u964d",
+  low_balance: "\u4f59\u989d\u504f\u4f4e",
+  overdraft: "\u4f59\u989d\u900f\u652f",
+  unit_rate: "\u989d\u5ea6\u6bd4\u4f8b\u5f02\u5e38"
+};
+
+async function showAlerts() {
+  try {
+    const { alerts } = await invoke("list_alerts_command");
+    if (!alerts.length) {
+      state.activeAlerts = 0;
+      renderAlertBadge();
+      return;
+    }
+    const lines = alerts.map((item) => {
+      const label = ALERT_KIND_LABELS[item.kind] || item.kind;
+      const times = item.occurrences > 1 ? ` (x${item.occurrences})` : "";
+      return `[${label}] ${item.username}: ${item.message}${times}`;
+    });
+    if (!confirm(`${lines.join("\n")}\n\n\u786e\u8ba4\u4ee5\u4e0a ${alerts.length} \u6761\u544a\u8b66\uff1f`)) return;
+    const response = await invoke("ack_alert_command", { id: null });
+    state.activeAlerts = response.active_count;
+    renderAlertBadge();
+    pushLog(`\u5df2\u786e\u8ba4 ${alerts.length} \u6761\u544a\u8b66`);
+  } catch (error) {
+    setStatus(`\u8bfb\u53d6\u544a\u8b66\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
 function renderAccountsTable() {
   if (state.accounts.length === 0) {
     refs.accountsBody.innerHTML = `<tr><td colspan="4" class="empty-state">\u6682\u65e0\u8d26\u53f7</td></tr>`;
@@ -740,9 +1472,9 @@ function renderAccountsTable() {
   }
   refs.accountsBody.innerHTML = state.accounts.map((item) => `
     <tr>
-      <td>${esc(item.username)}</td>
+      <td>${renderAccountName(item)}</td>
       <td class="td-masked">${maskText(item.password)}</td>
-      <td class="td-masked">${item.api_key ? maskText(item.api_key) : "-"}</td>
+      <td class="td-masked">${item.api_key ? maskText(item.api_key) : "-"}${(item.keys || []).length > 1 ? ` (+${item.keys.length - 1})` : ""}</td>
       <td>
         <button class="ghost" data-action="edit" data-username="${escAttr(item.username)}">\u7f16\u8f91</button>
         <button class="danger" data-action="delete" data-username="${escAttr(item.username)}">\u5220\u9664</button>
@@ -751,6 +1483,12 @@ function renderAccountsTable() {
   `).join("");
 }
 
+// \u8bbe\u7f6e\u4e86\u522b\u540d\u65f6\u663e\u793a\u522b\u540d\uff0c\u9f20\u6807\u60ac\u505c\u663e\u793a\u767b\u5f55\u7528\u6237\u540d
+function renderAccountName(item) {
+  if (!item.alias) return `<span class="account-name">${esc(item.username)}</span>`;
+  return `<span class="account-name" title="${escAttr(item.username)}">${esc(item.alias)}</span>`;
+}
+
 // ========== Dropdown ==========
 
 function onResultsAction(e) {
@@ -829,7 +1567,19 @@ async function handleResultAction(action, username) {
   if (action === "copy_key") await copyApiKey(username);
   else if (action === "set_claude") await setClaudeToken(username);
   else if (action === "set_openai") await setOpenAiToken(username);
+  else if (action === "export_env") await exportEnv(username);
+  else if (action === "create_token") await createToken(username);
+  else if (action === "interactive_login") await interactiveLogin(username);
+  else if (action === "resync_quota") await resyncQuota(username);
+  else if (action === "preview_quota") await previewQuotaSync(username);
+  else if (action === "mark_web_done") await updateWebState("mark_web_done_command", username);
+  else if (action === "reset_web_state") await updateWebState("reset_web_state_command", username);
+  else if (action === "export_cookies") await exportCookies(username);
+  else if (action === "import_cookies") await importCookies(username);
+  else if (action === "change_password") await changePassword(username);
   else if (action === "delete_account") await deleteAccount(username);
+  else if (action === "account_stats") await showAccountStats(username);
+  else if (action === "toggle_pin" || action === "move_up" || action === "move_down") await reorderAccount(username, action);
 }
 
 // ========== Accounts Tab ==========
@@ -844,14 +1594,35 @@ function onAccountsAction(e) {
   else if (action === "delete") deleteAccount(username);
 }
 
-function fillEditor(username) {
+async function fillEditor(username) {
+  if (state.locked && !(await requestUnlock())) return;
   const account = state.accounts.find((item) => item.username === username);
   if (!account) return;
   state.editingUsername = username;
   refs.formTitle.textContent = `\u7f16\u8f91\u8d26\u53f7: ${username}`;
   refs.editUsername.value = account.username || "";
   refs.editPassword.value = account.password || "";
-  refs.editApiKey.value = account.api_key || "";
+  refs.editApiKey.value = formatAccountKeys(account);
+  refs.editAlias.value = account.alias || "";
+}
+
+// \u4e0e\u8d26\u53f7\u6587\u4ef6\u7b2c\u4e09\u5217\u4e00\u81f4: \u5355\u4e2a\u9ed8\u8ba4 Key \u76f4\u63a5\u663e\u793a\uff0c\u591a\u4e2a Key \u5199\u4f5c \u540d\u79f0=Key;\u540d\u79f0=Key
+function formatAccountKeys(account) {
+  const keys = account.keys || [];
+  if (keys.length === 0) return account.api_key || "";
+  if (keys.length === 1 && keys[0].name === "default") return keys[0].key;
+  return keys.map((item) => `${item.name}=${item.key}`).join(";");
+}
+
+// \u8d26\u53f7\u6709\u591a\u4e2a Key \u65f6\u8ba9\u7528\u6237\u9009\u62e9\u540d\u79f0\uff1b\u8fd4\u56de "" \u8868\u793a\u9ed8\u8ba4 Key\uff0cnull \u8868\u793a\u53d6\u6d88
+function pickAccountKey(username) {
+  const account = state.accounts.find((item) => item.username === username);
+  const keys = (account && account.keys) || [];
+  if (keys.length <= 1) return "";
+  const names = keys.map((item) => item.name);
+  const input = prompt(`\u9009\u62e9 ${username} \u7684 Key: ${names.join(" / ")}`, names[0]);
+  if (input === null) return null;
+  return input.trim();
 }
 
 function cancelEdit() {
@@ -860,12 +1631,14 @@ function cancelEdit() {
   refs.editUsername.value = "";
   refs.editPassword.value = "";
   refs.editApiKey.value = "";
+  refs.editAlias.value = "";
 }
 
 async function saveAccountFromEditor() {
   const username = refs.editUsername.value.trim();
   const password = refs.editPassword.value.trim();
   const apiKey = refs.editApiKey.value.trim();
+  const alias = refs.editAlias.value.trim();
   if (!username || !password) {
     setStatus("\u7528\u6237\u540d\u548c\u5bc6\u7801\u4e0d\u80fd\u4e3a\u7a7a", "warn");
     return;
@@ -875,7 +1648,8 @@ async function saveAccountFromEditor() {
       username,
       password,
       apiKey: apiKey || null,
-      api_key: apiKey || null
+      api_key: apiKey || null,
+      alias
     });
     state.accounts = response.accounts || [];
     cancelEdit();
@@ -891,29 +1665,45 @@ async function saveAccountFromEditor() {
 
 // ========== Query ==========
 
-async function runQuery() {
+// dueOnly \u4e3a true \u65f6(\u81ea\u52a8\u8f6e\u8be2\u4e14\u914d\u7f6e\u4e86\u6309\u8d26\u53f7\u95f4\u9694)\u53ea\u67e5\u8be2\u5df2\u5230\u671f\u7684\u8d26\u53f7\uff0c\u7ed3\u679c\u5408\u5e76\u8fdb\u73b0\u6709\u5217\u8868
+async function runQuery(dueOnly = false) {
   if (state.isRunning) return;
   state.isRunning = true;
   scheduleStatusRender();
-  const target = state.selectedUsername || null;
-  const title = target ? `\u67e5\u8be2\u8d26\u53f7: ${target}` : `\u67e5\u8be2\u5168\u90e8 ${state.accounts.length} \u4e2a\u8d26\u53f7`;
+  state.balanceChanges = {};
+  const target = dueOnly ? null : (state.selectedUsername || null);
+  const dueInterval = dueOnly ? state.queryInterval : null;
+  const title = dueOnly ? "\u67e5\u8be2\u5230\u671f\u8d26\u53f7"
+    : target ? `\u67e5\u8be2\u8d26\u53f7: ${target}` : `\u67e5\u8be2\u5168\u90e8 ${state.accounts.length} \u4e2a\u8d26\u53f7`;
   pushLog("==================================================");
   pushLog(title);
   setStatus("\u67e5\u8be2\u4e2d...", "busy");
   try {
     const r = await invoke("query_balances_command", {
       targetUsername: target,
-      target_username: target
+      target_username: target,
+      dueInterval,
+      due_interval: dueInterval
     });
-    state.results = r.results || [];
+    if (dueOnly) {
+      mergeResults(r.results || []);
+    } else {
+      state.results = r.results || [];
+    }
+    state.totalCurrency = r.total_currency || state.totalCurrency;
+    state.currencyRates = r.currency_rates || state.currencyRates;
     recalculateTotals();
     state.lastFinished = r.finished_at || "-";
     renderMeta();
     renderResults();
     renderTotalBadge();
     pushLog(`\u5b8c\u6210: \u6210\u529f ${r.success_count} / \u5931\u8d25 ${r.fail_count}`);
-    if (r.total_balance_count > 0) {
-      pushLog(`\u603b\u4f59\u989d: $${Number(r.total_balance || 0).toFixed(2)}`);
+    if (Array.isArray(r.deferred) && r.deferred.length > 0) {
+      pushLog(`\u8d85\u51fa\u65f6\u95f4\u9884\u7b97\uff0c\u63a8\u8fdf\u7f51\u9875\u767b\u5f55: ${r.deferred.join(", ")}`);
+    }
+    logFallbackAccounts(r.summary);
+    if (state.totalBalanceCount > 0) {
+      pushLog(`\u603b\u4f59\u989d: ${formatMoney(state.totalBalance, state.totalCurrency)}`);
     }
     pushLog("==================================================");
     setStatus(`\u67e5\u8be2\u5b8c\u6210\uff0c\u8017\u65f6 ${Number(r.elapsed_secs || 0).toFixed(2)}s`, "ok");
@@ -926,14 +1716,38 @@ async function runQuery() {
   } finally {
     state.isRunning = false;
     if (state.autoMode) state.autoCountdown = state.queryInterval;
+    if (state.autoMode && state.scheduledIntervals) await refreshScheduleCountdown();
     scheduleStatusRender();
   }
 }
 
+function mergeResults(rows) {
+  const byName = new Map(rows.map((item) => [item.username, item]));
+  state.results = state.results.map((item) => byName.get(item.username) || item);
+  const known = new Set(state.results.map((item) => item.username));
+  rows.forEach((item) => {
+    if (!known.has(item.username)) state.results.push(item);
+  });
+}
+
+// \u6309\u5404\u8d26\u53f7\u95f4\u9694\u53d6\u6700\u65e9\u5230\u671f\u65f6\u95f4\u4f5c\u4e3a\u5012\u8ba1\u65f6
+async function refreshScheduleCountdown() {
+  try {
+    const schedule = await invoke("get_schedule_command", {
+      defaultInterval: state.queryInterval,
+      default_interval: state.queryInterval
+    });
+    state.autoCountdown = Math.max(1, Number(schedule.next_due_in_secs || state.queryInterval));
+  } catch (error) {
+    pushLog(`\u8bfb\u53d6\u67e5\u8be2\u8ba1\u5212\u5931\u8d25: ${toErrorMessage(error)}`);
+  }
+}
+
 async function runWebLoginOnly() {
   if (state.isRunning) return;
   state.isRunning = true;
   scheduleStatusRender();
+  state.balanceChanges = {};
   const target = state.selectedUsername || null;
   const title = target
     ? `\u4ec5\u7f51\u9875\u767b\u5f55: ${target}`
@@ -947,12 +1761,18 @@ async function runWebLoginOnly() {
       target_username: target
     });
     state.results = r.results || [];
+    state.totalCurrency = r.total_currency || state.totalCurrency;
+    state.currencyRates = r.currency_rates || state.currencyRates;
     recalculateTotals();
     state.lastFinished = r.finished_at || "-";
     renderMeta();
     renderResults();
     renderTotalBadge();
     pushLog(`\u5b8c\u6210: \u6210\u529f ${r.success_count} / \u5931\u8d25 ${r.fail_count}`);
+    if (Array.isArray(r.deferred) && r.deferred.length > 0) {
+      pushLog(`\u8d85\u51fa\u65f6\u95f4\u9884\u7b97\uff0c\u63a8\u8fdf\u7f51\u9875\u767b\u5f55: ${r.deferred.join(", ")}`);
+    }
+    logFallbackAccounts(r.summary);
     pushLog("==================================================");
     setStatus(`\u7f51\u9875\u767b\u5f55\u5b8c\u6210\uff0c\u8017\u65f6 ${Number(r.elapsed_secs || 0).toFixed(2)}s`, "ok");
     await maybeAutoSwitchClaudeKey().catch((error) => {
@@ -967,90 +1787,85 @@ async function runWebLoginOnly() {
   }
 }
 
+async function runRetryFailed() {
+  if (state.isRunning) return;
+  state.isRunning = true;
+  scheduleStatusRender();
+  state.balanceChanges = {};
+  pushLog("==================================================");
+  pushLog("\u91cd\u8bd5\u4e0a\u4e00\u8f6e\u5931\u8d25\u7684\u8d26\u53f7");
+  setStatus("\u91cd\u8bd5\u4e2d...", "busy");
+  try {
+    const r = await invoke("retry_failed_command");
+    state.results = r.results || [];
+    state.totalCurrency = r.total_currency || state.totalCurrency;
+    state.currencyRates = r.currency_rates || state.currencyRates;
+    recalculateTotals();
+    state.lastFinished = r.finished_at || "-";
+    renderMeta();
+    renderResults();
+    renderTotalBadge();
+    pushLog(`\u91cd\u8bd5\u5b8c\u6210: \u6210\u529f ${r.success_count} / \u5931\u8d25 ${r.fail_count}`);
+    if (Array.isArray(r.deferred) && r.deferred.length > 0) {
+      pushLog(`\u8d85\u51fa\u65f6\u95f4\u9884\u7b97\uff0c\u63a8\u8fdf\u7f51\u9875\u767b\u5f55: ${r.deferred.join(", ")}`);
+    }
+    logFallbackAccounts(r.summary);
+    pushLog("==================================================");
+    setStatus(`\u91cd\u8bd5\u5b8c\u6210\uff0c\u8017\u65f6 ${Number(r.elapsed_secs || 0).toFixed(2)}s`, "ok");
+  } catch (error) {
+    setStatus(`\u91cd\u8bd5\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    pushLog(`\u91cd\u8bd5\u5931\u8d25: ${toErrorMessage(error)}`);
+  } finally {
+    state.isRunning = false;
+    scheduleStatusRender();
+  }
+}
+
 async function maybeAutoSwitchClaudeKey() {
   if (!state.autoSwitchEnabled) return;
 
   const threshold = Number(state.autoSwitchThreshold);
   if (!Number.isFinite(threshold) || threshold < 0) return;
 
-  await refreshClaudeAccount();
-  const currentAccount = state.claudeAccount;
-  if (!currentAccount) return;
-
-  const keyableAccounts = new Set(
-    state.accounts
-      .filter((item) => item && String(item.api_key || "").trim() !== "")
-      .map((item) => item.username)
-  );
-
-  const rows = await getMergedResultsForDecision();
-  const currentRow = rows.find((row) => row && row.username === currentAccount);
-  const currentBalance = currentRow && currentRow.success
-    ? parseBalance(currentRow.balance_text || "")
-    : null;
-  if (currentBalance === null) return;
-  if (currentBalance >= threshold) return;
-
-  let best = null;
-  rows.forEach((row) => {
-    if (!row || !row.success) return;
-    if (!keyableAccounts.has(row.username)) return;
-    const v = parseBalance(row.balance_text || "");
-    if (v === null) return;
-    if (!best || v > best.balance) {
-      best = { username: row.username, balance: v };
-    }
+  const result = await invoke("auto_switch_claude_command", {
+    strategy: state.autoSwitchStrategy,
+    threshold
   });
-
-  if (!best || best.username === currentAccount) return;
-  await invoke("save_claude_token_command", { username: best.username });
+  if (!result.switched) return;
   await refreshClaudeAccount();
   renderMeta();
   renderResults();
 
-  const msg = `\u4f59\u989d\u4f4e\u4e8e\u9608\u503c $${threshold.toFixed(1)}\uff0c\u5df2\u81ea\u52a8\u5207\u6362 Claude Token: ${currentAccount}($${currentBalance.toFixed(1)}) -> ${best.username}($${best.balance.toFixed(1)})`;
-  pushLog(msg);
-  setStatus(`\u5df2\u81ea\u52a8\u5207\u6362 Claude: ${best.username} ($${best.balance.toFixed(1)})`, "ok");
-}
-
-async function getMergedResultsForDecision() {
-  const hasAllRows = state.results.length >= state.accounts.length && state.accounts.length > 0;
-  if (hasAllRows) return state.results;
-
-  let cached = [];
-  try {
-    const r = await invoke("get_cached_results_command");
-    cached = Array.isArray(r) ? r : [];
-  } catch (_) {
-    cached = [];
-  }
-
-  const map = new Map();
-  cached.forEach((row) => {
-    if (row && row.username) map.set(row.username, row);
-  });
-  state.results.forEach((row) => {
-    if (row && row.username) map.set(row.username, row);
-  });
-  return Array.from(map.values());
+  pushLog(`\u4f59\u989d\u4f4e\u4e8e\u9608\u503c $${threshold.toFixed(1)}\uff0c${result.message}`);
+  setStatus(`\u5df2\u81ea\u52a8\u5207\u6362 Claude: ${result.to} ($${Number(result.balance || 0).toFixed(1)})`, "ok");
 }
 
 // ========== Account Actions ==========
 
 async function copyApiKey(username) {
+  if (state.locked && !(await requestUnlock())) return;
   const account = state.accounts.find((item) => item.username === username);
   if (!account || !account.api_key) {
     setStatus(`${username} \u672a\u914d\u7f6e API Key`, "warn");
     return;
   }
-  await navigator.clipboard.writeText(account.api_key);
+  const name = pickAccountKey(username);
+  if (name === null) return;
+  const selected = name ? (account.keys || []).find((item) => item.name.toLowerCase() === name.toLowerCase()) : null;
+  if (name && !selected) {
+    setStatus(`${username} \u6ca1\u6709\u540d\u4e3a ${name} \u7684 Key`, "warn");
+    return;
+  }
+  await navigator.clipboard.writeText(selected ? selected.key : account.api_key);
   setStatus(`\u5df2\u590d\u5236 ${username} \u7684 API Key`, "ok");
   pushLog(`\u5df2\u590d\u5236 ${username} \u7684 API Key`);
 }
 
 async function setClaudeToken(username) {
+  const key = pickAccountKey(username);
+  if (key === null) return;
   try {
-    const msg = await invoke("save_claude_token_command", { username });
+    const msg = await invoke("save_claude_token_command", { username, key: key || null });
     await refreshClaudeAccount();
     renderMeta();
     renderResults();
@@ -1062,8 +1877,10 @@ async function setClaudeToken(username) {
 }
 
 async function setOpenAiToken(username) {
+  const key = pickAccountKey(username);
+  if (key === null) return;
   try {
-    const msg = await invoke("save_openai_key_command", { username });
+    const msg = await invoke("save_openai_key_command", { username, key: key || null });
     setStatus(`\u5df2\u8bbe\u7f6e ${username} \u7684 OpenAI Key`, "ok");
     pushLog(msg);
   } catch (error) {
@@ -1071,6 +1888,231 @@ async function setOpenAiToken(username) {
   }
 }
 
+async function exportEnv(username) {
+  const input = prompt("Shell \u7c7b\u578b (bash / zsh / powershell):", "bash");
+  const shell = (input || "").trim();
+  if (!shell) return;
+  const key = pickAccountKey(username);
+  if (key === null) return;
+  try {
+    const result = await invoke("export_env_command", { username, shell, key: key || null });
+    await navigator.clipboard.writeText(result.content);
+    setStatus(`\u5df2\u590d\u5236 ${username} \u7684 ${result.shell} \u73af\u5883\u53d8\u91cf`, "ok");
+    pushLog(`\u5df2\u590d\u5236 ${username} \u7684 ${result.shell} \u73af\u5883\u53d8\u91cf`);
+  } catch (error) {
+    setStatus(`\u5bfc\u51fa\u73af\u5883\u53d8\u91cf\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+async function createToken(username) {
+  if (state.isRunning) {
+    setStatus("\u67e5\u8be2\u8fdb\u884c\u4e2d\uff0c\u8bf7\u7a0d\u540e\u518d\u8bd5", "warn");
+    return;
+  }
+  const input = prompt("\u4ee4\u724c\u7528\u9014 (\u5982 claude / codex\uff0c\u53ef\u7559\u7a7a):", "");
+  if (input === null) return;
+  setStatus(`\u6b63\u5728\u4e3a ${username} \u65b0\u5efa\u4ee4\u724c...`, "busy");
+  try {
+    const result = await invoke("create_account_token_command", { username, tool: input.trim() || null });
+    let message = `\u5df2\u4e3a ${username} \u65b0\u5efa\u4ee4\u724c ${result.label.name}`;
+    if (result.key) {
+      await navigator.clipboard.writeText(result.key);
+      message += "\uff0cKey \u5df2\u590d\u5236";
+    } else {
+      message += "\uff0c\u7ad9\u70b9\u672a\u8fd4\u56de\u660e\u6587 Key\uff0c\u8bf7\u5728\u63a7\u5236\u53f0\u590d\u5236";
+    }
+    setStatus(message, "ok");
+    pushLog(message);
+  } catch (error) {
+    setStatus(`\u65b0\u5efa\u4ee4\u724c\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+async function interactiveLogin(username) {
+  if (state.isRunning) {
+    setStatus("\u67e5\u8be2\u8fdb\u884c\u4e2d\uff0c\u8bf7\u7a0d\u540e\u518d\u8bd5", "warn");
+    return;
+  }
+  state.isRunning = true;
+  scheduleStatusRender();
+  setStatus(`\u7b49\u5f85\u624b\u52a8\u767b\u5f55: ${username}`, "busy");
+  pushLog(`\u5df2\u4e3a ${username} \u6253\u5f00\u6d4f\u89c8\u5668\uff0c\u8bf7\u5728\u7a97\u53e3\u4e2d\u5b8c\u6210\u767b\u5f55`);
+  try {
+    const result = await invoke("interactive_login_command", { username });
+    const index = state.results.findIndex((item) => item.username === username);
+    if (index >= 0) state.results[index] = result;
+    else state.results.push(result);
+    recalculateTotals();
+    renderResults();
+    renderTotalBadge();
+    pushLog(`${username}: ${result.message || "-"}`);
+    setStatus(result.success ? `\u624b\u52a8\u767b\u5f55\u5b8c\u6210: ${username}` : `\u624b\u52a8\u767b\u5f55\u5931\u8d25: ${username}`, result.success ? "ok" : "error");
+  } catch (error) {
+    setStatus(`\u624b\u52a8\u767b\u5f55\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  } finally {
+    state.isRunning = false;
+    scheduleStatusRender();
+  }
+}
+
+async function previewQuotaSync(username) {
+  if (state.isRunning) return;
+  const key = pickAccountKey(username);
+  if (key === null) return;
+  state.isRunning = true;
+  scheduleStatusRender();
+  setStatus(`\u6b63\u5728\u9884\u6f14 ${username} \u7684\u989d\u5ea6\u540c\u6b65...`, "busy");
+  try {
+    const preview = await invoke("preview_quota_sync_command", { username, key: key || null });
+    const rateNote = preview.stored_rate != null ? "\u672a\u8bc6\u522b\uff0c\u6cbf\u7528\u5df2\u8bb0\u5f55\u6bd4\u4f8b" : "\u672a\u8bc6\u522b\uff0c\u4f7f\u7528\u9ed8\u8ba4\u503c";
+    const rateText = preview.rate_detected ? `${preview.unit_rate}` : `${preview.unit_rate} (${rateNote})`;
+    const lines = preview.tokens.map((item) =>
+      `${item.selected ? "-> " : "   "}${item.name} ${item.key}: ${item.unlimited ? "\u4e0d\u9650\u989d\u5ea6" : item.remain_quota}`
+    );
+    const warnings = [];
+    if (preview.rate_conflict) warnings.push(`\u8bc6\u522b\u5230\u7684\u6bd4\u4f8b\u4e0e\u5df2\u8bb0\u5f55\u7684 ${preview.stored_rate} \u76f8\u5dee\u8fc7\u5927\uff0c\u5b9e\u9645\u540c\u6b65\u4f1a\u8df3\u8fc7\u5199\u5165`);
+    if (preview.guard_rejection) warnings.push(`\u5b9e\u9645\u540c\u6b65\u4f1a\u62d2\u7edd\u5199\u5165: ${preview.guard_rejection}`);
+    const summary = `${username} \u4f59\u989d $${preview.balance.toFixed(2)} x \u6bd4\u4f8b ${rateText} = \u989d\u5ea6\u503c ${preview.target_quota}\uff0c\u5c06\u5199\u5165 ${preview.target}`;
+    setStatus(summary, preview.rate_detected && warnings.length === 0 ? "ok" : "warn");
+    pushLog(`[\u9884\u6f14] ${[summary, ...warnings].join("; ")}`);
+    alert(`${[summary, ...warnings].join("\n")}\n\n${lines.join("\n") || "\u6ca1\u6709\u4ee4\u724c"}`);
+  } catch (error) {
+    setStatus(`\u989d\u5ea6\u540c\u6b65\u9884\u6f14\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  } finally {
+    state.isRunning = false;
+    scheduleStatusRender();
+  }
+}
+
+async function resyncQuota(username) {
+  if (state.isRunning) return;
+  const key = username ? pickAccountKey(username) : "";
+  if (key === null) return;
+  state.isRunning = true;
+  scheduleStatusRender();
+  setStatus(username ? `\u6b63\u5728\u91cd\u65b0\u540c\u6b65 ${username} \u7684\u989d\u5ea6...` : "\u6b63\u5728\u91cd\u65b0\u540c\u6b65\u5931\u8d25\u8d26\u53f7\u7684\u989d\u5ea6...", "busy");
+  try {
+    const results = await invoke("resync_quota_command", { username, key: key || null });
+    for (const row of results) {
+      const index = state.results.findIndex((item) => item.username === row.username);
+      if (index >= 0) state.results[index] = row;
+      else state.results.push(row);
+    }
+    recalculateTotals();
+    renderResults();
+    renderTotalBadge();
+    const synced = results.filter((item) => item.apikey_sync_success === true).length;
+    const msg = `\u989d\u5ea6\u91cd\u65b0\u540c\u6b65\u5b8c\u6210: \u6210\u529f ${synced} / \u5171 ${results.length}`;
+    setStatus(msg, "ok");
+    pushLog(msg);
+  } catch (error) {
+    setStatus(`\u91cd\u65b0\u540c\u6b65\u989d\u5ea6\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  } finally {
+    state.isRunning = false;
+    scheduleStatusRender();
+  }
+}
+
+async function updateWebState(command, username) {
+  try {
+    const msg = await invoke(command, { username });
+    setStatus(msg, "ok");
+    pushLog(msg);
+  } catch (error) {
+    setStatus(`\u66f4\u65b0\u7b7e\u5230\u72b6\u6001\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+async function exportCookies(username) {
+  const path = prompt("\u5bfc\u51fa\u5230\u6587\u4ef6(\u7559\u7a7a\u4ec5\u4fdd\u5b58\u5230\u914d\u7f6e\u76ee\u5f55):", "");
+  if (path === null) return;
+  setStatus(`\u6b63\u5728\u8bfb\u53d6 ${username} \u7684 Cookie...`, "busy");
+  try {
+    const msg = await invoke("export_account_cookies_command", { username, path: path.trim() || null });
+    setStatus(msg, "ok");
+    pushLog(`${username}: ${msg}`);
+  } catch (error) {
+    setStatus(`\u5bfc\u51fa Cookie \u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+async function importCookies(username) {
+  const path = prompt("Cookie \u6587\u4ef6\u8def\u5f84(JSON \u6216 cookies.txt):", "");
+  if (!path || !path.trim()) return;
+  try {
+    const msg = await invoke("import_account_cookies_command", { username, path: path.trim() });
+    setStatus(msg, "ok");
+    pushLog(`${username}: ${msg}`);
+  } catch (error) {
+    setStatus(`\u5bfc\u5165 Cookie \u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+async function changePassword(username) {
+  const input = prompt(`\u4e3a ${username} \u8bbe\u7f6e\u65b0\u5bc6\u7801(\u5c06\u767b\u5f55\u7ad9\u70b9\u4fee\u6539\u5e76\u7528\u65b0\u5bc6\u7801\u9a8c\u8bc1):`, "");
+  const newPassword = (input || "").trim();
+  if (!newPassword) return;
+  setStatus(`\u6b63\u5728\u4fee\u6539 ${username} \u7684\u5bc6\u7801...`, "busy");
+  try {
+    const msg = await invoke("change_password_command", { username, newPassword });
+    const account = state.accounts.find((item) => item.username === username);
+    if (account) account.password = newPassword;
+    setStatus(msg, "ok");
+    pushLog(`${username}: ${msg}`);
+  } catch (error) {
+    setStatus(`\u4fee\u6539\u5bc6\u7801\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    pushLog(`${username}: \u4fee\u6539\u5bc6\u7801\u5931\u8d25: ${toErrorMessage(error)}`);
+  }
+}
+
+async function showAccountStats(username) {
+  try {
+    const s = await invoke("get_account_stats_command", { username, recent: 10 });
+    const percent = (value) => `${(Number(value || 0) * 100).toFixed(0)}%`;
+    const lines = [
+      `${username}: \u5171 ${s.total} \u6b21\u68c0\u67e5\uff0c\u6210\u529f\u7387 ${percent(s.success_rate)}`,
+      `\u5e73\u5747\u8017\u65f6 ${(Number(s.avg_duration_ms || 0) / 1000).toFixed(1)}s\uff0c\u7f51\u9875 ${s.web_count} / API ${s.api_count} (\u7f51\u9875\u5360 ${percent(s.web_ratio)})`,
+      s.first_at ? `\u8bb0\u5f55\u8303\u56f4: ${s.first_at} ~ ${s.last_at}` : "\u6682\u65e0\u68c0\u67e5\u8bb0\u5f55",
+      ""
+    ];
+    for (const item of s.recent || []) {
+      const mark = item.success ? "[OK]" : "[\u5931\u8d25]";
+      lines.push(`${mark} ${item.at} ${item.source}${item.success ? "" : ` - ${item.message}`}`);
+    }
+    alert(lines.join("\n"));
+  } catch (error) {
+    setStatus(`\u8bfb\u53d6\u68c0\u67e5\u7edf\u8ba1\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+// \u6309\u5f53\u524d\u7ed3\u679c\u987a\u5e8f\u8c03\u6574\u540e\u6574\u4f53\u63d0\u4ea4\uff0c\u4e0a\u79fb/\u4e0b\u79fb\u53ea\u5728\u540c\u4e3a\u7f6e\u9876\u6216\u540c\u4e3a\u672a\u7f6e\u9876\u7684\u8d26\u53f7\u4e4b\u95f4\u4ea4\u6362
+async function reorderAccount(username, action) {
+  const order = state.results.map((item) => item.username);
+  const pinned = new Set(state.pinned);
+  const index = order.indexOf(username);
+  if (index < 0) return;
+  if (action === "toggle_pin") {
+    if (pinned.has(username)) pinned.delete(username);
+    else pinned.add(username);
+  } else {
+    const target = action === "move_up" ? index - 1 : index + 1;
+    if (target < 0 || target >= order.length || pinned.has(order[target]) !== pinned.has(username)) return;
+    [order[index], order[target]] = [order[target], order[index]];
+  }
+  try {
+    const r = await invoke("reorder_accounts_command", { order, pinned: [...pinned] });
+    const rank = new Map((r.order || []).map((name, i) => [name, i]));
+    const byRank = (a, b) => (rank.get(a.username) ?? Infinity) - (rank.get(b.username) ?? Infinity);
+    state.pinned = r.pinned || [];
+    state.results.sort(byRank);
+    state.accounts.sort(byRank);
+    renderResults();
+    renderAccountSelect();
+  } catch (error) {
+    setStatus(`\u8c03\u6574\u8d26\u53f7\u987a\u5e8f\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
 async function deleteAccount(username) {
   if (!confirm(`\u786e\u8ba4\u5220\u9664\u8d26\u53f7 ${username} ?`)) return;
   try {
@@ -1085,10 +2127,34 @@ async function deleteAccount(username) {
     renderResults();
     renderTotalBadge();
     renderAccountsTable();
-    setStatus(r.message || "\u5220\u9664\u5b8c\u6210", r.success ? "ok" : "warn");
+    const message = r.success ? `${r.message}\uff0c\u53ef\u5728\u5de5\u5177\u83dc\u5355\u300c\u6062\u590d\u5df2\u5220\u9664\u8d26\u53f7\u300d\u64a4\u9500` : r.message;
+    setStatus(message || "\u5220\u9664\u5b8c\u6210", r.success ? "ok" : "warn");
     pushLog(r.message || "\u5220\u9664\u5b8c\u6210");
   } catch (error) {
-    setStatus(`\u5220\u9664\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    setStatus(`\u5220\u9664\u5931\u8d25: ${toErrorMessage(error)}`, errorCode(error) === "busy" ? "warn" : "error");
+  }
+}
+
+async function restoreAccount() {
+  let removed;
+  try {
+    removed = await invoke("list_removed_accounts_command");
+  } catch (error) {
+    setStatus(`\u8bfb\u53d6\u5220\u9664\u8bb0\u5f55\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    return;
+  }
+  if (!removed.length) {
+    setStatus("\u6ca1\u6709\u53ef\u6062\u590d\u7684\u5df2\u5220\u9664\u8d26\u53f7", "ok");
+    return;
+  }
+  const lines = removed.map((item) => `${item.username}  ${item.key_count} \u4e2a Key  \u5220\u9664\u4e8e ${item.removed_at}`);
+  const input = prompt(`\u8f93\u5165\u8981\u6062\u590d\u7684\u8d26\u53f7:\n${lines.join("\n")}`, removed[0].username);
+  const username = (input || "").trim();
+  if (!username) return;
+  try {
+    applyAccountsResponse(await invoke("restore_account_command", { username }));
+  } catch (error) {
+    setStatus(`\u6062\u590d\u8d26\u53f7\u5931\u8d25: ${toErrorMessage(error)}`, "error");
   }
 }
 
@@ -1096,7 +2162,20 @@ async function reloadAccounts() {
   if (state.isRunning) return;
   setStatus("\u6b63\u5728\u91cd\u65b0\u52a0\u8f7d...", "busy");
   try {
-    const r = await invoke("reload_accounts_command");
+    let r = await invoke("reload_accounts_command", { resolution: null });
+    if (r.conflicts && r.conflicts.length && !r.success) {
+      const lines = r.conflicts.map((item) => `${item.username}: \u754c\u9762${item.local}\uff0c\u6587\u4ef6${item.disk}`);
+      const input = prompt(
+        `${r.message}:\n${lines.join("\n")}\n\n\u8f93\u5165 disk \u4ee5\u8d26\u53f7\u6587\u4ef6\u4e3a\u51c6\uff0cmemory \u4fdd\u7559\u754c\u9762\u4fee\u6539:`,
+        "disk"
+      );
+      const resolution = (input || "").trim().toLowerCase();
+      if (!resolution) {
+        setStatus("\u5df2\u53d6\u6d88\u91cd\u65b0\u52a0\u8f7d", "warn");
+        return;
+      }
+      r = await invoke("reload_accounts_command", { resolution });
+    }
     state.accounts = r.accounts || [];
     syncResultsWithAccounts();
     renderMeta();
@@ -1111,6 +2190,114 @@ async function reloadAccounts() {
   }
 }
 
+async function discoverAccounts() {
+  if (state.isRunning) {
+    setStatus("\u67e5\u8be2\u8fdb\u884c\u4e2d\uff0c\u8bf7\u7a0d\u540e\u518d\u8bd5", "warn");
+    return;
+  }
+  const input = prompt("\u7ba1\u7406\u5458\u8d26\u53f7(\u7559\u7a7a\u4f7f\u7528\u914d\u7f6e\u4e2d\u7684 web_check.discovery_admin):", state.selectedUsername || "");
+  if (input === null) return;
+  setStatus("\u6b63\u5728\u767b\u5f55\u7ba1\u7406\u5458\u8d26\u53f7\u53d1\u73b0\u8d26\u53f7...", "busy");
+  let discovery;
+  try {
+    discovery = await invoke("discover_accounts_command", { adminUsername: input.trim() || null });
+  } catch (error) {
+    setStatus(`\u53d1\u73b0\u8d26\u53f7\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    return;
+  }
+  if (discovery.users_error) {
+    pushLog(`\u65e0\u6cd5\u8bfb\u53d6\u5b50\u8d26\u53f7\u5217\u8868: ${discovery.users_error}`);
+  }
+  const users = (discovery.users || []).filter((item) => !item.exists);
+  const tokens = (discovery.tokens || []).filter((item) => !item.exists && !item.masked);
+  if (users.length === 0 && tokens.length === 0) {
+    setStatus("\u6ca1\u6709\u53d1\u73b0\u53ef\u5bfc\u5165\u7684\u65b0\u8d26\u53f7\u6216\u4ee4\u724c", "ok");
+    return;
+  }
+  const lines = [
+    ...users.map((item) => `\u8d26\u53f7 ${item.username}${item.display_name ? ` (${item.display_name})` : ""}${item.enabled ? "" : " [\u5df2\u505c\u7528]"}`),
+    ...tokens.map((item) => `\u4ee4\u724c ${item.name || "-"} -> ${discovery.admin}`)
+  ];
+  if (!confirm(`\u53d1\u73b0\u4ee5\u4e0b\u53ef\u5bfc\u5165\u6761\u76ee:\n${lines.join("\n")}\n\n\u662f\u5426\u9010\u4e2a\u9009\u62e9\u5bfc\u5165\uff1f`)) return;
+
+  const imports = [];
+  for (const user of users) {
+    const password = prompt(`\u8f93\u5165 ${user.username} \u7684\u767b\u5f55\u5bc6\u7801(\u7559\u7a7a\u8df3\u8fc7\u8be5\u8d26\u53f7):`, "");
+    if (password === null) return;
+    if (password.trim()) imports.push({ username: user.username, password: password.trim(), keys: [] });
+  }
+  if (tokens.length > 0 && confirm(`\u628a ${tokens.length} \u4e2a\u4ee4\u724c\u8ffd\u52a0\u4e3a ${discovery.admin} \u7684 Key\uff1f`)) {
+    imports.push({
+      username: discovery.admin,
+      keys: tokens.map((item) => ({ name: item.name || "token", key: item.key }))
+    });
+  }
+  if (imports.length === 0) {
+    setStatus("\u672a\u9009\u62e9\u4efb\u4f55\u5bfc\u5165\u6761\u76ee", "ok");
+    return;
+  }
+  try {
+    applyAccountsResponse(await invoke("import_discovered_accounts_command", { imports }));
+  } catch (error) {
+    setStatus(`\u5bfc\u5165\u8d26\u53f7\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+function applyAccountsResponse(r) {
+  state.accounts = r.accounts || state.accounts;
+  syncResultsWithAccounts();
+  renderMeta();
+  renderAccountSelect();
+  renderResults();
+  renderAccountsTable();
+  pushLog(r.message);
+  setStatus(r.message, r.success ? "ok" : "warn");
+}
+
+async function exportSharedConfig() {
+  const path = prompt("\u5bfc\u51fa\u5230\u6587\u4ef6(\u7559\u7a7a\u4fdd\u5b58\u5230\u914d\u7f6e\u76ee\u5f55 shared-accounts.json):", "");
+  if (path === null) return;
+  const sanitized = confirm("\u662f\u5426\u8fde API Key \u4e5f\u53bb\u6389\uff0c\u53ea\u5bfc\u51fa\u7528\u6237\u540d\uff1f\n\u786e\u5b9a: \u4ec5\u7528\u6237\u540d  \u53d6\u6d88: \u7528\u6237\u540d\u4e0e Key(\u5747\u4e0d\u542b\u5bc6\u7801)");
+  try {
+    const message = await invoke("export_shared_config_command", { path: path.trim() || null, sanitized });
+    setStatus(message, "ok");
+    pushLog(message);
+  } catch (error) {
+    setStatus(`\u5bfc\u51fa\u5171\u4eab\u8d26\u53f7\u5217\u8868\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
+async function importSharedConfig() {
+  if (state.isRunning) {
+    setStatus("\u67e5\u8be2\u8fdb\u884c\u4e2d\uff0c\u8bf7\u7a0d\u540e\u518d\u8bd5", "warn");
+    return;
+  }
+  const path = (prompt("\u5171\u4eab\u8d26\u53f7\u5217\u8868\u6587\u4ef6\u8def\u5f84:", "") || "").trim();
+  if (!path) return;
+  let file;
+  try {
+    file = await invoke("read_shared_config_command", { path });
+  } catch (error) {
+    setStatus(`\u8bfb\u53d6\u5171\u4eab\u8d26\u53f7\u5217\u8868\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+    return;
+  }
+  const known = new Set(state.accounts.map((item) => item.username));
+  const entries = file.accounts || [];
+  const fresh = entries.filter((item) => !known.has(item.username));
+  if (!confirm(`\u5171\u4eab\u8d26\u53f7\u5217\u8868\u542b ${entries.length} \u4e2a\u8d26\u53f7\uff0c\u5176\u4e2d ${fresh.length} \u4e2a\u672c\u5730\u6ca1\u6709\u3002\n\u5df2\u6709\u8d26\u53f7\u53ea\u8ffd\u52a0\u7f3a\u5c11\u7684 Key\uff0c\u4e0d\u4fee\u6539\u672c\u5730\u5bc6\u7801\u3002\u7ee7\u7eed\u5bfc\u5165\uff1f`)) return;
+  const passwords = {};
+  for (const item of fresh) {
+    const password = prompt(`\u8f93\u5165 ${item.username} \u7684\u767b\u5f55\u5bc6\u7801(\u7559\u7a7a\u8df3\u8fc7\u8be5\u8d26\u53f7):`, "");
+    if (password === null) return;
+    if (password.trim()) passwords[item.username] = password.trim();
+  }
+  try {
+    applyAccountsResponse(await invoke("import_shared_config_command", { path, passwords }));
+  } catch (error) {
+    setStatus(`\u5bfc\u5165\u5171\u4eab\u8d26\u53f7\u5217\u8868\u5931\u8d25: ${toErrorMessage(error)}`, "error");
+  }
+}
+
 // ========== Auto Mode ==========
 
 function toggleAutoMode() {
@@ -1134,14 +2321,26 @@ function ensureAutoTimer() {
   state.autoTimer = setInterval(async () => {
     if (!state.autoMode || state.isRunning) return;
     if (state.autoCountdown <= 0) {
-      await runQuery();
+      await runQuery(state.scheduledIntervals);
       return;
     }
     state.autoCountdown -= 1;
+    if (state.prewarmLeadSecs > 0 && state.autoCountdown === state.prewarmLeadSecs) {
+      prewarmBrowserPool();
+    }
     scheduleStatusRender();
   }, 1000);
 }
 
+async function prewarmBrowserPool() {
+  try {
+    const msg = await invoke("prewarm_browser_pool_command");
+    pushLog(msg);
+  } catch (error) {
+    pushLog(`\u6d4f\u89c8\u5668\u6c60\u9884\u70ed\u5931\u8d25: ${toErrorMessage(error)}`);
+  }
+}
+
 function clearAutoTimer() {
   if (state.autoTimer) {
     clearInterval(state.autoTimer);
@@ -1169,6 +2368,7 @@ function renderStatus() {
     : "";
   refs.btnQuery.disabled = state.isRunning;
   refs.btnWebLogin.disabled = state.isRunning;
+  refs.btnRetryFailed.disabled = state.isRunning;
 }
 
 function setStatus(text, type) {
@@ -1222,6 +2422,14 @@ function formatLogLine(raw) {
   return `<div class="log-line ${cls}">${stamp}${accountTag}<span class="log-msg">${body}</span></div>`;
 }
 
+// \u672c\u8f6e\u5148\u67e5\u8be2\u5931\u8d25\u540e\u6539\u7528\u5176\u4ed6\u6765\u6e90\u7684\u8d26\u53f7\uff0c\u4fbf\u4e8e\u5224\u65ad\u662f\u5426\u9700\u8981\u8c03\u6574\u67e5\u8be2\u987a\u5e8f
+function logFallbackAccounts(summary) {
+  const accounts = summary && Array.isArray(summary.fallback_accounts) ? summary.fallback_accounts : [];
+  if (accounts.length > 0) {
+    pushLog(`\u56de\u9000\u5230\u5176\u4ed6\u6765\u6e90\u7684\u8d26\u53f7(${accounts.length}): ${accounts.join(", ")}`);
+  }
+}
+
 function pushLog(message) {
   const now = new Date();
   const stamp = [
@@ -1249,15 +2457,25 @@ function recalculateTotals() {
   let count = 0;
   state.results.forEach((item) => {
     if (!item || !item.success) return;
+    // \u6309\u6c47\u7387\u6362\u7b97\u4e3a\u7edf\u4e00\u8d27\u5e01\uff0c\u6ca1\u6709\u6c47\u7387\u7684\u8d27\u5e01\u4e0d\u8ba1\u5165\u603b\u989d
+    const rate = state.currencyRates[item.currency || state.totalCurrency];
+    if (!rate) return;
     const v = parseBalance(item.balance_text || "");
     if (v === null) return;
-    total += v;
+    total += v * rate;
     count += 1;
   });
   state.totalBalance = total;
   state.totalBalanceCount = count;
 }
 
+const CURRENCY_SYMBOLS = { USD: "$", CNY: "\u00a5", EUR: "\u20ac", GBP: "\u00a3" };
+
+function formatMoney(value, currency) {
+  const symbol = CURRENCY_SYMBOLS[currency];
+  return symbol ? `${symbol}${value.toFixed(2)}` : `${value.toFixed(2)} ${currency}`;
+}
+
 function parseBalance(text) {
   const matched = String(text || "").match(/-?[\d,]+(?:\.\d+)?/);
   if (!matched) return null;
@@ -1272,7 +2490,7 @@ function getSearchFilteredResults() {
   if (!q) return rows;
 
   rows = rows.filter((item) => {
-    const username = String(item.username || "").toLowerCase();
+    const username = `${item.username || ""} ${item.alias || ""}`.toLowerCase();
     const message = String(item.message || "").toLowerCase();
     const source = String(item.source || "").toLowerCase();
     const balanceText = String(item.balance_text || "").toLowerCase();
@@ -1290,11 +2508,12 @@ function renderSummaryChips(searchFilteredRows) {
     return;
   }
 
-  const counts = { all: 0, ok: 0, fail: 0, cache: 0, idle: 0 };
+  const counts = { all: 0, ok: 0, fail: 0, banned: 0, cache: 0, idle: 0 };
   const sums = {
     all: { total: 0, count: 0 },
     ok: { total: 0, count: 0 },
     fail: { total: 0, count: 0 },
+    banned: { total: 0, count: 0 },
     cache: { total: 0, count: 0 },
     idle: { total: 0, count: 0 }
   };
@@ -1321,6 +2540,7 @@ function renderSummaryChips(searchFilteredRows) {
     { key: "all", label: "\u5168\u90e8", count: counts.all },
     { key: "ok", label: "\u6210\u529f", count: counts.ok },
     { key: "fail", label: "\u5931\u8d25", count: counts.fail },
+    ...(counts.banned ? [{ key: "banned", label: "\u5c01\u7981", count: counts.banned }] : []),
     { key: "cache", label: "\u7f13\u5b58", count: counts.cache },
     { key: "idle", label: "\u5f85\u673a", count: counts.idle }
   ];
@@ -1373,6 +2593,7 @@ async function copyStatusUsernames(status) {
     all: "\u5168\u90e8",
     ok: "\u6210\u529f",
     fail: "\u5931\u8d25",
+    banned: "\u5c01\u7981",
     cache: "\u7f13\u5b58",
     idle: "\u5f85\u673a"
   };
@@ -1383,6 +2604,7 @@ async function copyStatusUsernames(status) {
 
 function getRowStatusKey(item) {
   if (!item) return "idle";
+  if (item.banned || item.health === "banned") return "banned";
   if (item.source === "cache") return "cache";
   if (item.source === "-") return "idle";
   return item.success ? "ok" : "fail";
@@ -1390,7 +2612,7 @@ function getRowStatusKey(item) {
 
 function normalizeDisplayStatus(value) {
   const v = String(value || "all");
-  const allowed = new Set(["all", "ok", "fail", "cache", "idle"]);
+  const allowed = new Set(["all", "ok", "fail", "banned", "cache", "idle"]);
   return allowed.has(v) ? v : "all";
 }
 
@@ -1456,7 +2678,8 @@ function formatResultsAsCsv(rows) {
   const lines = [header.join(",")];
   (Array.isArray(rows) ? rows : []).forEach((item) => {
     const statusKey = getRowStatusKey(item);
-    const statusText = statusKey === "cache" ? "\u7f13\u5b58"
+    const statusText = statusKey === "banned" ? "\u5c01\u7981"
+      : statusKey === "cache" ? "\u7f13\u5b58"
       : statusKey === "idle" ? "\u5f85\u673a"
       : statusKey === "ok" ? "\u6210\u529f" : "\u5931\u8d25";
     const row = [
@@ -1511,6 +2734,11 @@ function maskText(text) {
   return text.slice(0, 2) + "\u00b7".repeat(Math.min(text.length - 4, 8)) + text.slice(-2);
 }
 
+// \u540e\u7aef\u547d\u4ee4\u9519\u8bef\u7684\u5206\u7c7b: validation / not_found / busy / locked / io / internal
+function errorCode(error) {
+  return error && typeof error === "object" && typeof error.code === "string" ? error.code : "";
+}
+
 function toErrorMessage(error) {
   if (!error) return "\u672a\u77e5\u9519\u8bef";
   if (typeof error === "string") return error;
@@ -1518,6 +2746,17 @@ function toErrorMessage(error) {
   return String(error);
 }
 
+// \u7ed3\u679c\u6765\u6e90\u5355\u5143\u683c\u7684\u60ac\u6d6e\u63d0\u793a: \u672c\u6b21\u68c0\u67e5\u4f9d\u6b21\u5c1d\u8bd5\u7684\u8def\u5f84
+function formatAttempts(item) {
+  const attempts = Array.isArray(item.attempts) ? item.attempts : [];
+  if (!attempts.length) return "";
+  return attempts.map((step, index) => {
+    const seconds = (Number(step.duration_ms || 0) / 1000).toFixed(1);
+    const message = step.message ? ` ${step.message}` : "";
+    return `${index + 1}. ${step.source} ${step.outcome} ${seconds}s${message}`;
+  }).join("\n");
+}
+
 function esc(value) {
   return String(value || "")
     .replaceAll("&", "&amp;")
@@ -1548,6 +2787,11 @@ function loadUiPrefs() {
       }
     }
 
+    const strategy = localStorage.getItem("art_rs.auto_switch.strategy");
+    if (strategy === "least_recent" || strategy === "highest_balance") {
+      state.autoSwitchStrategy = strategy;
+    }
+
     const viewStatus = localStorage.getItem("art_rs.view.status");
     if (viewStatus !== null) {
       state.displayStatus = normalizeDisplayStatus(viewStatus);
@@ -1569,6 +2813,7 @@ function saveUiPrefs() {
   try {
     localStorage.setItem("art_rs.auto_switch.enabled", state.autoSwitchEnabled ? "1" : "0");
     localStorage.setItem("art_rs.auto_switch.threshold", String(state.autoSwitchThreshold));
+    localStorage.setItem("art_rs.auto_switch.strategy", state.autoSwitchStrategy);
     localStorage.setItem("art_rs.view.status", normalizeDisplayStatus(state.displayStatus));
     localStorage.setItem("art_rs.view.sort", normalizeDisplaySort(state.displaySort));
     localStorage.setItem("art_rs.view.summary_compact", state.summaryCompact ? "1" : "0");